@@ -0,0 +1,159 @@
+// src/alternate.rs
+
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::normalize_link;
+
+/// The file in each domain output directory mapping pages to their alternate versions, one
+/// `page<TAB>alternate<TAB>kind` per line.
+pub const ALTERNATES_FILE: &str = "alternates.tsv";
+
+/// The kind of an alternate version of a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlternateKind {
+    Amp,        // `<link rel="amphtml">`
+    Mobile,     // `<link rel="alternate" media="...">`
+}
+
+impl fmt::Display for AlternateKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AlternateKind::Amp => write!(f, "amp"),
+            AlternateKind::Mobile => write!(f, "mobile"),
+        }
+    }
+}
+
+/// An alternate version of a page that a page links to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlternateLink {
+    pub url: String,
+    pub kind: AlternateKind,
+    pub media: Option<String>,      // The media query of mobile alternates
+}
+
+/// What a page declares about its alternate versions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageAlternates {
+    pub alternates: Vec<AlternateLink>,
+    pub canonical: Option<String>,  // `<link rel="canonical">`, absolute
+    pub is_amp: bool,               // `<html amp>` or `<html ⚡>`
+}
+
+impl PageAlternates {
+    /// Returns the page this one is an alternate version of: the canonical URL of an AMP page.
+    pub fn primary(&self, url: &str) -> Option<&str> {
+        self.canonical.as_deref().filter(|canonical| self.is_amp && *canonical != url)
+    }
+}
+
+/// Finds the AMP and mobile versions a page links to with `<link>` tags, its canonical URL, and
+/// whether it is an AMP page itself. Language (`hreflang`) and feed alternates are not versions
+/// of the same page and are ignored.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{extract_alternates, AlternateKind};
+/// let html = r#"<link rel="amphtml" href="/amp/post"><link rel="alternate" media="only screen and (max-width: 640px)" href="https://m.example.com/post">"#;
+/// let page = extract_alternates(html, "https://example.com/post");
+/// assert_eq!(page.alternates[0].url, "https://example.com/amp/post");
+/// assert_eq!(page.alternates[1].kind, AlternateKind::Mobile);
+/// ```
+pub fn extract_alternates(html: &str, base_url: &str) -> PageAlternates {
+    let document = Html::parse_document(html);
+    let link_selector = Selector::parse("link[rel][href]").unwrap();
+    let html_selector = Selector::parse("html").unwrap();
+    let mut page = PageAlternates {
+        is_amp: document.select(&html_selector).next().is_some_and(|root| root.value().attrs().any(|(name, _)| name == "amp" || name == "⚡")),
+        ..PageAlternates::default()
+    };
+
+    for link in document.select(&link_selector) {
+        let rel = link.value().attr("rel").unwrap_or("").to_ascii_lowercase();
+        let rels: Vec<&str> = rel.split_whitespace().collect();
+        let url = normalize_link(link.value().attr("href").unwrap_or(""), base_url);
+        let media = link.value().attr("media").map(str::to_string);
+        if rels.contains(&"amphtml") {
+            page.alternates.push(AlternateLink { url, kind: AlternateKind::Amp, media: None });
+        } else if rels.contains(&"alternate") && media.is_some() && link.value().attr("hreflang").is_none() {
+            page.alternates.push(AlternateLink { url, kind: AlternateKind::Mobile, media });
+        } else if rels.contains(&"canonical") && page.canonical.is_none() {
+            page.canonical = Some(url);
+        }
+    }
+    page
+}
+
+/// How a crawl treats the AMP and mobile versions of pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlternatePolicy {
+    Separate,           // Store every version as a page of its own
+    #[default]
+    SkipAlternates,     // Store the primary page; alternate versions are recorded but not stored
+    PreferAlternates,   // Store the AMP or mobile version instead of a primary page that has one
+}
+
+/// The alternate versions seen during a crawl, by page.
+#[derive(Debug, Default)]
+pub struct AlternateMap {
+    primaries: Mutex<BTreeMap<String, (String, AlternateKind)>>,     // Alternate URL -> page and kind
+}
+
+impl AlternateMap {
+    /// Records that `alternate` is a version of `page`, returning `false` if this was known.
+    pub fn record(&self, page: &str, alternate: &str, kind: AlternateKind) -> bool {
+        let mut primaries = self.primaries.lock().unwrap();
+        if primaries.contains_key(alternate) {
+            return false;
+        }
+        primaries.insert(alternate.to_string(), (page.to_string(), kind));
+        true
+    }
+
+    /// Returns the page `url` is an alternate version of.
+    pub fn primary_of(&self, url: &str) -> Option<String> {
+        self.primaries.lock().unwrap().get(url).map(|(page, _)| page.clone())
+    }
+
+    /// Returns every `(page, alternate, kind)` mapping, by alternate URL.
+    pub fn pairs(&self) -> Vec<(String, String, AlternateKind)> {
+        self.primaries.lock().unwrap().iter().map(|(alternate, (page, kind))| (page.clone(), alternate.clone(), *kind)).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.primaries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.primaries.lock().unwrap().is_empty()
+    }
+
+    // Method to forget every mapping
+    pub fn clear(&self) {
+        self.primaries.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amp_pages_know_their_primary() {
+        let amp = r#"<html amp><head><link rel="canonical" href="https://a.test/post"><link rel="alternate" hreflang="de" media="all" href="/de/post"><link rel="alternate" type="application/rss+xml" href="/feed"></head></html>"#;
+        let page = extract_alternates(amp, "https://a.test/amp/post");
+        assert!(page.is_amp && page.alternates.is_empty());
+        assert_eq!(page.primary("https://a.test/amp/post"), Some("https://a.test/post"));
+        assert_eq!(extract_alternates("<link rel=canonical href=/post>", "https://a.test/post?x=1").primary("https://a.test/post?x=1"), None);
+
+        let map = AlternateMap::default();
+        assert!(map.record("https://a.test/post", "https://a.test/amp/post", AlternateKind::Amp));
+        assert!(!map.record("https://a.test/other", "https://a.test/amp/post", AlternateKind::Amp));
+        assert_eq!(map.primary_of("https://a.test/amp/post").as_deref(), Some("https://a.test/post"));
+    }
+}
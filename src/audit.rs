@@ -0,0 +1,265 @@
+// src/audit.rs
+
+use reqwest::{Client, Method, Url};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{create_dir_all, write};
+use std::io::Result as IoResult;
+use std::path::Path;
+
+use crate::{
+    extract_alternates, extract_link_records, fetch_robots_txt_with_config, page, request, robots, Finding, FindingKind, Frontier,
+    RobotsDirectives, RobotsInfo, ScraperConfig, Severity,
+};
+
+/// The file in a domain output directory the robots audit of the domain is written to.
+pub const ROBOTS_AUDIT_FILE: &str = "robots_audit.txt";
+
+/// What a robots audit learned about one page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageAudit {
+    pub url: String,
+    pub status: Option<u16>,            // `None` when robots.txt disallows the page, so it was not fetched
+    pub final_url: Option<String>,      // Where redirects ended, if elsewhere
+    pub meta_robots: RobotsDirectives,
+    pub header_robots: RobotsDirectives,
+    pub canonical: Option<String>,
+    pub in_sitemap: bool,
+}
+
+/// A contradiction between the robots.txt rules, sitemaps, robots directives and canonical URLs of a site.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditIssue {
+    pub url: String,
+    pub severity: Severity,
+    pub description: String,
+}
+
+impl fmt::Display for AuditIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Robots Audit - Severity: {}, URL: {}, Issue: {}", self.severity, self.url, self.description)
+    }
+}
+
+/// The result of `audit_robots`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotsAudit {
+    pub robots: RobotsInfo,
+    pub sitemap_urls: Vec<String>,
+    pub pages: Vec<PageAudit>,          // In crawl order
+    pub issues: Vec<AuditIssue>,
+}
+
+/// Audits how a site tells crawlers what to index, without scraping or storing any content.
+///
+/// The audit reads `robots.txt` and its sitemaps, then walks the site through the crawl frontier
+/// from `url` and the sitemap URLs (same host, up to the config's max depth), recording the
+/// status, `<meta name="robots">`, `X-Robots-Tag` and canonical URL of every page. URLs that
+/// robots.txt disallows are never requested. The contradictions found are reported, e.g.:
+///
+/// * a sitemap listing a disallowed, `noindex`, redirected, failing, or non-canonical URL,
+/// * a canonical URL that robots.txt disallows,
+/// * `<meta name="robots">` and `X-Robots-Tag` disagreeing about `noindex`.
+///
+/// The issues are also appended to the findings log, and written to `robots_audit.txt` in the
+/// domain output directory with `set_legacy_finding_files(true)`.
+///
+/// # Arguments
+///
+/// * `url` - The base URL of the site.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `config` - An optional reference to `ScraperConfig` for the depth, domain lists, user agent and request limits.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::audit_robots;
+/// # use reqwest::Client;
+/// # async fn example(client: Client) {
+/// let audit = audit_robots("https://example.com", &client, None).await;
+/// for issue in &audit.issues {
+///     println!("{}", issue);
+/// }
+/// # }
+/// ```
+pub async fn audit_robots(url: &str, client: &Client, config: Option<&ScraperConfig>) -> RobotsAudit {
+    let default_config = ScraperConfig::default();
+    let config = config.unwrap_or(&default_config);
+    // A seed that is not a URL has no site to audit
+    let Ok(seed) = Url::parse(url) else { return RobotsAudit::default() };
+    let host = seed.host_str().unwrap_or_default().to_string();
+
+    let robots = fetch_robots_txt_with_config(url, client, config).await;
+    let sitemap_urls = robots::fetch_sitemap_urls_with_config(&robots.sitemaps, client, config.max_sitemap_urls(), config).await;
+    let in_sitemap: HashSet<&str> = sitemap_urls.iter().map(String::as_str).collect();
+
+    let mut frontier = Frontier::new();
+    frontier.push(url, 0, 0.0);
+    for sitemap_url in &sitemap_urls {
+        frontier.push(sitemap_url, 0, 0.0);
+    }
+
+    let mut pages = Vec::new();
+    while let Some(entry) = frontier.pop() {
+        let parsed = Url::parse(&entry.url).ok();
+        if !config.is_url_allowed(&entry.url) || parsed.as_ref().and_then(Url::host_str) != Some(host.as_str()) {
+            continue;
+        }
+        let mut audit = PageAudit {
+            url: entry.url.clone(),
+            status: None,
+            final_url: None,
+            meta_robots: RobotsDirectives::default(),
+            header_robots: RobotsDirectives::default(),
+            canonical: None,
+            in_sitemap: in_sitemap.contains(entry.url.as_str()),
+        };
+        if !robots.is_allowed(&path_of(&entry.url)) {
+            pages.push(audit);
+            continue;
+        }
+
+        let Ok(response) = request::send(client, Method::GET, &entry.url, config, |request| request).await else {
+            pages.push(audit);
+            continue;
+        };
+        audit.status = Some(response.status().as_u16());
+        audit.final_url = Some(response.url().to_string()).filter(|final_url| *final_url != entry.url);
+        audit.header_robots = RobotsDirectives::from_headers(response.headers());
+        let content_type = response.headers().get("content-type").and_then(|value| value.to_str().ok()).map(str::to_string);
+        let body = request::read_body(response, config.max_page_bytes(), config).await.ok().flatten().unwrap_or_default();
+        let html = page::decode_body(&body, content_type.as_deref());
+
+        audit.meta_robots = RobotsDirectives::from_html(&html);
+        audit.canonical = extract_alternates(&html, &entry.url).canonical;
+        if entry.depth < config.max_depth() {
+            for record in extract_link_records(&html, &entry.url) {
+                frontier.push(&record.url, entry.depth + 1, -(entry.depth as f64 + 1.0));
+            }
+        }
+        pages.push(audit);
+    }
+
+    let issues = pages.iter().flat_map(|page| page_issues(page, &robots)).collect();
+    let audit = RobotsAudit { robots, sitemap_urls, pages, issues };
+    config.log_findings(&audit.issues.iter().map(|issue| Finding::new(FindingKind::RobotsAudit, &issue.url, &issue.to_string())).collect::<Vec<_>>());
+    if !config.legacy_finding_files() {
+        return audit;
+    }
+    let dir = config.domain_dir(url);
+    if let Err(e) = write_robots_audit(&audit, &dir) {
+        let error_message = format!("Failed to write robots audit to '{}': {}", dir.display(), e);
+        console_error!("{}", error_message);
+        config.log_error(&error_message);
+    }
+    audit
+}
+
+// Lists the contradictions of one audited page
+fn page_issues(page: &PageAudit, robots: &RobotsInfo) -> Vec<AuditIssue> {
+    let mut issues = Vec::new();
+    let mut flag = |severity: Severity, description: String| issues.push(AuditIssue { url: page.url.clone(), severity, description });
+    let noindex = page.meta_robots.noindex || page.header_robots.noindex;
+
+    if page.in_sitemap {
+        match page.status {
+            None if !robots.is_allowed(&path_of(&page.url)) => flag(Severity::High, "Sitemap lists a URL disallowed by robots.txt".to_string()),
+            Some(status) if status >= 400 => flag(Severity::Medium, format!("Sitemap lists a URL answering HTTP {}", status)),
+            _ => {}
+        }
+        if noindex {
+            flag(Severity::High, "Sitemap lists a noindex page".to_string());
+        }
+        if let Some(final_url) = &page.final_url {
+            flag(Severity::Low, format!("Sitemap lists a URL redirecting to {}", final_url));
+        }
+        if let Some(canonical) = page.canonical.as_ref().filter(|canonical| **canonical != page.url) {
+            flag(Severity::Medium, format!("Sitemap lists a non-canonical URL (canonical: {})", canonical));
+        }
+    }
+    if let Some(canonical) = &page.canonical {
+        if !robots.is_allowed(&path_of(canonical)) {
+            flag(Severity::High, format!("Canonical URL {} is disallowed by robots.txt", canonical));
+        }
+        if noindex && *canonical != page.url {
+            flag(Severity::Low, format!("Noindex page declares another page as canonical ({})", canonical));
+        }
+    }
+    if page.status.is_some() && page.meta_robots.noindex != page.header_robots.noindex {
+        flag(Severity::Medium, "<meta name=\"robots\"> and X-Robots-Tag disagree about noindex".to_string());
+    }
+    issues
+}
+
+// Returns the path and query of a URL, the part robots.txt rules match
+pub(crate) fn path_of(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(parsed) => match parsed.query() {
+            Some(query) => format!("{}?{}", parsed.path(), query),
+            None => parsed.path().to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Writes the issues of an audit to `robots_audit.txt` in `dir`, one per line.
+pub fn write_robots_audit(audit: &RobotsAudit, dir: &Path) -> IoResult<()> {
+    create_dir_all(dir)?;
+    let lines: String = audit.issues.iter().map(|issue| format!("{}\n", issue)).collect();
+    write(dir.join(ROBOTS_AUDIT_FILE), lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_findings;
+    use crate::test_support::{http_response, serve};
+
+    #[tokio::test]
+    async fn test_audit_reports_contradictions() {
+        let base = serve(|request| {
+            let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+            let host = request.lines().find_map(|line| line.to_ascii_lowercase().strip_prefix("host: ").map(str::to_string)).unwrap_or_default();
+            let base = format!("http://{}", host.trim());
+            match path.as_str() {
+                "/robots.txt" => http_response("200 OK", &[], format!("Disallow: /private\nSitemap: {}/sitemap.xml\n", base).as_bytes()),
+                "/sitemap.xml" => {
+                    let urls: String = ["/", "/private/a", "/hidden", "/gone", "/copy"].iter().map(|path| format!("<url><loc>{}{}</loc></url>", base, path)).collect();
+                    http_response("200 OK", &[("Content-Type", "application/xml")], format!("<urlset>{}</urlset>", urls).as_bytes())
+                }
+                "/hidden" => http_response("200 OK", &[("X-Robots-Tag", "noindex")], b"<p>Hidden</p>"),
+                "/gone" => http_response("404 Not Found", &[], b""),
+                "/copy" => http_response("200 OK", &[], b"<link rel='canonical' href='/private/original'><p>Copy</p>"),
+                _ => http_response("200 OK", &[], b"<a href='/private/b'>Staff</a>"),
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+
+        let audit = audit_robots(&base, &Client::new(), Some(&config)).await;
+
+        assert_eq!(audit.sitemap_urls.len(), 5);
+        let private = audit.pages.iter().find(|page| page.url.ends_with("/private/b")).unwrap();
+        assert_eq!(private.status, None);
+        let issues: Vec<(&str, &str)> = audit.issues.iter().map(|issue| (&issue.url[base.len()..], issue.description.as_str())).collect();
+        assert!(issues.contains(&("/private/a", "Sitemap lists a URL disallowed by robots.txt")));
+        assert!(issues.contains(&("/hidden", "Sitemap lists a noindex page")));
+        assert!(issues.contains(&("/hidden", "<meta name=\"robots\"> and X-Robots-Tag disagree about noindex")));
+        assert!(issues.contains(&("/gone", "Sitemap lists a URL answering HTTP 404")));
+        assert!(issues.iter().any(|(url, issue)| *url == "/copy" && issue.starts_with("Canonical URL") && issue.ends_with("is disallowed by robots.txt")));
+        assert!(!issues.iter().any(|(url, _)| *url == "/private/b"));
+
+        let findings = read_findings(&config.findings_path()).unwrap();
+        assert_eq!(findings.iter().filter(|finding| finding.kind == FindingKind::RobotsAudit).count(), audit.issues.len());
+        assert!(!config.domain_dir(&base).join(ROBOTS_AUDIT_FILE).exists());
+
+        config.set_legacy_finding_files(true);
+        let audit = audit_robots(&base, &Client::new(), Some(&config)).await;
+        let report = std::fs::read_to_string(config.domain_dir(&base).join(ROBOTS_AUDIT_FILE)).unwrap();
+        assert_eq!(report.lines().count(), audit.issues.len());
+
+        assert_eq!(audit_robots("example.com", &Client::new(), Some(&config)).await, RobotsAudit::default());
+    }
+}
@@ -0,0 +1,102 @@
+// src/botwall.rs
+
+use scraper::{Html, Node, Selector};
+use std::fmt;
+
+// Markers of the interstitials anti-bot services serve instead of the page
+const CHALLENGE_MARKERS: [&str; 14] = [
+    "cf-chl-",
+    "challenge-platform",
+    "just a moment...",
+    "checking your browser",
+    "attention required! | cloudflare",
+    "ddos protection by",
+    "_incapsula_resource",
+    "px-captcha",
+    "captcha-delivery.com",
+    "distil_r_captcha",
+    "are you a robot",
+    "verify you are human",
+    "unusual traffic from your computer",
+    "request unsuccessful. incapsula",
+];
+
+// Placeholders of pages that only fill in their content with JavaScript
+const SHELL_ROOTS: [&str; 4] = ["#root", "#app", "#__next", "#__nuxt"];
+
+/// Why a fetched page looks like a bot wall rather than the page itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotWall {
+    Challenge,      // An anti-bot interstitial or CAPTCHA page
+    EmptyShell,     // Scripts and an empty mount point, with no text without JavaScript
+}
+
+impl fmt::Display for BotWall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BotWall::Challenge => write!(f, "bot challenge"),
+            BotWall::EmptyShell => write!(f, "empty JavaScript shell"),
+        }
+    }
+}
+
+/// Detects challenge and empty-shell pages, which a static fetch gets instead of the content of
+/// protected or client-rendered sites.
+///
+/// A page is a challenge when it carries a known anti-bot marker (Cloudflare, Incapsula,
+/// PerimeterX, DataDome, ...), or a CAPTCHA widget on a `403`/`429`/`503` answer. It is an empty
+/// shell when its body has almost no text outside scripts but runs scripts, and either asks to
+/// enable JavaScript or leaves an app mount point such as `#root` empty.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{detect_bot_wall, BotWall};
+/// let challenge = "<title>Just a moment...</title><div id='cf-chl-widget'></div>";
+/// assert_eq!(detect_bot_wall(403, challenge), Some(BotWall::Challenge));
+/// let shell = "<body><div id='root'></div><script src='/app.js'></script></body>";
+/// assert_eq!(detect_bot_wall(200, shell), Some(BotWall::EmptyShell));
+/// assert_eq!(detect_bot_wall(200, "<p>Hello</p>"), None);
+/// ```
+pub fn detect_bot_wall(status: u16, html: &str) -> Option<BotWall> {
+    let lower = html.to_lowercase();
+    if CHALLENGE_MARKERS.iter().any(|marker| lower.contains(marker)) {
+        return Some(BotWall::Challenge);
+    }
+    let captcha = ["g-recaptcha", "h-captcha", "cf-turnstile"].iter().any(|widget| lower.contains(widget));
+    if captcha && matches!(status, 403 | 429 | 503) {
+        return Some(BotWall::Challenge);
+    }
+
+    let document = Html::parse_document(html);
+    let selector = |css: &str| Selector::parse(css).unwrap();
+    // Pages without scripts render nothing more in a browser
+    document.select(&selector("script")).next()?;
+    let visible_text: usize = document
+        .select(&selector("body"))
+        .flat_map(|body| body.descendants())
+        .filter(|node| !node.ancestors().any(|ancestor| matches!(ancestor.value(), Node::Element(element) if matches!(element.name(), "script" | "style" | "noscript" | "template"))))
+        .filter_map(|node| node.value().as_text().map(|text| text.trim().len()))
+        .sum();
+    let asks_for_javascript = document.select(&selector("noscript")).any(|noscript| noscript.text().collect::<String>().to_lowercase().contains("javascript"));
+    let empty_root = SHELL_ROOTS.iter().any(|root| document.select(&selector(root)).any(|element| element.text().all(|text| text.trim().is_empty())));
+    (visible_text < 200 && (asks_for_javascript || empty_root)).then_some(BotWall::EmptyShell)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bot_walls_are_told_from_pages() {
+        assert_eq!(detect_bot_wall(200, "<script src='https://ct.captcha-delivery.com/c.js'></script>"), Some(BotWall::Challenge));
+        let captcha = "<form><div class='g-recaptcha' data-sitekey='x'></div></form>";
+        assert_eq!(detect_bot_wall(429, captcha), Some(BotWall::Challenge));
+        assert_eq!(detect_bot_wall(200, captcha), None);
+
+        let shell = "<body><noscript>You need to enable JavaScript to run this app.</noscript><div id='app'></div><script src='/main.js'></script></body>";
+        assert_eq!(detect_bot_wall(200, shell), Some(BotWall::EmptyShell));
+        let rendered = format!("<body><div id='app'><p>{}</p></div><script src='/main.js'></script></body>", "Catalog ".repeat(40));
+        assert_eq!(detect_bot_wall(200, &rendered), None);
+    }
+}
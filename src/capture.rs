@@ -0,0 +1,245 @@
+// src/capture.rs
+
+use regex::Regex;
+use serde_json::Value;
+use std::fs::{create_dir_all, write};
+use std::io::Result as IoResult;
+use std::path::{Path, PathBuf};
+
+use crate::pages::{page_id, PageIndex};
+use crate::robots::unescape_xml;
+use crate::{normalize_link, ContentRoute};
+
+/// The URLs and email addresses found inside a JSON or XML payload.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PayloadData {
+    pub urls: Vec<String>,      // Absolute, in payload order, without duplicates
+    pub emails: Vec<String>,
+}
+
+/// Pretty-prints a JSON document with two-space indentation.
+///
+/// # Returns
+///
+/// `None` when `json` is not valid JSON.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::pretty_print_json;
+/// assert_eq!(pretty_print_json(r#"{"a":[1]}"#).unwrap(), "{\n  \"a\": [\n    1\n  ]\n}");
+/// assert!(pretty_print_json("{oops").is_none());
+/// ```
+pub fn pretty_print_json(json: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(json).ok()?;
+    serde_json::to_string_pretty(&value).ok()
+}
+
+/// Extracts the URLs and email addresses inside a JSON or XML payload, so API responses can be
+/// crawled further and mined like pages.
+///
+/// JSON string values that are absolute `http(s)` URLs or root-relative paths (`/api/items/2`)
+/// count as URLs; in XML, absolute URLs anywhere in the document do.
+///
+/// # Arguments
+///
+/// * `body` - The decoded payload.
+/// * `route` - `ContentRoute::Json` or `ContentRoute::Xml`.
+/// * `base_url` - The URL the payload was fetched from, used to resolve relative paths.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{extract_payload_data, ContentRoute};
+/// let json = r#"{"next": "/api/items?page=2", "owner": {"contact": "ops@example.com"}}"#;
+/// let data = extract_payload_data(json, ContentRoute::Json, "https://example.com/api/items");
+/// assert_eq!(data.urls, vec!["https://example.com/api/items?page=2"]);
+/// assert_eq!(data.emails, vec!["ops@example.com"]);
+/// ```
+pub fn extract_payload_data(body: &str, route: ContentRoute, base_url: &str) -> PayloadData {
+    let email_regex = Regex::new(r"[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}").unwrap();
+    let mut data = PayloadData::default();
+    let add = |list: &mut Vec<String>, item: String| {
+        if !list.contains(&item) {
+            list.push(item);
+        }
+    };
+
+    let strings = match (route, serde_json::from_str::<Value>(body)) {
+        (ContentRoute::Json, Ok(value)) => {
+            let mut strings = Vec::new();
+            collect_json_strings(&value, &mut strings);
+            strings
+        }
+        _ => vec![unescape_xml(body)],
+    };
+
+    let url_regex = Regex::new(r#"https?://[^\s<>"'\]\[{}]+"#).unwrap();
+    for string in &strings {
+        let is_path = string.starts_with('/') && !string.starts_with("//") && !string.contains(char::is_whitespace);
+        if route == ContentRoute::Json && is_path {
+            add(&mut data.urls, normalize_link(string, base_url));
+        } else {
+            for url in url_regex.find_iter(string) {
+                add(&mut data.urls, url.as_str().trim_end_matches(['.', ',', ')', ';']).to_string());
+            }
+        }
+        for email in email_regex.find_iter(string) {
+            add(&mut data.emails, email.as_str().to_string());
+        }
+    }
+    data
+}
+
+// Gathers every string value of a JSON document, depth first
+fn collect_json_strings(value: &Value, strings: &mut Vec<String>) {
+    match value {
+        Value::String(string) => strings.push(string.clone()),
+        Value::Array(items) => items.iter().for_each(|item| collect_json_strings(item, strings)),
+        Value::Object(fields) => fields.values().for_each(|field| collect_json_strings(field, strings)),
+        _ => {}
+    }
+}
+
+/// Pretty-prints an XML document with one element per line and two-space indentation.
+///
+/// Elements holding only text stay on one line; comments, CDATA sections and declarations are
+/// kept verbatim. The input is not validated, so malformed XML is indented as well as it can be.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::pretty_print_xml;
+/// let xml = "<urlset><url><loc>https://example.com/</loc></url></urlset>";
+/// assert_eq!(pretty_print_xml(xml), "<urlset>\n  <url>\n    <loc>https://example.com/</loc>\n  </url>\n</urlset>");
+/// ```
+pub fn pretty_print_xml(xml: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut inline = false;         // The previous token opened an element or was text
+    let mut rest = xml.trim();
+
+    let new_line = |out: &mut String, depth: usize| {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&"  ".repeat(depth));
+    };
+
+    while !rest.is_empty() {
+        if rest.starts_with('<') {
+            let end = tag_end(rest);
+            let tag = &rest[..end];
+            rest = &rest[end..];
+
+            if tag.starts_with("</") {
+                depth = depth.saturating_sub(1);
+                if !inline {
+                    new_line(&mut out, depth);
+                }
+                out.push_str(tag);
+                inline = false;
+            } else {
+                new_line(&mut out, depth);
+                out.push_str(tag);
+                let opens = !(tag.starts_with("<?") || tag.starts_with("<!") || tag.ends_with("/>"));
+                if opens {
+                    depth += 1;
+                }
+                inline = opens;
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = rest[..end].trim();
+            rest = &rest[end..];
+            if !text.is_empty() {
+                if !inline {
+                    new_line(&mut out, depth);
+                }
+                out.push_str(text);
+                inline = true;
+            }
+        }
+    }
+    out
+}
+
+// Returns the length of the tag `rest` starts with, honoring comments, CDATA and quoted attributes
+fn tag_end(rest: &str) -> usize {
+    for (open, close) in [("<!--", "-->"), ("<![CDATA[", "]]>")] {
+        if rest.starts_with(open) {
+            return rest.find(close).map_or(rest.len(), |i| i + close.len());
+        }
+    }
+
+    let mut quote = None;
+    for (i, c) in rest.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    rest.len()
+}
+
+/// Returns the path of the captured payload of `url` inside a domain output directory.
+pub fn capture_path(dir: &Path, url: &str, route: ContentRoute) -> PathBuf {
+    let extension = if route == ContentRoute::Xml { "xml" } else { "json" };
+    dir.join("captures").join(format!("{}.{}", page_id(url), extension))
+}
+
+/// Stores a JSON or XML response under `{dir}/captures`, pretty-printed, and lists it in the
+/// page index of `dir`. Payloads that fail to parse are stored as received.
+///
+/// # Arguments
+///
+/// * `body` - The decoded response body.
+/// * `url` - The URL the response was fetched from.
+/// * `dir` - The domain output directory.
+/// * `route` - `ContentRoute::Json` or `ContentRoute::Xml`.
+///
+/// # Returns
+///
+/// The path of the stored file.
+pub fn save_capture(body: &str, url: &str, dir: &Path, route: ContentRoute) -> IoResult<PathBuf> {
+    save_capture_in_index(body, url, dir, route, &PageIndex::default())
+}
+
+// Stores a capture like `save_capture`, listing it in the page index of a crawl
+pub(crate) fn save_capture_in_index(body: &str, url: &str, dir: &Path, route: ContentRoute, index: &PageIndex) -> IoResult<PathBuf> {
+    let pretty = match route {
+        ContentRoute::Xml => pretty_print_xml(body),
+        _ => pretty_print_json(body).unwrap_or_else(|| body.to_string()),
+    };
+
+    let path = capture_path(dir, url, route);
+    create_dir_all(dir.join("captures"))?;
+    write(&path, pretty)?;
+    index.record(dir, url)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captures_are_pretty_printed() {
+        let xml = r#"<?xml version="1.0"?><feed><!-- a > b --><entry id="1"><title a=">">T</title><br/></entry><empty></empty></feed>"#;
+        assert_eq!(
+            pretty_print_xml(xml),
+            "<?xml version=\"1.0\"?>\n<feed>\n  <!-- a > b -->\n  <entry id=\"1\">\n    <title a=\">\">T</title>\n    <br/>\n  </entry>\n  <empty></empty>\n</feed>"
+        );
+
+        let feed = "<feed><link href=\"https://a.test/post?id=1&amp;ref=rss\"/><author>Ed (ed@a.test)</author></feed>";
+        let data = extract_payload_data(feed, ContentRoute::Xml, "https://a.test/feed.xml");
+        assert_eq!(data, PayloadData { urls: vec!["https://a.test/post?id=1&ref=rss".to_string()], emails: vec!["ed@a.test".to_string()] });
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = save_capture(r#"{"items":[]}"#, "https://api.test/v1/items", temp_dir.path(), ContentRoute::Json).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\n  \"items\": []\n}");
+        assert_eq!(crate::read_page_index(temp_dir.path()).unwrap()[0].1, "https://api.test/v1/items");
+    }
+}
@@ -0,0 +1,175 @@
+// src/classify.rs
+
+use reqwest::Url;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+
+/// The file in each domain output directory listing the class of every page, one `url<TAB>class` per line.
+pub const PAGE_CLASSES_FILE: &str = "page_classes.tsv";
+
+// Phrases of page titles and headings that mark an error page served with a success status
+const ERROR_PHRASES: [&str; 8] = ["404", "not found", "page not found", "server error", "something went wrong", "access denied", "forbidden", "error 500"];
+
+// Query parameters that carry a search term
+const SEARCH_PARAMS: [&str; 6] = ["q", "query", "search", "s", "keyword", "term"];
+
+/// What kind of page a crawled page is, for routing pages in downstream pipelines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PageClass {
+    Listing,        // Repeated links to other pages: categories, archives, indexes
+    Detail,         // One item or article
+    SearchResults,  // The answer to a search query
+    Login,          // A sign-in form
+    Error,          // An error status, or an error page served as a success (soft 404)
+    Other,
+}
+
+impl fmt::Display for PageClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PageClass::Listing => "listing",
+            PageClass::Detail => "detail",
+            PageClass::SearchResults => "search",
+            PageClass::Login => "login",
+            PageClass::Error => "error",
+            PageClass::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Labels a page from its status, URL and structure.
+///
+/// The heuristics, in order: an error status or an error title/heading on a short page is an
+/// error page; a password field makes a login page; a search parameter or `/search` path with
+/// repeated results is a search page; long article text or an item's structured data makes a
+/// detail page, unless the page repeats the same linked block many times, which makes a listing.
+///
+/// # Arguments
+///
+/// * `html` - The HTML of the page.
+/// * `url` - The URL of the page.
+/// * `status` - The HTTP status the page was served with.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{classify_page, PageClass};
+/// let login = r#"<form action="/session"><input name="user"><input type="password" name="pass"></form>"#;
+/// assert_eq!(classify_page(login, "https://example.com/account", 200), PageClass::Login);
+/// assert_eq!(classify_page("<h1>Page not found</h1>", "https://example.com/old", 200), PageClass::Error);
+/// ```
+pub fn classify_page(html: &str, url: &str, status: u16) -> PageClass {
+    if status >= 400 {
+        return PageClass::Error;
+    }
+    let document = Html::parse_document(html);
+    let selector = |css: &str| Selector::parse(css).unwrap();
+
+    let body_text: usize = document.select(&selector("body")).map(|body| body.text().map(str::len).sum::<usize>()).sum();
+    let headings: String = document.select(&selector("title, h1")).flat_map(|element| element.text()).collect::<String>().to_lowercase();
+    if body_text < 3000 && ERROR_PHRASES.iter().any(|phrase| headings.contains(phrase)) {
+        return PageClass::Error;
+    }
+    if document.select(&selector("input[type=password]")).next().is_some() {
+        return PageClass::Login;
+    }
+
+    let repeated = repeated_link_blocks(&document);
+    let parsed = Url::parse(url).ok();
+    let is_search = parsed.as_ref().is_some_and(|parsed| {
+        parsed.path().to_ascii_lowercase().contains("/search") || parsed.query_pairs().any(|(name, value)| SEARCH_PARAMS.contains(&name.as_ref()) && !value.is_empty())
+    });
+    if is_search && repeated >= 3 {
+        return PageClass::SearchResults;
+    }
+
+    let paragraph_text: usize = document.select(&selector("p")).filter(|p| !in_navigation(p)).map(|p| p.text().map(str::len).sum::<usize>()).sum();
+    let item_data = document.select(&selector("script[type='application/ld+json'], [itemtype]")).any(|element| {
+        let text = element.value().attr("itemtype").map(str::to_string).unwrap_or_else(|| element.text().collect());
+        ["Product", "Article", "NewsArticle", "BlogPosting", "Recipe", "JobPosting", "Event"].iter().any(|kind| text.contains(kind))
+    });
+    if repeated >= 5 && paragraph_text < repeated * 300 {
+        return PageClass::Listing;
+    }
+    if item_data || paragraph_text >= 1000 || (document.select(&selector("h1")).count() == 1 && paragraph_text >= 200) {
+        return PageClass::Detail;
+    }
+    if repeated >= 5 {
+        return PageClass::Listing;
+    }
+    PageClass::Other
+}
+
+// Returns how often the most repeated block holding a link occurs outside the navigation,
+// grouping blocks by tag name and class
+fn repeated_link_blocks(document: &Html) -> usize {
+    let block_selector = Selector::parse("article, li, tr, div[class]").unwrap();
+    let link_selector = Selector::parse("a[href]").unwrap();
+    let mut counts: HashMap<(String, String), usize> = HashMap::new();
+
+    for block in document.select(&block_selector) {
+        if in_navigation(&block) || block.select(&link_selector).next().is_none() {
+            continue;
+        }
+        let key = (block.value().name().to_string(), block.value().attr("class").unwrap_or("").to_string());
+        *counts.entry(key).or_insert(0) += 1;
+    }
+    counts.into_values().max().unwrap_or(0)
+}
+
+// Returns whether an element sits in the navigation, header or footer of a page
+fn in_navigation(element: &ElementRef) -> bool {
+    element.ancestors().filter_map(ElementRef::wrap).any(|ancestor| matches!(ancestor.value().name(), "nav" | "header" | "footer"))
+}
+
+/// The classes of the pages of a crawl, by URL.
+#[derive(Debug, Default)]
+pub struct PageClassMap {
+    classes: Mutex<BTreeMap<String, PageClass>>,
+}
+
+impl PageClassMap {
+    pub fn record(&self, url: &str, class: PageClass) {
+        self.classes.lock().unwrap().insert(url.to_string(), class);
+    }
+
+    pub fn class_of(&self, url: &str) -> Option<PageClass> {
+        self.classes.lock().unwrap().get(url).copied()
+    }
+
+    /// Returns every `(url, class)` pair, by URL.
+    pub fn pairs(&self) -> Vec<(String, PageClass)> {
+        self.classes.lock().unwrap().iter().map(|(url, class)| (url.clone(), *class)).collect()
+    }
+
+    // Method to forget every class
+    pub fn clear(&self) {
+        self.classes.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages_are_classified_by_structure() {
+        let items: String = (1..=8).map(|i| format!("<div class='card'><a href='/p/{0}'>Item {0}</a><span>$9</span></div>", i)).collect();
+        let nav: String = (1..=20).map(|i| format!("<li><a href='/c/{0}'>Cat {0}</a></li>", i)).collect();
+        let listing = format!("<nav><ul>{}</ul></nav><h1>Shoes</h1>{}", nav, items);
+        assert_eq!(classify_page(&listing, "https://shop.test/shoes", 200), PageClass::Listing);
+        assert_eq!(classify_page(&listing, "https://shop.test/find?q=shoes", 200), PageClass::SearchResults);
+
+        let article = format!("<nav><ul>{}</ul></nav><h1>Review</h1><p>{}</p>", nav, "Long text. ".repeat(100));
+        assert_eq!(classify_page(&article, "https://shop.test/blog/review", 200), PageClass::Detail);
+        let product = r#"<script type="application/ld+json">{"@type": "Product", "name": "Boot"}</script><h1>Boot</h1><p>Warm.</p>"#;
+        assert_eq!(classify_page(product, "https://shop.test/p/1", 200), PageClass::Detail);
+
+        assert_eq!(classify_page(&article, "https://shop.test/blog/review", 503), PageClass::Error);
+        assert_eq!(classify_page("<p>Hello</p>", "https://shop.test/", 200), PageClass::Other);
+    }
+}
@@ -0,0 +1,273 @@
+// src/compare.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs::{read, read_dir};
+use std::io::{ErrorKind, Result as IoResult};
+use std::path::{Path, PathBuf};
+
+use crate::pages::read_hashed_page_index;
+use crate::{page_file, read_findings, run, read_manifest, sha256_hex, Finding, FindingKind, RunManifest, FINDINGS_FILE, MANIFEST_FILE, PAGE_INDEX_FILE};
+
+/// A media file whose content differs between two crawls.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaChange {
+    pub url: String,
+    pub old_sha256: String,
+    pub new_sha256: String,
+}
+
+/// What changed between two crawl outputs, see `compare_crawls`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrawlComparison {
+    pub new_urls: Vec<String>,
+    pub removed_urls: Vec<String>,
+    pub changed_pages: Vec<String>,     // Pages in both crawls whose extracted content differs
+    pub new_findings: Vec<Finding>,     // Findings of the new crawl the old one did not make
+    pub changed_media: Vec<MediaChange>,
+}
+
+impl CrawlComparison {
+    pub fn is_empty(&self) -> bool {
+        self.new_urls.is_empty() && self.removed_urls.is_empty() && self.changed_pages.is_empty() && self.new_findings.is_empty() && self.changed_media.is_empty()
+    }
+}
+
+impl fmt::Display for CrawlComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Crawl Comparison - New URLs: {}, Removed URLs: {}, Changed Pages: {}, New Findings: {}, Changed Media: {}",
+            self.new_urls.len(),
+            self.removed_urls.len(),
+            self.changed_pages.len(),
+            self.new_findings.len(),
+            self.changed_media.len()
+        )?;
+        for url in &self.new_urls {
+            writeln!(f, "+ {}", url)?;
+        }
+        for url in &self.removed_urls {
+            writeln!(f, "- {}", url)?;
+        }
+        for url in &self.changed_pages {
+            writeln!(f, "~ {}", url)?;
+        }
+        for finding in &self.new_findings {
+            writeln!(f, "! {}", finding)?;
+        }
+        for change in &self.changed_media {
+            writeln!(f, "# {} ({} -> {})", change.url, &change.old_sha256[..12.min(change.old_sha256.len())], &change.new_sha256[..12.min(change.new_sha256.len())])?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two crawl outputs, e.g. yesterday's and today's crawl of a monitored site.
+///
+/// Each side is an output directory, or the `run_manifest.json` of a run (its output directory is
+/// then read from the manifest). Pages are matched by URL through the `pages.tsv` index of every
+/// domain directory, and a page counts as changed when its extracted content file differs. Pages
+/// both crawls indexed with a crawl database hash are compared by that hash instead, so pages an
+/// incremental crawl skipped as unchanged count as unchanged.
+/// Findings are matched by type, URL and evidence, ignoring when they were made, and media by URL
+/// through the `manifest.jsonl` files of the downloads.
+///
+/// # Arguments
+///
+/// * `old` - The earlier crawl output.
+/// * `new` - The later crawl output.
+///
+/// # Returns
+///
+/// The `CrawlComparison`, with every list sorted; an error if a run manifest cannot be read.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::compare_crawls;
+/// # use std::path::Path;
+/// # fn example() -> std::io::Result<()> {
+/// let diff = compare_crawls(Path::new("./crawls/monday"), Path::new("./crawls/tuesday"))?;
+/// print!("{}", diff);
+/// # Ok(())
+/// # }
+/// ```
+pub fn compare_crawls(old: &Path, new: &Path) -> IoResult<CrawlComparison> {
+    compare_outputs(&crawl_root(old)?, &crawl_root(new)?, None, None)
+}
+
+/// Compares two runs by their session IDs, like `compare_crawls`.
+///
+/// Every run keeps its manifest in `runs/{session id}.json` of its output root, which later runs do
+/// not overwrite. The manifests are looked up in `root` and the directories below it, so runs
+/// writing to sibling output directories (e.g. `./crawls/monday`, `./crawls/tuesday`) are found
+/// through their parent. Findings and media are only taken from each run's own session, which keeps
+/// runs sharing an output directory apart; their pages are read from the shared index alike.
+///
+/// # Arguments
+///
+/// * `root` - The directory to look for the run manifests in.
+/// * `old_run` - The session ID of the earlier run.
+/// * `new_run` - The session ID of the later run.
+///
+/// # Returns
+///
+/// The `CrawlComparison`; a `NotFound` error if the manifest of a run is not found.
+pub fn compare_runs(root: &Path, old_run: &str, new_run: &str) -> IoResult<CrawlComparison> {
+    let output_of = |session_id: &str| {
+        let manifest_file = run::session_manifest_file(session_id);
+        let manifest = walk_dirs(root).into_iter().map(|dir| dir.join(&manifest_file)).find(|path| path.is_file());
+        match manifest {
+            Some(manifest) => Ok(RunManifest::read(&manifest)?.outputs.output_dir),
+            None => Err(std::io::Error::new(ErrorKind::NotFound, format!("No manifest of run '{}' below '{}'", session_id, root.display()))),
+        }
+    };
+    compare_outputs(&output_of(old_run)?, &output_of(new_run)?, Some(old_run), Some(new_run))
+}
+
+// Compares two crawl output roots, keeping only the findings and media of the given sessions
+fn compare_outputs(old: &Path, new: &Path, old_session: Option<&str>, new_session: Option<&str>) -> IoResult<CrawlComparison> {
+    let (old_pages, new_pages) = (page_hashes(old), page_hashes(new));
+    let (old_media, new_media) = (media_hashes(old, old_session), media_hashes(new, new_session));
+
+    let known_findings: HashSet<(FindingKind, String, String)> = read_findings(&old.join(FINDINGS_FILE))?
+        .into_iter()
+        .filter(|finding| in_session(old_session, finding.session_id.as_deref()))
+        .map(|finding| (finding.kind, finding.url, finding.evidence))
+        .collect();
+    let mut new_findings: Vec<Finding> = read_findings(&new.join(FINDINGS_FILE))?
+        .into_iter()
+        .filter(|finding| in_session(new_session, finding.session_id.as_deref()))
+        .filter(|finding| !known_findings.contains(&(finding.kind, finding.url.clone(), finding.evidence.clone())))
+        .collect();
+    new_findings.sort_by(|a, b| (&a.url, a.kind.to_string(), &a.evidence).cmp(&(&b.url, b.kind.to_string(), &b.evidence)));
+    new_findings.dedup_by(|a, b| (a.kind, &a.url, &a.evidence) == (b.kind, &b.url, &b.evidence));
+
+    Ok(CrawlComparison {
+        new_urls: new_pages.keys().filter(|url| !old_pages.contains_key(*url)).cloned().collect(),
+        removed_urls: old_pages.keys().filter(|url| !new_pages.contains_key(*url)).cloned().collect(),
+        changed_pages: new_pages.iter().filter(|(url, page)| old_pages.get(*url).is_some_and(|old_page| old_page.differs_from(page))).map(|(url, _)| url.clone()).collect(),
+        new_findings,
+        changed_media: new_media
+            .iter()
+            .filter_map(|(url, new_sha256)| {
+                let old_sha256 = old_media.get(url).filter(|old_sha256| *old_sha256 != new_sha256)?;
+                Some(MediaChange { url: url.clone(), old_sha256: old_sha256.clone(), new_sha256: new_sha256.clone() })
+            })
+            .collect(),
+    })
+}
+
+// Returns whether an output of the session `session_id` is kept when comparing `session` (every session for `None`)
+fn in_session(session: Option<&str>, session_id: Option<&str>) -> bool {
+    session.is_none() || session_id == session
+}
+
+// Resolves a crawl output given as a directory or as the run manifest of a run
+fn crawl_root(path: &Path) -> IoResult<PathBuf> {
+    if path.is_file() {
+        Ok(RunManifest::read(path)?.outputs.output_dir)
+    } else {
+        Ok(path.to_path_buf())
+    }
+}
+
+// Returns the directories below `root` (itself included), depth first
+fn walk_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if let Ok(entries) = read_dir(&dir) {
+            pending.extend(entries.filter_map(Result::ok).map(|entry| entry.path()).filter(|path| path.is_dir()));
+        }
+        dirs.push(dir);
+    }
+    dirs
+}
+
+// The hashes a page of a crawl is compared by
+struct PageHashes {
+    content_file: String,           // Empty when the crawl stored no content file for the page
+    database: Option<String>,       // From the page index, in incremental crawls
+}
+
+impl PageHashes {
+    fn differs_from(&self, other: &PageHashes) -> bool {
+        match (&self.database, &other.database) {
+            (Some(hash), Some(other_hash)) => hash != other_hash,
+            _ => self.content_file != other.content_file,
+        }
+    }
+}
+
+// Returns the hashes of every indexed page of a crawl, by URL
+fn page_hashes(root: &Path) -> BTreeMap<String, PageHashes> {
+    let mut pages = BTreeMap::new();
+    for dir in walk_dirs(root).into_iter().filter(|dir| dir.join(PAGE_INDEX_FILE).is_file()) {
+        for (_, url, database) in read_hashed_page_index(&dir).unwrap_or_default() {
+            let content_file = read(page_file(&dir, "content", &url)).map(|content| sha256_hex(&content)).unwrap_or_default();
+            pages.insert(url, PageHashes { content_file, database });
+        }
+    }
+    pages
+}
+
+// Returns the SHA-256 of every downloaded file of a crawl, or of one of its sessions, by URL; later
+// manifest entries win
+fn media_hashes(root: &Path, session: Option<&str>) -> BTreeMap<String, String> {
+    let mut media = BTreeMap::new();
+    for dir in walk_dirs(root).into_iter().filter(|dir| dir.join(MANIFEST_FILE).is_file()) {
+        for entry in read_manifest(&dir.join(MANIFEST_FILE)).unwrap_or_default() {
+            if in_session(session, entry.session_id.as_deref()) {
+                media.insert(entry.url, entry.sha256);
+            }
+        }
+    }
+    media
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{append_findings, record_download, record_page};
+    use std::fs::{create_dir_all, write};
+
+    #[tokio::test]
+    async fn test_crawls_are_compared() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let crawl = |name: &str, pages: &[(&str, &str)], findings: &[Finding], image: &[u8]| {
+            let root = temp_dir.path().join(name);
+            let dir = root.join("a.test");
+            create_dir_all(&dir).unwrap();
+            for (url, content) in pages {
+                record_page(&dir, url).unwrap();
+                write(page_file(&dir, "content", url), content).unwrap();
+            }
+            append_findings(&root, findings).unwrap();
+            write(dir.join("logo.png"), image).unwrap();
+            root
+        };
+        let email = Finding::new(FindingKind::Email, "https://a.test/", "ops@a.test");
+        let old = crawl("old", &[("https://a.test/", "Home"), ("https://a.test/old", "Old")], std::slice::from_ref(&email), b"v1");
+        let new = crawl(
+            "new",
+            &[("https://a.test/", "Home, updated"), ("https://a.test/new", "New")],
+            &[email.clone(), Finding::new(FindingKind::OpenDirectory, "https://a.test/backup", "HTTP 200")],
+            b"v2",
+        );
+        for root in [&old, &new] {
+            record_download("https://a.test/logo.png", &root.join("a.test").join("logo.png"), 2).await.unwrap();
+        }
+
+        let diff = compare_crawls(&old, &new).unwrap();
+        assert_eq!(diff.new_urls, vec!["https://a.test/new"]);
+        assert_eq!(diff.removed_urls, vec!["https://a.test/old"]);
+        assert_eq!(diff.changed_pages, vec!["https://a.test/"]);
+        assert_eq!(diff.new_findings.len(), 1);
+        assert_eq!(diff.new_findings[0].kind, FindingKind::OpenDirectory);
+        assert_eq!(diff.changed_media[0].new_sha256, sha256_hex(b"v2"));
+        assert!(compare_crawls(&new, &new).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+// src/css.rs
+
+use reqwest::Client;
+use scraper::{Html, Selector};
+
+use crate::{fetch_script, normalize_link, sha256_hex, ScraperConfig};
+
+// How deep `@import` chains are followed
+const MAX_IMPORT_DEPTH: usize = 5;
+
+/// Extracts the raw targets of every `url(...)` reference in a stylesheet or `style` attribute.
+///
+/// Quoted and unquoted forms are supported; the values are returned as written, unresolved.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::extract_css_urls;
+/// let urls = extract_css_urls(r#"body { background: url("/bg.png") } .icon { background: url(icon.svg) }"#);
+/// assert_eq!(urls, vec!["/bg.png", "icon.svg"]);
+/// ```
+pub fn extract_css_urls(css: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    let lowercase = css.to_ascii_lowercase();
+    let mut rest = 0;
+
+    while let Some(offset) = lowercase[rest..].find("url(") {
+        let start = rest + offset + 4;
+        let Some(length) = css[start..].find(')') else { break };
+        let value = css[start..start + length].trim().trim_matches(|c| c == '"' || c == '\'').trim();
+        if !value.is_empty() {
+            urls.push(value.to_string());
+        }
+        rest = start + length + 1;
+    }
+    urls
+}
+
+/// Collects the absolute URLs referenced from the `<style>` blocks and `style` attributes of a page,
+/// without duplicates.
+pub fn collect_css_urls(document: &Html, base_url: &str) -> Vec<String> {
+    let style_selector = Selector::parse("style").unwrap();
+    let attribute_selector = Selector::parse("[style]").unwrap();
+
+    let blocks = document.select(&style_selector).map(|style| style.text().collect::<String>());
+    let attributes = document
+        .select(&attribute_selector)
+        .filter_map(|element| element.value().attr("style").map(str::to_string));
+
+    let mut urls = Vec::new();
+    for css in blocks.chain(attributes) {
+        for css_url in extract_css_urls(&css) {
+            let absolute_url = if css_url.starts_with("data:") { css_url } else { normalize_link(&css_url, base_url) };
+            if !urls.contains(&absolute_url) {
+                urls.push(absolute_url);
+            }
+        }
+    }
+    urls
+}
+
+/// Extracts the raw targets of the `@import` rules of a stylesheet, in both the
+/// `@import url(...)` and the `@import "..."` forms.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::extract_css_imports;
+/// let imports = extract_css_imports(r#"@import url("base.css"); @IMPORT 'print.css' print; a { color: red }"#);
+/// assert_eq!(imports, vec!["base.css", "print.css"]);
+/// ```
+pub fn extract_css_imports(css: &str) -> Vec<String> {
+    let mut imports = Vec::new();
+    let lowercase = css.to_ascii_lowercase();
+    let mut rest = 0;
+
+    while let Some(offset) = lowercase[rest..].find("@import") {
+        let start = rest + offset + 7;
+        let rule = css[start..].trim_start();
+        let rule_start = css.len() - rule.len();
+        rest = start;
+
+        let (value, length) = if rule.get(..4).is_some_and(|prefix| prefix.eq_ignore_ascii_case("url(")) {
+            let Some(end) = rule.find(')') else { break };
+            (rule[4..end].trim().trim_matches(|c| c == '"' || c == '\'').trim(), end + 1)
+        } else if let Some(quote) = rule.chars().next().filter(|c| *c == '"' || *c == '\'') {
+            let Some(end) = rule[1..].find(quote) else { break };
+            (&rule[1..end + 1], end + 2)
+        } else {
+            continue;
+        };
+        if !value.is_empty() {
+            imports.push(value.to_string());
+        }
+        rest = rule_start + length;
+    }
+    imports
+}
+
+/// Collects the absolute URLs of the stylesheets a page links with `<link rel="stylesheet">` or
+/// imports from its `<style>` blocks, without duplicates.
+pub fn collect_stylesheet_urls(document: &Html, base_url: &str) -> Vec<String> {
+    let link_selector = Selector::parse(r#"link[rel~="stylesheet" i][href]"#).unwrap();
+    let style_selector = Selector::parse("style").unwrap();
+
+    let linked = document.select(&link_selector).filter_map(|link| link.value().attr("href")).map(str::to_string);
+    let imported = document
+        .select(&style_selector)
+        .flat_map(|style| extract_css_imports(&style.text().collect::<String>()));
+
+    let mut urls = Vec::new();
+    for href in linked.chain(imported) {
+        let absolute_url = normalize_link(&href, base_url);
+        if !absolute_url.is_empty() && !urls.contains(&absolute_url) {
+            urls.push(absolute_url);
+        }
+    }
+    urls
+}
+
+/// A fetched stylesheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stylesheet {
+    pub url: String,
+    pub css: String,
+}
+
+/// The stylesheets fetched for a page and the assets (fonts, background images, ...) they reference.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StylesheetAssets {
+    pub stylesheets: Vec<Stylesheet>,
+    pub assets: Vec<String>,    // Absolute URLs, or `data:` URIs
+}
+
+/// Fetches stylesheets, following their `@import` chains, and collects the `url()` assets they
+/// reference, resolved against the stylesheet that references them.
+///
+/// Stylesheets already seen by the config's `stylesheet_cache`, by URL or by content, are skipped,
+/// so shared stylesheets are handled once per crawl; stylesheets larger than `max_script_bytes`
+/// are skipped as well.
+///
+/// # Arguments
+///
+/// * `stylesheet_urls` - The stylesheets of a page, e.g. from `collect_stylesheet_urls`.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `config` - An optional reference to a `ScraperConfig`.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::fetch_stylesheet_assets;
+/// # use reqwest::Client;
+/// # async fn example(client: Client) {
+/// let found = fetch_stylesheet_assets(&["https://example.com/site.css".to_string()], &client, None).await;
+/// for asset in &found.assets {
+///     println!("Asset: {}", asset);
+/// }
+/// # }
+/// ```
+pub async fn fetch_stylesheet_assets(stylesheet_urls: &[String], client: &Client, config: Option<&ScraperConfig>) -> StylesheetAssets {
+    let default_config = ScraperConfig::default();
+    let config = config.unwrap_or(&default_config);
+    let mut found = StylesheetAssets::default();
+    let mut pending: Vec<(String, usize)> = stylesheet_urls.iter().rev().map(|url| (url.clone(), 0)).collect();
+
+    while let Some((sheet_url, depth)) = pending.pop() {
+        if !config.is_url_allowed(&sheet_url) || !config.stylesheet_cache().insert_url(&sheet_url) {
+            continue;
+        }
+
+        let css = match fetch_script(client, &sheet_url, config).await {
+            Ok(Some(css)) => css,
+            Ok(None) => {
+                console!("Skipping stylesheet larger than {} bytes: {}", config.max_script_bytes(), sheet_url);
+                continue;
+            }
+            Err(error_message) => {
+                console_error!("{}", error_message);
+                config.log_error(&error_message);
+                continue;
+            }
+        };
+        if !config.stylesheet_cache().insert_hash(&sha256_hex(css.as_bytes())) {
+            continue;
+        }
+
+        let imports: Vec<String> = extract_css_imports(&css).iter().map(|import| normalize_link(import, &sheet_url)).collect();
+        for css_url in extract_css_urls(&css) {
+            let absolute_url = if css_url.starts_with("data:") { css_url } else { normalize_link(&css_url, &sheet_url) };
+            if !absolute_url.is_empty() && !imports.contains(&absolute_url) && !found.assets.contains(&absolute_url) {
+                found.assets.push(absolute_url);
+            }
+        }
+        if depth < MAX_IMPORT_DEPTH {
+            pending.extend(imports.into_iter().filter(|import| !import.is_empty()).rev().map(|import| (import, depth + 1)));
+        }
+        found.stylesheets.push(Stylesheet { url: sheet_url, css });
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{http_response, serve};
+
+    #[test]
+    fn test_collect_css_urls() {
+        let html = r#"
+            <style>.hero { background-image: URL('/img/hero.jpg'); }</style>
+            <div style="background: url(data:image/gif;base64,R0lGODlhAQABAAAAACw=)"></div>
+            <span style="background: url('/img/hero.jpg')"></span>"#;
+        let document = Html::parse_document(html);
+
+        assert_eq!(
+            collect_css_urls(&document, "https://example.com/page"),
+            vec!["https://example.com/img/hero.jpg", "data:image/gif;base64,R0lGODlhAQABAAAAACw="]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stylesheet_imports_and_assets() {
+        let base = serve(|request| {
+            let body = if request.starts_with("GET /css/site.css") {
+                "@import 'theme/dark.css'; body { background: url(../img/bg.png) }"
+            } else if request.starts_with("GET /css/theme/dark.css") {
+                "@import url(/css/site.css); @font-face { src: url('fonts/x.woff2') format('woff2') }"
+            } else {
+                ""
+            };
+            http_response("200 OK", &[("Content-Type", "text/css")], body.as_bytes())
+        })
+        .await;
+
+        let html = r#"<link rel="Stylesheet" href="/css/site.css"><style>@import "/css/site.css";</style>"#;
+        let sheets = collect_stylesheet_urls(&Html::parse_document(html), &base);
+        assert_eq!(sheets, vec![format!("{}/css/site.css", base)]);
+
+        let config = ScraperConfig::default();
+        let found = fetch_stylesheet_assets(&sheets, &Client::new(), Some(&config)).await;
+        assert_eq!(found.stylesheets.len(), 2);
+        assert_eq!(found.assets, vec![format!("{}/img/bg.png", base), format!("{}/css/theme/fonts/x.woff2", base)]);
+        assert!(fetch_stylesheet_assets(&sheets, &Client::new(), Some(&config)).await.stylesheets.is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+// src/deadletter.rs
+
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Result as IoResult, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the dead-letter list written into the output directory at the end of a crawl.
+pub const DEAD_LETTER_FILE: &str = "dead_letters.jsonl";
+
+/// What kind of request a dead letter is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeadLetterKind {
+    Page,
+    Media,
+}
+
+/// A URL that kept failing after every retry, with the reason of its last failure.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub kind: DeadLetterKind,
+    pub url: String,
+    pub path: Option<String>,   // Where a media file was to be saved
+    pub reason: String,
+    pub attempts: u32,
+    pub timestamp: u64,         // Seconds since the Unix epoch
+}
+
+impl DeadLetter {
+    pub fn page(url: &str, reason: &str, attempts: u32) -> Self {
+        DeadLetter { kind: DeadLetterKind::Page, url: url.to_string(), path: None, reason: reason.to_string(), attempts, timestamp: now() }
+    }
+
+    pub fn media(url: &str, path: &Path, reason: &str, attempts: u32) -> Self {
+        DeadLetter {
+            kind: DeadLetterKind::Media,
+            url: url.to_string(),
+            path: Some(path.display().to_string()),
+            reason: reason.to_string(),
+            attempts,
+            timestamp: now(),
+        }
+    }
+}
+
+/// Collects the dead letters of a crawl until they are persisted.
+#[derive(Debug, Default)]
+pub struct DeadLetterQueue {
+    entries: Mutex<Vec<DeadLetter>>,
+}
+
+impl DeadLetterQueue {
+    pub fn push(&self, letter: DeadLetter) {
+        self.entries.lock().unwrap().push(letter);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().unwrap().is_empty()
+    }
+
+    /// Returns a copy of the queued dead letters.
+    pub fn entries(&self) -> Vec<DeadLetter> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Removes and returns every queued dead letter.
+    pub fn take(&self) -> Vec<DeadLetter> {
+        std::mem::take(&mut *self.entries.lock().unwrap())
+    }
+}
+
+/// Appends dead letters to a JSON-lines file.
+pub fn write_dead_letters(path: &Path, letters: &[DeadLetter]) -> IoResult<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for letter in letters {
+        writeln!(file, "{}", serde_json::to_string(letter)?)?;
+    }
+    Ok(())
+}
+
+// Replaces the contents of a dead-letter file with `letters`, writing them to a temporary file
+// first so the file is never left truncated
+pub(crate) fn replace_dead_letters(path: &Path, letters: &[DeadLetter]) -> IoResult<()> {
+    let temp_path = path.with_extension("tmp");
+    let _ = std::fs::remove_file(&temp_path);
+    write_dead_letters(&temp_path, letters)?;
+    std::fs::rename(temp_path, path)
+}
+
+/// Reads a dead-letter file, skipping lines that cannot be parsed.
+pub fn read_dead_letters(path: &Path) -> IoResult<Vec<DeadLetter>> {
+    let file = std::fs::File::open(path)?;
+    let mut letters = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(letter) = serde_json::from_str(&line?) {
+            letters.push(letter);
+        }
+    }
+    Ok(letters)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_letters_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(DEAD_LETTER_FILE);
+        let queue = DeadLetterQueue::default();
+        queue.push(DeadLetter::page("https://a.test/", "HTTP 503 Service Unavailable", 3));
+        queue.push(DeadLetter::media("https://a.test/v.mp4", Path::new("out/v.mp4"), "connection reset", 4));
+
+        write_dead_letters(&path, &queue.take()).unwrap();
+        assert!(queue.is_empty());
+
+        let letters = read_dead_letters(&path).unwrap();
+        assert_eq!(letters.len(), 2);
+        assert_eq!(letters[0].kind, DeadLetterKind::Page);
+        assert_eq!(letters[1].path.as_deref(), Some("out/v.mp4"));
+    }
+}
@@ -0,0 +1,193 @@
+// src/dedup.rs
+
+use reqwest::Url;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+// Query parameters that only track where a visitor came from, never what a page shows
+const TRACKING_PARAMS: [&str; 6] = ["gclid", "fbclid", "msclkid", "mc_cid", "mc_eid", "_ga"];
+
+/// Decides which pages of a crawl count as duplicates and are skipped.
+///
+/// The crawler asks `seen_url` before requesting a URL and `seen_content` before extracting a
+/// fetched page. Policies remember what they have seen across every crawl sharing the config,
+/// like the script cache; `Scraper::reset` calls `reset`.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{DedupPolicy, ScraperConfig};
+/// # use std::collections::HashSet;
+/// # use std::sync::{Arc, Mutex};
+/// // Treat every product variant (`/product/42?color=red`) as the same page
+/// #[derive(Default)]
+/// struct IgnoreQuery(Mutex<HashSet<String>>);
+///
+/// impl DedupPolicy for IgnoreQuery {
+///     fn seen_url(&self, url: &str) -> bool {
+///         let key = url.split(['?', '#']).next().unwrap_or(url).to_string();
+///         !self.0.lock().unwrap().insert(key)
+///     }
+///
+///     fn forget_url(&self, url: &str) {
+///         self.0.lock().unwrap().remove(url.split(['?', '#']).next().unwrap_or(url));
+///     }
+///
+///     fn reset(&self) {
+///         self.0.lock().unwrap().clear();
+///     }
+/// }
+///
+/// let mut config = ScraperConfig::default();
+/// config.set_dedup_policy(Arc::new(IgnoreQuery::default()));
+/// ```
+pub trait DedupPolicy: Send + Sync {
+    /// Marks a URL as crawled, returning `true` if it (or a URL the policy considers equal) was crawled before.
+    fn seen_url(&self, url: &str) -> bool;
+
+    /// Marks a page body as crawled by its SHA-256 hex digest, returning `true` if it duplicates
+    /// an earlier page. By default content is never a duplicate.
+    fn seen_content(&self, url: &str, content_hash: &str) -> bool {
+        let _ = (url, content_hash);
+        false
+    }
+
+    /// Forgets a URL so it can be crawled again, e.g. when dead letters are re-driven.
+    fn forget_url(&self, url: &str);
+
+    /// Forgets everything seen so far.
+    fn reset(&self);
+}
+
+/// The default policy: a URL is a duplicate only if exactly the same URL was crawled.
+#[derive(Debug, Default)]
+pub struct ExactUrlDedup {
+    urls: Mutex<HashSet<String>>,
+}
+
+impl DedupPolicy for ExactUrlDedup {
+    fn seen_url(&self, url: &str) -> bool {
+        !self.urls.lock().unwrap().insert(url.to_string())
+    }
+
+    fn forget_url(&self, url: &str) {
+        self.urls.lock().unwrap().remove(url);
+    }
+
+    fn reset(&self) {
+        self.urls.lock().unwrap().clear();
+    }
+}
+
+/// Compares URLs by their `canonicalize_url` form, so `HTTP://Example.com/a/?b=2&a=1#top` and
+/// `http://example.com/a?a=1&b=2` are the same page.
+#[derive(Debug, Default)]
+pub struct CanonicalUrlDedup {
+    urls: Mutex<HashSet<String>>,
+}
+
+impl DedupPolicy for CanonicalUrlDedup {
+    fn seen_url(&self, url: &str) -> bool {
+        !self.urls.lock().unwrap().insert(canonicalize_url(url))
+    }
+
+    fn forget_url(&self, url: &str) {
+        self.urls.lock().unwrap().remove(&canonicalize_url(url));
+    }
+
+    fn reset(&self) {
+        self.urls.lock().unwrap().clear();
+    }
+}
+
+/// Compares URLs exactly, and additionally skips pages whose body is identical to an earlier
+/// page, e.g. the same article served under several paths.
+#[derive(Debug, Default)]
+pub struct ContentHashDedup {
+    urls: ExactUrlDedup,
+    hashes: Mutex<HashSet<String>>,
+}
+
+impl DedupPolicy for ContentHashDedup {
+    fn seen_url(&self, url: &str) -> bool {
+        self.urls.seen_url(url)
+    }
+
+    fn seen_content(&self, _url: &str, content_hash: &str) -> bool {
+        !self.hashes.lock().unwrap().insert(content_hash.to_string())
+    }
+
+    fn forget_url(&self, url: &str) {
+        self.urls.forget_url(url);
+    }
+
+    fn reset(&self) {
+        self.urls.reset();
+        self.hashes.lock().unwrap().clear();
+    }
+}
+
+/// Returns the canonical form of a URL: lowercase scheme and host, no default port, fragment,
+/// trailing slash, or tracking parameters (`utm_*`, `gclid`, `fbclid`, ...), and the remaining
+/// query parameters sorted.
+///
+/// # Returns
+///
+/// The URL unchanged if it cannot be parsed.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::canonicalize_url;
+/// assert_eq!(canonicalize_url("HTTPS://Example.com:443/Docs/?utm_source=x&b=2&a=1#intro"), "https://example.com/Docs?a=1&b=2");
+/// ```
+pub fn canonicalize_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else { return url.to_string() };
+    parsed.set_fragment(None);
+
+    let mut pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| !name.starts_with("utm_") && !TRACKING_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    pairs.sort();
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    let path = parsed.path().to_string();
+    if path.len() > 1 && path.ends_with('/') {
+        parsed.set_path(path.trim_end_matches('/'));
+    }
+    parsed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_built_in_dedup_policies() {
+        let exact = ExactUrlDedup::default();
+        assert!(!exact.seen_url("https://a.test/x"));
+        assert!(exact.seen_url("https://a.test/x"));
+        assert!(!exact.seen_url("https://a.test/x/"));
+        exact.forget_url("https://a.test/x");
+        assert!(!exact.seen_url("https://a.test/x"));
+
+        let canonical = CanonicalUrlDedup::default();
+        assert!(!canonical.seen_url("https://a.test/x?b=2&a=1"));
+        assert!(canonical.seen_url("https://A.test/x/?a=1&b=2&utm_medium=mail#top"));
+        assert!(!canonical.seen_url("https://a.test/x?a=2"));
+        assert_eq!(canonicalize_url("https://a.test"), canonicalize_url("https://a.test/#top"));
+
+        let content = ContentHashDedup::default();
+        assert!(!content.seen_content("https://a.test/1", "abc"));
+        assert!(content.seen_content("https://a.test/2", "abc"));
+        assert!(!exact.seen_content("https://a.test/2", "abc"));
+        content.reset();
+        assert!(!content.seen_content("https://a.test/2", "abc"));
+    }
+}
@@ -0,0 +1,606 @@
+// src/download.rs
+
+use reqwest::{header, Client, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::io::Result as IoResult;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::sleep;
+
+use crate::request::{self, RequestError};
+use crate::{mime_matches, sha256_file, sniff_mime, DeadLetter, ScraperConfig};
+
+/// What to do when the bytes of a download don't match the `Content-Type` the server claimed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MimePolicy {
+    Ignore,     // Keep the file without checking
+    Warn,       // Keep the file, but print and log the mismatch
+    Reject,     // Delete the file and report the download as failed
+}
+
+/// The facts about a completed download handed to a `DownloadValidator` before the file is kept.
+///
+/// The file still lives at its temporary `.part` path while validators run.
+#[derive(Debug)]
+pub struct DownloadCheck<'a> {
+    pub url: &'a str,
+    pub path: &'a Path,
+    pub content_type: Option<&'a str>,
+    pub sniffed_type: Option<&'static str>,
+    pub size: u64,
+}
+
+/// A post-download hook, e.g. a virus scanner: returning `Err(reason)` discards the file.
+pub type DownloadValidator = Arc<dyn Fn(&DownloadCheck) -> Result<(), String> + Send + Sync>;
+
+/// Sidecar state stored next to a partial download, used to resume it with a `Range` request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PartialDownload {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    total_size: Option<u64>,
+}
+
+/// A download moved into place, with the `ETag` it was served with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CompletedDownload {
+    pub size: u64,
+    pub etag: Option<String>,
+}
+
+/// The leading bytes of a file fetched with a `Range` request, and the size of the whole file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SampledDownload {
+    pub size: u64,
+    pub total_size: Option<u64>,    // From `Content-Range`, or `Content-Length` when the range was ignored
+    pub etag: Option<String>,
+}
+
+// Extensions of files too large to download just to catalog them: video containers, archives and disk images
+const SAMPLED_EXTENSIONS: [&str; 20] = [
+    "mp4", "webm", "mov", "m4v", "mkv", "avi", "ogv", "wmv", "flv", "zip", "tar", "gz", "tgz", "bz2", "xz", "7z", "rar", "iso", "dmg", "img",
+];
+
+/// Why a single download attempt failed, and whether trying again may help.
+struct AttemptError {
+    error: std::io::Error,
+    retryable: bool,
+}
+
+impl AttemptError {
+    fn retryable(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        AttemptError { error: std::io::Error::other(error), retryable: true }
+    }
+
+    fn fatal(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        AttemptError { error: std::io::Error::other(error), retryable: false }
+    }
+}
+
+impl From<std::io::Error> for AttemptError {
+    fn from(error: std::io::Error) -> Self {
+        AttemptError { error, retryable: false }
+    }
+}
+
+// Turns a request that was not answered into the failure of an attempt, dead-lettering hosts over quota
+fn request_failure(error: RequestError, media_url: &str, file_path: &Path, config: &ScraperConfig) -> AttemptError {
+    match error {
+        RequestError::Failed(e) => AttemptError::retryable(e),
+        RequestError::QuotaExhausted(_) => {
+            config.dead_letters().push(DeadLetter::media(media_url, file_path, "byte quota exhausted", 0));
+            AttemptError::fatal(error.to_string())
+        }
+        RequestError::OutOfScope => AttemptError::fatal(error.to_string()),
+    }
+}
+
+/// Returns the path of the partial file a download streams into before it completes.
+pub(crate) fn part_path(file_path: &Path) -> PathBuf {
+    let mut name = file_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    file_path.with_file_name(name)
+}
+
+/// Returns the path of the sidecar state file describing a partial download.
+pub(crate) fn state_path(file_path: &Path) -> PathBuf {
+    let mut name = file_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part.state");
+    file_path.with_file_name(name)
+}
+
+/// Downloads `media_url` to `file_path`, resuming an interrupted earlier download when possible.
+///
+/// The body is streamed into `<file>.part` next to a `<file>.part.state` sidecar holding the
+/// URL and validators (`ETag`/`Last-Modified`). When an attempt fails midway, the partial file
+/// is kept and the next attempt (or the next call) sends `Range: bytes=<len>-` with `If-Range`,
+/// appending the rest if the server answers `206 Partial Content` and starting over otherwise.
+/// The file is only moved to `file_path` once complete. Every chunk received is accounted to
+/// the config's `BandwidthLimiter`, which sleeps as needed to honor the bandwidth caps.
+///
+/// # Returns
+///
+/// The size of the completed file in bytes, and its `ETag`.
+pub(crate) async fn download_resumable(
+    client: &Client,
+    media_url: &str,
+    file_path: &Path,
+    config: &ScraperConfig,
+) -> IoResult<CompletedDownload> {
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut attempt = 0;
+    loop {
+        match download_attempt(client, media_url, file_path, config).await {
+            Ok(download) => return Ok(download),
+            Err(failure) if failure.retryable && attempt < config.download_retries() => {
+                attempt += 1;
+                console_error!(
+                    "Download of '{}' interrupted ({}), resuming (attempt {} of {})",
+                    media_url,
+                    failure.error,
+                    attempt,
+                    config.download_retries()
+                );
+                sleep(Duration::from_millis(500 * attempt as u64)).await;
+            }
+            Err(failure) if failure.retryable => {
+                let reason = failure.error.to_string();
+                config.dead_letters().push(DeadLetter::media(media_url, file_path, &reason, attempt + 1));
+                return Err(failure.error);
+            }
+            Err(failure) => return Err(failure.error),
+        }
+    }
+}
+
+async fn download_attempt(
+    client: &Client,
+    media_url: &str,
+    file_path: &Path,
+    config: &ScraperConfig,
+) -> Result<CompletedDownload, AttemptError> {
+    let part = part_path(file_path);
+    let state_file = state_path(file_path);
+
+    // Only resume a partial file that belongs to the same URL
+    let mut previous = read_state(&state_file).await.filter(|state| state.url == media_url);
+    let mut resume_from = match previous {
+        Some(_) => tokio::fs::metadata(&part).await.map(|m| m.len()).unwrap_or(0),
+        None => 0,
+    };
+
+    let host = request::host_of(media_url);
+    let mut response = loop {
+        let resume = previous.as_ref().filter(|_| resume_from > 0);
+        let sent = request::send(client, Method::GET, media_url, config, |mut request| {
+            if let Some(state) = resume {
+                request = request.header(header::RANGE, format!("bytes={}-", resume_from));
+                if let Some(validator) = state.etag.as_ref().or(state.last_modified.as_ref()) {
+                    request = request.header(header::IF_RANGE, validator);
+                }
+            }
+            request
+        });
+        let response = sent.await.map_err(|e| request_failure(e, media_url, file_path, config))?;
+
+        // A partial answer that does not continue the partial file cannot be appended to it: start over without a range
+        if response.status() == StatusCode::PARTIAL_CONTENT && resume_from > 0 && content_range_start(&response) != Some(resume_from) {
+            console_error!("Restarting download of '{}': the server answered another range than bytes={}-", media_url, resume_from);
+            discard_partial(&part, &state_file).await;
+            (previous, resume_from) = (None, 0);
+            continue;
+        }
+        break response;
+    };
+    let status = response.status();
+
+    let append = match status {
+        StatusCode::PARTIAL_CONTENT if resume_from > 0 => true,
+        StatusCode::RANGE_NOT_SATISFIABLE if resume_from > 0 => {
+            // The partial file already holds the whole resource if it matches the recorded size
+            if previous.as_ref().and_then(|state| state.total_size) == Some(resume_from) {
+                validate(media_url, &part, &state_file, None, resume_from, config).await?;
+                let etag = previous.and_then(|state| state.etag);
+                return finish(&part, &state_file, file_path, CompletedDownload { size: resume_from, etag }).await;
+            }
+            discard_partial(&part, &state_file).await;
+            return Err(AttemptError::retryable(format!("stale partial download for '{}'", media_url)));
+        }
+        status if status.is_success() => false,
+        status if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS => {
+            return Err(AttemptError::retryable(format!("status code {}", status)));
+        }
+        status => return Err(AttemptError::fatal(format!("status code {}", status))),
+    };
+
+    let header_value = |name: header::HeaderName| {
+        response.headers().get(name).and_then(|v| v.to_str().ok()).map(|v| v.to_string())
+    };
+    let content_type = header_value(header::CONTENT_TYPE);
+    let total_size = if append {
+        content_range_total(&response)
+    } else {
+        response.content_length()
+    };
+    let state = PartialDownload {
+        url: media_url.to_string(),
+        etag: header_value(header::ETAG),
+        last_modified: header_value(header::LAST_MODIFIED),
+        total_size,
+    };
+    tokio::fs::write(&state_file, serde_json::to_vec(&state).map_err(std::io::Error::other)?).await?;
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&part)
+        .await?;
+
+    let mut written = if append { resume_from } else { 0 };
+    while let Some(chunk) = response.chunk().await.map_err(AttemptError::retryable)? {
+        // The quota is checked between chunks too, so one large body cannot overrun it
+        if config.host_quota_exhausted(&host) {
+            return Err(request_failure(RequestError::QuotaExhausted(host), media_url, file_path, config));
+        }
+        file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+        config.metrics().record_traffic(&host, 0, chunk.len() as u64);
+        config.bandwidth_limiter().consume(&host, chunk.len() as u64).await;
+    }
+    file.flush().await?;
+    drop(file);
+
+    if let Some(total) = total_size {
+        if written < total {
+            return Err(AttemptError::retryable(format!("received {} of {} bytes", written, total)));
+        }
+    }
+    validate(media_url, &part, &state_file, content_type.as_deref(), written, config).await?;
+    finish(&part, &state_file, file_path, CompletedDownload { size: written, etag: state.etag }).await
+}
+
+// Applies the MIME policy and the user validator to a completed partial file, discarding it on rejection
+async fn validate(
+    media_url: &str,
+    part: &Path,
+    state_file: &Path,
+    content_type: Option<&str>,
+    size: u64,
+    config: &ScraperConfig,
+) -> Result<(), AttemptError> {
+    let mut head = vec![0u8; 512];
+    let mut file = tokio::fs::File::open(part).await?;
+    let read = file.read(&mut head).await?;
+    drop(file);
+    let sniffed_type = sniff_mime(&head[..read]);
+
+    if config.mime_policy() != MimePolicy::Ignore && !mime_matches(content_type, sniffed_type) {
+        let message = format!(
+            "Content of '{}' looks like {} but was served as {}",
+            media_url,
+            sniffed_type.unwrap_or("unknown"),
+            content_type.unwrap_or("unknown")
+        );
+        if config.mime_policy() == MimePolicy::Reject {
+            discard_partial(part, state_file).await;
+            return Err(AttemptError::fatal(message));
+        }
+        console_error!("{}", message);
+        config.log_error(&message);
+    }
+
+    if let Some(validator) = config.download_validator() {
+        let check = DownloadCheck {
+            url: media_url,
+            path: part,
+            content_type,
+            sniffed_type,
+            size,
+        };
+        if let Err(reason) = validator(&check) {
+            discard_partial(part, state_file).await;
+            return Err(AttemptError::fatal(format!("rejected by download validator: {}", reason)));
+        }
+    }
+    Ok(())
+}
+
+// Moves a completed partial file into place and removes its sidecar
+async fn finish(part: &Path, state_file: &Path, file_path: &Path, download: CompletedDownload) -> Result<CompletedDownload, AttemptError> {
+    tokio::fs::rename(part, file_path).await?;
+    let _ = tokio::fs::remove_file(state_file).await;
+    Ok(download)
+}
+
+/// Returns whether `media_url` names a video or archive, which `ScraperConfig::set_media_sample_size`
+/// samples instead of downloading.
+pub(crate) fn is_sampled(media_url: &str) -> bool {
+    let path = media_url.split(['?', '#']).next().unwrap_or("");
+    let file_name = path.rsplit('/').next().unwrap_or("");
+    file_name.rsplit_once('.').is_some_and(|(_, extension)| SAMPLED_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+/// Saves the first `sample_size` bytes of `media_url` to `file_path`, enough for its magic bytes and
+/// container metadata, with a `Range: bytes=0-<sample_size - 1>` request. Servers that ignore the
+/// range get their body cut off once the sample is read. The sample goes through the MIME policy
+/// and the `DownloadValidator` like a full download.
+pub(crate) async fn download_sample(
+    client: &Client,
+    media_url: &str,
+    file_path: &Path,
+    sample_size: u64,
+    config: &ScraperConfig,
+) -> IoResult<SampledDownload> {
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let part = part_path(file_path);
+    let state_file = state_path(file_path);
+
+    let host = request::host_of(media_url);
+    let range = format!("bytes=0-{}", sample_size.saturating_sub(1));
+    let sent = request::send(client, Method::GET, media_url, config, |request| request.header(header::RANGE, range));
+    let mut response = sent.await.map_err(|e| request_failure(e, media_url, file_path, config).error)?;
+    let total_size = match response.status() {
+        StatusCode::PARTIAL_CONTENT => content_range_total(&response),
+        status if status.is_success() => response.content_length(),
+        status => return Err(std::io::Error::other(format!("status code {}", status))),
+    };
+    let header_value = |name: header::HeaderName| response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let content_type = header_value(header::CONTENT_TYPE);
+    let etag = header_value(header::ETAG);
+
+    let mut file = tokio::fs::File::create(&part).await?;
+    let mut written = 0;
+    while written < sample_size {
+        let Some(chunk) = response.chunk().await.map_err(std::io::Error::other)? else {
+            break;
+        };
+        if config.host_quota_exhausted(&host) {
+            return Err(request_failure(RequestError::QuotaExhausted(host), media_url, file_path, config).error);
+        }
+        let keep = chunk.len().min((sample_size - written) as usize);
+        file.write_all(&chunk[..keep]).await?;
+        written += keep as u64;
+        config.metrics().record_traffic(&host, 0, chunk.len() as u64);
+        config.bandwidth_limiter().consume(&host, chunk.len() as u64).await;
+    }
+    file.flush().await?;
+    drop(file);
+    // Dropping the response closes the connection instead of reading the rest of the body
+    drop(response);
+
+    validate(media_url, &part, &state_file, content_type.as_deref(), written, config).await.map_err(|failure| failure.error)?;
+    tokio::fs::rename(&part, file_path).await?;
+    Ok(SampledDownload { size: written, total_size, etag })
+}
+
+/// Returns the size of the file at `file_path` when an earlier run downloaded it from `media_url`
+/// and neither side changed since: the file still has the size and SHA-256 of its manifest entry,
+/// and a `HEAD` request answers with the recorded `ETag` (or, without ETags, the same `Content-Length`).
+pub(crate) async fn unchanged_download(client: &Client, media_url: &str, file_path: &Path, config: &ScraperConfig) -> Option<u64> {
+    // A sample is never a reason to skip downloading the whole file
+    let entry = config.manifest_index().lookup(file_path).filter(|entry| entry.url == media_url && entry.total_size.is_none())?;
+    let local_size = tokio::fs::metadata(file_path).await.ok()?.len();
+    if local_size != entry.size || sha256_file(file_path).await.ok()? != entry.sha256 {
+        return None;
+    }
+
+    let response = request::send(client, Method::HEAD, media_url, config, |request| request).await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+
+    let etag = response.headers().get(header::ETAG).and_then(|value| value.to_str().ok());
+    let unchanged = match (etag, entry.etag.as_deref()) {
+        (Some(etag), Some(recorded)) => etag == recorded,
+        _ => response.content_length() == Some(entry.size),
+    };
+    unchanged.then_some(entry.size)
+}
+
+async fn discard_partial(part: &Path, state_file: &Path) {
+    let _ = tokio::fs::remove_file(part).await;
+    let _ = tokio::fs::remove_file(state_file).await;
+}
+
+async fn read_state(state_file: &Path) -> Option<PartialDownload> {
+    let contents = tokio::fs::read(state_file).await.ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+// Parses the `Content-Range: bytes <start>-<end>/<total>` header of a 206 response
+fn content_range(response: &reqwest::Response) -> Option<(u64, Option<u64>)> {
+    let value = response.headers().get(header::CONTENT_RANGE)?.to_str().ok()?;
+    let range = value.strip_prefix("bytes ")?;
+    let (span, total) = range.split_once('/')?;
+    let start = span.split_once('-')?.0.trim().parse().ok()?;
+    Some((start, total.trim().parse().ok()))
+}
+
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+    content_range(response).map(|(start, _)| start)
+}
+
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+    content_range(response).and_then(|(_, total)| total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{http_response, serve};
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_download_resumes_partial_file() {
+        let base = serve(|request| {
+            if request.to_ascii_lowercase().contains("range: bytes=6-") {
+                http_response("206 Partial Content", &[("Content-Range", "bytes 6-11/12"), ("ETag", "\"v1\"")], b"world!")
+            } else {
+                http_response("200 OK", &[("ETag", "\"v1\"")], b"hello world!")
+            }
+        })
+        .await;
+        let media_url = format!("{}/big.bin", base);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("big.bin");
+
+        // Simulate an earlier download interrupted after six bytes
+        let state = PartialDownload {
+            url: media_url.clone(),
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            total_size: Some(12),
+        };
+        std::fs::write(part_path(&file_path), b"hello ").unwrap();
+        std::fs::write(state_path(&file_path), serde_json::to_vec(&state).unwrap()).unwrap();
+
+        let download = download_resumable(&Client::new(), &media_url, &file_path, &ScraperConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(download, CompletedDownload { size: 12, etag: Some("\"v1\"".to_string()) });
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"hello world!");
+        assert!(!part_path(&file_path).exists());
+        assert!(!state_path(&file_path).exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_restarts_on_wrong_content_range() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let base = serve(move |request| {
+            let range = request.lines().find_map(|line| line.to_ascii_lowercase().strip_prefix("range: ").map(str::to_string));
+            seen.lock().unwrap().push(range.clone());
+            match range.as_deref() {
+                Some("bytes=6-") => http_response("206 Partial Content", &[("Content-Range", "bytes 0-11/12")], b"hello world!"),
+                _ => http_response("200 OK", &[], b"hello world!"),
+            }
+        })
+        .await;
+        let media_url = format!("{}/big.bin", base);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("big.bin");
+
+        let state = PartialDownload { url: media_url.clone(), etag: None, last_modified: None, total_size: Some(12) };
+        std::fs::write(part_path(&file_path), b"hello ").unwrap();
+        std::fs::write(state_path(&file_path), serde_json::to_vec(&state).unwrap()).unwrap();
+
+        let mut config = ScraperConfig::default();
+        config.set_download_retries(0);
+        let download = download_resumable(&Client::new(), &media_url, &file_path, &config).await.unwrap();
+
+        assert_eq!(download.size, 12);
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"hello world!");
+        assert_eq!(*requests.lock().unwrap(), vec![Some("bytes=6-".to_string()), None]);
+    }
+
+    #[tokio::test]
+    async fn test_download_mime_policy_and_validator() {
+        let base = serve(|request| {
+            if request.starts_with("GET /error.png") {
+                http_response("200 OK", &[("Content-Type", "image/png")], b"<html><body>Rate limited</body></html>")
+            } else {
+                http_response("200 OK", &[("Content-Type", "image/png")], b"\x89PNG\r\n\x1a\nimage")
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let client = Client::new();
+
+        let mut config = ScraperConfig::default();
+        config.set_mime_policy(MimePolicy::Reject);
+        let html_path = temp_dir.path().join("error.png");
+        assert!(download_resumable(&client, &format!("{}/error.png", base), &html_path, &config).await.is_err());
+        assert!(!html_path.exists());
+        assert!(!part_path(&html_path).exists());
+
+        config.set_download_validator(Some(Arc::new(|check: &DownloadCheck| {
+            if check.sniffed_type == Some("image/png") && check.size < 100 {
+                Err("suspiciously small".to_string())
+            } else {
+                Ok(())
+            }
+        })));
+        let png_path = temp_dir.path().join("tiny.png");
+        let error = download_resumable(&client, &format!("{}/tiny.png", base), &png_path, &config).await.unwrap_err();
+        assert!(error.to_string().contains("suspiciously small"));
+        assert!(!png_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_restarts_partial_file_of_other_url() {
+        let base = serve(|_| http_response("200 OK", &[], b"fresh")).await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("file.bin");
+
+        let state = PartialDownload {
+            url: "https://elsewhere.test/file.bin".to_string(),
+            etag: None,
+            last_modified: None,
+            total_size: Some(100),
+        };
+        std::fs::write(part_path(&file_path), b"stale bytes").unwrap();
+        std::fs::write(state_path(&file_path), serde_json::to_vec(&state).unwrap()).unwrap();
+
+        let media_url = format!("{}/file.bin", base);
+        download_resumable(&Client::new(), &media_url, &file_path, &ScraperConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"fresh");
+    }
+
+    #[tokio::test]
+    async fn test_download_stops_at_the_host_quota() {
+        let base = serve(|_| http_response("200 OK", &[], &[b'x'; 1_048_576])).await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("big.bin");
+        let mut config = ScraperConfig::default();
+        config.set_host_byte_quota(Some(10_000));
+
+        assert!(download_resumable(&Client::new(), &format!("{}/big.bin", base), &file_path, &config).await.is_err());
+        assert!(!file_path.exists());
+        assert!(config.metrics().traffic("127.0.0.1").received < 1_048_576);
+        assert_eq!(config.dead_letters().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_videos_and_archives_are_sampled() {
+        let base = serve(|request| {
+            if request.starts_with("GET /clip.mp4") && request.to_ascii_lowercase().contains("range: bytes=0-7") {
+                http_response("206 Partial Content", &[("Content-Range", "bytes 0-7/1048576")], b"\0\0\0\x18ftyp")
+            } else {
+                // Servers ignoring the range send the whole body
+                let mut archive = b"PK\x03\x04".to_vec();
+                archive.extend_from_slice(&[0; 100]);
+                http_response("200 OK", &[("Content-Type", "application/zip")], &archive)
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::default();
+        config.set_media_sample_size(Some(8));
+
+        let clip = temp_dir.path().join("clip.mp4");
+        assert_eq!(crate::download_media_with_config(&Client::new(), &format!("{}/clip.mp4", base), &clip, Some(&config)).await.unwrap(), 8);
+        let archive = temp_dir.path().join("backup.zip");
+        let sample = download_sample(&Client::new(), &format!("{}/backup.zip", base), &archive, 8, &config).await.unwrap();
+        assert_eq!((sample.size, sample.total_size), (8, Some(104)));
+        assert_eq!(std::fs::read(&archive).unwrap(), b"PK\x03\x04\0\0\0\0");
+
+        let entries = crate::read_manifest(&crate::manifest_path(temp_dir.path())).unwrap();
+        assert_eq!((entries[0].size, entries[0].total_size), (8, Some(1048576)));
+        assert!(is_sampled("https://a.test/dl/backup.tar.gz?v=2"));
+        assert!(!is_sampled("https://a.test/logo.png"));
+    }
+}
@@ -0,0 +1,213 @@
+// src/embeds.rs
+
+use reqwest::{Client, Method, Url};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::io::Result as IoResult;
+
+use crate::{normalize_link, request, ScraperConfig};
+
+/// Video hosting providers whose embeds are recognised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum EmbedProvider {
+    YouTube,
+    Vimeo,
+}
+
+/// A video embedded in a page through an `<iframe>`, optionally enriched with oEmbed metadata.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VideoEmbed {
+    pub provider: EmbedProvider,
+    pub embed_url: String,      // The iframe source as found in the page
+    pub video_url: String,      // The canonical watch URL of the video
+    pub title: Option<String>,
+    pub author_name: Option<String>,
+    pub thumbnail_url: Option<String>,
+}
+
+impl VideoEmbed {
+    /// Returns the provider's oEmbed endpoint for this video.
+    pub fn oembed_endpoint(&self) -> String {
+        let endpoint = match self.provider {
+            EmbedProvider::YouTube => "https://www.youtube.com/oembed",
+            EmbedProvider::Vimeo => "https://vimeo.com/api/oembed.json",
+        };
+        Url::parse_with_params(endpoint, &[("url", self.video_url.as_str()), ("format", "json")])
+            .map(|url| url.to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize)]
+struct OEmbedResponse {
+    title: Option<String>,
+    author_name: Option<String>,
+    thumbnail_url: Option<String>,
+}
+
+/// Finds YouTube and Vimeo iframes in an HTML page without making any requests.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+/// * `base_url` - The URL of the page, used to resolve relative and protocol-relative sources.
+///
+/// # Returns
+///
+/// A `Vec<VideoEmbed>` with one entry per recognised video, metadata fields left empty.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{extract_video_embeds, EmbedProvider};
+/// let html = r#"<iframe src="//www.youtube.com/embed/dQw4w9WgXcQ"></iframe>"#;
+/// let embeds = extract_video_embeds(html, "https://example.com");
+/// assert_eq!(embeds[0].provider, EmbedProvider::YouTube);
+/// assert_eq!(embeds[0].video_url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+/// ```
+pub fn extract_video_embeds(html: &str, base_url: &str) -> Vec<VideoEmbed> {
+    let document = Html::parse_document(html);
+    let iframe_selector = Selector::parse("iframe[src], iframe[data-src]").unwrap();
+    let mut embeds: Vec<VideoEmbed> = Vec::new();
+
+    for iframe in document.select(&iframe_selector) {
+        let src = match iframe.value().attr("src").or_else(|| iframe.value().attr("data-src")) {
+            Some(src) => src,
+            None => continue,
+        };
+        let embed_url = normalize_link(src, base_url);
+
+        if let Some((provider, video_url)) = identify_video(&embed_url) {
+            if !embeds.iter().any(|embed| embed.video_url == video_url) {
+                embeds.push(VideoEmbed {
+                    provider,
+                    embed_url,
+                    video_url,
+                    title: None,
+                    author_name: None,
+                    thumbnail_url: None,
+                });
+            }
+        }
+    }
+    embeds
+}
+
+/// Fills in the title, author and thumbnail of an embed from the provider's oEmbed endpoint.
+///
+/// # Arguments
+///
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `embed` - The embed to enrich.
+///
+/// # Returns
+///
+/// An `IoResult<()>` indicating whether the metadata could be fetched.
+pub async fn fetch_oembed(client: &Client, embed: &mut VideoEmbed) -> IoResult<()> {
+    fetch_oembed_with_config(client, embed, &ScraperConfig::default()).await
+}
+
+// Same as `fetch_oembed`, sending the request through the crawl's request layer
+async fn fetch_oembed_with_config(client: &Client, embed: &mut VideoEmbed, config: &ScraperConfig) -> IoResult<()> {
+    let endpoint = embed.oembed_endpoint();
+    let response = request::send(client, Method::GET, &endpoint, config, |request| request).await.map_err(|e| std::io::Error::other(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(std::io::Error::other(format!(
+            "oEmbed request to '{}' failed with status {}",
+            endpoint,
+            response.status()
+        )));
+    }
+
+    let body = request::read_body(response, config.max_page_bytes(), config)
+        .await
+        .map_err(std::io::Error::other)?
+        .ok_or_else(|| std::io::Error::other(format!("oEmbed response of '{}' is larger than {} bytes", endpoint, config.max_page_bytes())))?;
+    let metadata: OEmbedResponse = serde_json::from_slice(&body)?;
+    embed.title = metadata.title;
+    embed.author_name = metadata.author_name;
+    embed.thumbnail_url = metadata.thumbnail_url;
+    Ok(())
+}
+
+/// Extracts the video embeds of a page and looks each of them up through oEmbed.
+///
+/// Embeds whose metadata cannot be fetched are still returned, with empty metadata.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+/// * `url` - The URL of the current page being scraped.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::scrape_video_embeds;
+/// # use reqwest::Client;
+/// # async fn example(client: Client) {
+/// let embeds = scrape_video_embeds("<iframe src='https://player.vimeo.com/video/76979871'></iframe>", "https://example.com", &client).await;
+/// println!("{:?}", embeds[0].title);
+/// # }
+/// ```
+pub async fn scrape_video_embeds(html: &str, url: &str, client: &Client) -> Vec<VideoEmbed> {
+    scrape_video_embeds_with_config(html, url, client, &ScraperConfig::default()).await
+}
+
+// Same as `scrape_video_embeds`, logging failures to the config's error log
+pub(crate) async fn scrape_video_embeds_with_config(html: &str, url: &str, client: &Client, config: &ScraperConfig) -> Vec<VideoEmbed> {
+    let mut embeds = extract_video_embeds(html, url);
+    for embed in embeds.iter_mut() {
+        if let Err(e) = fetch_oembed_with_config(client, embed, config).await {
+            let error_message = format!("Failed to fetch oEmbed metadata for '{}': {}", embed.video_url, e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+        }
+    }
+    embeds
+}
+
+// Maps an iframe source to its provider and canonical watch URL
+fn identify_video(embed_url: &str) -> Option<(EmbedProvider, String)> {
+    let url = Url::parse(embed_url).ok()?;
+    let host = url.host_str()?.trim_start_matches("www.");
+    let mut segments = url.path_segments()?.filter(|segment| !segment.is_empty());
+
+    match host {
+        "youtube.com" | "youtube-nocookie.com" => {
+            if segments.next()? != "embed" {
+                return None;
+            }
+            let id = segments.next()?;
+            Some((EmbedProvider::YouTube, format!("https://www.youtube.com/watch?v={}", id)))
+        }
+        "player.vimeo.com" => {
+            if segments.next()? != "video" {
+                return None;
+            }
+            let id = segments.next()?;
+            Some((EmbedProvider::Vimeo, format!("https://vimeo.com/{}", id)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_video_embeds() {
+        let html = r#"
+            <iframe src="https://www.youtube-nocookie.com/embed/abc123?rel=0"></iframe>
+            <iframe data-src="https://player.vimeo.com/video/76979871"></iframe>
+            <iframe src="/widgets/chat"></iframe>"#;
+        let embeds = extract_video_embeds(html, "https://example.com");
+
+        assert_eq!(embeds.len(), 2);
+        assert_eq!(embeds[0].video_url, "https://www.youtube.com/watch?v=abc123");
+        assert_eq!(embeds[1].provider, EmbedProvider::Vimeo);
+        assert_eq!(embeds[1].video_url, "https://vimeo.com/76979871");
+        assert!(embeds[1].oembed_endpoint().starts_with("https://vimeo.com/api/oembed.json?url=https%3A%2F%2Fvimeo.com%2F76979871"));
+    }
+}
@@ -0,0 +1,252 @@
+// src/engine.rs
+
+use reqwest::Client;
+use std::collections::HashSet;
+use std::io::Result as IoResult;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::{crawl_run, finish_crawl, redrive_with_state, run, visit_entry, write_run_manifest, CrawlSummary, FrontierEntry, RunManifest, ScraperConfig};
+
+/// Page counters of a crawl.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrawlStats {
+    pub pages_scraped: usize,
+    pub pages_unchanged: usize,     // Skipped by incremental crawls
+    pub pages_failed: usize,
+    pub links_out_of_scope: usize,  // Rejected by the domain allow/deny lists
+    pub pages_too_large: usize,     // Skipped by the page size and nesting guards
+    pub pages_circuit_open: usize,  // Skipped while their host's circuit was open
+    pub pages_over_quota: usize,    // Skipped once their host used up its byte quota
+    pub pages_duplicate: usize,     // Skipped as duplicates by the `DedupPolicy`
+    pub pages_alternate: usize,     // AMP and mobile versions not stored, see `AlternatePolicy`
+    pub pages_over_budget: usize,   // Skipped once their partition used up its page budget
+}
+
+/// A self-contained crawl job.
+///
+/// Each `Scraper` owns its client, config, visited set, and stats, and writes only to the output
+/// directory and error log of its own config, so several instances can run side by side in one
+/// tokio runtime, each on its own spawned task.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::{Scraper, ScraperConfig};
+/// # use reqwest::Client;
+/// # use std::path::Path;
+/// # async fn example() {
+/// let mut jobs = Vec::new();
+/// for (url, name) in [("https://example.com", "job-a"), ("https://example.org", "job-b")] {
+///     let mut config = ScraperConfig::new(true, 2, None);
+///     config.set_output_dir(&Path::new("./jobs").join(name));
+///     config.set_error_log(&Path::new("./jobs").join(format!("{}.log", name)));
+///
+///     let mut scraper = Scraper::new(Client::new(), config);
+///     jobs.push(tokio::spawn(async move {
+///         scraper.crawl(url).await;
+///         scraper
+///     }));
+/// }
+/// for job in jobs {
+///     let scraper = job.await.unwrap();
+///     println!("{:?}", scraper.stats());
+/// }
+/// # }
+/// ```
+pub struct Scraper {
+    client: Client,
+    config: ScraperConfig,
+    visited: HashSet<String>,
+    stats: CrawlStats,
+    elapsed: Duration,
+    session_id: Option<String>,     // The session of the latest crawl
+}
+
+impl Scraper {
+    pub fn new(client: Client, config: ScraperConfig) -> Self {
+        Scraper { client, config, visited: HashSet::new(), stats: CrawlStats::default(), elapsed: Duration::ZERO, session_id: None }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn config(&self) -> &ScraperConfig {
+        &self.config
+    }
+
+    // Method to adjust the config between crawls
+    pub fn config_mut(&mut self) -> &mut ScraperConfig {
+        &mut self.config
+    }
+
+    pub fn visited(&self) -> &HashSet<String> {
+        &self.visited
+    }
+
+    pub fn stats(&self) -> &CrawlStats {
+        &self.stats
+    }
+
+    /// Returns the summary of every crawl since the instance was created or reset.
+    pub fn summary(&self) -> CrawlSummary {
+        let summary = CrawlSummary::new(&self.stats, self.config.metrics(), self.elapsed);
+        CrawlSummary { session_id: self.session_id.clone(), ..summary }
+    }
+
+    /// Returns the ID of the session of the latest crawl of this instance.
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    // Method to forget all state of earlier crawls of this instance, so the next crawl starts afresh
+    pub fn reset(&mut self) {
+        self.visited.clear();
+        self.stats = CrawlStats::default();
+        self.session_id = None;
+        self.config.dead_letters().take();
+        self.config.metrics().reset();
+        self.config.script_cache().clear();
+        self.config.stylesheet_cache().clear();
+        self.config.header_findings().clear();
+        self.config.parameter_findings().clear();
+        self.config.alternates().clear();
+        self.config.page_classes().clear();
+        self.config.partitions().clear();
+        self.config.manifest_index().clear();
+        self.config.page_index().clear();
+        self.config.dedup_policy().reset();
+        if let Some(breaker) = self.config.circuit_breaker() {
+            breaker.reset();
+        }
+        if let Some(checker) = self.config.external_link_checker() {
+            checker.clear();
+        }
+        if let Some(db) = self.config.crawl_database() {
+            db.lock().unwrap().reset_delta();
+        }
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// Crawls from `url`, and the config's extra seeds, with this instance's config, skipping pages
+    /// it has already visited.
+    ///
+    /// # Returns
+    ///
+    /// The `CrawlSummary` of every crawl of this instance so far.
+    pub async fn crawl(&mut self, url: &str) -> CrawlSummary {
+        let started = Instant::now();
+        self.start_session();
+        run::in_session(self.session_id.clone(), async {
+            let mut manifest = RunManifest::start(url, &self.config);
+            write_run_manifest(&manifest, &self.config);
+            let seeds = std::iter::once(url.to_string()).chain(self.config.seeds().iter().cloned()).collect();
+            crawl_run(seeds, &self.client, &self.config, &mut self.visited, &mut self.stats).await;
+            finish_crawl(&self.client, &self.config).await;
+            self.elapsed += started.elapsed();
+            let summary = self.summary();
+            manifest.finish(&summary);
+            write_run_manifest(&manifest, &self.config);
+            summary
+        })
+        .await
+    }
+
+    // Starts a new session, which the following crawl steps of this instance run in
+    pub(crate) fn start_session(&mut self) {
+        self.session_id = Some(self.config.new_session_id());
+    }
+
+    // Visits one frontier entry with this instance's state, returning its links unless it was skipped
+    pub(crate) async fn visit(&mut self, entry: &FrontierEntry) -> Option<Vec<(String, String)>> {
+        let started = Instant::now();
+        let links = run::in_session(self.session_id.clone(), visit_entry(entry, &self.client, &self.config, &mut self.visited, &mut self.stats)).await;
+        self.elapsed += started.elapsed();
+        links
+    }
+
+    /// Retries the URLs of a dead-letter file with this instance's config; see `redrive_dead_letters`.
+    pub async fn redrive(&mut self, path: &Path) -> IoResult<usize> {
+        run::in_session(self.session_id.clone(), redrive_with_state(path, &self.client, &self.config, &mut self.visited, &mut self.stats)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{http_response, serve};
+
+    #[tokio::test]
+    async fn test_scrapers_run_isolated_on_spawned_tasks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut jobs = Vec::new();
+
+        for (name, body) in [("a", "<p>A</p><a href='http://127.0.0.1:9/'>dead</a>"), ("b", "<p>B</p>")] {
+            let base = serve(move |_| http_response("200 OK", &[], body.as_bytes())).await;
+            let mut config = ScraperConfig::new(true, 1, Some("test-agent".to_string()));
+            config.set_page_retries(0);
+            config.set_output_dir(&temp_dir.path().join(name));
+            config.set_error_log(&temp_dir.path().join(format!("{}.log", name)));
+
+            let mut scraper = Scraper::new(Client::new(), config);
+            jobs.push(tokio::spawn(async move {
+                scraper.crawl(&base).await;
+                scraper
+            }));
+        }
+
+        let a = jobs.remove(0).await.unwrap();
+        let b = jobs.remove(0).await.unwrap();
+        assert_eq!((a.stats().pages_scraped, a.stats().pages_failed), (1, 1));
+        assert_eq!((b.stats().pages_scraped, b.stats().pages_failed), (1, 0));
+        assert_eq!(a.visited().len(), 2);
+        assert_eq!((a.summary().pages_fetched, a.summary().pages_failed), (1, 1));
+
+        for name in ["a", "b"] {
+            let dir = temp_dir.path().join(name).join("127.0.0.1");
+            let index = crate::read_page_index(&dir).unwrap();
+            assert_eq!(index.len(), 1);
+            assert!(crate::page_file(&dir, "content", &index[0].1).exists());
+        }
+        assert!(temp_dir.path().join("a.log").exists());
+        assert!(!temp_dir.path().join("b.log").exists());
+
+        let dead_letters = crate::read_dead_letters(&a.config().dead_letter_path()).unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].url, "http://127.0.0.1:9/");
+        assert!(!b.config().dead_letter_path().exists());
+
+        let manifest = crate::RunManifest::read(&a.config().run_manifest_path()).unwrap();
+        assert!(manifest.seed.starts_with("http://127.0.0.1:"));
+        assert_eq!(manifest.outputs.dead_letters, a.config().dead_letter_path());
+        assert_eq!(manifest.summary, Some(a.summary()));
+        assert!(manifest.finished_at >= Some(manifest.started_at));
+    }
+
+    #[tokio::test]
+    async fn test_reset_forgets_every_earlier_crawl() {
+        let checked = serve(|_| http_response("200 OK", &[], b"")).await.replace("127.0.0.1", "localhost");
+        let page = format!("<p>Home</p><a href='{}/elsewhere'>Elsewhere</a>", checked);
+        let base = serve(move |_| http_response("200 OK", &[], page.as_bytes())).await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_external_link_checker(Some(crate::ExternalLinkChecker::new(1, Duration::ZERO)));
+        config.load_crawl_database(&temp_dir.path().join("crawl_db.json")).unwrap();
+
+        let mut scraper = Scraper::new(Client::new(), config);
+        scraper.crawl(&format!("{}/", base)).await;
+        scraper.config().dead_letters().push(crate::DeadLetter::page("https://a.test/", "HTTP 500", 1));
+        assert!(scraper.session_id().is_some() && !scraper.config().external_link_checker().unwrap().is_empty());
+
+        scraper.reset();
+        assert!(scraper.visited().is_empty() && scraper.session_id().is_none());
+        assert_eq!(scraper.summary().pages_fetched, 0);
+        assert!(scraper.config().dead_letters().is_empty());
+        assert!(scraper.config().external_link_checker().unwrap().is_empty());
+        let db = scraper.config().crawl_database().unwrap().lock().unwrap();
+        assert_eq!(db.delta(), &crate::CrawlDelta::default());
+        assert_eq!(db.len(), 1);
+    }
+}
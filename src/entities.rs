@@ -0,0 +1,127 @@
+// src/entities.rs
+
+use reqwest::Url;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::normalize_link;
+
+/// File name of the entity summary written into the directory of every crawled domain.
+pub const ENTITIES_FILE: &str = "entities.json";
+
+// First path segments of social sites that are pages of the site rather than accounts
+const RESERVED_PATHS: [&str; 16] = [
+    "share", "sharer", "sharer.php", "intent", "home", "login", "signup", "search", "hashtag", "explore", "p", "watch", "embed", "shorts", "results", "feed",
+];
+
+/// A kind of entity aggregated across the pages of a crawl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Email,
+    Phone,          // From `tel:` links
+    SocialHandle,   // `platform:handle`, from links to social profiles
+    Endpoint,       // API endpoints referenced by inline scripts
+}
+
+impl fmt::Display for EntityKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            EntityKind::Email => "email",
+            EntityKind::Phone => "phone number",
+            EntityKind::SocialHandle => "social handle",
+            EntityKind::Endpoint => "endpoint",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One entity of a domain: where it was seen first, and on how many pages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntitySummary {
+    pub kind: EntityKind,
+    pub value: String,
+    pub first_seen: String,     // The first page it was found on
+    pub occurrences: usize,     // Pages it was found on
+}
+
+/// The entities found on the pages of one domain, by kind, then value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainEntities {
+    pub domain: String,
+    pub entities: Vec<EntitySummary>,
+}
+
+impl DomainEntities {
+    /// Returns how many distinct entities of `kind` the domain has.
+    pub fn count(&self, kind: EntityKind) -> usize {
+        self.entities.iter().filter(|entity| entity.kind == kind).count()
+    }
+}
+
+/// Finds the social profiles a page links to, as `platform:handle` (e.g. `twitter:acme`,
+/// `linkedin:company/acme`), each once. Share buttons, searches and single posts are not profiles.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::extract_social_handles;
+/// let html = r#"<a href="https://x.com/acme">X</a> <a href="https://twitter.com/intent/tweet?url=a">Share</a>
+/// <a href="https://www.linkedin.com/company/acme/">LinkedIn</a>"#;
+/// assert_eq!(extract_social_handles(html, "https://acme.test/"), vec!["twitter:acme", "linkedin:company/acme"]);
+/// ```
+pub fn extract_social_handles(html: &str, base_url: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href]").unwrap();
+    let mut handles: Vec<String> = Vec::new();
+    for link in document.select(&selector) {
+        let Some(handle) = social_handle(&normalize_link(link.value().attr("href").unwrap_or(""), base_url)) else { continue };
+        if !handles.contains(&handle) {
+            handles.push(handle);
+        }
+    }
+    handles
+}
+
+// Returns the `platform:handle` of a link to a social profile
+fn social_handle(link: &str) -> Option<String> {
+    let url = Url::parse(link).ok()?;
+    let host = url.host_str()?.to_ascii_lowercase();
+    let host = host.strip_prefix("www.").or_else(|| host.strip_prefix("m.")).unwrap_or(&host);
+    let segments: Vec<&str> = url.path_segments()?.filter(|segment| !segment.is_empty()).collect();
+    let first = *segments.first()?;
+    if RESERVED_PATHS.contains(&first.to_ascii_lowercase().as_str()) {
+        return None;
+    }
+    let platform = host.trim_end_matches(".com");
+    let handle = match host {
+        "twitter.com" | "x.com" => format!("twitter:{}", first),
+        "instagram.com" | "facebook.com" | "github.com" => format!("{}:{}", platform, first),
+        "tiktok.com" | "youtube.com" if first.starts_with('@') => format!("{}:{}", platform, &first[1..]),
+        "youtube.com" if matches!(first, "c" | "channel" | "user") => format!("youtube:{}", segments.get(1)?),
+        "linkedin.com" if matches!(first, "in" | "company") => format!("linkedin:{}/{}", first, segments.get(1)?),
+        _ => return None,
+    };
+    Some(handle.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_social_profiles_are_told_from_share_links() {
+        let html = r#"
+            <a href="https://www.facebook.com/sharer/sharer.php?u=x">Share</a>
+            <a href="https://www.facebook.com/AcmeShop">Facebook</a>
+            <a href="https://m.facebook.com/acmeshop">Facebook (mobile)</a>
+            <a href="https://www.youtube.com/@AcmeTV">YouTube</a>
+            <a href="https://www.youtube.com/watch?v=abc">A video</a>
+            <a href="https://www.instagram.com/p/Cx1/">A post</a>
+            <a href="https://github.com/acme/scraper">Code</a>
+            <a href="https://www.linkedin.com/in/">Incomplete</a>
+            <a href="/about">About</a>"#;
+        assert_eq!(extract_social_handles(html, "https://acme.test/"), vec!["facebook:acmeshop", "youtube:acmetv", "github:acme"]);
+    }
+}
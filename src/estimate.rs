@@ -0,0 +1,181 @@
+// src/estimate.rs
+
+use reqwest::{Client, Method, Url};
+use std::collections::HashSet;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::audit::path_of;
+use crate::{extract_link_records, fetch_robots_txt_with_config, page, request, robots, Frontier, RobotsInfo, ScraperConfig};
+
+/// How many pages `estimate` fetches to sample a site.
+pub const ESTIMATE_SAMPLE_PAGES: usize = 20;
+
+/// The estimated size of a site, see `estimate`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SiteEstimate {
+    pub robots: RobotsInfo,
+    pub sitemap_urls: usize,
+    pub sampled_pages: usize,
+    pub discovered_urls: usize,         // Distinct in-scope URLs seen while sampling, fetched or not
+    pub complete: bool,                 // The sample reached every page it could find
+    pub average_page_bytes: u64,
+    pub estimated_pages: usize,
+    pub estimated_bytes: u64,
+    pub estimated_duration: Duration,   // At one request at a time, honoring the crawl delay
+}
+
+impl fmt::Display for SiteEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Site Estimate - Pages: {}{}, Bytes: {}, Average Page: {} bytes, Duration: {}s, Sitemap URLs: {}, Sampled: {}, Discovered: {}",
+            if self.complete { "" } else { "~" },
+            self.estimated_pages,
+            self.estimated_bytes,
+            self.average_page_bytes,
+            self.estimated_duration.as_secs(),
+            self.sitemap_urls,
+            self.sampled_pages,
+            self.discovered_urls
+        )
+    }
+}
+
+/// Estimates the page count and size of a site before crawling it, to set sensible budgets.
+///
+/// The estimate combines the URL count of the sitemaps listed in `robots.txt` with a short
+/// sampling crawl of up to `ESTIMATE_SAMPLE_PAGES` pages on the same host, breadth first and
+/// never requesting or counting what robots.txt disallows. The page count is the larger of the sitemap count and the
+/// URLs the sample discovered (exact when the sample reached every page), and the byte count
+/// that times the average sampled page size. The duration assumes one request at a time, paced by
+/// the slowest of the sampled response time, the robots.txt crawl delay and the config's
+/// per-host request interval.
+///
+/// # Arguments
+///
+/// * `url` - The base URL of the site.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `config` - An optional reference to `ScraperConfig` for the domain lists, user agent, request limits and sitemap cap.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::estimate;
+/// # use reqwest::Client;
+/// # async fn example(client: Client) {
+/// let estimate = estimate("https://example.com", &client, None).await;
+/// println!("{}", estimate);
+/// # }
+/// ```
+pub async fn estimate(url: &str, client: &Client, config: Option<&ScraperConfig>) -> SiteEstimate {
+    let default_config = ScraperConfig::default();
+    let config = config.unwrap_or(&default_config);
+    let host = Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default();
+    let robots = fetch_robots_txt_with_config(url, client, config).await;
+    let sitemap_urls = robots::fetch_sitemap_urls_with_config(&robots.sitemaps, client, config.max_sitemap_urls(), config).await;
+    let in_scope = |link: &str| {
+        config.is_url_allowed(link) && robots.is_allowed(&path_of(link)) && Url::parse(link).ok().as_ref().and_then(Url::host_str) == Some(host.as_str())
+    };
+
+    let mut frontier = Frontier::new();
+    let mut discovered: HashSet<String> = HashSet::new();
+    for seed in std::iter::once(url.to_string()).chain(sitemap_urls.iter().cloned()) {
+        if in_scope(&seed) && discovered.insert(seed.clone()) {
+            frontier.push(&seed, 0, 0.0);
+        }
+    }
+
+    let (mut sampled_pages, mut sampled_bytes, mut response_time) = (0, 0u64, Duration::ZERO);
+    while sampled_pages < ESTIMATE_SAMPLE_PAGES {
+        let Some(entry) = frontier.pop() else { break };
+
+        let (Ok(response), head_time) = request::send_timed(client, Method::GET, &entry.url, config, |request| request).await else { continue };
+        let started = Instant::now();
+        let content_type = response.headers().get("content-type").and_then(|value| value.to_str().ok()).map(str::to_string);
+        let Ok(Some(body)) = request::read_body(response, config.max_page_bytes(), config).await else { continue };
+        response_time += head_time + started.elapsed();
+        sampled_pages += 1;
+        sampled_bytes += body.len() as u64;
+
+        let html = page::decode_body(&body, content_type.as_deref());
+        for record in extract_link_records(&html, &entry.url) {
+            if in_scope(&record.url) && discovered.insert(record.url.clone()) {
+                frontier.push(&record.url, entry.depth + 1, -(entry.depth as f64 + 1.0));
+            }
+        }
+    }
+
+    let complete = frontier.is_empty();
+    let average_page_bytes = if sampled_pages == 0 { 0 } else { sampled_bytes / sampled_pages as u64 };
+    let estimated_pages = if complete && sitemap_urls.len() <= sampled_pages { sampled_pages } else { sitemap_urls.len().max(discovered.len()) };
+    let pace = [
+        if sampled_pages == 0 { Duration::ZERO } else { response_time / sampled_pages as u32 },
+        robots.crawl_delay.and_then(|delay| Duration::try_from_secs_f64(delay).ok()).unwrap_or_default(),
+        config.request_limiter().per_host_interval().unwrap_or_default(),
+    ]
+    .into_iter()
+    .max()
+    .unwrap_or_default();
+
+    SiteEstimate {
+        robots,
+        sitemap_urls: sitemap_urls.len(),
+        sampled_pages,
+        discovered_urls: discovered.len(),
+        complete,
+        average_page_bytes,
+        estimated_pages,
+        estimated_bytes: average_page_bytes.saturating_mul(estimated_pages as u64),
+        // A site may ask for any delay, so the estimate saturates rather than overflows
+        estimated_duration: u32::try_from(estimated_pages).ok().and_then(|pages| pace.checked_mul(pages)).unwrap_or(Duration::MAX),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{http_response, serve};
+
+    #[tokio::test]
+    async fn test_estimate_combines_sitemap_and_sample() {
+        let base = serve(|request| {
+            let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+            let host = request.lines().find_map(|line| line.to_ascii_lowercase().strip_prefix("host: ").map(str::to_string)).unwrap_or_default();
+            let base = format!("http://{}", host.trim());
+            match path.as_str() {
+                "/robots.txt" => http_response("200 OK", &[], format!("Disallow: /private\nCrawl-delay: 2\nSitemap: {}/sitemap.xml\n", base).as_bytes()),
+                "/sitemap.xml" => {
+                    let urls: String = (1..=30).map(|i| format!("<url><loc>{}/item/{}</loc></url>", base, i)).collect();
+                    http_response("200 OK", &[("Content-Type", "application/xml")], format!("<urlset>{}</urlset>", urls).as_bytes())
+                }
+                "/private" => panic!("disallowed page requested"),
+                _ => http_response("200 OK", &[], format!("<a href='/private'>Staff</a><p>{}</p>", "x".repeat(65)).as_bytes()),
+            }
+        })
+        .await;
+
+        let estimate = estimate(&base, &Client::new(), None).await;
+
+        assert_eq!((estimate.sitemap_urls, estimate.sampled_pages), (30, ESTIMATE_SAMPLE_PAGES));
+        assert!(!estimate.complete);
+        assert_eq!(estimate.average_page_bytes, 100);
+        assert_eq!((estimate.discovered_urls, estimate.estimated_pages), (31, 31));
+        assert_eq!(estimate.estimated_bytes, 3100);
+        assert!(estimate.estimated_duration >= Duration::from_secs(62));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_survives_huge_crawl_delays() {
+        let base = serve(|request| match request.split_whitespace().nth(1).unwrap_or("/") {
+            "/robots.txt" => http_response("200 OK", &[], b"Crawl-delay: 1e19\n"),
+            _ => http_response("200 OK", &[], b"<a href='/next'>Next</a>"),
+        })
+        .await;
+
+        let estimate = estimate(&base, &Client::new(), None).await;
+
+        assert_eq!(estimate.robots.crawl_delay, Some(1e19));
+        assert_eq!(estimate.estimated_duration, Duration::MAX);
+    }
+}
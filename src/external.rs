@@ -0,0 +1,236 @@
+// src/external.rs
+
+use futures::future::join_all;
+use reqwest::header::RANGE;
+use reqwest::{Client, Method, StatusCode, Url};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::{sleep_until, Instant};
+
+use crate::{request, ScraperConfig};
+
+// How long a single verification request may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+// How many links may wait for their check before the crawl stops to check them
+const QUEUE_CAPACITY: usize = 1_000;
+
+/// The verification result of one off-site link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalLink {
+    pub url: String,
+    pub found_on: String,           // The page the link was first seen on
+    pub status: Option<u16>,        // HTTP status, `None` when the request failed
+    pub error: Option<String>,
+}
+
+impl ExternalLink {
+    /// Returns whether the link failed to answer or answered with a 4xx/5xx status.
+    pub fn is_broken(&self) -> bool {
+        self.status.is_none_or(|status| status >= 400)
+    }
+}
+
+impl fmt::Display for ExternalLink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.status, &self.error) {
+            (Some(status), _) => write!(f, "External Link - URL: {}, Status: {}, Found On: {}", self.url, status, self.found_on),
+            (None, error) => write!(
+                f,
+                "External Link - URL: {}, Error: {}, Found On: {}",
+                self.url,
+                error.as_deref().unwrap_or("Unknown"),
+                self.found_on
+            ),
+        }
+    }
+}
+
+/// Returns whether `link` points to a different host than `page_url`.
+pub fn is_external_link(link: &str, page_url: &str) -> bool {
+    let host = |url: &str| Url::parse(url).ok().and_then(|parsed| parsed.host_str().map(|h| h.trim_start_matches("www.").to_ascii_lowercase()));
+    host(link) != host(page_url)
+}
+
+/// Verifies off-site links with HEAD requests instead of crawling them.
+///
+/// Checks run in their own pool of `concurrency` requests and are spaced at least `min_interval`
+/// apart across all hosts, so verification stays polite no matter how many links a site has.
+/// Each URL is checked once. A crawl queues the links of its pages and checks them when it
+/// finishes, or whenever the queue fills up, instead of waiting for them on every page.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::{recursive_scrape_with_config, ExternalLinkChecker, ScraperConfig};
+/// # use reqwest::Client;
+/// # use std::collections::HashSet;
+/// # use std::time::Duration;
+/// # async fn example(client: Client) {
+/// let mut config = ScraperConfig::default();
+/// config.set_external_link_checker(Some(ExternalLinkChecker::new(4, Duration::from_millis(500))));
+/// recursive_scrape_with_config("https://example.com", &client, Some(&config), &mut HashSet::new()).await;
+/// let broken = config.external_link_checker().unwrap().results().into_iter().filter(|link| link.is_broken()).count();
+/// println!("{} broken external links", broken);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ExternalLinkChecker {
+    concurrency: usize,
+    pool: Semaphore,
+    min_interval: Duration,
+    next_slot: Mutex<Instant>,
+    results: Mutex<BTreeMap<String, Option<ExternalLink>>>,    // `None` while a check is queued or running
+    queue: Mutex<VecDeque<(String, String)>>,                   // Links of a crawl waiting for their check, with the page they were found on
+}
+
+impl ExternalLinkChecker {
+    pub fn new(concurrency: usize, min_interval: Duration) -> Self {
+        ExternalLinkChecker {
+            concurrency: concurrency.max(1),
+            pool: Semaphore::new(concurrency.max(1)),
+            min_interval,
+            next_slot: Mutex::new(Instant::now()),
+            results: Mutex::new(BTreeMap::new()),
+            queue: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    pub fn min_interval(&self) -> Duration {
+        self.min_interval
+    }
+
+    /// Verifies the links not checked yet, returning their results.
+    pub async fn verify_all(&self, client: &Client, links: &[String], found_on: &str, user_agent: Option<&str>) -> Vec<ExternalLink> {
+        let mut config = ScraperConfig::default();
+        config.set_user_agent(user_agent.map(str::to_string));
+        self.verify_all_with_config(client, links, found_on, &config).await
+    }
+
+    // Verifies the links not checked yet like `verify_all`, sending each check through the request layer of the config
+    pub(crate) async fn verify_all_with_config(&self, client: &Client, links: &[String], found_on: &str, config: &ScraperConfig) -> Vec<ExternalLink> {
+        let pending: Vec<&String> = {
+            let mut results = self.results.lock().unwrap();
+            let mut pending = Vec::new();
+            for link in links {
+                if !results.contains_key(link) {
+                    results.insert(link.to_string(), None);
+                    pending.push(link);
+                }
+            }
+            pending
+        };
+        join_all(pending.into_iter().map(|link| self.verify(client, link, found_on, config))).await
+    }
+
+    // Queues the links not checked or queued yet for `drain`, returning whether the queue is full
+    pub(crate) fn enqueue(&self, links: Vec<String>, found_on: &str) -> bool {
+        let mut results = self.results.lock().unwrap();
+        let mut queue = self.queue.lock().unwrap();
+        for link in links {
+            if !results.contains_key(&link) {
+                results.insert(link.clone(), None);
+                queue.push_back((link, found_on.to_string()));
+            }
+        }
+        queue.len() >= QUEUE_CAPACITY
+    }
+
+    // Checks every queued link through the request layer of the config, returning their results
+    pub(crate) async fn drain(&self, client: &Client, config: &ScraperConfig) -> Vec<ExternalLink> {
+        let queued: Vec<(String, String)> = self.queue.lock().unwrap().drain(..).collect();
+        join_all(queued.iter().map(|(link, found_on)| self.verify(client, link, found_on, config))).await
+    }
+
+    async fn verify(&self, client: &Client, url: &str, found_on: &str, config: &ScraperConfig) -> ExternalLink {
+        let _permit = self.pool.acquire().await.expect("external link pool closed");
+        let deadline = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let start = (*next_slot).max(Instant::now());
+            *next_slot = start + self.min_interval;
+            start
+        };
+        sleep_until(deadline).await;
+
+        // Some servers refuse HEAD; ask for a single byte instead of the whole page
+        let mut outcome = request::send(client, Method::HEAD, url, config, |request| request.timeout(CHECK_TIMEOUT)).await;
+        if outcome.as_ref().is_ok_and(|response| matches!(response.status(), StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED)) {
+            outcome = request::send(client, Method::GET, url, config, |request| request.timeout(CHECK_TIMEOUT).header(RANGE, "bytes=0-0")).await;
+        }
+
+        let (status, error) = match outcome {
+            Ok(response) => (Some(response.status().as_u16()), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let link = ExternalLink { url: url.to_string(), found_on: found_on.to_string(), status, error };
+        self.results.lock().unwrap().insert(url.to_string(), Some(link.clone()));
+        link
+    }
+
+    /// Returns the finished checks, by URL.
+    pub fn results(&self) -> Vec<ExternalLink> {
+        self.results.lock().unwrap().values().flatten().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Method to forget every check and queued link, so the next crawl checks its links again
+    pub fn clear(&self) {
+        self.results.lock().unwrap().clear();
+        self.queue.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{http_response, serve};
+
+    #[tokio::test]
+    async fn test_external_links_are_checked_once_with_head() {
+        let base = serve(|request| match request.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+            ["HEAD", "/ok"] => http_response("200 OK", &[], b""),
+            ["HEAD", "/no-head"] => http_response("405 Method Not Allowed", &[], b""),
+            ["GET", "/no-head"] if request.contains("bytes=0-0") => http_response("206 Partial Content", &[], b"x"),
+            _ => http_response("404 Not Found", &[], b""),
+        })
+        .await;
+
+        let checker = ExternalLinkChecker::new(2, Duration::from_millis(50));
+        let links: Vec<String> = ["/ok", "/no-head", "/gone", "/ok"].iter().map(|path| format!("{}{}", base, path)).collect();
+        let started = Instant::now();
+        let checked = checker.verify_all(&Client::new(), &links, "https://a.test/", None).await;
+
+        assert_eq!(checked.iter().map(|link| link.status).collect::<Vec<_>>(), vec![Some(200), Some(206), Some(404)]);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert!(checker.verify_all(&Client::new(), &links, "https://a.test/", None).await.is_empty());
+        assert_eq!(checker.results().iter().filter(|link| link.is_broken()).count(), 1);
+
+        assert!(is_external_link("https://other.test/x", "https://www.a.test/"));
+        assert!(!is_external_link("https://a.test/x", "https://www.a.test/"));
+
+        // Queued links are checked once, only when the queue is drained
+        let queued = ExternalLinkChecker::new(2, Duration::ZERO);
+        let config = ScraperConfig::default();
+        assert!(!queued.enqueue(links.clone(), "https://a.test/"));
+        assert!(!queued.enqueue(links.clone(), "https://a.test/other"));
+        assert!(queued.results().is_empty());
+        let drained = queued.drain(&Client::new(), &config).await;
+        assert_eq!(drained.iter().map(|link| link.status).collect::<Vec<_>>(), vec![Some(200), Some(206), Some(404)]);
+        assert!(drained.iter().all(|link| link.found_on == "https://a.test/"));
+        assert!(queued.drain(&Client::new(), &config).await.is_empty());
+    }
+}
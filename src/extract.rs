@@ -0,0 +1,131 @@
+// src/extract.rs
+
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::io::{Error as IoError, ErrorKind, Result as IoResult};
+use std::path::Path;
+
+use crate::{normalize_link, UrlPattern};
+
+/// A field of an extraction schema: a CSS selector, and the attribute to read from the matching
+/// elements (their text when `None`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaField {
+    pub name: String,
+    pub selector: String,
+    #[serde(default)]
+    pub attribute: Option<String>,
+    #[serde(default)]
+    pub multiple: bool,         // Keep every match instead of the first one
+}
+
+/// A named set of fields to extract from the pages of one kind, e.g. `product` or `article`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractionSchema {
+    pub name: String,
+    pub fields: Vec<SchemaField>,
+}
+
+impl ExtractionSchema {
+    pub fn new(name: &str) -> Self {
+        ExtractionSchema { name: name.to_string(), fields: Vec::new() }
+    }
+
+    /// Adds a field read from the text (or `attribute`) of the first element matching `selector`.
+    pub fn field(mut self, name: &str, selector: &str, attribute: Option<&str>) -> Self {
+        self.fields.push(SchemaField { name: name.to_string(), selector: selector.to_string(), attribute: attribute.map(str::to_string), multiple: false });
+        self
+    }
+
+    /// Adds a field read from every element matching `selector`.
+    pub fn list_field(mut self, name: &str, selector: &str, attribute: Option<&str>) -> Self {
+        self.fields.push(SchemaField { name: name.to_string(), selector: selector.to_string(), attribute: attribute.map(str::to_string), multiple: true });
+        self
+    }
+}
+
+/// The values a schema extracted from one page, by field name. Fields that matched nothing are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractedRecord {
+    pub schema: String,
+    pub url: String,
+    pub fields: BTreeMap<String, Vec<String>>,
+}
+
+/// Applies an extraction schema to a page. `href` and `src` attributes are resolved against the page URL.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{extract_with_schema, ExtractionSchema};
+/// let schema = ExtractionSchema::new("product").field("name", "h1", None).field("image", "img.main", Some("src"));
+/// let html = r#"<h1> Blue Kettle </h1><img class="main" src="/img/kettle.jpg">"#;
+/// let record = extract_with_schema(html, "https://shop.example.com/product/42", &schema);
+/// assert_eq!(record.fields["name"], vec!["Blue Kettle"]);
+/// assert_eq!(record.fields["image"], vec!["https://shop.example.com/img/kettle.jpg"]);
+/// ```
+pub fn extract_with_schema(html: &str, url: &str, schema: &ExtractionSchema) -> ExtractedRecord {
+    let document = Html::parse_document(html);
+    let mut record = ExtractedRecord { schema: schema.name.clone(), url: url.to_string(), fields: BTreeMap::new() };
+
+    for field in &schema.fields {
+        let Ok(selector) = Selector::parse(&field.selector) else {
+            console_error!("Skipping field '{}' of schema '{}': invalid selector '{}'", field.name, schema.name, field.selector);
+            continue;
+        };
+        let values = document.select(&selector).filter_map(|element| match field.attribute.as_deref() {
+            Some(attribute @ ("href" | "src")) => element.value().attr(attribute).map(|link| normalize_link(link, url)),
+            Some(attribute) => element.value().attr(attribute).map(str::to_string),
+            None => Some(element.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" ")),
+        });
+        let values: Vec<String> = values.filter(|value| !value.is_empty()).take(if field.multiple { usize::MAX } else { 1 }).collect();
+        if !values.is_empty() {
+            record.fields.insert(field.name.clone(), values);
+        }
+    }
+    record
+}
+
+/// Binds an extraction schema to the URLs matching a pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractionRule {
+    pub pattern: UrlPattern,
+    pub schema: ExtractionSchema,
+}
+
+// The on-disk form of an extraction rule
+#[derive(Deserialize)]
+struct ExtractionRuleFile {
+    pattern: String,
+    schema: ExtractionSchema,
+}
+
+/// Reads extraction rules from a JSON file holding a list of `{"pattern": ..., "schema": {"name": ..., "fields": [...]}}`.
+pub fn load_extraction_rules<P: AsRef<Path>>(path: P) -> IoResult<Vec<ExtractionRule>> {
+    let rules: Vec<ExtractionRuleFile> = serde_json::from_str(&read_to_string(path)?).map_err(|e| IoError::new(ErrorKind::InvalidData, e))?;
+    Ok(rules.into_iter().map(|rule| ExtractionRule { pattern: UrlPattern::new(&rule.pattern), schema: rule.schema }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_fields_are_extracted() {
+        let schema = ExtractionSchema::new("article")
+            .field("title", "h1", None)
+            .field("author", "meta[name=author]", Some("content"))
+            .list_field("tags", "a.tag", Some("href"))
+            .field("missing", ".nope", None)
+            .field("broken", "[[", None);
+        let html = r#"<meta name="author" content="Ada"><h1>Release <em>notes</em></h1><h1>Other</h1><a class="tag" href="/t/rust">rust</a><a class="tag" href="../t/web">web</a>"#;
+
+        let record = extract_with_schema(html, "https://blog.test/posts/1", &schema);
+        assert_eq!(record.fields["title"], vec!["Release notes"]);
+        assert_eq!(record.fields["author"], vec!["Ada"]);
+        assert_eq!(record.fields["tags"], vec!["https://blog.test/t/rust", "https://blog.test/t/web"]);
+        assert_eq!(record.fields.len(), 3);
+    }
+}
@@ -0,0 +1,139 @@
+// src/findings.rs
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Result as IoResult, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The file in the output directory every crawl appends its findings to, one JSON object per line.
+pub const FINDINGS_FILE: &str = "findings.jsonl";
+
+/// What a finding is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    Email,              // An email address in a page
+    JsKeyword,          // A keyword such as `apiKey` in an inline or external script
+    ErrorPage,          // An exception or stack trace in a page
+    OpenDirectory,      // A well-known directory answering with a success status
+    CrossOriginFrame,   // A frame or iframe of a page loading another origin
+    Header,             // A response header leaking details about the server
+    FormIssue,          // A security issue of a form, e.g. a POST form without a CSRF token
+    Parameter,          // An unadvertised parameter that changes the response of an endpoint
+    BrokenLink,         // An off-site link that fails or answers with an error status
+    RobotsAudit,        // A contradiction between robots.txt, sitemaps, robots directives and canonical URLs
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            FindingKind::Email => "email",
+            FindingKind::JsKeyword => "js_keyword",
+            FindingKind::ErrorPage => "error_page",
+            FindingKind::OpenDirectory => "open_directory",
+            FindingKind::CrossOriginFrame => "cross_origin_frame",
+            FindingKind::Header => "header",
+            FindingKind::FormIssue => "form_issue",
+            FindingKind::Parameter => "parameter",
+            FindingKind::BrokenLink => "broken_link",
+            FindingKind::RobotsAudit => "robots_audit",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One line of `findings.jsonl`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    #[serde(rename = "type")]
+    pub kind: FindingKind,
+    pub url: String,            // The page (or script, or directory) the finding was made on
+    pub evidence: String,       // What was found, e.g. the address or the matching code
+    pub timestamp: u64,         // Seconds since the Unix epoch
+    #[serde(default)]
+    pub session_id: Option<String>,     // The crawl session that made the finding
+}
+
+impl Finding {
+    /// Creates a finding made now.
+    pub fn new(kind: FindingKind, url: &str, evidence: &str) -> Self {
+        Finding {
+            kind,
+            url: url.to_string(),
+            evidence: evidence.to_string(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            session_id: None,
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Finding - Type: {}, URL: {}, Evidence: {}", self.kind, self.url, self.evidence)
+    }
+}
+
+/// Appends findings to the `findings.jsonl` of an output directory, all in one write.
+pub fn append_findings(dir: &Path, findings: &[Finding]) -> IoResult<()> {
+    if findings.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for finding in findings {
+        lines.push_str(&serde_json::to_string(finding)?);
+        lines.push('\n');
+    }
+    create_dir_all(dir)?;
+    OpenOptions::new().create(true).append(true).open(dir.join(FINDINGS_FILE))?.write_all(lines.as_bytes())
+}
+
+/// Reads the findings of a `findings.jsonl` file, skipping lines that cannot be parsed. A missing file has no findings.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::{read_findings, FindingKind, FINDINGS_FILE};
+/// # use std::path::Path;
+/// let emails = read_findings(&Path::new("./scraped_data").join(FINDINGS_FILE))
+///     .unwrap()
+///     .into_iter()
+///     .filter(|finding| finding.kind == FindingKind::Email);
+/// for finding in emails {
+///     println!("{} on {}", finding.evidence, finding.url);
+/// }
+/// ```
+pub fn read_findings(path: &Path) -> IoResult<Vec<Finding>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut findings = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(finding) = serde_json::from_str(&line?) {
+            findings.push(finding);
+        }
+    }
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_findings_round_trip_through_jsonl() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let first = Finding::new(FindingKind::Email, "https://a.test/contact", "ops@a.test");
+        let second = Finding::new(FindingKind::OpenDirectory, "https://a.test/backup", "HTTP 200");
+        append_findings(temp_dir.path(), std::slice::from_ref(&first)).unwrap();
+        append_findings(temp_dir.path(), std::slice::from_ref(&second)).unwrap();
+
+        let path = temp_dir.path().join(FINDINGS_FILE);
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with(r#"{"type":"email","url":"https://a.test/contact","evidence":"ops@a.test""#));
+        assert_eq!(read_findings(&path).unwrap(), vec![first, second]);
+        assert!(read_findings(&temp_dir.path().join("missing.jsonl")).unwrap().is_empty());
+    }
+}
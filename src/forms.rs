@@ -0,0 +1,161 @@
+// src/forms.rs
+
+use reqwest::Url;
+use scraper::{Html, Selector};
+use std::fmt;
+
+use crate::{normalize_link, Severity};
+
+// Substrings of hidden field names that carry anti-CSRF tokens across common frameworks
+pub(crate) const CSRF_FIELD_MARKERS: [&str; 6] = ["csrf", "xsrf", "authenticity_token", "requestverificationtoken", "_token", "nonce"];
+
+/// An input, select, or textarea of a form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormInput {
+    pub name: String,
+    pub input_type: String,     // Lowercase `type`, or the tag name for `select` and `textarea`
+}
+
+/// A security-relevant problem with a form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormIssue {
+    pub severity: Severity,
+    pub description: String,
+}
+
+/// The security summary of one form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormSummary {
+    pub action: String,                 // Absolute URL the form submits to
+    pub method: String,                 // Uppercase, `GET` when unspecified
+    pub inputs: Vec<FormInput>,
+    pub hidden_fields: Vec<String>,
+    pub csrf_token: Option<String>,     // Name of the field (or meta tag) holding the token
+    pub file_uploads: Vec<String>,
+    pub issues: Vec<FormIssue>,
+}
+
+impl fmt::Display for FormSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = |fields: &[String]| if fields.is_empty() { "None".to_string() } else { fields.join(" ") };
+        let inputs: Vec<String> = self.inputs.iter().map(|input| format!("{}:{}", input.name, input.input_type)).collect();
+        write!(
+            f,
+            "Form - Action: {}, Method: {}, Inputs: {}, Hidden: {}, CSRF Token: {}, File Uploads: {}",
+            self.action,
+            self.method,
+            names(&inputs),
+            names(&self.hidden_fields),
+            self.csrf_token.as_deref().unwrap_or("Missing"),
+            names(&self.file_uploads)
+        )
+    }
+}
+
+/// Extracts the forms of a page with a security summary of each: hidden fields, the CSRF token
+/// (or its absence), file-upload inputs, and issues such as POST forms without a token.
+///
+/// A `<meta name="csrf-token">` tag counts as the token of every form on the page, since
+/// frameworks such as Rails and Laravel send it from there.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+/// * `base_url` - The URL of the page, used to resolve form actions.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::extract_forms;
+/// let html = r#"<form method="post" action="/login"><input name="user"><input type="password" name="pass"></form>"#;
+/// let forms = extract_forms(html, "https://example.com");
+/// assert_eq!(forms[0].action, "https://example.com/login");
+/// assert_eq!(forms[0].csrf_token, None);
+/// assert_eq!(forms[0].issues[0].description, "POST form without a CSRF token");
+/// ```
+pub fn extract_forms(html: &str, base_url: &str) -> Vec<FormSummary> {
+    let document = Html::parse_document(html);
+    let form_selector = Selector::parse("form").unwrap();
+    let input_selector = Selector::parse("input, select, textarea").unwrap();
+    let meta_selector = Selector::parse(r#"meta[name="csrf-token" i], meta[name="_csrf" i], meta[name="csrf-param" i]"#).unwrap();
+    let meta_token = document.select(&meta_selector).next().and_then(|meta| meta.value().attr("name")).map(|name| format!("meta:{}", name));
+
+    let page_is_https = base_url.starts_with("https:");
+    let mut forms = Vec::new();
+    for form in document.select(&form_selector) {
+        let action = normalize_link(form.value().attr("action").unwrap_or(""), base_url);
+        let method = form.value().attr("method").unwrap_or("get").trim().to_ascii_uppercase();
+
+        let inputs: Vec<FormInput> = form
+            .select(&input_selector)
+            .map(|input| {
+                let tag = input.value().name();
+                let input_type = if tag == "input" { input.value().attr("type").unwrap_or("text").to_ascii_lowercase() } else { tag.to_string() };
+                FormInput { name: input.value().attr("name").unwrap_or("Unnamed Input").to_string(), input_type }
+            })
+            .collect();
+        let named = |input_type: &str| -> Vec<String> {
+            inputs.iter().filter(|input| input.input_type == input_type).map(|input| input.name.clone()).collect()
+        };
+        let hidden_fields = named("hidden");
+        let file_uploads = named("file");
+        let csrf_token = hidden_fields
+            .iter()
+            .find(|name| CSRF_FIELD_MARKERS.iter().any(|marker| name.to_ascii_lowercase().contains(marker)))
+            .cloned()
+            .or_else(|| meta_token.clone());
+
+        let mut issues = Vec::new();
+        let mut flag = |severity, description: &str| issues.push(FormIssue { severity, description: description.to_string() });
+        if method == "POST" && csrf_token.is_none() {
+            flag(Severity::Medium, "POST form without a CSRF token");
+        }
+        if page_is_https && Url::parse(&action).is_ok_and(|url| url.scheme() == "http") {
+            flag(Severity::Medium, "Form submits over plain HTTP");
+        }
+        if method == "GET" && !named("password").is_empty() {
+            flag(Severity::Medium, "Password sent in the query string");
+        }
+        if !file_uploads.is_empty() {
+            flag(Severity::Low, "File upload input");
+        }
+
+        forms.push(FormSummary { action, method, inputs, hidden_fields, csrf_token, file_uploads, issues });
+    }
+    forms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_form_security_summary() {
+        let html = r#"
+            <form action="/upload" method="POST" enctype="multipart/form-data">
+                <input type="hidden" name="csrfmiddlewaretoken" value="x">
+                <input type="hidden" name="folder" value="7">
+                <input type="FILE" name="avatar">
+            </form>
+            <form action="http://example.com/search"><input type="password" name="pin"><select name="scope"></select></form>"#;
+        let forms = extract_forms(html, "https://example.com/account");
+
+        assert_eq!(forms[0].hidden_fields, vec!["csrfmiddlewaretoken", "folder"]);
+        assert_eq!(forms[0].csrf_token.as_deref(), Some("csrfmiddlewaretoken"));
+        assert_eq!(forms[0].file_uploads, vec!["avatar"]);
+        assert_eq!(forms[0].issues, vec![FormIssue { severity: Severity::Low, description: "File upload input".to_string() }]);
+
+        assert_eq!(forms[1].method, "GET");
+        assert_eq!(forms[1].inputs[1].input_type, "select");
+        let issues: Vec<_> = forms[1].issues.iter().map(|issue| issue.description.as_str()).collect();
+        assert_eq!(issues, vec!["Form submits over plain HTTP", "Password sent in the query string"]);
+
+        let with_meta = extract_forms(r#"<meta name="csrf-token" content="t"><form method="post"></form>"#, "https://example.com");
+        assert_eq!(with_meta[0].csrf_token.as_deref(), Some("meta:csrf-token"));
+        assert!(with_meta[0].issues.is_empty());
+        assert_eq!(
+            with_meta[0].to_string(),
+            "Form - Action: https://example.com/, Method: POST, Inputs: None, Hidden: None, CSRF Token: meta:csrf-token, File Uploads: None"
+        );
+    }
+}
@@ -0,0 +1,93 @@
+// src/frames.rs
+
+use reqwest::Url;
+use scraper::{Html, Selector};
+
+use crate::normalize_link;
+
+/// A `<frame>` or `<iframe>` of a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameSource {
+    pub url: String,            // The frame source, absolute
+    pub tag: String,            // `frame` or `iframe`
+    pub same_origin: bool,      // Same scheme, host and port as the page
+}
+
+/// Finds the frames of a page: the `<frame>`s of a frameset and its `<iframe>`s. Sources that
+/// cannot be fetched (`about:`, `javascript:`, `data:`) are skipped, and each source is listed once.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::extract_frames;
+/// let html = r#"<frameset cols="20%,80%"><frame src="nav.html"><frame src="main.html"></frameset>"#;
+/// let frames = extract_frames(html, "https://example.com/legacy/index.html");
+/// assert_eq!(frames[1].url, "https://example.com/legacy/main.html");
+/// assert!(frames[1].same_origin);
+/// ```
+pub fn extract_frames(html: &str, base_url: &str) -> Vec<FrameSource> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("frame[src], iframe[src]").unwrap();
+    let origin = Url::parse(base_url).ok().map(|parsed| parsed.origin());
+    let mut frames: Vec<FrameSource> = Vec::new();
+
+    for frame in document.select(&selector) {
+        let src = frame.value().attr("src").unwrap_or("").trim();
+        let scheme = src.split(':').next().unwrap_or("").to_ascii_lowercase();
+        if src.is_empty() || (src.contains(':') && matches!(scheme.as_str(), "about" | "javascript" | "data")) {
+            continue;
+        }
+        let url = normalize_link(src, base_url);
+        if frames.iter().any(|known| known.url == url) {
+            continue;
+        }
+        let same_origin = Url::parse(&url).ok().map(|parsed| parsed.origin()) == origin;
+        frames.push(FrameSource { url, tag: frame.value().name().to_string(), same_origin });
+    }
+    frames
+}
+
+// Returns the content lines of a frame, attributed to its URL, for the content file of the page
+// embedding it: its headings and paragraphs, or its body text when it has neither
+pub(crate) fn frame_content_lines(html: &str, frame_url: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let collapse = |text: String| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut lines = Vec::new();
+
+    let header_selector = Selector::parse("h1, h2, h3, h4, h5, h6").unwrap();
+    for header in document.select(&header_selector) {
+        lines.push(format!("Frame - URL: {}, Header: {}", frame_url, header.inner_html()));
+    }
+    let paragraph_selector = Selector::parse("p").unwrap();
+    for paragraph in document.select(&paragraph_selector) {
+        lines.push(format!("Frame - URL: {}, Paragraph: {}", frame_url, paragraph.inner_html()));
+    }
+    if lines.is_empty() {
+        let body_selector = Selector::parse("body").unwrap();
+        let text = collapse(document.select(&body_selector).flat_map(|body| body.text()).collect::<Vec<_>>().join(" "));
+        if !text.is_empty() {
+            lines.push(format!("Frame - URL: {}, Text: {}", frame_url, text));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_are_found_and_attributed() {
+        let html = r#"<iframe src="https://ads.test/slot"></iframe><iframe src="about:blank"></iframe><iframe src="javascript:void(0)"></iframe><iframe src="/chat"></iframe>"#;
+        let frames = extract_frames(html, "https://a.test/");
+        assert_eq!(frames.len(), 2);
+        assert_eq!((frames[0].tag.as_str(), frames[0].same_origin), ("iframe", false));
+        assert_eq!((frames[1].url.as_str(), frames[1].same_origin), ("https://a.test/chat", true));
+        let frameset = extract_frames(r#"<frameset><frame src="/nav.html"><frame src="/nav.html"></frameset>"#, "https://a.test/");
+        assert_eq!((frameset.len(), frameset[0].tag.as_str()), (1, "frame"));
+
+        let lines = frame_content_lines("<body><a href='/a'>Home</a>\n <a href='/b'>About</a></body>", "https://a.test/nav.html");
+        assert_eq!(lines, vec!["Frame - URL: https://a.test/nav.html, Text: Home About"]);
+        assert_eq!(frame_content_lines("<h1>Main</h1><p>Hi</p>", "https://a.test/main.html")[1], "Frame - URL: https://a.test/main.html, Paragraph: Hi");
+    }
+}
@@ -0,0 +1,249 @@
+// src/frontier.rs
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Result as IoResult, Write};
+use std::path::Path;
+
+use crate::MediaKind;
+
+// Extensions of binary downloads that are never pages, beyond the media kinds `MediaKind` knows
+const BINARY_EXTENSIONS: [&str; 16] = ["zip", "gz", "tgz", "tar", "bz2", "xz", "7z", "rar", "exe", "msi", "dmg", "pkg", "iso", "apk", "bin", "wasm"];
+
+/// What a `UrlScorer` knows about a discovered URL.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UrlCandidate<'a> {
+    pub url: &'a str,
+    pub depth: i32,
+    pub anchor_text: &'a str,           // Empty for seeds and links without text
+    pub referrer: Option<&'a str>,      // The page the link was found on, `None` for seeds
+    pub referrer_relevance: f64,        // The priority the referring page was crawled with, `0.0` for seeds
+}
+
+/// Decides the crawl order: the frontier always crawls the queued URL with the highest priority
+/// next, and URLs of equal priority in the order they were found.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{ScraperConfig, UrlCandidate, UrlScorer};
+/// # use std::sync::Arc;
+/// // Crawl product pages first, and pages linked from them next
+/// struct ProductsFirst;
+///
+/// impl UrlScorer for ProductsFirst {
+///     fn score(&self, candidate: &UrlCandidate) -> f64 {
+///         let product = candidate.url.contains("/products/") || candidate.anchor_text.to_lowercase().contains("buy");
+///         let own = if product { 10.0 } else { 0.0 };
+///         own + candidate.referrer_relevance * 0.5 - candidate.depth as f64
+///     }
+/// }
+///
+/// let mut config = ScraperConfig::default();
+/// config.set_url_scorer(Arc::new(ProductsFirst));
+/// ```
+pub trait UrlScorer: Send + Sync {
+    fn score(&self, candidate: &UrlCandidate) -> f64;
+}
+
+/// The default scorer: shallower pages first, i.e. a breadth-first crawl.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BreadthFirstScorer;
+
+impl UrlScorer for BreadthFirstScorer {
+    fn score(&self, candidate: &UrlCandidate) -> f64 {
+        -(candidate.depth as f64)
+    }
+}
+
+/// What a discovered URL is expected to be, judged from its extension before it is fetched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlClass {
+    Page,               // HTML to extract, also the guess for URLs without a known extension
+    Asset(MediaKind),   // A static file for the media pipeline
+}
+
+/// Classifies a URL as a page or a static asset by the extension of its path: images, videos,
+/// audio, documents, scripts, stylesheets, fonts and archives are assets. The `Content-Type` of
+/// pages is still checked once fetched, so assets behind extensionless URLs are stored as media too.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{classify_url, MediaKind, UrlClass};
+/// assert_eq!(classify_url("https://example.com/brochure.PDF?v=3"), UrlClass::Asset(MediaKind::Document));
+/// assert_eq!(classify_url("https://example.com/downloads/setup.zip"), UrlClass::Asset(MediaKind::Other));
+/// assert_eq!(classify_url("https://example.com/products/index.php"), UrlClass::Page);
+/// ```
+pub fn classify_url(url: &str) -> UrlClass {
+    // Only the path counts: `https://example.zip` is a host on the `.zip` TLD, not an archive
+    let path = Url::parse(url).map(|url| url.path().to_string()).unwrap_or_else(|_| url.split(['?', '#']).next().unwrap_or("").to_string());
+    if let Some(kind) = MediaKind::from_url(&path) {
+        return UrlClass::Asset(kind);
+    }
+    let file_name = path.rsplit('/').next().unwrap_or("");
+    match file_name.rsplit_once('.') {
+        Some((_, extension)) if BINARY_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()) => UrlClass::Asset(MediaKind::Other),
+        _ => UrlClass::Page,
+    }
+}
+
+/// A URL taken from the frontier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrontierEntry {
+    pub url: String,
+    pub depth: i32,
+    pub priority: f64,
+}
+
+impl FrontierEntry {
+    /// Returns whether the entry is expected to be a page or an asset, see `classify_url`.
+    pub fn class(&self) -> UrlClass {
+        classify_url(&self.url)
+    }
+}
+
+// Heap entry ordered by priority, then by insertion order (earlier first)
+#[derive(Debug)]
+struct Queued {
+    entry: FrontierEntry,
+    sequence: u64,
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.entry.priority.total_cmp(&other.entry.priority).then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// The URLs waiting to be crawled, highest priority first. Each URL is queued at most once.
+#[derive(Debug, Default)]
+pub struct Frontier {
+    heap: BinaryHeap<Queued>,
+    queued: HashSet<String>,
+    next_sequence: u64,
+}
+
+impl Frontier {
+    pub fn new() -> Self {
+        Frontier::default()
+    }
+
+    /// Queues a URL, returning `false` if it was queued before.
+    pub fn push(&mut self, url: &str, depth: i32, priority: f64) -> bool {
+        if !self.queued.insert(url.to_string()) {
+            return false;
+        }
+        self.heap.push(Queued { entry: FrontierEntry { url: url.to_string(), depth, priority }, sequence: self.next_sequence });
+        self.next_sequence += 1;
+        true
+    }
+
+    /// Removes and returns the URL to crawl next.
+    pub fn pop(&mut self) -> Option<FrontierEntry> {
+        self.heap.pop().map(|queued| queued.entry)
+    }
+
+    /// Returns the URL `pop` would return next, without removing it.
+    pub fn peek(&self) -> Option<&FrontierEntry> {
+        self.heap.peek().map(|queued| &queued.entry)
+    }
+
+    /// Returns every queued URL in the order they would be popped.
+    pub fn entries(&self) -> Vec<&FrontierEntry> {
+        let mut queued: Vec<&Queued> = self.heap.iter().collect();
+        queued.sort_by(|a, b| b.cmp(a));
+        queued.into_iter().map(|queued| &queued.entry).collect()
+    }
+
+    /// Drops the queued URLs `keep` returns `false` for, returning how many were dropped.
+    /// Dropped URLs still count as queued, so `push` won't queue them again.
+    pub fn retain(&mut self, mut keep: impl FnMut(&FrontierEntry) -> bool) -> usize {
+        let before = self.heap.len();
+        self.heap.retain(|queued| keep(&queued.entry));
+        before - self.heap.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
+
+/// Writes frontier entries to a JSON lines file, one entry per line, replacing the file. A
+/// discovery pass (see `ScraperConfig::set_discovery_only`) exports the pages it found this way,
+/// for `import_frontier` to queue them in a later pass, run elsewhere or later.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{export_frontier, import_frontier, FrontierEntry};
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("frontier.jsonl");
+/// let entries = vec![FrontierEntry { url: "https://example.com/a".to_string(), depth: 1, priority: -1.0 }];
+/// export_frontier(&entries, &path).unwrap();
+/// assert_eq!(import_frontier(&path).unwrap(), entries);
+/// ```
+pub fn export_frontier(entries: &[FrontierEntry], path: &Path) -> IoResult<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = BufWriter::new(File::create(path)?);
+    for entry in entries {
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    }
+    file.flush()
+}
+
+/// Reads the entries of a file written by `export_frontier`, in file order, skipping lines that
+/// cannot be parsed.
+pub fn import_frontier(path: &Path) -> IoResult<Vec<FrontierEntry>> {
+    let mut entries = Vec::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frontier_orders_by_priority_then_discovery() {
+        let mut frontier = Frontier::new();
+        assert!(frontier.push("https://a.test/deep", 2, -2.0));
+        assert!(frontier.push("https://a.test/one", 1, -1.0));
+        assert!(frontier.push("https://a.test/two", 1, -1.0));
+        assert!(!frontier.push("https://a.test/one", 1, 5.0));
+        assert!(frontier.push("https://a.test/hot", 3, 7.5));
+
+        let order: Vec<String> = std::iter::from_fn(|| frontier.pop()).map(|entry| entry.url).collect();
+        assert_eq!(order, vec!["https://a.test/hot", "https://a.test/one", "https://a.test/two", "https://a.test/deep"]);
+
+        let candidate = UrlCandidate { url: "https://a.test/", depth: 3, anchor_text: "", referrer: None, referrer_relevance: 0.0 };
+        assert_eq!(BreadthFirstScorer.score(&candidate), -3.0);
+    }
+}
@@ -0,0 +1,287 @@
+// src/ftp.rs
+
+// A minimal passive-mode FTP client, enough to list directories and fetch files that pages
+// link to with `ftp://` URLs. Only compiled with the `ftp` feature.
+
+use reqwest::Url;
+use std::io::Result as IoResult;
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::links::percent_decode;
+
+// How long a connection, a reply, a directory listing or a pause in a download may take before
+// the server counts as stalled
+const FTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Fails with `TimedOut` when an FTP operation does not finish within `FTP_TIMEOUT`
+async fn timed<T>(operation: impl std::future::Future<Output = IoResult<T>>) -> IoResult<T> {
+    tokio::time::timeout(FTP_TIMEOUT, operation)
+        .await
+        .unwrap_or_else(|_| Err(std::io::Error::new(std::io::ErrorKind::TimedOut, format!("FTP server stalled for {:?}", FTP_TIMEOUT))))
+}
+
+struct FtpSession {
+    control: BufReader<TcpStream>,
+    peer_ip: IpAddr,
+}
+
+impl FtpSession {
+    // Connects and logs in with the URL credentials, or anonymously
+    async fn open(url: &Url) -> IoResult<FtpSession> {
+        let host = url.host_str().ok_or_else(|| std::io::Error::other(format!("FTP URL '{}' has no host", url)))?;
+        let stream = timed(TcpStream::connect((host, url.port().unwrap_or(21)))).await?;
+        let peer_ip = stream.peer_addr()?.ip();
+        let mut session = FtpSession { control: BufReader::new(stream), peer_ip };
+
+        session.expect(&[220]).await?;
+        let user = if url.username().is_empty() { "anonymous" } else { url.username() };
+        let (code, _) = session.command(&format!("USER {}", user)).await?;
+        if code == 331 {
+            let password = url.password().unwrap_or("anonymous@");
+            session.command_expect(&format!("PASS {}", password), &[230, 202]).await?;
+        } else if code != 230 {
+            return Err(std::io::Error::other(format!("FTP login rejected with code {}", code)));
+        }
+        session.command_expect("TYPE I", &[200]).await?;
+        Ok(session)
+    }
+
+    // Reads one (possibly multi-line) reply and returns its code and text
+    async fn read_reply(&mut self) -> IoResult<(u16, String)> {
+        let mut line = String::new();
+        if timed(self.control.read_line(&mut line)).await? == 0 {
+            return Err(std::io::Error::other("FTP server closed the control connection"));
+        }
+        let code: u16 = line
+            .get(..3)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| std::io::Error::other(format!("Malformed FTP reply '{}'", line.trim_end())))?;
+
+        let mut text = line.clone();
+        if line.as_bytes().get(3) == Some(&b'-') {
+            let terminator = format!("{} ", code);
+            loop {
+                line.clear();
+                if timed(self.control.read_line(&mut line)).await? == 0 {
+                    break;
+                }
+                text.push_str(&line);
+                if line.starts_with(&terminator) {
+                    break;
+                }
+            }
+        }
+        Ok((code, text))
+    }
+
+    async fn expect(&mut self, codes: &[u16]) -> IoResult<String> {
+        let (code, text) = self.read_reply().await?;
+        if codes.contains(&code) {
+            Ok(text)
+        } else {
+            Err(std::io::Error::other(format!("Unexpected FTP reply: {}", text.trim_end())))
+        }
+    }
+
+    async fn command(&mut self, command: &str) -> IoResult<(u16, String)> {
+        timed(self.control.get_mut().write_all(format!("{}\r\n", command).as_bytes())).await?;
+        self.read_reply().await
+    }
+
+    async fn command_expect(&mut self, command: &str, codes: &[u16]) -> IoResult<String> {
+        timed(self.control.get_mut().write_all(format!("{}\r\n", command).as_bytes())).await?;
+        self.expect(codes).await
+    }
+
+    // Opens a passive data connection; the advertised address is ignored in favour of the
+    // control connection's peer, which avoids NAT misconfigurations and FTP bounce tricks
+    async fn passive(&mut self) -> IoResult<TcpStream> {
+        let reply = self.command_expect("PASV", &[227]).await?;
+        let Some(port) = pasv_port(&reply) else {
+            return Err(std::io::Error::other(format!("Malformed PASV reply: {}", reply.trim_end())));
+        };
+        timed(TcpStream::connect((self.peer_ip, port))).await
+    }
+
+    async fn quit(mut self) {
+        let _ = self.command("QUIT").await;
+    }
+}
+
+// Returns the data port of a `227 Entering Passive Mode (h1,h2,h3,h4,p1,p2)` reply, whose six fields must each be a byte
+fn pasv_port(reply: &str) -> Option<u16> {
+    let fields: Vec<u8> = reply.split(['(', ')']).nth(1)?.split(',').map(|field| field.trim().parse().ok()).collect::<Option<_>>()?;
+    match fields[..] {
+        [_, _, _, _, high, low] => Some(u16::from_be_bytes([high, low])),
+        _ => None,
+    }
+}
+
+// FTP paths are the percent-decoded URL path; a decoded line break would end the command and
+// smuggle another one onto the control connection
+fn ftp_path(url: &Url) -> IoResult<String> {
+    let path = percent_decode(url.path());
+    if path.contains(['\r', '\n']) {
+        return Err(std::io::Error::other(format!("FTP URL '{}' has a line break in its path", url)));
+    }
+    Ok(path)
+}
+
+/// Lists the entries of an FTP directory.
+///
+/// # Arguments
+///
+/// * `url` - An `ftp://` URL pointing to a directory.
+///
+/// # Returns
+///
+/// An `IoResult<Vec<String>>` with the absolute `ftp://` URL of every entry.
+pub async fn ftp_list(url: &str) -> IoResult<Vec<String>> {
+    let url = Url::parse(url).map_err(std::io::Error::other)?;
+    let path = ftp_path(&url)?;
+    let mut session = FtpSession::open(&url).await?;
+
+    let mut data = session.passive().await?;
+    session.command_expect(&format!("NLST {}", path), &[125, 150]).await?;
+    let mut listing = String::new();
+    timed(data.read_to_string(&mut listing)).await?;
+    drop(data);
+    session.expect(&[226, 250]).await?;
+    session.quit().await;
+
+    // Directory URLs must end with a slash for entries to resolve below them
+    let base = if url.path().ends_with('/') { url.clone() } else { Url::parse(&format!("{}/", url)).map_err(std::io::Error::other)? };
+    Ok(listing
+        .lines()
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| base.join(entry).ok().map(|entry_url| entry_url.to_string()))
+        .collect())
+}
+
+/// Downloads a file over FTP.
+///
+/// # Arguments
+///
+/// * `url` - An `ftp://` URL pointing to a file.
+/// * `file_path` - The file path where the file will be saved.
+///
+/// # Returns
+///
+/// An `IoResult<u64>` with the number of bytes saved.
+pub async fn ftp_download(url: &str, file_path: &Path) -> IoResult<u64> {
+    let url = Url::parse(url).map_err(std::io::Error::other)?;
+    let path = ftp_path(&url)?;
+    let mut session = FtpSession::open(&url).await?;
+
+    let mut data = session.passive().await?;
+    session.command_expect(&format!("RETR {}", path), &[125, 150]).await?;
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut file = tokio::fs::File::create(file_path).await?;
+    // Large files may take long, so only a pause in the transfer times out
+    let mut size = 0;
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = timed(data.read(&mut buffer)).await?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read]).await?;
+        size += read as u64;
+    }
+    file.flush().await?;
+    drop(data);
+    session.expect(&[226, 250]).await?;
+    session.quit().await;
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    // Serves a fixed directory listing and file over a scripted FTP conversation
+    async fn fake_ftp_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut control = BufReader::new(stream);
+                    control.get_mut().write_all(b"220-Welcome\r\n220 Ready\r\n").await.unwrap();
+                    let mut data_listener = None;
+                    let mut line = String::new();
+                    while control.read_line(&mut line).await.unwrap_or(0) > 0 {
+                        let command = line.trim_end().to_string();
+                        line.clear();
+                        let reply: &[u8] = if command.starts_with("USER") {
+                            b"331 Password please\r\n"
+                        } else if command.starts_with("PASS") {
+                            b"230 Logged in\r\n"
+                        } else if command.starts_with("TYPE") {
+                            b"200 Binary\r\n"
+                        } else if command.starts_with("PASV") {
+                            let data = TcpListener::bind("127.0.0.1:0").await.unwrap();
+                            let data_port = data.local_addr().unwrap().port();
+                            data_listener = Some(data);
+                            let reply = format!("227 Entering Passive Mode (127,0,0,1,{},{})\r\n", data_port / 256, data_port % 256);
+                            control.get_mut().write_all(reply.as_bytes()).await.unwrap();
+                            continue;
+                        } else if command.starts_with("NLST") || command.starts_with("RETR") {
+                            control.get_mut().write_all(b"150 Opening data connection\r\n").await.unwrap();
+                            let (mut data, _) = data_listener.take().unwrap().accept().await.unwrap();
+                            let payload: &[u8] = if command.starts_with("NLST") { b"readme.txt\r\nrelease.tar.gz\r\n" } else { b"hello over ftp" };
+                            data.write_all(payload).await.unwrap();
+                            drop(data);
+                            b"226 Transfer complete\r\n"
+                        } else {
+                            b"221 Bye\r\n"
+                        };
+                        control.get_mut().write_all(reply).await.unwrap();
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_pasv_port_rejects_malformed_replies() {
+        assert_eq!(pasv_port("227 Entering Passive Mode (127,0,0,1,195,80)\r\n"), Some(50_000));
+        assert_eq!(pasv_port("227 Entering Passive Mode (127,0,0,1,300,80)\r\n"), None);
+        assert_eq!(pasv_port("227 Entering Passive Mode (127,0,0,1,195)\r\n"), None);
+        assert_eq!(pasv_port("227 Entering Passive Mode (127,0,0,1,195,x)\r\n"), None);
+        assert_eq!(pasv_port("227 Entering Passive Mode\r\n"), None);
+    }
+
+    #[test]
+    fn test_ftp_path_rejects_line_breaks() {
+        assert_eq!(ftp_path(&Url::parse("ftp://a.test/pub/read%20me.txt").unwrap()).unwrap(), "/pub/read me.txt");
+        assert!(ftp_path(&Url::parse("ftp://a.test/a%0D%0ADELE%20b").unwrap()).is_err());
+        assert!(ftp_path(&Url::parse("ftp://a.test/a%0Ab").unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ftp_list_and_download() {
+        let port = fake_ftp_server().await;
+
+        let entries = ftp_list(&format!("ftp://127.0.0.1:{}/pub", port)).await.unwrap();
+        assert_eq!(
+            entries,
+            vec![format!("ftp://127.0.0.1:{}/pub/readme.txt", port), format!("ftp://127.0.0.1:{}/pub/release.tar.gz", port)]
+        );
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("readme.txt");
+        let size = ftp_download(&entries[0], &file_path).await.unwrap();
+        assert_eq!(size, 14);
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"hello over ftp");
+    }
+}
@@ -0,0 +1,152 @@
+// src/headers.rs
+
+use reqwest::header::HeaderMap;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+// Longest header value kept in a finding, in characters
+const MAX_VALUE_CHARS: usize = 200;
+
+/// How serious a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A response header that leaks details about the server, e.g. a version banner or debug output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderFinding {
+    pub header: String,         // Lowercase header name
+    pub value: String,
+    pub severity: Severity,
+    pub description: String,
+}
+
+impl fmt::Display for HeaderFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Header Finding - Severity: {}, Header: {}, Value: {}, Description: {}",
+            self.severity, self.header, self.value, self.description
+        )
+    }
+}
+
+/// Scans response headers for information leaks: versioned `Server` banners, `X-Powered-By` and
+/// similar technology headers, `X-Debug-*` headers, and verbose error headers.
+///
+/// # Returns
+///
+/// The findings, all of `Severity::Low`, in header order.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::scan_response_headers;
+/// # use reqwest::header::{HeaderMap, HeaderValue};
+/// let mut headers = HeaderMap::new();
+/// headers.insert("server", HeaderValue::from_static("Apache/2.4.41 (Ubuntu)"));
+/// headers.insert("x-powered-by", HeaderValue::from_static("PHP/7.4.3"));
+/// assert_eq!(scan_response_headers(&headers).len(), 2);
+/// ```
+pub fn scan_response_headers(headers: &HeaderMap) -> Vec<HeaderFinding> {
+    let mut findings = Vec::new();
+    for (name, value) in headers {
+        let header = name.as_str();
+        let value = String::from_utf8_lossy(value.as_bytes()).trim().to_string();
+
+        let description = match header {
+            "server" if value.chars().any(|c| c.is_ascii_digit()) => "Server version disclosed",
+            "x-powered-by" | "x-aspnet-version" | "x-aspnetmvc-version" | "x-generator" => "Server technology disclosed",
+            "x-backend-server" | "x-upstream" => "Internal host disclosed",
+            "x-chromelogger-data" => "Debug header exposed",
+            _ if header.starts_with("x-debug") => "Debug header exposed",
+            _ if header.starts_with("x-error") || header.contains("exception") || header.contains("stack") => "Verbose error header",
+            _ => continue,
+        };
+        findings.push(HeaderFinding {
+            header: header.to_string(),
+            value: value.chars().take(MAX_VALUE_CHARS).collect(),
+            severity: Severity::Low,
+            description: description.to_string(),
+        });
+    }
+    findings
+}
+
+/// Collects the header findings of a crawl per host, without duplicates.
+#[derive(Debug, Default)]
+pub struct HeaderFindingLog {
+    hosts: Mutex<BTreeMap<String, Vec<HeaderFinding>>>,
+}
+
+impl HeaderFindingLog {
+    /// Adds the findings of one response, returning the ones not yet known for `host`.
+    pub fn record(&self, host: &str, findings: Vec<HeaderFinding>) -> Vec<HeaderFinding> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let known = hosts.entry(host.to_string()).or_default();
+        let mut added = Vec::new();
+        for finding in findings {
+            if !known.contains(&finding) {
+                known.push(finding.clone());
+                added.push(finding);
+            }
+        }
+        added
+    }
+
+    pub fn for_host(&self, host: &str) -> Vec<HeaderFinding> {
+        self.hosts.lock().unwrap().get(host).cloned().unwrap_or_default()
+    }
+
+    /// Returns a copy of every host's findings, by host name.
+    pub fn hosts(&self) -> BTreeMap<String, Vec<HeaderFinding>> {
+        self.hosts.lock().unwrap().clone()
+    }
+
+    // Method to forget every finding
+    pub fn clear(&self) {
+        self.hosts.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_header_findings_per_host() {
+        let mut headers = HeaderMap::new();
+        headers.insert("server", HeaderValue::from_static("nginx"));
+        headers.insert("x-debug-token", HeaderValue::from_static("a1b2c3"));
+        headers.insert("x-exception-message", HeaderValue::from_static("NullReferenceException at Orders.cs:42"));
+        headers.insert("content-type", HeaderValue::from_static("text/html"));
+
+        let findings = scan_response_headers(&headers);
+        let described: Vec<_> = findings.iter().map(|f| (f.header.as_str(), f.description.as_str())).collect();
+        assert_eq!(described, vec![("x-debug-token", "Debug header exposed"), ("x-exception-message", "Verbose error header")]);
+
+        let log = HeaderFindingLog::default();
+        assert_eq!(log.record("a.test", findings.clone()).len(), 2);
+        assert!(log.record("a.test", findings.clone()).is_empty());
+        assert_eq!(log.record("b.test", findings).len(), 2);
+        assert_eq!(log.for_host("a.test").len(), 2);
+    }
+}
@@ -0,0 +1,160 @@
+// src/health.rs
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The request history of one host during a crawl.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostHealth {
+    pub requests: usize,
+    pub failures: usize,                // Connection errors, timeouts, 5xx and 429 answers
+    pub consecutive_failures: u32,
+    pub total_latency: Duration,
+    pub open_until: Option<Instant>,    // Set while the circuit of the host is open
+}
+
+impl HostHealth {
+    /// Returns the share of failed requests, from `0.0` to `1.0`.
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.requests as f64
+        }
+    }
+
+    pub fn average_latency(&self) -> Duration {
+        self.total_latency / self.requests.max(1) as u32
+    }
+}
+
+/// Tracks the error rate and latency of every host and stops requests to hosts that keep failing.
+///
+/// Once a host fails `failure_threshold` requests in a row, its circuit opens: its queued URLs are
+/// skipped (and dead-lettered) for `cooldown`, instead of spending retries and wall-clock time on a
+/// dead host. After the cooldown a single request is let through; a success closes the circuit,
+/// a failure opens it for another cooldown.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{CircuitBreaker, ScraperConfig};
+/// # use std::time::Duration;
+/// let mut config = ScraperConfig::default();
+/// config.set_circuit_breaker(Some(CircuitBreaker::new(3, Duration::from_secs(120))));
+/// ```
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    hosts: Mutex<BTreeMap<String, HostHealth>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker { failure_threshold: failure_threshold.max(1), cooldown, hosts: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub fn failure_threshold(&self) -> u32 {
+        self.failure_threshold
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    /// Returns whether requests to `host` are currently refused.
+    ///
+    /// When the cooldown of an open circuit has run out, the circuit moves to half-open: this call
+    /// returns `false` once, letting one probe request through.
+    pub fn is_open(&self, host: &str) -> bool {
+        let mut hosts = self.hosts.lock().unwrap();
+        let Some(health) = hosts.get_mut(host) else { return false };
+        match health.open_until {
+            Some(until) if Instant::now() < until => true,
+            Some(_) => {
+                // Half-open: one more failure reopens the circuit right away
+                health.open_until = None;
+                health.consecutive_failures = self.failure_threshold - 1;
+                false
+            }
+            None => false,
+        }
+    }
+
+    pub fn record_success(&self, host: &str, latency: Duration) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let health = Self::entry(&mut hosts, host);
+        health.requests += 1;
+        health.total_latency += latency;
+        health.consecutive_failures = 0;
+    }
+
+    /// Records a failed request, returning whether it opened the circuit of `host`.
+    pub fn record_failure(&self, host: &str, latency: Duration) -> bool {
+        let mut hosts = self.hosts.lock().unwrap();
+        let health = Self::entry(&mut hosts, host);
+        health.requests += 1;
+        health.failures += 1;
+        health.total_latency += latency;
+        health.consecutive_failures += 1;
+        if health.open_until.is_none() && health.consecutive_failures >= self.failure_threshold {
+            health.open_until = Some(Instant::now() + self.cooldown);
+            return true;
+        }
+        false
+    }
+
+    pub fn health(&self, host: &str) -> Option<HostHealth> {
+        self.hosts.lock().unwrap().get(host).cloned()
+    }
+
+    /// Returns a copy of every host's health, by host name.
+    pub fn hosts(&self) -> BTreeMap<String, HostHealth> {
+        self.hosts.lock().unwrap().clone()
+    }
+
+    // Method to forget every host's history and close all circuits
+    pub fn reset(&self) {
+        self.hosts.lock().unwrap().clear();
+    }
+
+    fn entry<'a>(hosts: &'a mut BTreeMap<String, HostHealth>, host: &str) -> &'a mut HostHealth {
+        hosts.entry(host.to_string()).or_insert(HostHealth {
+            requests: 0,
+            failures: 0,
+            consecutive_failures: 0,
+            total_latency: Duration::ZERO,
+            open_until: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+        let latency = Duration::from_millis(10);
+        assert!(!breaker.record_failure("dead.test", latency));
+        breaker.record_success("dead.test", latency);
+        assert!(!breaker.record_failure("dead.test", latency));
+        assert!(breaker.record_failure("dead.test", latency));
+        assert!(breaker.is_open("dead.test"));
+        assert!(!breaker.is_open("alive.test"));
+
+        let health = breaker.health("dead.test").unwrap();
+        assert_eq!((health.requests, health.failures), (4, 3));
+        assert_eq!(health.error_rate(), 0.75);
+        assert_eq!(health.average_latency(), latency);
+
+        // After the cooldown one probe goes through, and its failure reopens the circuit
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(!breaker.is_open("dead.test"));
+        assert!(breaker.record_failure("dead.test", latency));
+        assert!(breaker.is_open("dead.test"));
+    }
+}
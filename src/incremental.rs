@@ -0,0 +1,196 @@
+// src/incremental.rs
+
+use reqwest::header::{HeaderMap, HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Result as IoResult;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::manifest::sha256_hex;
+
+/// What the crawl database knows about one page from previous runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawlRecord {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_hash: String,
+    pub links: Vec<String>,     // Outgoing links, so unchanged pages can still be crawled through
+    pub last_seen: u64,         // Seconds since the Unix epoch
+}
+
+/// How a page compares with its previous crawl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageChange {
+    New,
+    Changed,
+    Unchanged,
+}
+
+/// The pages of the current run that are new or changed since the previous run, plus a count of
+/// the unchanged ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrawlDelta {
+    pub new: Vec<String>,
+    pub changed: Vec<String>,
+    pub unchanged: usize,
+}
+
+/// A per-URL database of ETags, Last-Modified dates, and content hashes kept across runs.
+///
+/// In incremental mode the crawler sends conditional requests built from it and skips the
+/// extraction and storage of pages that come back unchanged.
+#[derive(Debug, Default)]
+pub struct CrawlDatabase {
+    path: Option<PathBuf>,
+    records: HashMap<String, CrawlRecord>,
+    delta: CrawlDelta,
+}
+
+impl CrawlDatabase {
+    /// Starts an empty database that `save` writes to `path`, replacing any file there.
+    pub fn new(path: &Path) -> Self {
+        CrawlDatabase { path: Some(path.to_path_buf()), ..CrawlDatabase::default() }
+    }
+
+    /// Opens the database stored at `path`, starting empty when the file does not exist yet.
+    pub fn open(path: &Path) -> IoResult<Self> {
+        let records = match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(CrawlDatabase { path: Some(path.to_path_buf()), records, delta: CrawlDelta::default() })
+    }
+
+    /// Writes the database back to the file it was opened from.
+    pub fn save(&self) -> IoResult<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, serde_json::to_string(&self.records)?)?;
+        std::fs::rename(temp_path, path)
+    }
+
+    /// Returns the file `save` writes to, `None` for a database kept in memory only.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn get(&self, url: &str) -> Option<&CrawlRecord> {
+        self.records.get(url)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Returns the changes recorded during the current run.
+    pub fn delta(&self) -> &CrawlDelta {
+        &self.delta
+    }
+
+    // Method to forget what the current run has seen, keeping the records
+    pub fn reset_delta(&mut self) {
+        self.delta = CrawlDelta::default();
+    }
+
+    /// Adds `If-None-Match` / `If-Modified-Since` headers for a page crawled before.
+    pub fn conditional_request(&self, url: &str, request: RequestBuilder) -> RequestBuilder {
+        let Some(record) = self.records.get(url) else { return request };
+        let mut request = request;
+        if let Some(etag) = &record.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &record.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    }
+
+    /// Records a `304 Not Modified` answer for a known page and returns its stored links.
+    pub fn mark_not_modified(&mut self, url: &str) -> Option<Vec<String>> {
+        let record = self.records.get_mut(url)?;
+        record.last_seen = now();
+        self.delta.unchanged += 1;
+        Some(record.links.clone())
+    }
+
+    /// Reports how a page compares with its previous crawl, without storing it.
+    pub fn compare(&self, url: &str, content: &str) -> PageChange {
+        match self.records.get(url) {
+            None => PageChange::New,
+            Some(previous) if previous.content_hash == sha256_hex(content.as_bytes()) => PageChange::Unchanged,
+            Some(_) => PageChange::Changed,
+        }
+    }
+
+    /// Stores the latest version of a page and reports how it compares with the previous crawl.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the page.
+    /// * `headers` - The response headers, for the ETag and Last-Modified validators.
+    /// * `content` - The body of the page.
+    /// * `links` - The links found on the page.
+    ///
+    /// # Returns
+    ///
+    /// `PageChange::Unchanged` when the content hash matches the stored one.
+    pub fn update(&mut self, url: &str, headers: &HeaderMap, content: &str, links: Vec<String>) -> PageChange {
+        let header = |name: HeaderName| headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string);
+        let change = self.compare(url, content);
+        let record = CrawlRecord {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            content_hash: sha256_hex(content.as_bytes()),
+            links,
+            last_seen: now(),
+        };
+        match change {
+            PageChange::New => self.delta.new.push(url.to_string()),
+            PageChange::Changed => self.delta.changed.push(url.to_string()),
+            PageChange::Unchanged => self.delta.unchanged += 1,
+        }
+        self.records.insert(url.to_string(), record);
+        change
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_crawl_database_tracks_changes_across_runs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("crawl_db.json");
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_static("\"v1\""));
+
+        let mut db = CrawlDatabase::open(&path).unwrap();
+        assert_eq!(db.update("https://a.test/", &headers, "<p>one</p>", vec!["https://a.test/b".to_string()]), PageChange::New);
+        db.save().unwrap();
+
+        let mut db = CrawlDatabase::open(&path).unwrap();
+        assert_eq!(db.get("https://a.test/").unwrap().etag.as_deref(), Some("\"v1\""));
+        assert_eq!(db.compare("https://a.test/", "<p>two</p>"), PageChange::Changed);
+        assert!(db.delta().changed.is_empty());
+        assert_eq!(db.update("https://a.test/", &headers, "<p>one</p>", Vec::new()), PageChange::Unchanged);
+        assert_eq!(db.update("https://a.test/", &headers, "<p>two</p>", Vec::new()), PageChange::Changed);
+        assert_eq!(db.mark_not_modified("https://a.test/"), Some(Vec::new()));
+        assert_eq!(db.delta(), &CrawlDelta { new: Vec::new(), changed: vec!["https://a.test/".to_string()], unchanged: 2 });
+    }
+}
@@ -0,0 +1,138 @@
+// src/inject.rs
+
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::{Request, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::UrlPattern;
+
+/// What a request rule adds to the requests of the URLs it matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Injection {
+    Query(String, String),      // A query parameter, replacing one of the same name
+    Header(String, String),     // A request header, replacing one of the same name
+}
+
+/// A query parameter or header added to the requests of the URLs matching a pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestRule {
+    pub pattern: UrlPattern,
+    pub injection: Injection,
+}
+
+/// The request rules of a config, e.g. "append `?nocache=1` to `/news/*`" or "send
+/// `X-Requested-With: XMLHttpRequest` to `/api/*`".
+///
+/// Unlike rate-limit rules, every matching rule applies, in the order they were added. URLs are
+/// only rewritten on the wire: pages are still recorded, deduplicated and stored under the URL
+/// without the injected parameters.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::RequestRules;
+/// let mut rules = RequestRules::default();
+/// rules.add_query("/news/*", "nocache", "1");
+/// assert_eq!(rules.rewrite_url("https://example.com/news/today?page=2"), "https://example.com/news/today?page=2&nocache=1");
+/// assert_eq!(rules.rewrite_url("https://example.com/about"), "https://example.com/about");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RequestRules {
+    rules: Vec<RequestRule>,
+}
+
+impl RequestRules {
+    // Method to add a query parameter to the URLs matching `pattern`
+    pub fn add_query(&mut self, pattern: &str, name: &str, value: &str) {
+        self.rules.push(RequestRule { pattern: UrlPattern::new(pattern), injection: Injection::Query(name.to_string(), value.to_string()) });
+    }
+
+    // Method to send a header to the URLs matching `pattern`
+    pub fn add_header(&mut self, pattern: &str, name: &str, value: &str) {
+        self.rules.push(RequestRule { pattern: UrlPattern::new(pattern), injection: Injection::Header(name.to_string(), value.to_string()) });
+    }
+
+    pub fn rules(&self) -> &[RequestRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // Returns the injections of the rules matching `url`, in order
+    fn matching<'a>(&'a self, url: &'a str) -> impl Iterator<Item = &'a Injection> {
+        self.rules.iter().filter(move |rule| rule.pattern.matches(url)).map(|rule| &rule.injection)
+    }
+
+    /// Returns the URL to request for `url`, with the query parameters of the matching rules.
+    pub fn rewrite_url(&self, url: &str) -> String {
+        let params: Vec<(&str, &str)> = self
+            .matching(url)
+            .filter_map(|injection| match injection {
+                Injection::Query(name, value) => Some((name.as_str(), value.as_str())),
+                Injection::Header(..) => None,
+            })
+            .collect();
+        let Ok(mut parsed) = Url::parse(url) else { return url.to_string() };
+        if params.is_empty() {
+            return url.to_string();
+        }
+
+        let kept: Vec<(String, String)> = parsed
+            .query_pairs()
+            .filter(|(name, _)| !params.iter().any(|(injected, _)| injected == name))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        parsed.query_pairs_mut().clear().extend_pairs(kept).extend_pairs(params);
+        parsed.to_string()
+    }
+
+    /// Applies the matching rules to a built request: query parameters are added to its URL and
+    /// headers inserted, replacing headers of the same name such as `User-Agent`.
+    pub fn apply(&self, request: &mut Request) {
+        let url = request.url().to_string();
+        for injection in self.matching(&url) {
+            if let Injection::Header(name, value) = injection {
+                match (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+                    (Ok(name), Ok(value)) => {
+                        request.headers_mut().insert(name, value);
+                    }
+                    _ => console_error!("Skipping invalid injected header '{}: {}'", name, value),
+                }
+            }
+        }
+        if let Ok(rewritten) = Url::parse(&self.rewrite_url(&url)) {
+            *request.url_mut() = rewritten;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_rules_inject_queries_and_headers() {
+        let mut rules = RequestRules::default();
+        rules.add_query("/news/*", "nocache", "1");
+        rules.add_query("https://example.com/*", "lang", "en");
+        rules.add_header("/api/*", "X-Requested-With", "XMLHttpRequest");
+
+        assert_eq!(rules.rewrite_url("https://example.com/news/a?lang=de&nocache=0"), "https://example.com/news/a?nocache=1&lang=en");
+        assert_eq!(rules.rewrite_url("https://other.test/api/items"), "https://other.test/api/items");
+
+        let client = reqwest::Client::new();
+        let mut request = client.get("https://other.test/api/items").header("User-Agent", "default").build().unwrap();
+        rules.add_header("/api/*", "User-Agent", "mobile");
+        rules.apply(&mut request);
+        assert_eq!(request.headers()["x-requested-with"], "XMLHttpRequest");
+        assert_eq!(request.headers().get_all("user-agent").iter().collect::<Vec<_>>(), vec!["mobile"]);
+        let mut request = client.get("https://example.com/news/a").build().unwrap();
+        rules.apply(&mut request);
+        assert!(request.headers().get("x-requested-with").is_none());
+        assert_eq!(request.url().as_str(), "https://example.com/news/a?nocache=1&lang=en");
+    }
+}
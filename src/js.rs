@@ -0,0 +1,192 @@
+// src/js.rs
+
+use regex::Regex;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::normalize_link;
+
+// Characters of context kept on each side of a match
+const SNIPPET_CONTEXT_CHARS: usize = 40;
+// Matches reported per keyword and script, so one noisy bundle cannot flood the report
+const MAX_MATCHES_PER_KEYWORD: usize = 25;
+
+/// A keyword match in a script, located by line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsFinding {
+    pub source_url: String,     // The script URL, or the page URL for inline scripts
+    pub kind: String,           // The keyword that matched
+    pub line: usize,            // 1-based, relative to the script
+    pub column: usize,          // 1-based, in characters
+    pub snippet: String,        // The match with a little surrounding code
+}
+
+impl fmt::Display for JsFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{} [{}] {}", self.source_url, self.line, self.column, self.kind, self.snippet)
+    }
+}
+
+/// Searches script source for keywords.
+///
+/// # Arguments
+///
+/// * `source` - The JavaScript source.
+/// * `source_url` - Where the script came from, copied into each finding.
+/// * `keywords` - The strings to look for, matched case-sensitively.
+///
+/// # Returns
+///
+/// The findings in source order. Snippets are limited to the line of the match and a few dozen
+/// characters either side, so minified bundles stay readable.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::find_js_keywords;
+/// let findings = find_js_keywords("let a = 1;\nconst apiKey = 'abc';", "https://example.com/app.js", &["apiKey"]);
+/// assert_eq!((findings[0].line, findings[0].column), (2, 7));
+/// assert_eq!(findings[0].snippet, "const apiKey = 'abc';");
+/// ```
+pub fn find_js_keywords(source: &str, source_url: &str, keywords: &[&str]) -> Vec<JsFinding> {
+    let mut findings = Vec::new();
+
+    for &keyword in keywords.iter().filter(|keyword| !keyword.is_empty()) {
+        let (mut line, mut line_start, mut scanned) = (1, 0, 0);
+        for (offset, _) in source.match_indices(keyword).take(MAX_MATCHES_PER_KEYWORD) {
+            for (i, byte) in source.as_bytes()[scanned..offset].iter().enumerate() {
+                if *byte == b'\n' {
+                    line += 1;
+                    line_start = scanned + i + 1;
+                }
+            }
+            scanned = offset;
+
+            findings.push(JsFinding {
+                source_url: source_url.to_string(),
+                kind: keyword.to_string(),
+                line,
+                column: source[line_start..offset].chars().count() + 1,
+                snippet: snippet_around(source, line_start, offset, keyword.len()),
+            });
+        }
+    }
+
+    findings.sort_by_key(|finding| (finding.line, finding.column));
+    findings
+}
+
+// Returns the match plus up to `SNIPPET_CONTEXT_CHARS` of its line on either side
+fn snippet_around(source: &str, line_start: usize, offset: usize, len: usize) -> String {
+    let line_end = source[offset..].find('\n').map_or(source.len(), |i| offset + i);
+    let before: Vec<char> = source[line_start..offset].chars().rev().take(SNIPPET_CONTEXT_CHARS + 1).collect();
+    let after: Vec<char> = source[offset + len..line_end].chars().take(SNIPPET_CONTEXT_CHARS + 1).collect();
+
+    let mut snippet = String::new();
+    if before.len() > SNIPPET_CONTEXT_CHARS {
+        snippet.push_str("...");
+    }
+    snippet.extend(before.iter().take(SNIPPET_CONTEXT_CHARS).rev());
+    snippet.push_str(&source[offset..offset + len]);
+    snippet.extend(after.iter().take(SNIPPET_CONTEXT_CHARS));
+    if after.len() > SNIPPET_CONTEXT_CHARS {
+        snippet.push_str("...");
+    }
+    snippet.trim().to_string()
+}
+
+/// Finds the API endpoints a script requests: string literals that are absolute or root-relative
+/// URLs pointing at `/api/`, `/graphql`, `/rest/`, or `.json`/`.xml` resources.
+///
+/// # Arguments
+///
+/// * `source` - The JavaScript source.
+/// * `base_url` - The URL of the page running the script, used to resolve relative paths.
+///
+/// # Returns
+///
+/// The absolute endpoint URLs in source order, without duplicates.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::find_js_endpoints;
+/// let source = r#"fetch("/api/products?limit=20").then(r => r.json()); const logo = "/img/logo.png";"#;
+/// assert_eq!(find_js_endpoints(source, "https://shop.example.com/"), vec!["https://shop.example.com/api/products?limit=20"]);
+/// ```
+pub fn find_js_endpoints(source: &str, base_url: &str) -> Vec<String> {
+    let literal_regex = Regex::new(r#"["'`]((?:https?://|/)[^"'`\s]{1,300})["'`]"#).unwrap();
+    let mut endpoints = Vec::new();
+    for captures in literal_regex.captures_iter(source) {
+        let literal = &captures[1];
+        let path = literal.split(['?', '#']).next().unwrap_or("").to_ascii_lowercase();
+        let is_endpoint = path.contains("/api/")
+            || path.ends_with("/api")
+            || path.contains("/graphql")
+            || path.contains("/rest/")
+            || path.ends_with(".json")
+            || path.ends_with(".xml");
+        if !is_endpoint || literal.starts_with("//") || literal.contains("${") {
+            continue;
+        }
+        let endpoint = normalize_link(literal, base_url);
+        if !endpoints.contains(&endpoint) {
+            endpoints.push(endpoint);
+        }
+    }
+    endpoints
+}
+
+/// Remembers the external scripts (or stylesheets) a crawl has already processed, by URL and by content hash,
+/// so shared bundles are fetched, scanned, and saved once.
+#[derive(Debug, Default)]
+pub struct ScriptCache {
+    seen: Mutex<(HashSet<String>, HashSet<String>)>,   // URLs, SHA-256 content hashes
+}
+
+impl ScriptCache {
+    /// Marks a script URL as seen, returning whether it was new.
+    pub fn insert_url(&self, url: &str) -> bool {
+        self.seen.lock().unwrap().0.insert(url.to_string())
+    }
+
+    /// Marks a script body as seen by its hash, returning whether it was new.
+    pub fn insert_hash(&self, hash: &str) -> bool {
+        self.seen.lock().unwrap().1.insert(hash.to_string())
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.lock().unwrap().1.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Method to forget every script seen so far
+    pub fn clear(&self) {
+        let mut seen = self.seen.lock().unwrap();
+        seen.0.clear();
+        seen.1.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_findings_in_minified_bundles_are_capped() {
+        let bundle = format!("{}var token=\"t-1\";{}\n  token()", "a".repeat(100_000), "b".repeat(100_000));
+        let findings = find_js_keywords(&bundle, "https://a.test/bundle.js", &["token", "secret"]);
+
+        assert_eq!(findings.len(), 2);
+        assert_eq!((findings[0].line, findings[0].column), (1, 100_005));
+        assert_eq!(findings[0].snippet, format!("...{}var token=\"t-1\";{}...", "a".repeat(36), "b".repeat(33)));
+        assert_eq!((findings[1].line, findings[1].column, findings[1].snippet.as_str()), (2, 3, "token()"));
+
+        let repeated = "token ".repeat(100);
+        assert_eq!(find_js_keywords(&repeated, "inline", &["token"]).len(), MAX_MATCHES_PER_KEYWORD);
+    }
+}
@@ -1,13 +1,11 @@
 // src/lib.rs
 
-use reqwest::{ Client, Url, header };
+use reqwest::{ Client, Method, Url, header };
 use scraper::{ Html, Selector };
-use std::collections::{ HashSet, VecDeque };
+use std::collections::{ BTreeMap, HashMap, HashSet, VecDeque };
 use std::fs::{ create_dir_all, File };
 use std::io::Write;
 use std::path::Path;
-use tempfile::TempDir;
-use tokio::io::AsyncWriteExt;
 use regex::Regex;
 use std::time::Duration;
 use tokio::time::sleep;
@@ -15,11 +13,128 @@ use tokio::time::sleep;
 use std::future::Future;
 use std::path::{PathBuf};
 use std::pin::Pin;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::io::Result as IoResult;
 use tokio::process::Command;
 
 use tempfile::Builder;
 
+// Prints a line like `println!`, prefixed with the session of the running crawl
+macro_rules! console {
+    ($($arg:tt)*) => {
+        $crate::run::console_line(false, format_args!($($arg)*))
+    };
+}
+
+// Prints a line like `eprintln!`, prefixed with the session of the running crawl
+macro_rules! console_error {
+    ($($arg:tt)*) => {
+        $crate::run::console_line(true, format_args!($($arg)*))
+    };
+}
+
+mod alternate;
+mod audit;
+mod botwall;
+mod capture;
+mod classify;
+mod compare;
+mod css;
+mod deadletter;
+mod dedup;
+mod download;
+mod embeds;
+mod engine;
+mod entities;
+mod estimate;
+mod external;
+mod extract;
+mod findings;
+mod frontier;
+mod forms;
+mod frames;
+mod headers;
+mod health;
+mod incremental;
+mod inject;
+mod js;
+#[cfg(feature = "ftp")]
+mod ftp;
+mod links;
+mod manifest;
+mod normalize;
+mod page;
+mod params;
+mod pages;
+mod partition;
+mod media;
+mod pattern;
+mod redact;
+mod reload;
+mod request;
+mod render;
+mod repl;
+mod reviews;
+mod robots;
+mod run;
+mod scope;
+mod seeds;
+mod snapshot;
+mod summary;
+mod throttle;
+mod tls;
+#[cfg(test)]
+mod test_support;
+
+pub use alternate::{extract_alternates, AlternateKind, AlternateLink, AlternateMap, AlternatePolicy, PageAlternates, ALTERNATES_FILE};
+pub use audit::{audit_robots, write_robots_audit, AuditIssue, PageAudit, RobotsAudit, ROBOTS_AUDIT_FILE};
+pub use botwall::{detect_bot_wall, BotWall};
+pub use capture::{capture_path, extract_payload_data, pretty_print_json, pretty_print_xml, save_capture, PayloadData};
+pub use classify::{classify_page, PageClass, PageClassMap, PAGE_CLASSES_FILE};
+pub use compare::{compare_crawls, compare_runs, CrawlComparison, MediaChange};
+pub use css::{collect_css_urls, collect_stylesheet_urls, extract_css_imports, extract_css_urls, fetch_stylesheet_assets, Stylesheet, StylesheetAssets};
+pub use deadletter::{read_dead_letters, write_dead_letters, DeadLetter, DeadLetterKind, DeadLetterQueue, DEAD_LETTER_FILE};
+pub use dedup::{canonicalize_url, CanonicalUrlDedup, ContentHashDedup, DedupPolicy, ExactUrlDedup};
+pub use download::{DownloadCheck, DownloadValidator, MimePolicy};
+pub use engine::{CrawlStats, Scraper};
+pub use estimate::{estimate, SiteEstimate, ESTIMATE_SAMPLE_PAGES};
+pub use external::{is_external_link, ExternalLink, ExternalLinkChecker};
+pub use frontier::{classify_url, export_frontier, import_frontier, BreadthFirstScorer, Frontier, FrontierEntry, UrlCandidate, UrlClass, UrlScorer};
+pub use forms::{extract_forms, FormInput, FormIssue, FormSummary};
+pub use frames::{extract_frames, FrameSource};
+pub use entities::{extract_social_handles, DomainEntities, EntityKind, EntitySummary, ENTITIES_FILE};
+pub use headers::{scan_response_headers, HeaderFinding, HeaderFindingLog, Severity};
+pub use health::{CircuitBreaker, HostHealth};
+pub use embeds::{extract_video_embeds, fetch_oembed, scrape_video_embeds, EmbedProvider, VideoEmbed};
+#[cfg(feature = "ftp")]
+pub use ftp::{ftp_download, ftp_list};
+pub use incremental::{CrawlDatabase, CrawlDelta, CrawlRecord, PageChange};
+pub use js::{find_js_endpoints, find_js_keywords, JsFinding, ScriptCache};
+pub use links::{classify_link, extract_contacts, extract_link_records, Contacts, LinkKind, LinkRecord};
+pub use manifest::{manifest_path, read_manifest, record_download, record_download_with_etag, sha256_file, sha256_hex, verify_manifest, ManifestEntry, ManifestIndex, MANIFEST_FILE};
+pub use media::{collect_image_urls, mime_matches, parse_data_uri, sniff_mime, DataUri, MediaKind, parse_srcset, select_srcset_candidate, SrcsetCandidate, SrcsetDescriptor};
+pub use scope::{DomainFilters, DomainList};
+pub use seeds::{read_bookmarks, read_har, read_seed_file};
+pub use tls::TlsPolicy;
+pub use snapshot::{save_html_snapshot, snapshot_path, RobotsDirectives};
+pub use reviews::{extract_aggregate_rating, extract_reviews, AggregateRating, Review};
+pub use robots::{fetch_sitemap_urls, parse_robots_txt, parse_robots_txt_for, parse_sitemap, RobotsInfo, Sitemap};
+pub use extract::{extract_with_schema, load_extraction_rules, ExtractedRecord, ExtractionRule, ExtractionSchema, SchemaField};
+pub use findings::{append_findings, read_findings, Finding, FindingKind, FINDINGS_FILE};
+pub use inject::{Injection, RequestRule, RequestRules};
+pub use normalize::normalize_html;
+pub use page::{document_depth, route_response, ContentRoute};
+pub use params::{discover_parameters, ParameterFinding, ParameterFindingLog, DEFAULT_PARAMETER_WORDLIST};
+pub use pages::{page_file, page_id, read_page_index, record_page, PageIndex, PAGE_INDEX_FILE};
+pub use partition::{CrawlPartition, CrawlPartitions, PARTITIONS_FILE};
+pub use pattern::UrlPattern;
+pub use redact::{PiiKind, PiiRedactor};
+pub use reload::{ConfigWatcher, LiveConfig, LiveRateLimitRule, LiveSettings};
+pub use render::{CommandRenderer, PageRenderer, RenderFuture};
+pub use repl::{run_repl, CrawlSession, PageResult, ReplCommand, StepOutcome};
+pub use run::{replay_run, CrawlSettings, RunManifest, RunOutputs, RUNS_DIR, RUN_MANIFEST_FILE};
+pub use summary::{CrawlMetrics, CrawlSummary, HostLatency, HostTraffic};
+pub use throttle::{BandwidthLimiter, RateLimitRule, RequestLimiter};
 
 /// Generates a random user-agent string from a predefined list.
 ///
@@ -31,11 +146,12 @@ use tempfile::Builder;
 /// # Example
 ///
 /// ```
+/// # use knee_scraper::random_user_agent;
 /// let user_agent = random_user_agent();
 /// println!("Using user agent: {}", user_agent);
 /// ```
 pub fn random_user_agent() -> String {
-    let user_agents = vec![
+    let user_agents = [
         "Mozilla/5.0 (Windows NT 10.0; Win64; x64)...",
         "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7)...",
         "Mozilla/5.0 (iPhone; CPU iPhone OS 14_6 like Mac OS X)...",
@@ -56,10 +172,15 @@ pub fn random_user_agent() -> String {
 ///
 /// # Example
 ///
-/// ```
+/// ```no_run
+/// # use knee_scraper::recursive_scrape;
+/// # use reqwest::Client;
+/// # use std::collections::HashSet;
+/// # async fn example() {
 /// let client = Client::new();
 /// let mut visited = HashSet::new();
 /// recursive_scrape("https://example.com", &client, &mut visited).await;
+/// # }
 /// ```
 pub fn recursive_scrape<'a>(
     url: &'a str,
@@ -77,7 +198,7 @@ pub fn recursive_scrape<'a>(
             Ok(response) => {
                 match response.text().await {
                     Ok(html) => {
-                        println!("Scraping: {}", url);
+                        console!("Scraping: {}", url);
                         scrape_content(&html, url, client).await;
                         scrape_js(&html);
                         scrape_for_errors(&html);
@@ -91,691 +212,3695 @@ pub fn recursive_scrape<'a>(
                     }
                     Err(e) => {
                         let error_message = format!("Failed to get HTML content from '{}': {}", url, e);
-                        eprintln!("{}", error_message);
+                        console_error!("{}", error_message);
                         log_error_to_file(&error_message);
                     }
                 }
             }
             Err(e) => {
                 let error_message = format!("Failed to request '{}': {}", url, e);
-                eprintln!("{}", error_message);
+                console_error!("{}", error_message);
                 log_error_to_file(&error_message);
             }
         }
     })
 }
 
-
-/// Extracts all links from an HTML page, normalizing them to absolute URLs.
-///
-/// # Arguments
+/// Recursively scrapes web pages starting from the given URL, honoring a `ScraperConfig`.
 ///
-/// * `html` - The HTML content of the page as a string slice.
-/// * `base_url` - The base URL to resolve relative links.
+/// Works like `recursive_scrape`, but uses the configured user agent, stops following links
+/// past `max_depth` (or entirely when `follow_links` is off), and applies the config's
+/// content options to every page through `scrape_content_with_config`.
 ///
-/// # Returns
+/// # Arguments
 ///
-/// A `HashSet` containing all unique absolute links found on the page.
+/// * `url` - The URL to start scraping from.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `config` - An optional reference to `ScraperConfig`; `None` uses the defaults.
+/// * `visited` - A mutable reference to a `HashSet<String>` to keep track of visited URLs.
 ///
 /// # Example
 ///
+/// ```no_run
+/// # use knee_scraper::{recursive_scrape_with_config, ScraperConfig};
+/// # use reqwest::Client;
+/// # use std::collections::HashSet;
+/// # async fn example() {
+/// let client = Client::new();
+/// let config = ScraperConfig::new(true, 2, None);
+/// let mut visited = HashSet::new();
+/// recursive_scrape_with_config("https://example.com", &client, Some(&config), &mut visited).await;
+/// # }
 /// ```
-/// let links = extract_links("<a href='/about'>About</a>", "https://example.com");
-/// assert!(links.contains("https://example.com/about"));
-/// ```
-pub fn extract_links(html: &str, base_url: &str) -> HashSet<String> {
-    let document = Html::parse_document(html);
-    let selector = Selector::parse("a[href]").unwrap();
-    let mut urls = HashSet::new();
-
-    for element in document.select(&selector) {
-        if let Some(link) = element.value().attr("href") {
-            let absolute_link = normalize_link(link, base_url);
-            urls.insert(absolute_link);
-        }
-    }
-    urls
+pub async fn recursive_scrape_with_config(
+    url: &str,
+    client: &Client,
+    config: Option<&ScraperConfig>,
+    visited: &mut HashSet<String>,
+) {
+    let default_config = ScraperConfig::default();
+    let config = config.unwrap_or(&default_config);
+    config.resolve_crawl_database();
+    scrape_to_depth(url, client, config, visited, &mut CrawlStats::default(), 0).await;
+    finish_crawl(client, config).await;
 }
 
-/// Normalizes a link to an absolute URL based on the base URL.
+/// Retries only the URLs of a dead-letter file, instead of re-running the whole crawl.
+///
+/// Pages are fetched and scraped again without following their links, and media files are
+/// downloaded to the path they were meant for. The file is emptied first; URLs that fail again
+/// are appended back to the config's dead-letter file.
 ///
 /// # Arguments
 ///
-/// * `link` - The link to normalize.
-/// * `base_url` - The base URL of the current page.
+/// * `path` - The dead-letter file, usually `ScraperConfig::dead_letter_path()`.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `config` - An optional reference to `ScraperConfig`; `None` uses the defaults.
 ///
 /// # Returns
 ///
-/// A `String` containing the absolute URL.
+/// An `IoResult<usize>` with the number of URLs that were retried.
 ///
 /// # Example
 ///
+/// ```no_run
+/// # use knee_scraper::{redrive_dead_letters, ScraperConfig};
+/// # use reqwest::Client;
+/// # async fn example() -> std::io::Result<()> {
+/// let config = ScraperConfig::default();
+/// let retried = redrive_dead_letters(&config.dead_letter_path(), &Client::new(), Some(&config)).await?;
+/// println!("Retried {} URLs", retried);
+/// # Ok(())
+/// # }
 /// ```
-/// let absolute_link = normalize_link("/about", "https://example.com");
-/// assert_eq!(absolute_link, "https://example.com/about");
-/// ```
-pub fn normalize_link(link: &str, base_url: &str) -> String {
-    if link.starts_with("http") {
-        link.to_string() // Already an absolute URL
-    } else {
-        match Url::parse(base_url) {
-            Ok(base) => base.join(link).map(|url| url.to_string()).unwrap_or_default(),
-            Err(_) => link.to_string(), // Return as-is if base URL is invalid
-        }
-    }
+pub async fn redrive_dead_letters(path: &Path, client: &Client, config: Option<&ScraperConfig>) -> IoResult<usize> {
+    let default_config = ScraperConfig::default();
+    let config = config.unwrap_or(&default_config);
+    let mut visited = HashSet::new();
+    let mut stats = CrawlStats::default();
+    redrive_with_state(path, client, config, &mut visited, &mut stats).await
 }
 
-
-/// Downloads a media file (image or video) and saves it to the local directory.
-///
-/// # Arguments
-///
-/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
-/// * `media_url` - The URL of the media file to download.
-/// * `file_path` - The file path where the media file will be saved.
-///
-/// # Example
-///
-/// ```
-/// download_media(&client, "https://example.com/image.jpg", Path::new("./downloads/image.jpg")).await;
-/// ```
-pub async fn download_media(client: &Client, media_url: &str, file_path: &Path) {
-    // Ensure the 'captcha_images' directory exists
-    let captcha_images_dir = Path::new("./captcha_images");
-    if let Err(e) = tokio::fs::create_dir_all(&captcha_images_dir).await {
-        let error_message = format!("Failed to create 'captcha_images' directory: {}", e);
-        eprintln!("{}", error_message);
-        log_error_to_file(&error_message);
-        return;
+pub(crate) async fn redrive_with_state(
+    path: &Path,
+    client: &Client,
+    config: &ScraperConfig,
+    visited: &mut HashSet<String>,
+    stats: &mut CrawlStats,
+) -> IoResult<usize> {
+    let letters = read_dead_letters(path)?;
+    config.resolve_crawl_database();
+    // Letters queued before the redrive belong to the dead-letter file of the config, not to `path`
+    let earlier = config.dead_letters().take();
+
+    for letter in &letters {
+        console!("Retrying {:?}: {} (last failure: {})", letter.kind, letter.url, letter.reason);
+        match (letter.kind, &letter.path) {
+            (DeadLetterKind::Media, Some(file_path)) => {
+                let _ = download_media_with_config(client, &letter.url, Path::new(file_path), Some(config)).await;
+            }
+            _ => {
+                visited.remove(&letter.url);
+                config.dedup_policy().forget_url(&letter.url);
+                scrape_to_depth(&letter.url, client, config, visited, stats, config.max_depth()).await;
+            }
+        }
     }
 
-    // Proceed with the media download
-    if let Ok(response) = client.get(media_url).send().await {
-        if response.status().is_success() {
-            if let Ok(bytes) = response.bytes().await {
-                if let Some(parent) = file_path.parent() {
-                    if let Err(e) = tokio::fs::create_dir_all(parent).await {
-                        let error_message = format!("Failed to create directory '{}': {}", parent.display(), e);
-                        eprintln!("{}", error_message);
-                        log_error_to_file(&error_message);
-                        return;
-                    }
-                }
+    // The URLs failing again replace the file only once every retry is done
+    let remaining = config.dead_letters().take();
+    for letter in earlier {
+        config.dead_letters().push(letter);
+    }
+    deadletter::replace_dead_letters(path, &remaining)?;
+    if !remaining.is_empty() {
+        console!("{} URLs failed again, see {}", remaining.len(), path.display());
+    }
 
-                let mut file = match tokio::fs::File::create(file_path).await {
-                    Ok(f) => f,
-                    Err(e) => {
-                        let error_message = format!("Failed to create file '{}': {}", file_path.display(), e);
-                        eprintln!("{}", error_message);
-                        log_error_to_file(&error_message);
-                        return;
-                    }
-                };
+    finish_crawl(client, config).await;
+    Ok(letters.len())
+}
 
-                if let Err(e) = file.write_all(&bytes).await {
-                    let error_message = format!("Failed to write file '{}': {}", file_path.display(), e);
-                    eprintln!("{}", error_message);
-                    log_error_to_file(&error_message);
-                } else {
-                    println!("Successfully downloaded and saved the media file: {}", file_path.display());
-                }
-            } else {
-                let error_message = format!("Failed to read bytes from the response for '{}'", media_url);
-                eprintln!("{}", error_message);
-                log_error_to_file(&error_message);
-            }
-        } else {
-            let error_message = format!("Failed to download media from '{}': Status code {}", media_url, response.status());
-            eprintln!("{}", error_message);
-            log_error_to_file(&error_message);
+// Writes the run manifest into the output root, as the latest run and as the run of its session,
+// logging instead of failing the crawl
+pub(crate) fn write_run_manifest(manifest: &RunManifest, config: &ScraperConfig) {
+    let session_path = manifest.session_id.as_deref().map(|session_id| config.session_manifest_path(session_id));
+    for path in std::iter::once(config.run_manifest_path()).chain(session_path) {
+        if let Err(e) = manifest.write(&path) {
+            let error_message = format!("Failed to write run manifest to '{}': {}", path.display(), e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
         }
-    } else {
-        let error_message = format!("Failed to make request to '{}'", media_url);
-        eprintln!("{}", error_message);
-        log_error_to_file(&error_message);
     }
 }
 
+// Checks the queued off-site links, then persists the dead letters and, in incremental mode, what
+// was seen, reporting only the delta
+pub(crate) async fn finish_crawl(client: &Client, config: &ScraperConfig) {
+    if let Some(checker) = config.external_link_checker() {
+        check_queued_links(checker, client, config).await;
+    }
 
-
-/// Scrapes all meaningful content from an HTML page, including text, images, videos, meta tags, and forms.
-///
-/// # Arguments
-///
-/// * `html` - The HTML content of the page as a string slice.
-/// * `url` - The URL of the current page being scraped.
-/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
-///
-/// # Example
-///
-/// ```
-/// scrape_content("<html>...</html>", "https://example.com", &client).await;
-/// ```
-pub async fn scrape_content(html: &str, url: &str, client: &Client) {
-    // Create a directory structure for storing scraped data
-    let domain = extract_domain(url);
-    let dir = format!("./scraped_data/{}", domain);
-
-    // Ensure the directory structure exists
-    if let Err(e) = create_dir_all(&dir) {
-        eprintln!("Failed to create directory '{}': {}", dir, e);
-        return;
+    let dead_letters = config.dead_letters().take();
+    if !dead_letters.is_empty() {
+        let path = config.dead_letter_path();
+        match write_dead_letters(&path, &dead_letters) {
+            Ok(()) => console!("{} URLs failed after all retries, see {}", dead_letters.len(), path.display()),
+            Err(e) => {
+                let error_message = format!("Failed to write dead letters to '{}': {}", path.display(), e);
+                console_error!("{}", error_message);
+                config.log_error(&error_message);
+            }
+        }
     }
 
-    // Store text content (headers and paragraphs)
-    let mut text_file = match File::create(format!("{}/content.txt", dir)) {
-        Ok(file) => file,
-        Err(e) => {
-            eprintln!("Failed to create text file: {}", e);
-            return;
+    // Broken links, header and parameter findings are in the findings log; their text files are legacy output
+    let legacy_finding_files = config.legacy_finding_files();
+    if let Some(checker) = config.external_link_checker().filter(|checker| !checker.is_empty()) {
+        console!("Verified {} external links", checker.len());
+        if legacy_finding_files {
+            let lines: String = checker.results().iter().map(|link| format!("{}\n", link)).collect();
+            write_output(config, &config.output_dir().join("external_links.txt"), &lines);
         }
-    };
+    }
 
-    let document = Html::parse_document(html);
+    let header_findings = if legacy_finding_files { config.header_findings().hosts() } else { BTreeMap::new() };
+    for (host, findings) in header_findings {
+        let lines: String = findings.iter().map(|finding| format!("{}\n", finding)).collect();
+        write_output(config, &config.output_dir().join(&host).join("header_findings.txt"), &lines);
+    }
 
-    // Extract headers
-    let header_selector = Selector::parse("h1, h2, h3, h4, h5, h6").unwrap();
-    for header in document.select(&header_selector) {
-        writeln!(text_file, "Header: {}", header.inner_html()).unwrap();
+    let mut alternates: BTreeMap<PathBuf, String> = BTreeMap::new();
+    for (page, alternate, kind) in config.alternates().pairs() {
+        alternates.entry(config.domain_dir(&page)).or_default().push_str(&format!("{}\t{}\t{}\n", page, alternate, kind));
+    }
+    for (dir, lines) in alternates {
+        write_output(config, &dir.join(ALTERNATES_FILE), &lines);
     }
 
-    // Extract paragraphs
-    let paragraph_selector = Selector::parse("p").unwrap();
-    for paragraph in document.select(&paragraph_selector) {
-        writeln!(text_file, "Paragraph: {}", paragraph.inner_html()).unwrap();
+    let mut page_classes: BTreeMap<PathBuf, String> = BTreeMap::new();
+    for (url, class) in config.page_classes().pairs() {
+        page_classes.entry(config.domain_dir(&url)).or_default().push_str(&format!("{}\t{}\n", url, class));
+    }
+    for (dir, lines) in page_classes {
+        write_output(config, &dir.join(PAGE_CLASSES_FILE), &lines);
     }
 
-    // Scrape images
-    let img_selector = Selector::parse("img[src]").unwrap();
-    for img in document.select(&img_selector) {
-        if let Some(src) = img.value().attr("src") {
-            let img_url = normalize_link(src, url);
+    if !config.partitions().is_empty() {
+        let budgets: HashMap<String, Option<usize>> = config.partitions().partitions().into_iter().map(|partition| (partition.name, partition.max_pages)).collect();
+        let lines: String = config
+            .partitions()
+            .pages()
+            .into_iter()
+            .map(|(name, pages)| format!("{}\t{}\t{}\n", name, pages, budgets.get(&name).copied().flatten().map_or("-".to_string(), |budget| budget.to_string())))
+            .collect();
+        write_output(config, &config.output_dir().join(PARTITIONS_FILE), &lines);
+    }
 
-            let file_name = img_url
-                .split('/')
-                .last()
-                .unwrap_or("image.jpg")
-                .to_string();
-            let file_path = Path::new(&dir).join(file_name);
-            println!("Downloading image: {}", img_url);
-            download_media(client, &img_url, &file_path).await;
-        }
+    for domain in config.metrics().entities() {
+        let json = serde_json::to_string_pretty(&domain).unwrap_or_default();
+        write_output(config, &config.output_dir().join(&domain.domain).join(ENTITIES_FILE), &json);
     }
 
-    // Scrape videos
-    let video_selector = Selector::parse("video[src], source[src]").unwrap();
-    for video in document.select(&video_selector) {
-        if let Some(src) = video.value().attr("src") {
-            let video_url = normalize_link(src, url);
+    let parameter_findings = if legacy_finding_files { config.parameter_findings().hosts() } else { BTreeMap::new() };
+    for (host, findings) in parameter_findings {
+        let lines: String = findings.iter().map(|finding| format!("{}\n", finding)).collect();
+        write_output(config, &config.output_dir().join(&host).join("parameter_findings.txt"), &lines);
+    }
 
-            let file_name = video_url
-                .split('/')
-                .last()
-                .unwrap_or("video.mp4")
-                .to_string();
-            let file_path = Path::new(&dir).join(file_name);
-            println!("Downloading video: {}", video_url);
-            download_media(client, &video_url, &file_path).await;
+    if let Some(db) = config.crawl_database() {
+        let db = db.lock().unwrap();
+        let delta = db.delta();
+        console!("Incremental crawl: {} new, {} changed, {} unchanged", delta.new.len(), delta.changed.len(), delta.unchanged);
+        for url in delta.new.iter().chain(&delta.changed) {
+            console!("Changed: {}", url);
+        }
+        if let Err(e) = db.save() {
+            let error_message = format!("Failed to save crawl database: {}", e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
         }
     }
+}
 
-    // Scrape meta tags
-    let meta_selector = Selector::parse("meta[name][content]").unwrap();
-    for meta in document.select(&meta_selector) {
-        let name = meta.value().attr("name").unwrap_or("Unnamed");
-        let content = meta.value().attr("content").unwrap_or("");
-        writeln!(text_file, "Meta Tag - Name: {}, Content: {}", name, content).unwrap();
+// Writes an end-of-crawl output file, creating its directory; empty files are not written, and
+// failures are logged instead of failing the crawl
+fn write_output(config: &ScraperConfig, path: &Path, contents: &str) {
+    if contents.is_empty() {
+        return;
     }
-
-    // Scrape forms and inputs
-    let form_selector = Selector::parse("form").unwrap();
-    for form in document.select(&form_selector) {
-        writeln!(text_file, "Form found!").unwrap();
-
-        let input_selector = Selector::parse("input").unwrap();
-        for input in form.select(&input_selector) {
-            let input_name = input.value().attr("name").unwrap_or("Unnamed Input");
-            let input_type = input.value().attr("type").unwrap_or("text");
-            writeln!(
-                text_file,
-                "Input - Name: {}, Type: {}",
-                input_name, input_type
-            )
-            .unwrap();
-        }
+    let written = match path.parent() {
+        Some(dir) => create_dir_all(dir).and_then(|_| std::fs::write(path, contents)),
+        None => std::fs::write(path, contents),
+    };
+    if let Err(e) = written {
+        let error_message = format!("Failed to write '{}': {}", path.display(), e);
+        console_error!("{}", error_message);
+        config.log_error(&error_message);
     }
+}
 
-    // Scrape for emails
-    scrape_for_emails(html, &dir);
+// Depth-tracking worker behind `recursive_scrape_with_config`
+pub(crate) fn scrape_to_depth<'a>(
+    url: &'a str,
+    client: &'a Client,
+    config: &'a ScraperConfig,
+    visited: &'a mut HashSet<String>,
+    stats: &'a mut CrawlStats,
+    depth: i32,
+) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    crawl_seeds(vec![url.to_string()], client, config, visited, stats, depth)
 }
 
-/// Extracts the domain from a URL for folder naming purposes.
-///
-/// # Arguments
-///
-/// * `url` - The URL from which to extract the domain.
-///
-/// # Returns
-///
-/// A `String` containing the domain.
-///
-/// # Example
-///
-/// ```
-/// let domain = extract_domain("https://example.com/path");
-/// assert_eq!(domain, "example.com");
-/// ```
-pub fn extract_domain(url: &str) -> String {
-    let parsed_url = Url::parse(url).expect("Invalid URL");
-    parsed_url.host_str().unwrap_or("unknown_domain").to_string()
+// Crawls from several seeds through one priority frontier ordered by the config's `UrlScorer`
+pub(crate) fn crawl_seeds<'a>(
+    seeds: Vec<String>,
+    client: &'a Client,
+    config: &'a ScraperConfig,
+    visited: &'a mut HashSet<String>,
+    stats: &'a mut CrawlStats,
+    depth: i32,
+) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let mut frontier = seed_frontier(&seeds, depth, config);
+        drain_frontier(&mut frontier, client, config, visited, stats).await;
+    })
 }
 
-/// Scrapes JavaScript content for API keys or tokens.
-///
-/// # Arguments
-///
-/// * `html` - The HTML content of the page as a string slice.
-///
-/// # Example
-///
-/// ```
-/// scrape_js_content("<script>var apiKey = '12345';</script>");
-/// ```
-pub fn scrape_js(html: &str) {
-    let document = Html::parse_document(html);
-    let script_selector = Selector::parse("script").unwrap();
+// Crawls the seeds of a run and the config's imported frontier, then exports the crawled pages
+// when the config has a frontier export path
+pub(crate) async fn crawl_run(seeds: Vec<String>, client: &Client, config: &ScraperConfig, visited: &mut HashSet<String>, stats: &mut CrawlStats) {
+    config.resolve_crawl_database();
+    let mut frontier = seed_frontier(&seeds, 0, config);
+    for entry in config.imported_frontier() {
+        frontier.push(&entry.url, entry.depth, entry.priority);
+    }
+    let crawled = drain_frontier(&mut frontier, client, config, visited, stats).await;
 
-    for script in document.select(&script_selector) {
-        let script_content = script.inner_html();
-        if script_content.contains("apiKey") || script_content.contains("token") {
-            println!("Potential API key or token found in JS: {}", script_content);
+    let Some(path) = config.frontier_export() else { return };
+    match export_frontier(&crawled, path) {
+        Ok(()) => console!("Exported {} frontier entries to {}", crawled.len(), path.display()),
+        Err(e) => {
+            let error_message = format!("Failed to export the frontier to '{}': {}", path.display(), e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
         }
     }
 }
 
-/// Scrapes for errors and stack traces in the HTML content.
-///
-/// # Arguments
-///
-/// * `html` - The HTML content of the page as a string slice.
-///
-/// # Example
-///
-/// ```
-/// scrape_for_errors("<html><body>Error: Stack trace</body></html>");
-/// ```
-pub fn scrape_for_errors(html: &str) {
-    if html.contains("Exception") || html.contains("Stack trace") {
-        println!("Potential error or stack trace found in the page:\n{}", html);
+// Returns a frontier holding the seeds, scored by the config's `UrlScorer`
+fn seed_frontier(seeds: &[String], depth: i32, config: &ScraperConfig) -> Frontier {
+    let mut frontier = Frontier::new();
+    for seed in seeds {
+        let candidate = UrlCandidate { url: seed, depth, anchor_text: "", referrer: None, referrer_relevance: 0.0 };
+        frontier.push(seed, depth, config.url_scorer().score(&candidate));
     }
+    frontier
 }
 
-/// Scrapes for emails and saves them to a file.
-///
-/// # Arguments
-///
-/// * `html` - The HTML content of the page as a string slice.
-/// * `dir` - The directory where the emails.txt file will be saved.
-///
-/// # Example
-///
-/// ```
-/// scrape_for_emails("<p>Contact us at info@example.com</p>", "./scraped_data/example.com");
-/// ```
-pub fn scrape_for_emails(html: &str, dir: &str) {
-    let email_regex = match Regex::new(r"[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}") {
-        Ok(regex) => regex,
-        Err(e) => {
-            eprintln!("Failed to compile email regex: {}", e);
-            return;
-        }
-    };
-
-    let email_file_path = format!("{}/emails.txt", dir);
-    let mut email_file = match File::create(&email_file_path) {
-        Ok(file) => file,
-        Err(e) => {
-            eprintln!("Failed to create email file '{}': {}", email_file_path, e);
-            return;
-        }
-    };
-
-    for email in email_regex.find_iter(html) {
-        if writeln!(email_file, "{}", email.as_str()).is_err() {
-            eprintln!("Failed to write email '{}' to file '{}'", email.as_str(), email_file_path);
-        }
+// Visits frontier entries until the frontier is empty, returning the entries that were crawled
+async fn drain_frontier(frontier: &mut Frontier, client: &Client, config: &ScraperConfig, visited: &mut HashSet<String>, stats: &mut CrawlStats) -> Vec<FrontierEntry> {
+    let mut crawled = Vec::new();
+    while let Some(entry) = frontier.pop() {
+        let Some(links) = visit_entry(&entry, client, config, visited, stats).await else { continue };
+        queue_links(frontier, &entry, links, config, visited);
+        crawled.push(entry);
     }
+    crawled
 }
 
+// Checks the scope, quota, circuit and dedup policy for a frontier entry and scrapes it, returning
+// its links with their anchor text, or `None` when the entry was skipped
+pub(crate) async fn visit_entry(
+    entry: &FrontierEntry,
+    client: &Client,
+    config: &ScraperConfig,
+    visited: &mut HashSet<String>,
+    stats: &mut CrawlStats,
+) -> Option<Vec<(String, String)>> {
+    if visited.contains(&entry.url) {
+        return None;
+    }
+    // Seeds and links that only look like URLs can't be routed, stored or requested
+    if let Err(e) = Url::parse(&entry.url) {
+        let error_message = format!("Skipping unparsable URL '{}': {}", entry.url, e);
+        console_error!("{}", error_message);
+        config.log_error(&error_message);
+        visited.insert(entry.url.clone());
+        stats.pages_failed += 1;
+        return None;
+    }
+    if !config.is_url_allowed(&entry.url) {
+        stats.links_out_of_scope += 1;
+        return None;
+    }
+    if url_quota_exhausted(&entry.url, config) {
+        console!("Skipping {} (byte quota of its host used up)", entry.url);
+        config.dead_letters().push(DeadLetter::page(&entry.url, "byte quota exhausted", 0));
+        stats.pages_over_quota += 1;
+        return None;
+    }
+    if host_circuit_open(&entry.url, config) {
+        console!("Skipping {} (circuit open for its host)", entry.url);
+        config.dead_letters().push(DeadLetter::page(&entry.url, "circuit open", 0));
+        stats.pages_circuit_open += 1;
+        return None;
+    }
+    visited.insert(entry.url.clone());
+    if config.dedup_policy().seen_url(&entry.url) {
+        stats.pages_duplicate += 1;
+        return None;
+    }
+    if let (true, UrlClass::Asset(kind)) = (config.route_assets(), entry.class()) {
+        fetch_asset(&entry.url, kind, client, config).await;
+        return Some(Vec::new());
+    }
+    if !config.partitions().admit(&entry.url) {
+        console!("Skipping {} (page budget of its partition used up)", entry.url);
+        stats.pages_over_budget += 1;
+        return None;
+    }
 
-/// Fetches a web page and prints the response status, demonstrating cookie handling.
-///
-/// # Arguments
-///
-/// * `url` - The URL to fetch.
-/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
-///
-/// # Example
-///
-/// ```
-/// fetch_with_cookies("https://example.com", &client).await;
-/// ```
-pub async fn fetch_with_cookies(url: &str, client: &Client) {
-    if let Ok(response) = client.get(url).send().await {
-        println!("Response status: {}", response.status());
-        // Note: For actual cookie handling, enable the cookie store feature in reqwest.
+    let links = scrape_page(&entry.url, client, config, stats).await;
+    Some(verify_external_links(&entry.url, links, client, config).await)
+}
+
+// Hands a URL classified as an asset straight to the media pipeline instead of fetching it as a page
+async fn fetch_asset(url: &str, kind: MediaKind, client: &Client, config: &ScraperConfig) {
+    config.metrics().record_findings("assets routed", 1);
+    if config.discovery_only() {
+        return;
+    }
+    if !config.download_enabled(kind) {
+        console!("Skipping {} link: {}", kind, url);
+        return;
     }
+    let file_path = config.domain_dir(url).join(media_file_name(url, "download.bin"));
+    console!("Downloading {}: {}", kind, url);
+    let _ = download_media_with_config(client, url, &file_path, Some(config)).await;
 }
 
-/// Checks for common open directories on the server.
-///
-/// # Arguments
-///
-/// * `url` - The base URL to check.
-/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
-///
-/// # Example
-///
-/// ```
-/// check_open_directories("https://example.com", &client).await;
-/// ```
-pub async fn check_open_directories(url: &str, client: &Client) {
-    let directories = vec!["/backup", "/config", "/logs", "/uploads"];
-    for dir in directories {
-        let full_url = format!("{}{}", url, dir);
-        if let Ok(response) = client.get(&full_url).send().await {
-            if response.status().is_success() {
-                println!("Open directory found: {}", full_url);
-            }
+// Queues the unvisited links of a scraped entry, unless links aren't followed or its depth is the limit
+pub(crate) fn queue_links(frontier: &mut Frontier, entry: &FrontierEntry, links: Vec<(String, String)>, config: &ScraperConfig, visited: &HashSet<String>) {
+    if !config.follow_links() || entry.depth >= config.max_depth() {
+        return;
+    }
+    for (link, anchor_text) in links {
+        if visited.contains(&link) {
+            continue;
         }
+        let candidate = UrlCandidate {
+            url: &link,
+            depth: entry.depth + 1,
+            anchor_text: &anchor_text,
+            referrer: Some(&entry.url),
+            referrer_relevance: entry.priority,
+        };
+        frontier.push(&link, entry.depth + 1, config.url_scorer().score(&candidate));
     }
 }
 
-/// Fetches and parses the robots.txt file.
-///
-/// # Arguments
-///
-/// * `url` - The base URL to fetch robots.txt from.
-/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
-///
-/// # Example
-///
-/// ```
-/// fetch_robots_txt("https://example.com", &client).await;
-/// ```
-pub async fn fetch_robots_txt(url: &str, client: &Client) {
-    let robots_url = format!("{}/robots.txt", url.trim_end_matches('/'));
-    if let Ok(response) = client.get(&robots_url).send().await {
-        if let Ok(body) = response.text().await {
-            let disallowed_paths: Vec<&str> = body
-                .lines()
-                .filter(|line| line.starts_with("Disallow"))
-                .map(|line| line.split(": ").nth(1).unwrap_or("/"))
-                .collect();
-
-            for path in disallowed_paths {
-                println!("Disallowed path found: {}", path);
+// Fetches and scrapes one page, returning its links with their anchor text
+async fn scrape_page(url: &str, client: &Client, config: &ScraperConfig, stats: &mut CrawlStats) -> Vec<(String, String)> {
+    match send_page_request(url, client, config).await {
+        Ok(response) => {
+            // Unchanged since the last run: nothing to extract, but keep crawling through it
+            if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+                let known_page = config.crawl_database().and_then(|db| {
+                    let mut db = db.lock().unwrap();
+                    let links = db.mark_not_modified(url)?;
+                    Some((links, db.get(url)?.content_hash.clone()))
+                });
+                if let Some((links, content_hash)) = known_page {
+                    console!("Unchanged: {}", url);
+                    stats.pages_unchanged += 1;
+                    if !config.discovery_only() {
+                        record_unchanged_page(url, &content_hash, config);
+                    }
+                    return links.into_iter().map(|link| (link, String::new())).collect();
+                }
+            }
+
+            let status = response.status().as_u16();
+            let headers = response.headers().clone();
+            record_header_findings(url, &headers, config);
+            let content_type = headers.get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok());
+            match request::read_body(response, config.max_page_bytes(), config).await {
+                Ok(None) => {
+                    console!("Skipping page larger than {} bytes: {}", config.max_page_bytes(), url);
+                    stats.pages_too_large += 1;
+                    Vec::new()
+                }
+                Ok(Some(body)) => match route_response(content_type, &body) {
+                    ContentRoute::Html => {
+                        let html = page::decode_body(&body, content_type);
+                        drop(body);
+                        // Nesting is counted before anything parses the page, the bot wall check included
+                        let max_depth = config.max_nesting_depth();
+                        let Some((html, depth)) = with_page_timeout(url, config, move || {
+                            let depth = document_depth(&html, max_depth);
+                            (html, depth)
+                        })
+                        .await
+                        else {
+                            stats.pages_failed += 1;
+                            return Vec::new();
+                        };
+                        if depth > max_depth {
+                            console!("Skipping page nested deeper than {} elements: {}", max_depth, url);
+                            stats.pages_too_large += 1;
+                            return Vec::new();
+                        }
+                        let Some((html, status)) = render_if_blocked(html, url, status, config, stats).await else { return Vec::new() };
+                        scrape_html_page(&html, url, status, &headers, client, config, stats).await
+                    }
+                    route @ (ContentRoute::Json | ContentRoute::Xml) => {
+                        console!("Capturing: {}", url);
+                        stats.pages_scraped += 1;
+                        store_capture(&page::decode_body(&body, content_type), url, config, route)
+                    }
+                    ContentRoute::Media(kind) => {
+                        stats.pages_scraped += 1;
+                        if !config.discovery_only() {
+                            store_media_response(&body, url, config, kind).await;
+                        }
+                        Vec::new()
+                    }
+                },
+                Err(e) => {
+                    let error_message = format!("Failed to get HTML content from '{}': {}", url, e);
+                    console_error!("{}", error_message);
+                    config.log_error(&error_message);
+                    stats.pages_failed += 1;
+                    Vec::new()
+                }
             }
         }
+        Err(e) => {
+            let attempts = config.page_retries() + 1;
+            let error_message = format!("Failed to request '{}' after {} attempts: {}", url, attempts, e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+            config.dead_letters().push(DeadLetter::page(url, &e, attempts));
+            stats.pages_failed += 1;
+            Vec::new()
+        }
     }
 }
 
-/// Executes the entire scraping workflow for the provided URL, including:
-/// - Fetching `robots.txt` to check for disallowed paths
-/// - Checking for open directories
-/// - Fetching content with cookies
-/// - Performing recursive scraping on links found in the website
-///
-/// The function mimics human behavior by introducing random delays
-/// between requests to avoid overwhelming servers.
-///
-/// # Arguments
-/// * `url` - The URL to start scraping from.
-/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
-///
-/// # Example
-/// ```
-/// let client = Client::new();
-/// run("https://example.com", &client).await;
-/// ```
-pub async fn run(url: &str, client: &Client) {
-    let mut visited = HashSet::new();
-
-    println!("Starting scraping workflow for {}", url);
-
-    // Fetch `robots.txt`, open directories, and perform cookie-based scraping
-    fetch_robots_txt(url, client).await;
-    check_open_directories(url, client).await;
-    fetch_with_cookies(url, client).await;
+// Retries a page that looks like a bot wall once through the page renderer, returning the page
+// and status to scrape, or `None` when the page stays blocked. Without a renderer, the page is
+// scraped as fetched
+async fn render_if_blocked(html: String, url: &str, status: u16, config: &ScraperConfig, stats: &mut CrawlStats) -> Option<(String, u16)> {
+    let Some(wall) = detect_bot_wall(status, &html) else { return Some((html, status)) };
+    config.metrics().record_findings("bot walls", 1);
+    let Some(renderer) = config.page_renderer() else {
+        console!("Suspected {} at {} (no page renderer set)", wall, url);
+        return Some((html, status));
+    };
 
-    // Start recursive scraping from the base URL
-    recursive_scrape(url, client, &mut visited).await;
+    console!("Suspected {} at {}, retrying with the page renderer", wall, url);
+    config.request_limiter().wait(url).await;
+    let reason = match renderer.render(url).await {
+        Ok(rendered) if detect_bot_wall(200, &rendered).is_none() => {
+            config.metrics().record_findings("pages rendered", 1);
+            return Some((rendered, 200));
+        }
+        Ok(_) => format!("{} (also when rendered)", wall),
+        Err(e) => format!("{} (rendering failed: {})", wall, e),
+    };
+    let error_message = format!("Blocked at '{}': {}", url, reason);
+    console_error!("{}", error_message);
+    config.log_error(&error_message);
+    config.dead_letters().push(DeadLetter::page(url, &reason, 2));
+    stats.pages_failed += 1;
+    None
+}
 
-    // Introduce a delay to mimic human-like browsing behavior
-    random_delay(2, 5).await;
+// Extracts an HTML page, returning its links with their anchor text
+async fn scrape_html_page(html: &str, url: &str, status: u16, headers: &header::HeaderMap, client: &Client, config: &ScraperConfig, stats: &mut CrawlStats) -> Vec<(String, String)> {
+    if config.dedup_policy().seen_content(url, &sha256_hex(html.as_bytes())) {
+        console!("Skipping page with duplicate content: {}", url);
+        stats.pages_duplicate += 1;
+        return Vec::new();
+    }
 
-    println!("Scraping workflow completed for {}", url);
-}
+    let mut links: Vec<(String, String)> = Vec::new();
+    for record in extract_link_records(html, url) {
+        if !links.iter().any(|(link, _)| *link == record.url) {
+            links.push((record.url, record.anchor_text));
+        }
+    }
+    if config.discover_js_endpoints() {
+        for endpoint in inline_script_endpoints(html, url) {
+            if !links.iter().any(|(link, _)| *link == endpoint) {
+                links.push((endpoint, String::new()));
+            }
+        }
+    }
+    if let Some(skip_links) = handle_alternates(html, url, &mut links, config) {
+        stats.pages_alternate += 1;
+        return skip_links;
+    }
 
+    if !config.partitions().is_empty() {
+        config.partitions().record_hreflang(html, url);
+    }
+    if config.discovery_only() {
+        console!("Discovered: {}", url);
+        stats.pages_scraped += 1;
+        return links;
+    }
 
-use std::fs::OpenOptions;
-/// Logs an error message to a file.
-///
-/// # Arguments
-///
-/// * `message` - The error message to log.
-fn log_error_to_file(message: &str) {
-    let log_file_path = "error.log";
-    
-    // Open the file in append mode, creating it if it doesn't exist
-    let mut file = match OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_file_path)
-    {
-        Ok(f) => f,
-        Err(e) => {
-            eprintln!("Failed to open or create error log file '{}': {}", log_file_path, e);
-            return;
+    let class = classify_page(html, url, status);
+    config.page_classes().record(url, class);
+    config.metrics().record_findings(&format!("{} pages", class), 1);
+
+    // Rotating tokens and timestamps would make every page look changed
+    let monitored = config.crawl_database().map(|db| {
+        let content = if config.normalize_monitored_pages() { normalize_html(html) } else { html.to_string() };
+        let change = db.lock().unwrap().compare(url, &content);
+        (db, content, change)
+    });
+    // The database only learns of a page once the page is fully processed, so a failed page is retried next run
+    let record_page_version = |links: &[(String, String)]| {
+        if let Some((db, content, _)) = &monitored {
+            db.lock().unwrap().update(url, headers, content, links.iter().map(|(link, _)| link.clone()).collect());
         }
     };
 
-    // Write the error message to the file
-    if let Err(e) = writeln!(file, "{}", message) {
-        eprintln!("Failed to write to error log file '{}': {}", log_file_path, e);
+    let content_hash = monitored.as_ref().map(|(_, content, _)| sha256_hex(content.as_bytes()));
+    if monitored.as_ref().is_some_and(|(_, _, change)| *change == PageChange::Unchanged) {
+        console!("Unchanged: {}", url);
+        stats.pages_unchanged += 1;
+        record_unchanged_page(url, content_hash.as_deref().unwrap_or_default(), config);
+        record_page_version(&links);
+    } else {
+        console!("Scraping: {}", url);
+        let Some((extract, findings)) = extract_with_timeout(html, url, config).await else {
+            stats.pages_failed += 1;
+            return links;
+        };
+        stats.pages_scraped += 1;
+        if config.save_html_snapshots() {
+            store_snapshot(html, url, config, RobotsDirectives::from_headers(headers));
+        }
+        store_page(extract, html, url, content_hash.as_deref(), client, config).await;
+        config.metrics().record_findings("js findings", findings.iter().filter(|finding| finding.kind == FindingKind::JsKeyword).count());
+        config.log_findings(&findings);
+        let page_links = links.clone();
+        for (link, anchor_text) in merge_frames(html, url, client, config).await {
+            if !links.iter().any(|(known, _)| *known == link) {
+                links.push((link, anchor_text));
+            }
+        }
+        record_page_version(&page_links);
     }
+    links
 }
 
-
-/// Sleeps for a random duration between a given range, mimicking human browsing behavior.
-///
-/// # Arguments
-///
-/// * `min_secs` - Minimum number of seconds to sleep.
-/// * `max_secs` - Maximum number of seconds to sleep.
-///
-/// # Example
-///
-/// ```
-/// random_delay(1, 5).await;
-/// ```
-pub async fn random_delay(min_secs: u64, max_secs: u64) {
-    let delay = rand::random::<u64>() % (max_secs - min_secs + 1) + min_secs;
-    sleep(Duration::from_secs(delay)).await;
+// Lists a page skipped as unchanged in the page index of the crawl with its stored hash, so a comparison
+// with the crawl that last scraped it does not report it as removed
+fn record_unchanged_page(url: &str, content_hash: &str, config: &ScraperConfig) {
+    let dir = config.domain_dir(url);
+    if let Err(e) = create_dir_all(&dir).and_then(|_| config.page_index().record_hashed(&dir, url, Some(content_hash))) {
+        let msg = format!("Failed to update page index in '{}': {}", dir.display(), e);
+        console_error!("{}", msg);
+        config.log_error(&msg);
+    }
 }
 
+// Parses a page and finds its inline script keywords and error traces on a blocking thread, so
+// the page timeout of the config also bounds parsing. Nothing of a page that times out is stored
+async fn extract_with_timeout(html: &str, url: &str, config: &ScraperConfig) -> Option<(PageExtract, Vec<Finding>)> {
+    let options = ExtractOptions::new(config, url);
+    let legacy_finding_files = config.legacy_finding_files();
+    let (page_html, page_url) = (html.to_string(), url.to_string());
+    with_page_timeout(url, config, move || {
+        (extract_page(&page_html, &page_url, &options), page_findings(&page_html, &page_url, legacy_finding_files))
+    })
+    .await
+}
 
-/// Recursively scrapes web pages starting from the given URL, looking for the target phrase.
-/// If the target phrase is not found in the HTML content of a page, it stops scraping in that direction.
-///
-/// # Arguments
-/// * `url`: The starting URL for scraping.
-/// * `client`: An instance of `reqwest::Client` for making HTTP requests.
-/// * `config`: An optional reference to `ScraperConfig` for controlling scraper behavior.
-/// * `visited`: A `HashSet` that tracks visited URLs.
-/// * `target_phrase`: The phrase to search for in the HTML content.
-///
-/// This function performs breadth-first scraping, but only continues to follow links
-/// if the target phrase is found in the current page's content.
-pub async fn rec_scrape(url: &str, client: &Client, config: Option<&ScraperConfig>, visited: &mut HashSet<String>, target_phrase: &str) {
-    let mut queue = VecDeque::new();
-    queue.push_back(url.to_string());
-    let mut current_depth = 0; // Initialize scraping depth
+// Runs the processing of a page on a blocking thread, bounded by the page timeout of the config,
+// returning `None` when it times out or fails
+async fn with_page_timeout<T: Send + 'static>(url: &str, config: &ScraperConfig, process: impl FnOnce() -> T + Send + 'static) -> Option<T> {
+    let session_id = run::current_session_id();
+    let processing = tokio::task::spawn_blocking(move || run::in_session_blocking(session_id, process));
+    let outcome = match config.page_timeout() {
+        Some(limit) => match tokio::time::timeout(limit, processing).await {
+            Ok(outcome) => outcome,
+            Err(_) => {
+                let error_message = format!("Timed out processing '{}' after {:?}", url, limit);
+                console_error!("{}", error_message);
+                config.log_error(&error_message);
+                return None;
+            }
+        },
+        None => processing.await,
+    };
+    match outcome {
+        Ok(processed) => Some(processed),
+        Err(e) => {
+            let error_message = format!("Failed to process '{}': {}", url, e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+            None
+        }
+    }
+}
 
-    // Get configuration values or defaults
-    let follow_links = config.map_or(true, |c| c.follow_links()); // Default: true
-    let max_depth = config.map_or(3, |c| c.max_depth()); // Default: 3
-    let user_agent = config.and_then(|c| c.user_agent().cloned()); // Default: None (no user agent)
+// Returns the inline script keywords and error traces of a page as findings, printing them as
+// well when legacy finding output is kept
+fn page_findings(html: &str, url: &str, legacy_finding_files: bool) -> Vec<Finding> {
+    let js_findings = if legacy_finding_files { scrape_js(html) } else { inline_js_findings(html) };
+    let mut findings: Vec<Finding> = js_findings.iter().map(|finding| Finding::new(FindingKind::JsKeyword, url, &finding.to_string())).collect();
+    if let Some(snippet) = error_trace_snippet(html) {
+        if legacy_finding_files {
+            scrape_for_errors(html);
+        }
+        findings.push(Finding::new(FindingKind::ErrorPage, url, &snippet));
+    }
+    findings
+}
 
-    while let Some(current_url) = queue.pop_front() {
-        if visited.contains(&current_url) {
+// Appends the content of the frames of a page to its content file, attributed to each frame, and
+// returns the links found in them. Same-origin frames are fetched unless `follow_frames` is off;
+// cross-origin frames are recorded as findings, and fetched only with `follow_cross_origin_frames`
+async fn merge_frames(html: &str, url: &str, client: &Client, config: &ScraperConfig) -> Vec<(String, String)> {
+    let frames = extract_frames(html, url);
+    let cross_origin: Vec<Finding> = frames.iter().filter(|frame| !frame.same_origin).map(|frame| Finding::new(FindingKind::CrossOriginFrame, url, &frame.url)).collect();
+    config.log_findings(&cross_origin);
+
+    let (mut lines, mut links) = (Vec::new(), Vec::new());
+    for frame in frames {
+        let follow = if frame.same_origin { config.follow_frames() } else { config.follow_cross_origin_frames() };
+        if !follow || !config.is_url_allowed(&frame.url) || url_quota_exhausted(&frame.url, config) {
+            continue;
+        }
+        let response = match send_page_request(&frame.url, client, config).await {
+            Ok(response) => response,
+            Err(e) => {
+                let error_message = format!("Failed to request frame '{}' of '{}': {}", frame.url, url, e);
+                console_error!("{}", error_message);
+                config.log_error(&error_message);
+                continue;
+            }
+        };
+        let content_type = response.headers().get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).map(str::to_string);
+        let Ok(Some(body)) = request::read_body(response, config.max_page_bytes(), config).await else { continue };
+        if route_response(content_type.as_deref(), &body) != ContentRoute::Html {
             continue;
         }
 
-        println!("Visiting: {}", current_url);
-        visited.insert(current_url.clone());
+        let frame_html = page::decode_body(&body, content_type.as_deref());
+        console!("Merging frame: {}", frame.url);
+        lines.extend(frames::frame_content_lines(&frame_html, &frame.url).iter().map(|line| config.redact(line)));
+        links.extend(extract_link_records(&frame_html, &frame.url).into_iter().map(|record| (record.url, record.anchor_text)));
+    }
 
-        // Build the request with optional user agent
-        let mut request = client.get(&current_url);
-        if let Some(ref agent) = user_agent {
-            request = request.header(header::USER_AGENT, agent);
+    if !lines.is_empty() {
+        let path = page_file(&config.domain_dir(url), "content", url);
+        let appended = OpenOptions::new().create(true).append(true).open(&path).and_then(|mut file| lines.iter().try_for_each(|line| writeln!(file, "{}", line)));
+        if let Err(e) = appended {
+            let error_message = format!("Failed to append frame content to '{}': {}", path.display(), e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
         }
+    }
+    links
+}
 
-        let response = match request.send().await {
-            Ok(response) => response,
-            Err(_) => continue, // Skip the URL if there's an error
-        };
+// Records the AMP and mobile versions of a page and applies the alternate policy, returning the
+// links to crawl instead when the page is not to be stored
+fn handle_alternates(html: &str, url: &str, links: &mut Vec<(String, String)>, config: &ScraperConfig) -> Option<Vec<(String, String)>> {
+    let page = extract_alternates(html, url);
+    for alternate in &page.alternates {
+        config.alternates().record(url, &alternate.url, alternate.kind);
+    }
+    if let Some(primary) = page.primary(url) {
+        config.alternates().record(primary, url, AlternateKind::Amp);
+    }
 
-        if response.status().is_success() {
-            let html = match response.text().await {
-                Ok(html) => html,
-                Err(_) => continue, // Skip if there's an error reading the content
-            };
+    match config.alternate_policy() {
+        AlternatePolicy::Separate => None,
+        AlternatePolicy::SkipAlternates => {
+            if let Some(primary) = config.alternates().primary_of(url) {
+                console!("Skipping {} (alternate version of {})", url, primary);
+                return Some(vec![(primary, String::new())]);
+            }
+            links.retain(|(link, _)| !page.alternates.iter().any(|alternate| alternate.url == *link));
+            None
+        }
+        AlternatePolicy::PreferAlternates => {
+            let alternate = page.alternates.iter().find(|alternate| alternate.url != url && config.is_url_allowed(&alternate.url))?;
+            console!("Skipping {} (preferring its alternate version {})", url, alternate.url);
+            let mut links = std::mem::take(links);
+            links.insert(0, (alternate.url.clone(), String::new()));
+            Some(links)
+        }
+    }
+}
 
-            if should_scrape_content(&html, target_phrase) {
-                println!("Target phrase found in: {}", current_url);
+// Scans the headers of a page response, reporting what is new for its host
+fn record_header_findings(url: &str, headers: &header::HeaderMap, config: &ScraperConfig) {
+    let host = Url::parse(url).ok().and_then(|parsed| parsed.host_str().map(str::to_string)).unwrap_or_default();
+    let findings = config.header_findings().record(&host, scan_response_headers(headers));
+    config.metrics().record_findings("header findings", findings.len());
+    for finding in &findings {
+        console!("{} ({})", finding, host);
+    }
+    config.log_findings(&findings.iter().map(|finding| Finding::new(FindingKind::Header, url, &finding.to_string())).collect::<Vec<_>>());
+}
 
-                // Only follow links if target_phrase is found and depth is within limits
-                if follow_links && current_depth < max_depth {
-                    let links = extract_links(&html, &current_url);
-                    for link in links {
-                        if !visited.contains(&link) {
-                            queue.push_back(link); // Only add links if the phrase is found
-                        }
-                    }
-                    current_depth += 1; // Increase depth after following links
+// Returns whether the circuit breaker currently refuses requests to the host of `url`
+fn host_circuit_open(url: &str, config: &ScraperConfig) -> bool {
+    let host = Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default();
+    config.circuit_breaker().is_some_and(|breaker| breaker.is_open(&host))
+}
+
+// Returns whether the host of `url` has used up its byte quota
+fn url_quota_exhausted(url: &str, config: &ScraperConfig) -> bool {
+    let host = Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default();
+    config.host_quota_exhausted(&host)
+}
+
+// Sends a page request, retrying connection failures and 5xx/429 answers with a growing backoff
+// until they succeed, run out, or open the circuit of the host
+async fn send_page_request(url: &str, client: &Client, config: &ScraperConfig) -> Result<reqwest::Response, String> {
+    let attempts = config.page_retries() + 1;
+    let mut last_error = String::new();
+    for attempt in 1..=attempts {
+        if attempt > 1 && host_circuit_open(url, config) {
+            return Err(format!("{} (circuit open)", last_error));
+        }
+        if attempt > 1 && url_quota_exhausted(url, config) {
+            return Err(format!("{} (byte quota exhausted)", last_error));
+        }
+        let host = request::host_of(url);
+        let (result, elapsed) = request::send_timed(client, Method::GET, url, config, |request| match config.crawl_database() {
+            Some(db) => db.lock().unwrap().conditional_request(url, request),
+            None => request,
+        })
+        .await;
+        match result {
+            Err(e) if e.is_skipped() => return Err(e.to_string()),
+            _ => config.metrics().record_response_time(&host, elapsed),
+        }
+        match result {
+            Ok(response) if response.status().is_server_error() || response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                last_error = format!("HTTP {}", response.status());
+            }
+            Ok(response) => {
+                if let Some(breaker) = config.circuit_breaker() {
+                    breaker.record_success(&host, elapsed);
                 }
-            } else {
-                println!("Target phrase not found in: {}", current_url);
-                // Do not enqueue links from this page, discontinue following in this direction
-                continue;
+                return Ok(response);
             }
+            Err(e) => last_error = e.to_string(),
+        }
+        if config.circuit_breaker().is_some_and(|breaker| breaker.record_failure(&host, elapsed)) {
+            let error_message = format!("Opened circuit for {} after repeated failures: {}", host, last_error);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+        }
+        if attempt < attempts {
+            sleep(Duration::from_millis(500 * attempt as u64)).await;
         }
     }
+    Err(last_error)
 }
 
-/// Checks if the given content contains the target phrase.
-///
-/// # Arguments
-/// * `content`: The HTML content of the page as a string.
-/// * `target_phrase`: The phrase to search for within the content.
-///
-/// Returns `true` if the target phrase is found, otherwise `false`.
-pub fn should_scrape_content(content: &str, target_phrase: &str) -> bool {
-    content.contains(target_phrase)
+// Queues the off-site links of a page for verification when it is enabled, returning the links to crawl;
+// the queue is checked at the end of the crawl, or right away once it is full
+async fn verify_external_links(url: &str, links: Vec<(String, String)>, client: &Client, config: &ScraperConfig) -> Vec<(String, String)> {
+    let Some(checker) = config.external_link_checker() else { return links };
+    let (external, internal): (Vec<_>, Vec<_>) = links.into_iter().partition(|(link, _)| is_external_link(link, url));
+    // Links to hosts outside the domain lists are not checked either
+    let external: Vec<String> = external.into_iter().map(|(link, _)| link).filter(|link| config.is_url_allowed(link)).collect();
+    if checker.enqueue(external, url) {
+        check_queued_links(checker, client, config).await;
+    }
+    internal
 }
 
-pub struct ScraperConfig {
-    follow_links: bool,
-    max_depth: i32,
-    user_agent: Option<String>,
+// Checks the queued off-site links, reporting the broken ones as findings
+async fn check_queued_links(checker: &ExternalLinkChecker, client: &Client, config: &ScraperConfig) {
+    let checked = checker.drain(client, config).await;
+    let broken: Vec<Finding> = checked.iter().filter(|link| link.is_broken()).map(|link| Finding::new(FindingKind::BrokenLink, &link.found_on, &link.to_string())).collect();
+    config.metrics().record_findings("broken external links", broken.len());
+    config.log_findings(&broken);
 }
 
-impl ScraperConfig {
-    pub fn new(follow_links: bool, max_depth: i32, user_agent: Option<String>) -> Self {
-        ScraperConfig {
-            follow_links,
-            max_depth,
-            user_agent,
+// Runs parameter discovery on the in-scope forms and script endpoints of a page not probed before
+async fn probe_parameters(html: &str, url: &str, forms: &[FormSummary], client: &Client, config: &ScraperConfig) {
+    let Some(wordlist) = config.parameter_wordlist() else { return };
+    let mut targets: Vec<(String, String, Vec<&str>)> = forms
+        .iter()
+        .map(|form| (form.method.clone(), form.action.clone(), form.inputs.iter().map(|input| input.name.as_str()).collect()))
+        .collect();
+    targets.extend(inline_script_endpoints(html, url).into_iter().map(|endpoint| ("GET".to_string(), endpoint, Vec::new())));
+
+    for (method, endpoint, known) in targets {
+        if !endpoint.starts_with("http") || !config.is_url_allowed(&endpoint) || !config.parameter_findings().claim(&method, &endpoint) {
+            continue;
+        }
+        let names: Vec<String> = wordlist.iter().filter(|name| !known.contains(&name.as_str())).cloned().collect();
+        let findings = discover_parameters(&method, &endpoint, &names, client, Some(config)).await;
+        let host = Url::parse(&endpoint).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default();
+        config.metrics().record_findings("parameter findings", findings.len());
+        for finding in &findings {
+            console!("{}", finding);
         }
+        config.log_findings(&findings.iter().map(|finding| Finding::new(FindingKind::Parameter, &finding.url, &finding.to_string())).collect::<Vec<_>>());
+        config.parameter_findings().record(&host, &findings);
     }
+}
 
-    // Method to update whether or not to follow links
-    pub fn set_follow_links(&mut self, follow: bool) {
-        self.follow_links = follow;
+// Finds the API endpoints requested by the inline scripts of a page
+fn inline_script_endpoints(html: &str, url: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let script_selector = Selector::parse("script:not([src])").unwrap();
+    let mut endpoints = Vec::new();
+    for script in document.select(&script_selector) {
+        for endpoint in find_js_endpoints(&script.inner_html(), url) {
+            if !endpoints.contains(&endpoint) {
+                endpoints.push(endpoint);
+            }
+        }
     }
+    endpoints
+}
 
-    // Method to update the max depth of scraping
-    pub fn set_max_depth(&mut self, depth: i32) {
-        self.max_depth = depth;
+// Stores a JSON or XML response pretty-printed in the domain's captures directory next to the
+// emails and URLs found inside it, returning those URLs for the crawl to follow
+fn store_capture(body: &str, url: &str, config: &ScraperConfig, route: ContentRoute) -> Vec<(String, String)> {
+    let dir = config.domain_dir(url);
+    match capture::save_capture_in_index(&config.redact_payload(body, route), url, &dir, route, config.page_index()) {
+        Ok(path) => console!("Saved capture of {} to {:?}", url, path),
+        Err(e) => {
+            let error_message = format!("Failed to save capture of '{}': {}", url, e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+            return Vec::new();
+        }
     }
 
-    // Method to set a custom user agent
-    pub fn set_user_agent(&mut self, agent: Option<String>) {
-        self.user_agent = agent;
+    // Emails go to the findings log and the entities like those of pages, and to the emails file only with legacy finding files
+    let data = extract_payload_data(body, route, url);
+    config.metrics().record_findings("emails", data.emails.len());
+    let emails: Vec<Finding> = data.emails.iter().map(|email| Finding::new(FindingKind::Email, url, &config.mask_pii(email))).collect();
+    config.log_findings(&emails);
+    if config.legacy_finding_files() && !emails.is_empty() {
+        write_emails(&emails.iter().map(|email| email.evidence.as_str()).collect::<Vec<_>>(), &page_file(&dir, "emails", url));
+    }
+    let mut addresses: Vec<String> = emails.iter().map(|email| email.evidence.to_lowercase()).collect();
+    addresses.sort();
+    addresses.dedup();
+    for address in addresses {
+        config.metrics().record_entity(url, EntityKind::Email, &address);
+    }
+    if !data.urls.is_empty() {
+        match File::create(page_file(&dir, "links", url)) {
+            Ok(mut links_file) => {
+                for link in &data.urls {
+                    writeln!(links_file, "Link - URL: {}, Text: , Heading: None, Context: None", link).unwrap();
+                }
+            }
+            Err(e) => console_error!("Failed to create links file: {}", e),
+        }
+    }
+    data.urls.into_iter().map(|link| (link, String::new())).collect()
+}
+
+// Saves a binary response of an enabled media kind without fetching it again
+async fn store_media_response(body: &[u8], url: &str, config: &ScraperConfig, kind: MediaKind) {
+    if !config.download_enabled(kind) {
+        console!("Skipping {} response: {}", kind, url);
+        return;
+    }
+
+    let dir = config.domain_dir(url);
+    let file_path = dir.join(media_file_name(url, "download.bin"));
+    let saved = match create_dir_all(&dir) {
+        Ok(()) => tokio::fs::write(&file_path, body).await,
+        Err(e) => Err(e),
+    };
+    match saved {
+        Ok(()) => {
+            console!("Saved {} response to {:?}", kind, file_path);
+            config.metrics().record_download(body.len() as u64);
+            if let Err(e) = manifest::record_session_download(url, &file_path, body.len() as u64, None, None, config.session_id().as_deref()).await {
+                console_error!("Failed to record checksum for {:?}: {}", file_path, e);
+            }
+        }
+        Err(e) => {
+            let error_message = format!("Failed to save {} response to {:?}: {}", kind, file_path, e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+        }
+    }
+}
+
+// Saves the raw HTML of a page unless its meta tags or `X-Robots-Tag` headers forbid archiving
+fn store_snapshot(html: &str, url: &str, config: &ScraperConfig, header_directives: RobotsDirectives) {
+    let directives = header_directives.merge(RobotsDirectives::from_html(html));
+    // Only the text is masked, so the snapshot still renders; it is counted when redacted for the content file
+    let html = config.pii_redactor.as_ref().map_or_else(|| html.to_string(), |redactor| redactor.redact_html(html).0);
+    match save_html_snapshot(&html, url, &config.domain_dir(url), directives, config.ignore_archive_directives()) {
+        Ok(Some(path)) => console!("Saved HTML snapshot of {} to {:?}", url, path),
+        Ok(None) => console!("Skipping HTML snapshot of {} (noarchive/nosnippet)", url),
+        Err(e) => {
+            let error_message = format!("Failed to save HTML snapshot of '{}': {}", url, e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+        }
+    }
+}
+
+/// Extracts all links from an HTML page, normalizing them to absolute URLs.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+/// * `base_url` - The base URL to resolve relative links.
+///
+/// # Returns
+///
+/// A `HashSet` containing all unique absolute links found on the page.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::extract_links;
+/// let links = extract_links("<a href='/about'>About</a>", "https://example.com");
+/// assert!(links.contains("https://example.com/about"));
+/// ```
+pub fn extract_links(html: &str, base_url: &str) -> HashSet<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href]").unwrap();
+    let mut urls = HashSet::new();
+
+    for element in document.select(&selector) {
+        if let Some(link) = element.value().attr("href") {
+            // Only http(s) links can be crawled; mailto:, tel:, javascript: and friends are skipped
+            if let LinkKind::Web(absolute_link) = classify_link(link, base_url) {
+                urls.insert(absolute_link);
+            }
+        }
+    }
+    urls
+}
+
+/// Normalizes a link to an absolute URL based on the base URL.
+///
+/// # Arguments
+///
+/// * `link` - The link to normalize.
+/// * `base_url` - The base URL of the current page.
+///
+/// # Returns
+///
+/// A `String` containing the absolute URL.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::normalize_link;
+/// let absolute_link = normalize_link("/about", "https://example.com");
+/// assert_eq!(absolute_link, "https://example.com/about");
+/// ```
+pub fn normalize_link(link: &str, base_url: &str) -> String {
+    if link.starts_with("http") {
+        link.to_string() // Already an absolute URL
+    } else {
+        match Url::parse(base_url) {
+            Ok(base) => base.join(link).map(|url| url.to_string()).unwrap_or_default(),
+            Err(_) => link.to_string(), // Return as-is if base URL is invalid
+        }
+    }
+}
+
+
+/// Downloads a media file (image or video) and saves it to the local directory.
+///
+/// # Arguments
+///
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `media_url` - The URL of the media file to download.
+/// * `file_path` - The file path where the media file will be saved.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::download_media;
+/// # use reqwest::Client;
+/// # use std::path::Path;
+/// # async fn example(client: Client) {
+/// download_media(&client, "https://example.com/image.jpg", Path::new("./downloads/image.jpg")).await;
+/// # }
+/// ```
+pub async fn download_media(client: &Client, media_url: &str, file_path: &Path) {
+    let _ = download_media_with_config(client, media_url, file_path, None).await;
+}
+
+/// Downloads a media file like `download_media`, honoring the download options of a `ScraperConfig`.
+///
+/// Interrupted downloads are resumed with HTTP `Range` requests instead of starting over:
+/// the body streams into `<file>.part` with a `<file>.part.state` sidecar, and is retried up to
+/// `download_retries` times before giving up. The partial file is kept so a later call for the
+/// same URL and path picks up where the last one stopped.
+///
+/// Every completed download is recorded with its SHA-256 checksum in the `manifest.jsonl` of the
+/// directory it was saved into (see `record_download`). Before a file is kept, its leading bytes
+/// are checked against the claimed `Content-Type` according to the `MimePolicy`, and the
+/// configured `DownloadValidator`, if any, gets a chance to reject it. The transfer is paced by
+/// the config's `BandwidthLimiter` (see `set_bandwidth_limit`).
+///
+/// A file an earlier run downloaded to the same path is not downloaded again while its manifest
+/// entry still matches it and a `HEAD` request reports the same `ETag` (or `Content-Length`), see
+/// `set_skip_unchanged_media`.
+///
+/// With `set_media_sample_size`, videos and archives are not downloaded whole: only their first
+/// bytes are fetched with a `Range` request and saved in place of the file, and the manifest entry
+/// records the true size of the file from `Content-Range` (or `Content-Length`) as `total_size`.
+///
+/// # Arguments
+///
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `media_url` - The URL of the media file to download.
+/// * `file_path` - The file path where the media file will be saved.
+/// * `config` - An optional reference to `ScraperConfig`; `None` uses the defaults.
+///
+/// # Returns
+///
+/// An `IoResult<u64>` with the size of the saved file. Errors are also printed and logged.
+pub async fn download_media_with_config(
+    client: &Client,
+    media_url: &str,
+    file_path: &Path,
+    config: Option<&ScraperConfig>,
+) -> IoResult<u64> {
+    let default_config = ScraperConfig::default();
+    let config = config.unwrap_or(&default_config);
+
+    // Media on hosts outside the domain lists is never requested
+    if !config.is_url_allowed(media_url) {
+        console!("Skipping out-of-scope media: {}", media_url);
+        return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, format!("host of '{}' is out of scope", media_url)));
+    }
+
+    if config.skip_unchanged_media() {
+        if let Some(size) = download::unchanged_download(client, media_url, file_path, config).await {
+            console!("Unchanged since the last run, not downloading again: {}", file_path.display());
+            config.metrics().record_findings("unchanged media", 1);
+            return Ok(size);
+        }
+    }
+
+    if let Some(sample_size) = config.media_sample_size().filter(|_| download::is_sampled(media_url)) {
+        return sample_media(client, media_url, file_path, sample_size, config).await;
+    }
+
+    match download::download_resumable(client, media_url, file_path, config).await {
+        Ok(download::CompletedDownload { size, etag }) => {
+            console!("Successfully downloaded and saved the media file: {}", file_path.display());
+            config.metrics().record_download(size);
+            if let Err(e) = manifest::record_session_download(media_url, file_path, size, etag.as_deref(), None, config.session_id().as_deref()).await {
+                let error_message = format!("Failed to record '{}' in the download manifest: {}", file_path.display(), e);
+                console_error!("{}", error_message);
+                config.log_error(&error_message);
+            }
+            Ok(size)
+        }
+        Err(e) => {
+            let error_message = format!("Failed to download media from '{}' to '{}': {}", media_url, file_path.display(), e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+            Err(e)
+        }
+    }
+}
+
+// Saves the first bytes of a video or archive and records them with the true size of the file
+async fn sample_media(client: &Client, media_url: &str, file_path: &Path, sample_size: u64, config: &ScraperConfig) -> IoResult<u64> {
+    match download::download_sample(client, media_url, file_path, sample_size, config).await {
+        Ok(download::SampledDownload { size, total_size, etag }) => {
+            let total = total_size.map_or("unknown".to_string(), |total| total.to_string());
+            console!("Sampled {} of {} bytes of the media file: {}", size, total, file_path.display());
+            config.metrics().record_download(size);
+            config.metrics().record_findings("sampled media", 1);
+            if let Err(e) = manifest::record_session_download(media_url, file_path, size, etag.as_deref(), total_size, config.session_id().as_deref()).await {
+                let error_message = format!("Failed to record '{}' in the download manifest: {}", file_path.display(), e);
+                console_error!("{}", error_message);
+                config.log_error(&error_message);
+            }
+            Ok(size)
+        }
+        Err(e) => {
+            let error_message = format!("Failed to sample media from '{}' to '{}': {}", media_url, file_path.display(), e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+            Err(e)
+        }
+    }
+}
+
+
+
+/// Scrapes all meaningful content from an HTML page, including text, images, videos, embedded videos, meta tags, and forms.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+/// * `url` - The URL of the current page being scraped.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::scrape_content;
+/// # use reqwest::Client;
+/// # async fn example(client: Client) {
+/// scrape_content("<html>...</html>", "https://example.com", &client).await;
+/// # }
+/// ```
+pub async fn scrape_content(html: &str, url: &str, client: &Client) {
+    scrape_content_with_config(html, url, client, None).await;
+}
+
+/// Scrapes all meaningful content from an HTML page, like `scrape_content`, honoring the
+/// media options of a `ScraperConfig` (audio, embeds, objects, and subtitle tracks).
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+/// * `url` - The URL of the current page being scraped.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `config` - An optional reference to `ScraperConfig`; `None` uses the defaults.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::{scrape_content_with_config, ScraperConfig};
+/// # use reqwest::Client;
+/// # async fn example(client: Client) {
+/// let mut config = ScraperConfig::default();
+/// config.set_scrape_tracks(false);
+/// scrape_content_with_config("<html>...</html>", "https://example.com", &client, Some(&config)).await;
+/// # }
+/// ```
+pub async fn scrape_content_with_config(html: &str, url: &str, client: &Client, config: Option<&ScraperConfig>) {
+    let default_config = ScraperConfig::default();
+    let config = config.unwrap_or(&default_config);
+    let extract = extract_page(html, url, &ExtractOptions::new(config, url));
+    store_page(extract, html, url, None, client, config).await;
+}
+
+// The settings of a config that page extraction reads, owned so extraction can run on a blocking thread
+struct ExtractOptions {
+    target_image_width: Option<u32>,
+    scrape_stylesheets: bool,
+    scrape_audio: bool,
+    scrape_embeds: bool,
+    scrape_objects: bool,
+    scrape_tracks: bool,
+    schema: Option<ExtractionSchema>,
+}
+
+impl ExtractOptions {
+    fn new(config: &ScraperConfig, url: &str) -> Self {
+        ExtractOptions {
+            target_image_width: config.target_image_width(),
+            scrape_stylesheets: config.scrape_stylesheets(),
+            scrape_audio: config.scrape_audio(),
+            scrape_embeds: config.scrape_embeds(),
+            scrape_objects: config.scrape_objects(),
+            scrape_tracks: config.scrape_tracks(),
+            schema: config.extraction_schema_for(url).cloned(),
+        }
+    }
+}
+
+// Everything parsed out of a page, gathered before anything of the page is stored
+struct PageExtract {
+    headers: Vec<String>,       // Inner HTML of the headings
+    paragraphs: Vec<String>,    // Inner HTML of the paragraphs
+    media_lists: Vec<(Vec<String>, &'static str, Option<MediaKind>)>,
+    stylesheet_urls: Vec<String>,
+    css_assets: Vec<String>,
+    meta_lines: Vec<String>,
+    ftp_links: Vec<String>,
+    record: Option<ExtractedRecord>,
+    aggregate: Option<AggregateRating>,
+    reviews: Vec<Review>,
+    forms: Vec<FormSummary>,
+    contacts: Contacts,
+    link_records: Vec<LinkRecord>,
+    emails: Vec<String>,
+    social_handles: Vec<String>,
+    script_endpoints: Vec<String>,
+}
+
+// Parses a page into a `PageExtract` without touching the network or the output directory
+fn extract_page(html: &str, url: &str, options: &ExtractOptions) -> PageExtract {
+    let document = Html::parse_document(html);
+
+    // Extract headers and paragraphs
+    let header_selector = Selector::parse("h1, h2, h3, h4, h5, h6").unwrap();
+    let headers = document.select(&header_selector).map(|header| header.inner_html()).collect();
+    let paragraph_selector = Selector::parse("p").unwrap();
+    let paragraphs = document.select(&paragraph_selector).map(|paragraph| paragraph.inner_html()).collect();
+
+    let mut media_lists: Vec<(Vec<String>, &'static str, Option<MediaKind>)> = Vec::new();
+
+    // Scrape images, picking the best srcset/<picture> variant when one is offered, plus
+    // inline data: images from stylesheets and style attributes
+    let mut image_urls = collect_image_urls(&document, url, options.target_image_width);
+    let mut css_assets = Vec::new();
+    for css_url in collect_css_urls(&document, url) {
+        if image_urls.contains(&css_url) {
+            continue;
+        }
+        if css_url.get(..11).is_some_and(|prefix| prefix.eq_ignore_ascii_case("data:image/")) {
+            image_urls.push(css_url);
+        } else {
+            css_assets.push(css_url);
+        }
+    }
+    media_lists.push((image_urls, "image.jpg", Some(MediaKind::Image)));
+
+    // Linked and imported stylesheets, fetched when the page is stored for the assets they reference
+    let stylesheet_urls = if options.scrape_stylesheets { collect_stylesheet_urls(&document, url) } else { Vec::new() };
+
+    // Scrape videos
+    let video_urls = collect_media_urls(&document, "video[src], video source[src]", "src", url);
+    media_lists.push((video_urls, "video.mp4", Some(MediaKind::Video)));
+
+    // Scrape audio (podcasts, music players)
+    if options.scrape_audio {
+        let audio_urls = collect_media_urls(&document, "audio[src], audio source[src]", "src", url);
+        media_lists.push((audio_urls, "audio.mp3", Some(MediaKind::Audio)));
+    }
+
+    // Scrape plugin content embedded with <embed> and <object>, typed by file extension
+    if options.scrape_embeds {
+        let embed_urls = collect_media_urls(&document, "embed[src]", "src", url);
+        media_lists.push((embed_urls, "embed.bin", None));
+    }
+    if options.scrape_objects {
+        let object_urls = collect_media_urls(&document, "object[data]", "data", url);
+        media_lists.push((object_urls, "object.bin", None));
+    }
+
+    // Scrape subtitle and caption tracks along with the videos they belong to
+    if options.scrape_tracks {
+        let track_urls = collect_media_urls(&document, "track[src]", "src", url);
+        media_lists.push((track_urls, "track.vtt", Some(MediaKind::Video)));
+    }
+
+    // Scrape linked documents (PDF, office files)
+    let document_urls: Vec<String> = collect_media_urls(&document, "a[href]", "href", url)
+        .into_iter()
+        .filter(|link| MediaKind::from_url(link) == Some(MediaKind::Document))
+        .collect();
+    media_lists.push((document_urls, "document.pdf", Some(MediaKind::Document)));
+
+    // Scrape external scripts
+    let script_urls = collect_media_urls(&document, "script[src]", "src", url);
+    media_lists.push((script_urls, "script.js", Some(MediaKind::Script)));
+
+    // Scrape meta tags
+    let meta_selector = Selector::parse("meta[name][content]").unwrap();
+    let meta_lines: Vec<String> = document
+        .select(&meta_selector)
+        .map(|meta| {
+            let name = meta.value().attr("name").unwrap_or("Unnamed");
+            let content = meta.value().attr("content").unwrap_or("");
+            format!("Meta Tag - Name: {}, Content: {}", name, content)
+        })
+        .collect();
+
+    // FTP links, listed and downloaded when the page is stored
+    let ftp_links: Vec<String> = collect_media_urls(&document, "a[href]", "href", url)
+        .into_iter()
+        .filter_map(|link| match classify_link(&link, url) {
+            LinkKind::Ftp(ftp_url) => Some(ftp_url),
+            _ => None,
+        })
+        .collect();
+
+    PageExtract {
+        headers,
+        paragraphs,
+        media_lists,
+        stylesheet_urls,
+        css_assets,
+        meta_lines,
+        ftp_links,
+        record: options.schema.as_ref().map(|schema| extract_with_schema(html, url, schema)),
+        aggregate: extract_aggregate_rating(html),
+        reviews: extract_reviews(html),
+        forms: extract_forms(html, url),
+        contacts: extract_contacts(html, url),
+        link_records: extract_link_records(html, url),
+        emails: find_emails(html),
+        social_handles: extract_social_handles(html, url),
+        script_endpoints: inline_script_endpoints(html, url),
+    }
+}
+
+// Stores what was extracted from a page: its content, contacts and links files, media, stylesheets,
+// embeds, findings and entities. `content_hash` is the crawl database hash of the page, listed in the page index
+async fn store_page(extract: PageExtract, html: &str, url: &str, content_hash: Option<&str>, client: &Client, config: &ScraperConfig) {
+    let PageExtract {
+        headers,
+        paragraphs,
+        media_lists,
+        stylesheet_urls,
+        mut css_assets,
+        meta_lines,
+        ftp_links,
+        record,
+        aggregate,
+        reviews,
+        forms,
+        contacts,
+        link_records,
+        emails,
+        social_handles,
+        script_endpoints,
+    } = extract;
+
+    // Create a directory structure for storing scraped data
+    let dir = config.domain_dir(url).to_string_lossy().to_string();
+
+    // Ensure the directory structure exists
+    if let Err(e) = create_dir_all(&dir) {
+        console_error!("Failed to create directory '{}': {}", dir, e);
+        return;
+    }
+
+    // Each page gets its own files, named by URL hash and listed in the domain's page index,
+    // so pages of the same domain no longer overwrite each other
+    if let Err(e) = config.page_index().record_hashed(Path::new(&dir), url, content_hash) {
+        let msg = format!("Failed to update page index in '{}': {}", dir, e);
+        console_error!("{}", msg);
+        config.log_error(&msg);
+    }
+
+    // Store text content (headers and paragraphs)
+    let mut text_file = match File::create(page_file(Path::new(&dir), "content", url)) {
+        Ok(file) => file,
+        Err(e) => {
+            console_error!("Failed to create text file: {}", e);
+            return;
+        }
+    };
+    for header in &headers {
+        writeln!(text_file, "Header: {}", config.redact_markup(header)).unwrap();
+    }
+    for paragraph in &paragraphs {
+        writeln!(text_file, "Paragraph: {}", config.redact_markup(paragraph)).unwrap();
+    }
+
+    for (urls, fallback_name, kind) in &media_lists {
+        process_media_list(client, config, &mut text_file, urls, &dir, fallback_name, *kind).await;
+    }
+
+    // Scrape stylesheets and the fonts and background images referenced from page and stylesheet CSS
+    if !stylesheet_urls.is_empty() {
+        let found = fetch_stylesheet_assets(&stylesheet_urls, client, Some(config)).await;
+        config.metrics().record_findings("stylesheets", found.stylesheets.len());
+        for stylesheet in &found.stylesheets {
+            save_stylesheet(config, &mut text_file, stylesheet, &dir).await;
+        }
+        for asset in found.assets {
+            if !css_assets.contains(&asset) {
+                css_assets.push(asset);
+            }
+        }
+    }
+    process_media_list(client, config, &mut text_file, &css_assets, &dir, "asset.bin", None).await;
+
+    // Scrape embedded videos and their oEmbed metadata
+    let video_embeds = embeds::scrape_video_embeds_with_config(html, url, client, config).await;
+    config.metrics().record_findings("video embeds", video_embeds.len());
+    for embed in video_embeds {
+        writeln!(
+            text_file,
+            "Embed - Provider: {:?}, URL: {}, Title: {}, Author: {}, Thumbnail: {}",
+            embed.provider,
+            embed.video_url,
+            embed.title.as_deref().unwrap_or("Unknown"),
+            embed.author_name.as_deref().unwrap_or("Unknown"),
+            embed.thumbnail_url.as_deref().unwrap_or("None")
+        )
+        .unwrap();
+    }
+
+    for line in &meta_lines {
+        writeln!(text_file, "{}", config.redact(line)).unwrap();
+    }
+
+    // Store the fields of the schema bound to this section of the site, if any
+    if let Some(record) = record {
+        config.metrics().record_findings(&format!("{} records", record.schema), usize::from(!record.fields.is_empty()));
+        for (field, values) in &record.fields {
+            for value in values {
+                writeln!(text_file, "Extracted - Schema: {}, Field: {}, Value: {}", record.schema, field, config.redact(value)).unwrap();
+            }
+        }
+    }
+
+    // Scrape reviews and comments
+    if let Some(aggregate) = aggregate {
+        writeln!(
+            text_file,
+            "Aggregate Rating - Value: {}, Best: {}, Count: {}",
+            aggregate.rating_value.map_or("Unknown".to_string(), |v| v.to_string()),
+            aggregate.best_rating.map_or("Unknown".to_string(), |v| v.to_string()),
+            aggregate.review_count.map_or("Unknown".to_string(), |v| v.to_string())
+        )
+        .unwrap();
+    }
+    config.metrics().record_findings("reviews", reviews.len());
+    for review in reviews {
+        writeln!(
+            text_file,
+            "Review - Author: {}, Rating: {}, Date: {}, Text: {}",
+            config.mask_pii(review.author.as_deref().unwrap_or("Unknown")),
+            review.rating.map_or("Unknown".to_string(), |v| v.to_string()),
+            review.date.as_deref().unwrap_or("Unknown"),
+            config.redact(review.text.as_deref().unwrap_or(""))
+        )
+        .unwrap();
+    }
+
+    // Scrape forms with a security summary of each
+    config.metrics().record_findings("forms", forms.len());
+    config.metrics().record_findings("forms without csrf tokens", forms.iter().filter(|form| form.method == "POST" && form.csrf_token.is_none()).count());
+    config.metrics().record_findings("file upload forms", forms.iter().filter(|form| !form.file_uploads.is_empty()).count());
+    let mut form_issues = Vec::new();
+    for form in &forms {
+        writeln!(text_file, "{}", form).unwrap();
+        for issue in &form.issues {
+            let line = format!("Form Issue - Severity: {}, Action: {}, Description: {}", issue.severity, form.action, issue.description);
+            if config.legacy_finding_files() {
+                writeln!(text_file, "Form Issue - Severity: {}, Description: {}", issue.severity, issue.description).unwrap();
+            }
+            form_issues.push(Finding::new(FindingKind::FormIssue, url, &line));
+        }
+    }
+    config.log_findings(&form_issues);
+
+    // Probe forms and script endpoints for parameters they don't advertise, when a wordlist is set
+    if config.parameter_wordlist().is_some() {
+        probe_parameters(html, url, &forms, client, config).await;
+    }
+
+    // Harvest mailto:/tel: links instead of treating them as pages
+    config.metrics().record_findings("emails", contacts.emails.len());
+    config.metrics().record_findings("phone numbers", contacts.phones.len());
+    if !contacts.emails.is_empty() || !contacts.phones.is_empty() {
+        match File::create(page_file(Path::new(&dir), "contacts", url)) {
+            Ok(mut contacts_file) => {
+                for email in &contacts.emails {
+                    writeln!(contacts_file, "mailto:{}", config.mask_pii(email)).unwrap();
+                }
+                for phone in &contacts.phones {
+                    writeln!(contacts_file, "tel:{}", config.mask_phone(phone)).unwrap();
+                }
+            }
+            Err(e) => console_error!("Failed to create contacts file: {}", e),
+        }
+    }
+
+    // Record where each link was found, for relevance scoring and discovery reports
+    if !link_records.is_empty() {
+        match File::create(page_file(Path::new(&dir), "links", url)) {
+            Ok(mut links_file) => {
+                for record in &link_records {
+                    writeln!(
+                        links_file,
+                        "Link - URL: {}, Text: {}, Heading: {}, Context: {}",
+                        record.url,
+                        config.mask_pii(&record.anchor_text),
+                        config.mask_pii(record.heading.as_deref().unwrap_or("None")),
+                        config.mask_pii(record.context.as_deref().unwrap_or("None"))
+                    )
+                    .unwrap();
+                }
+            }
+            Err(e) => console_error!("Failed to create links file: {}", e),
+        }
+    }
+
+    // Record FTP links, listing and downloading them when enabled
+    config.metrics().record_findings("ftp links", ftp_links.len());
+    for ftp_url in &ftp_links {
+        writeln!(text_file, "FTP Link - URL: {}", ftp_url).unwrap();
+    }
+    #[cfg(feature = "ftp")]
+    if config.follow_ftp() {
+        process_ftp_links(config, &mut text_file, &ftp_links, &dir).await;
+    }
+
+    // Scrape for emails, into the findings log and, with legacy finding files, the page's emails file
+    // Each email is a line of the findings log already, so its redaction is not counted again
+    let emails: Vec<Finding> = emails.iter().map(|email| Finding::new(FindingKind::Email, url, &config.mask_pii(email))).collect();
+    config.log_findings(&emails);
+    if config.legacy_finding_files() {
+        write_emails(&emails.iter().map(|email| email.evidence.as_str()).collect::<Vec<_>>(), &page_file(Path::new(&dir), "emails", url));
+    }
+
+    // Merge the emails, phone numbers, social handles and endpoints of the page into the per-domain entities, each counted once
+    let mut entities: Vec<(EntityKind, String)> = emails.iter().map(|email| (EntityKind::Email, email.evidence.to_lowercase())).collect();
+    entities.extend(contacts.phones.iter().map(|phone| (EntityKind::Phone, config.mask_phone(phone))));
+    entities.extend(social_handles.into_iter().map(|handle| (EntityKind::SocialHandle, handle)));
+    entities.extend(script_endpoints.into_iter().map(|endpoint| (EntityKind::Endpoint, endpoint)));
+    entities.sort();
+    entities.dedup();
+    for (kind, value) in entities {
+        config.metrics().record_entity(url, kind, &value);
+    }
+}
+
+/// Lists FTP directories (URLs ending in `/`) into the content file and downloads FTP files
+/// whose type is enabled in the config into `{dir}/ftp`.
+#[cfg(feature = "ftp")]
+async fn process_ftp_links(config: &ScraperConfig, text_file: &mut File, ftp_links: &[String], dir: &str) {
+    let mut files = Vec::new();
+    for ftp_url in ftp_links.iter().filter(|ftp_url| config.is_url_allowed(ftp_url)) {
+        if !ftp_url.ends_with('/') {
+            files.push(ftp_url.clone());
+            continue;
+        }
+        match ftp_list(ftp_url).await {
+            Ok(entries) => {
+                // Entries resolve against the directory, so an absolute one may point anywhere
+                let in_scope = |entry: &String| Url::parse(entry).is_ok_and(|entry_url| entry_url.scheme() == "ftp") && config.is_url_allowed(entry);
+                for entry in entries.into_iter().filter(in_scope) {
+                    writeln!(text_file, "FTP Entry - URL: {}", entry).unwrap();
+                    files.push(entry);
+                }
+            }
+            Err(e) => {
+                let error_message = format!("Failed to list FTP directory '{}': {}", ftp_url, e);
+                console_error!("{}", error_message);
+                config.log_error(&error_message);
+            }
+        }
+    }
+
+    for ftp_url in files {
+        let kind = MediaKind::from_url(&ftp_url).unwrap_or(MediaKind::Other);
+        if !config.download_enabled(kind) {
+            continue;
+        }
+        let file_path = Path::new(dir).join("ftp").join(media_file_name(&ftp_url, "ftp.bin"));
+        match ftp_download(&ftp_url, &file_path).await {
+            Ok(size) => {
+                console!("Downloaded {} ({} bytes) to {:?}", ftp_url, size, file_path);
+                config.metrics().record_download(size);
+                if let Err(e) = manifest::record_session_download(&ftp_url, &file_path, size, None, None, config.session_id().as_deref()).await {
+                    console_error!("Failed to record checksum for {:?}: {}", file_path, e);
+                }
+            }
+            Err(e) => {
+                let error_message = format!("Failed to download '{}' over FTP: {}", ftp_url, e);
+                console_error!("{}", error_message);
+                config.log_error(&error_message);
+            }
+        }
+    }
+}
+
+/// Collects the absolute URLs held in `attr` by every element matching `selector`, without duplicates.
+fn collect_media_urls(document: &Html, selector: &str, attr: &str, base_url: &str) -> Vec<String> {
+    let selector = Selector::parse(selector).unwrap();
+    let mut urls = Vec::new();
+    for element in document.select(&selector) {
+        if let Some(src) = element.value().attr(attr) {
+            let media_url = normalize_link(src, base_url);
+            if !urls.contains(&media_url) {
+                urls.push(media_url);
+            }
+        }
+    }
+    urls
+}
+
+/// Records every URL of a media list in the content file and downloads the ones whose type is
+/// enabled in the config into `dir`, naming each file after the last path segment.
+///
+/// URLs without a `kind` are typed from their file extension.
+async fn process_media_list(
+    client: &Client,
+    config: &ScraperConfig,
+    text_file: &mut File,
+    urls: &[String],
+    dir: &str,
+    fallback_name: &str,
+    kind: Option<MediaKind>,
+) {
+    for media_url in urls {
+        if media_url.starts_with("data:") {
+            save_inline_media(config, text_file, media_url, dir, kind).await;
+            continue;
+        }
+
+        let media_kind = kind.or_else(|| MediaKind::from_url(media_url)).unwrap_or(MediaKind::Other);
+        writeln!(text_file, "Media - Type: {}, URL: {}", media_kind, media_url).unwrap();
+
+        if config.download_enabled(media_kind) && config.is_url_allowed(media_url) {
+            let file_path = Path::new(dir).join(media_file_name(media_url, fallback_name));
+            console!("Downloading {}: {}", media_kind, media_url);
+            let _ = download_media_with_config(client, media_url, &file_path, Some(config)).await;
+        }
+    }
+}
+
+// Records a fetched stylesheet and saves it into `dir` when stylesheet downloads are enabled
+async fn save_stylesheet(config: &ScraperConfig, text_file: &mut File, stylesheet: &Stylesheet, dir: &str) {
+    writeln!(text_file, "Media - Type: {}, URL: {}", MediaKind::Stylesheet, stylesheet.url).unwrap();
+    if !config.download_enabled(MediaKind::Stylesheet) {
+        return;
+    }
+
+    let file_path = Path::new(dir).join(media_file_name(&stylesheet.url, "style.css"));
+    if file_path.exists() {
+        return;
+    }
+    match tokio::fs::write(&file_path, &stylesheet.css).await {
+        Ok(()) => {
+            console!("Saved stylesheet to {:?}", file_path);
+            config.metrics().record_download(stylesheet.css.len() as u64);
+            if let Err(e) = manifest::record_session_download(&stylesheet.url, &file_path, stylesheet.css.len() as u64, None, None, config.session_id().as_deref()).await {
+                console_error!("Failed to record checksum for {:?}: {}", file_path, e);
+            }
+        }
+        Err(e) => {
+            let error_message = format!("Failed to save stylesheet to {:?}: {}", file_path, e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+        }
+    }
+}
+
+/// Decodes an inline `data:` URI and saves its payload into `dir` as `inline-<hash>.<ext>`, so
+/// identical inline media is only stored once.
+async fn save_inline_media(config: &ScraperConfig, text_file: &mut File, data_url: &str, dir: &str, kind: Option<MediaKind>) {
+    let Some(data_uri) = parse_data_uri(data_url) else {
+        console_error!("Skipping malformed data URI: {:.64}", data_url);
+        return;
+    };
+    let media_kind = kind.unwrap_or(if data_uri.mime_type.starts_with("image/") { MediaKind::Image } else { MediaKind::Other });
+    writeln!(text_file, "Media - Type: {}, URL: data:{} (inline, {} bytes)", media_kind, data_uri.mime_type, data_uri.data.len()).unwrap();
+
+    if !config.download_enabled(media_kind) {
+        return;
+    }
+    let file_name = format!("inline-{}.{}", &sha256_hex(&data_uri.data)[..16], data_uri.extension());
+    let file_path = Path::new(dir).join(file_name);
+    if file_path.exists() {
+        return;
+    }
+    match tokio::fs::write(&file_path, &data_uri.data).await {
+        Ok(()) => {
+            console!("Saved inline {} to {:?}", data_uri.mime_type, file_path);
+            config.metrics().record_download(data_uri.data.len() as u64);
+            if let Err(e) = manifest::record_session_download(&format!("data:{}", data_uri.mime_type), &file_path, data_uri.data.len() as u64, None, None, config.session_id().as_deref()).await {
+                console_error!("Failed to record checksum for {:?}: {}", file_path, e);
+            }
+        }
+        Err(e) => {
+            let error_message = format!("Failed to save inline media to {:?}: {}", file_path, e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+        }
+    }
+}
+
+/// Derives a file name from the last path segment of a media URL, ignoring any query string.
+fn media_file_name(media_url: &str, fallback_name: &str) -> String {
+    let path = media_url.split(['?', '#']).next().unwrap_or("");
+    match path.split('/').next_back() {
+        Some(name) if !name.is_empty() && !name.contains(':') => name.to_string(),
+        _ => fallback_name.to_string(),
+    }
+}
+
+/// Extracts the domain from a URL for folder naming purposes.
+///
+/// # Arguments
+///
+/// * `url` - The URL from which to extract the domain.
+///
+/// # Returns
+///
+/// A `String` containing the domain.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::extract_domain;
+/// let domain = extract_domain("https://example.com/path");
+/// assert_eq!(domain, "example.com");
+/// ```
+pub fn extract_domain(url: &str) -> String {
+    let parsed_url = Url::parse(url).expect("Invalid URL");
+    parsed_url.host_str().unwrap_or("unknown_domain").to_string()
+}
+
+/// Scrapes inline JavaScript for API keys or tokens.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+///
+/// # Returns
+///
+/// A `JsFinding` per match, with `source_url` set to `inline` and lines counted within each script.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::scrape_js;
+/// let findings = scrape_js("<script>var apiKey = '12345';</script>");
+/// assert_eq!(findings[0].kind, "apiKey");
+/// ```
+pub fn scrape_js(html: &str) -> Vec<JsFinding> {
+    let findings = inline_js_findings(html);
+    for finding in &findings {
+        console!("Potential API key or token found in JS: {}", finding);
+    }
+    findings
+}
+
+// Finds API keys and tokens in the inline scripts of a page
+fn inline_js_findings(html: &str) -> Vec<JsFinding> {
+    let document = Html::parse_document(html);
+    let script_selector = Selector::parse("script").unwrap();
+    document
+        .select(&script_selector)
+        .flat_map(|script| find_js_keywords(&script.inner_html(), "inline", &["apiKey", "token"]))
+        .collect()
+}
+
+/// Scrapes for errors and stack traces in the HTML content.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::scrape_for_errors;
+/// scrape_for_errors("<html><body>Error: Stack trace</body></html>");
+/// ```
+pub fn scrape_for_errors(html: &str) {
+    if error_trace_snippet(html).is_some() {
+        console!("Potential error or stack trace found in the page:\n{}", html);
+    }
+}
+
+// Returns the text around the first exception or stack trace of a page
+fn error_trace_snippet(html: &str) -> Option<String> {
+    let offset = html.find("Exception").or_else(|| html.find("Stack trace"))?;
+    let start = html.floor_char_boundary(offset.saturating_sub(60));
+    let end = html.ceil_char_boundary((offset + 120).min(html.len()));
+    Some(html[start..end].split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Scrapes for emails and saves them to a file.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+/// * `dir` - The directory where the emails.txt file will be saved.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::scrape_for_emails;
+/// scrape_for_emails("<p>Contact us at info@example.com</p>", "./scraped_data/example.com");
+/// ```
+pub fn scrape_for_emails(html: &str, dir: &str) {
+    let emails = find_emails(html);
+    write_emails(&emails.iter().map(String::as_str).collect::<Vec<_>>(), &Path::new(dir).join("emails.txt"));
+}
+
+// Returns the email addresses found in a page, in order of appearance
+fn find_emails(html: &str) -> Vec<String> {
+    let email_regex = Regex::new(r"[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}").unwrap();
+    email_regex.find_iter(html).map(|email| email.as_str().to_string()).collect()
+}
+
+// Writes the emails of a page to `path`, one per line
+fn write_emails(emails: &[&str], path: &Path) {
+    let mut email_file = match File::create(path) {
+        Ok(file) => file,
+        Err(e) => {
+            console_error!("Failed to create email file '{}': {}", path.display(), e);
+            return;
+        }
+    };
+
+    for email in emails {
+        if writeln!(email_file, "{}", email).is_err() {
+            console_error!("Failed to write email '{}' to file '{}'", email, path.display());
+        }
+    }
+}
+
+
+/// Fetches a web page and prints the response status, demonstrating cookie handling.
+///
+/// # Arguments
+///
+/// * `url` - The URL to fetch.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::fetch_with_cookies;
+/// # use reqwest::Client;
+/// # async fn example(client: Client) {
+/// fetch_with_cookies("https://example.com", &client).await;
+/// # }
+/// ```
+pub async fn fetch_with_cookies(url: &str, client: &Client) {
+    fetch_with_cookies_with_config(url, client, &ScraperConfig::default()).await;
+}
+
+// Fetches a URL like `fetch_with_cookies`, through the request layer of the config
+async fn fetch_with_cookies_with_config(url: &str, client: &Client, config: &ScraperConfig) {
+    if let Ok(response) = request::send(client, Method::GET, url, config, |request| request).await {
+        console!("Response status: {}", response.status());
+        // Note: For actual cookie handling, enable the cookie store feature in reqwest.
+    }
+}
+
+/// Checks for common open directories on the server.
+///
+/// # Arguments
+///
+/// * `url` - The base URL to check.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::check_open_directories;
+/// # use reqwest::Client;
+/// # async fn example(client: Client) {
+/// check_open_directories("https://example.com", &client).await;
+/// # }
+/// ```
+pub async fn check_open_directories(url: &str, client: &Client) {
+    for (full_url, _) in find_open_directories(url, client, &ScraperConfig::default()).await {
+        console!("Open directory found: {}", full_url);
+    }
+}
+
+/// Checks for common open directories like `check_open_directories`, recording each one found in
+/// the findings log of the config instead of printing it (unless legacy finding output is kept).
+///
+/// # Returns
+///
+/// The URLs of the open directories.
+pub async fn check_open_directories_with_config(url: &str, client: &Client, config: Option<&ScraperConfig>) -> Vec<String> {
+    let default_config = ScraperConfig::default();
+    let config = config.unwrap_or(&default_config);
+
+    let open = find_open_directories(url, client, config).await;
+    if config.legacy_finding_files() {
+        for (full_url, _) in &open {
+            console!("Open directory found: {}", full_url);
+        }
+    }
+    config.log_findings(&open.iter().map(|(full_url, status)| Finding::new(FindingKind::OpenDirectory, full_url, &format!("HTTP {}", status))).collect::<Vec<_>>());
+    open.into_iter().map(|(full_url, _)| full_url).collect()
+}
+
+// Requests the well-known directories of a site, returning those answering with a success status
+async fn find_open_directories(url: &str, client: &Client, config: &ScraperConfig) -> Vec<(String, u16)> {
+    let directories = vec!["/backup", "/config", "/logs", "/uploads"];
+    let mut open = Vec::new();
+    for dir in directories {
+        let full_url = format!("{}{}", url, dir);
+        if let Ok(response) = request::send(client, Method::GET, &full_url, config, |request| request).await {
+            if response.status().is_success() {
+                open.push((full_url, response.status().as_u16()));
+            }
+        }
+    }
+    open
+}
+
+/// Fetches and parses the robots.txt file.
+///
+/// # Arguments
+///
+/// * `url` - A URL of the site; robots.txt is fetched from the root of its origin.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+///
+/// # Returns
+///
+/// The parsed `RobotsInfo`, which is empty if the file is missing or cannot be fetched.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::fetch_robots_txt;
+/// # use reqwest::Client;
+/// # async fn example(client: Client) {
+/// let robots = fetch_robots_txt("https://example.com", &client).await;
+/// println!("{} sitemaps listed", robots.sitemaps.len());
+/// # }
+/// ```
+pub async fn fetch_robots_txt(url: &str, client: &Client) -> RobotsInfo {
+    fetch_robots_txt_with_config(url, client, &ScraperConfig::default()).await
+}
+
+// Fetches and parses robots.txt like `fetch_robots_txt`, through the request layer of the config
+pub(crate) async fn fetch_robots_txt_with_config(url: &str, client: &Client, config: &ScraperConfig) -> RobotsInfo {
+    let mut robots = RobotsInfo::default();
+    let Ok(page_url) = Url::parse(url) else { return robots };
+    let robots_url = format!("{}/robots.txt", page_url.origin().ascii_serialization());
+    if let Ok(response) = request::send(client, Method::GET, &robots_url, config, |request| request).await {
+        if !response.status().is_success() {
+            return robots;
+        }
+        let content_type = response.headers().get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).map(str::to_string);
+        if let Ok(Some(body)) = request::read_body(response, config.max_page_bytes(), config).await {
+            let body = page::decode_body(&body, content_type.as_deref());
+            robots = parse_robots_txt_for(&body, config.user_agent().map(String::as_str).unwrap_or("*"));
+
+            for path in &robots.disallowed {
+                console!("Disallowed path found: {}", path);
+            }
+            for sitemap in &robots.sitemaps {
+                console!("Sitemap found: {}", sitemap);
+            }
+        }
+    }
+    robots
+}
+
+/// Executes the entire scraping workflow for the provided URL, including:
+/// - Fetching `robots.txt` to check for disallowed paths
+/// - Checking for open directories
+/// - Fetching content with cookies
+/// - Performing recursive scraping on links found in the website
+///
+/// The function mimics human behavior by introducing random delays
+/// between requests to avoid overwhelming servers.
+///
+/// # Arguments
+/// * `url` - The URL to start scraping from.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+///
+/// # Returns
+///
+/// The `CrawlSummary` of the run, which is also printed.
+///
+/// # Example
+/// ```no_run
+/// # use knee_scraper::run;
+/// # use reqwest::Client;
+/// # async fn example() {
+/// let client = Client::new();
+/// let summary = run("https://example.com", &client).await;
+/// println!("Downloaded {} bytes", summary.bytes_downloaded);
+/// # }
+/// ```
+pub async fn run(url: &str, client: &Client) -> CrawlSummary {
+    // Follow links without a depth limit, like `recursive_scrape`
+    let config = ScraperConfig::new(true, i32::MAX, None);
+    let summary = run_with_config(url, client, Some(&config)).await;
+    console!("{}", summary);
+    summary
+}
+
+/// Executes the same workflow as `run` with a `ScraperConfig`, returning the summary without printing it.
+///
+/// # Arguments
+/// * `url` - The URL to start scraping from.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `config` - An optional reference to `ScraperConfig`; `None` uses the defaults.
+///
+/// # Returns
+///
+/// The `CrawlSummary` of the run.
+pub async fn run_with_config(url: &str, client: &Client, config: Option<&ScraperConfig>) -> CrawlSummary {
+    let default_config = ScraperConfig::default();
+    let config = config.unwrap_or(&default_config);
+    run::in_session(Some(config.new_session_id()), async {
+        let started = std::time::Instant::now();
+        let mut visited = HashSet::new();
+        let mut stats = CrawlStats::default();
+        let mut manifest = RunManifest::start(url, config);
+        write_run_manifest(&manifest, config);
+
+        console!("Starting scraping workflow for {}", url);
+
+        // Fetch `robots.txt`, open directories, and perform cookie-based scraping
+        let robots = fetch_robots_txt_with_config(url, client, config).await;
+        check_open_directories_with_config(url, client, Some(config)).await;
+        fetch_with_cookies_with_config(url, client, config).await;
+
+        // Seed the crawl with the base URL and the pages listed in the sitemaps that robots.txt points to
+        let mut seeds = vec![url.to_string()];
+        seeds.extend(config.seeds().iter().cloned());
+        if config.seed_from_sitemaps() && !robots.sitemaps.is_empty() {
+            let sitemap_urls = robots::fetch_sitemap_urls_with_config(&robots.sitemaps, client, config.max_sitemap_urls(), config).await;
+            console!("Seeding {} URLs from sitemaps", sitemap_urls.len());
+            seeds.extend(sitemap_urls);
+        }
+
+        // Start scraping from the seeds
+        crawl_run(seeds, client, config, &mut visited, &mut stats).await;
+        finish_crawl(client, config).await;
+
+        // Introduce a delay to mimic human-like browsing behavior
+        random_delay(2, 5).await;
+
+        let summary = CrawlSummary::new(&stats, config.metrics(), started.elapsed());
+        manifest.finish(&summary);
+        write_run_manifest(&manifest, config);
+        summary
+    })
+    .await
+}
+
+
+use std::fs::OpenOptions;
+/// Logs an error message to a file.
+///
+/// # Arguments
+///
+/// * `message` - The error message to log.
+fn log_error_to_file(message: &str) {
+    log_error_to(&default_output_dir().join(ERROR_LOG_FILE), message);
+}
+
+// Appends an error message to the given log file
+fn log_error_to(log_file_path: &Path, message: &str) {
+    if let Some(parent) = log_file_path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        let _ = create_dir_all(parent);
+    }
+
+    // Open the file in append mode, creating it if it doesn't exist
+    let mut file = match OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_file_path)
+    {
+        Ok(f) => f,
+        Err(e) => {
+            console_error!("Failed to open or create error log file '{}': {}", log_file_path.display(), e);
+            return;
+        }
+    };
+
+    // Write the error message to the file
+    if let Err(e) = writeln!(file, "{}", message) {
+        console_error!("Failed to write to error log file '{}': {}", log_file_path.display(), e);
+    }
+}
+
+
+/// Sleeps for a random duration between a given range, mimicking human browsing behavior.
+///
+/// # Arguments
+///
+/// * `min_secs` - Minimum number of seconds to sleep.
+/// * `max_secs` - Maximum number of seconds to sleep.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::random_delay;
+/// # async fn example() {
+/// random_delay(1, 5).await;
+/// # }
+/// ```
+pub async fn random_delay(min_secs: u64, max_secs: u64) {
+    let delay = rand::random::<u64>() % (max_secs - min_secs + 1) + min_secs;
+    sleep(Duration::from_secs(delay)).await;
+}
+
+
+/// Recursively scrapes web pages starting from the given URL, looking for the target phrase.
+/// If the target phrase is not found in the HTML content of a page, it stops scraping in that direction.
+///
+/// # Arguments
+/// * `url`: The starting URL for scraping.
+/// * `client`: An instance of `reqwest::Client` for making HTTP requests.
+/// * `config`: An optional reference to `ScraperConfig` for controlling scraper behavior.
+/// * `visited`: A `HashSet` that tracks visited URLs.
+/// * `target_phrase`: The phrase to search for in the HTML content.
+///
+/// This function performs breadth-first scraping, but only continues to follow links
+/// if the target phrase is found in the current page's content.
+pub async fn rec_scrape(url: &str, client: &Client, config: Option<&ScraperConfig>, visited: &mut HashSet<String>, target_phrase: &str) {
+    let mut queue = VecDeque::new();
+    queue.push_back(url.to_string());
+    let mut current_depth = 0; // Initialize scraping depth
+
+    // Get configuration values or defaults
+    let follow_links = config.is_none_or(|c| c.follow_links()); // Default: true
+    let max_depth = config.map_or(3, |c| c.max_depth()); // Default: 3
+    let in_scope = |link: &str| config.is_none_or(|c| c.is_url_allowed(link)); // Default: every host
+
+    while let Some(current_url) = queue.pop_front() {
+        if visited.contains(&current_url) || !in_scope(&current_url) {
+            continue;
+        }
+
+        console!("Visiting: {}", current_url);
+        visited.insert(current_url.clone());
+
+        // Send the request through the request layer of the config, or bare without one
+        let response = match config {
+            Some(config) => request::send(client, Method::GET, &current_url, config, |request| request).await.ok(),
+            None => client.get(&current_url).send().await.ok(),
+        };
+        let Some(response) = response else { continue }; // Skip the URL if there's an error
+
+        if response.status().is_success() {
+            let html = match response.text().await {
+                Ok(html) => html,
+                Err(_) => continue, // Skip if there's an error reading the content
+            };
+
+            if should_scrape_content(&html, target_phrase) {
+                console!("Target phrase found in: {}", current_url);
+
+                // Only follow links if target_phrase is found and depth is within limits
+                if follow_links && current_depth < max_depth {
+                    let links = extract_links(&html, &current_url);
+                    for link in links {
+                        if !visited.contains(&link) && in_scope(&link) {
+                            queue.push_back(link); // Only add links if the phrase is found
+                        }
+                    }
+                    current_depth += 1; // Increase depth after following links
+                }
+            } else {
+                console!("Target phrase not found in: {}", current_url);
+                // Do not enqueue links from this page, discontinue following in this direction
+                continue;
+            }
+        }
+    }
+}
+
+/// Checks if the given content contains the target phrase.
+///
+/// # Arguments
+/// * `content`: The HTML content of the page as a string.
+/// * `target_phrase`: The phrase to search for within the content.
+///
+/// Returns `true` if the target phrase is found, otherwise `false`.
+pub fn should_scrape_content(content: &str, target_phrase: &str) -> bool {
+    content.contains(target_phrase)
+}
+
+/// The environment variable that overrides the default output root, e.g. a writable volume in a container.
+pub const OUTPUT_DIR_ENV: &str = "KNEE_SCRAPER_OUTPUT_DIR";
+
+/// The name of the error log inside the output root.
+pub const ERROR_LOG_FILE: &str = "error.log";
+
+/// Returns the default output root: `$KNEE_SCRAPER_OUTPUT_DIR` when set, otherwise `./scraped_data`.
+///
+/// Everything a crawl writes (page data, scripts, CAPTCHA images, the error log, dead letters)
+/// is placed below this directory unless configured otherwise, so the crate never writes into the
+/// working directory itself.
+pub fn default_output_dir() -> PathBuf {
+    std::env::var_os(OUTPUT_DIR_ENV).filter(|dir| !dir.is_empty()).map_or_else(|| PathBuf::from("./scraped_data"), PathBuf::from)
+}
+
+pub struct ScraperConfig {
+    follow_links: bool,
+    max_depth: i32,
+    user_agent: Option<String>,
+    scrape_audio: bool,
+    scrape_embeds: bool,
+    scrape_objects: bool,
+    scrape_tracks: bool,
+    scrape_stylesheets: bool,
+    target_image_width: Option<u32>,
+    download_images: bool,
+    download_videos: bool,
+    download_audio: bool,
+    download_documents: bool,
+    download_scripts: bool,
+    download_stylesheets: bool,
+    download_fonts: bool,
+    record_media_only: bool,
+    download_retries: u32,
+    mime_policy: MimePolicy,
+    skip_unchanged_media: bool,
+    media_sample_size: Option<u64>,     // Bytes fetched of videos and archives instead of the whole file
+    legacy_finding_files: bool,
+    follow_frames: bool,
+    follow_cross_origin_frames: bool,
+    pii_redactor: Option<Arc<PiiRedactor>>,     // Set when PII is redacted
+    manifest_index: Arc<ManifestIndex>,
+    page_index: Arc<PageIndex>,
+    download_validator: Option<DownloadValidator>,
+    bandwidth_limiter: Arc<BandwidthLimiter>,
+    follow_ftp: bool,
+    domain_filters: Arc<DomainFilters>,
+    save_html_snapshots: bool,
+    ignore_archive_directives: bool,
+    crawl_database: Option<Arc<Mutex<CrawlDatabase>>>,
+    crawl_database_in_output_dir: bool,                         // Opens `crawl_db.json` in the output root at crawl start
+    output_crawl_database: OnceLock<Arc<Mutex<CrawlDatabase>>>,
+    output_dir: PathBuf,
+    error_log: Option<PathBuf>,         // `None` logs to `error.log` in the output root
+    request_limiter: Arc<RequestLimiter>,
+    request_rules: RequestRules,
+    extraction_rules: Vec<ExtractionRule>,
+    page_retries: u32,
+    seed_from_sitemaps: bool,
+    seeds: Vec<String>,                 // Crawled along with the start URL
+    imported_frontier: Vec<FrontierEntry>,  // Queued at their recorded depth along with the seeds
+    frontier_export: Option<PathBuf>,
+    discovery_only: bool,
+    route_assets: bool,
+    fixed_session_id: Option<String>,       // Used instead of a generated session ID
+    max_sitemap_urls: usize,
+    discover_js_endpoints: bool,
+    normalize_monitored_pages: bool,
+    alternate_policy: AlternatePolicy,
+    alternates: Arc<AlternateMap>,
+    page_classes: Arc<PageClassMap>,
+    partitions: Arc<CrawlPartitions>,
+    tls_policy: Arc<TlsPolicy>,
+    script_cache: Arc<ScriptCache>,
+    stylesheet_cache: Arc<ScriptCache>,
+    header_findings: Arc<HeaderFindingLog>,
+    parameter_wordlist: Option<Arc<Vec<String>>>,
+    parameter_findings: Arc<ParameterFindingLog>,
+    external_link_checker: Option<Arc<ExternalLinkChecker>>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    host_byte_quota: Option<u64>,
+    host_byte_quotas: HashMap<String, u64>,     // Per-host overrides of `host_byte_quota`
+    url_scorer: Arc<dyn UrlScorer>,
+    dedup_policy: Arc<dyn DedupPolicy>,
+    page_renderer: Option<Arc<dyn PageRenderer>>,
+    max_page_bytes: u64,
+    max_nesting_depth: usize,
+    page_timeout: Option<Duration>,
+    max_script_bytes: u64,
+    dead_letters: Arc<DeadLetterQueue>,
+    metrics: Arc<CrawlMetrics>,
+    findings_lock: Mutex<()>,       // Serializes appends to `findings.jsonl` of crawls sharing the config
+}
+
+impl Default for ScraperConfig {
+    fn default() -> Self {
+        ScraperConfig::new(true, 3, None)
+    }
+}
+
+impl ScraperConfig {
+    pub fn new(follow_links: bool, max_depth: i32, user_agent: Option<String>) -> Self {
+        ScraperConfig {
+            follow_links,
+            max_depth,
+            user_agent,
+            scrape_audio: true,
+            scrape_embeds: true,
+            scrape_objects: true,
+            scrape_tracks: true,
+            scrape_stylesheets: true,
+            target_image_width: None,
+            download_images: true,
+            download_videos: true,
+            download_audio: true,
+            download_documents: false,
+            download_scripts: false,
+            download_stylesheets: false,
+            download_fonts: false,
+            record_media_only: false,
+            download_retries: 3,
+            mime_policy: MimePolicy::Warn,
+            skip_unchanged_media: true,
+            media_sample_size: None,
+            legacy_finding_files: false,
+            follow_frames: true,
+            follow_cross_origin_frames: false,
+            pii_redactor: None,
+            manifest_index: Arc::new(ManifestIndex::default()),
+            page_index: Arc::new(PageIndex::default()),
+            download_validator: None,
+            bandwidth_limiter: Arc::new(BandwidthLimiter::default()),
+            follow_ftp: false,
+            domain_filters: Arc::new(DomainFilters::default()),
+            save_html_snapshots: false,
+            ignore_archive_directives: false,
+            crawl_database: None,
+            crawl_database_in_output_dir: false,
+            output_crawl_database: OnceLock::new(),
+            output_dir: default_output_dir(),
+            error_log: None,
+            request_limiter: Arc::new(RequestLimiter::default()),
+            request_rules: RequestRules::default(),
+            extraction_rules: Vec::new(),
+            page_retries: 2,
+            seed_from_sitemaps: true,
+            seeds: Vec::new(),
+            imported_frontier: Vec::new(),
+            frontier_export: None,
+            discovery_only: false,
+            route_assets: true,
+            fixed_session_id: None,
+            max_sitemap_urls: 1_000,
+            discover_js_endpoints: true,
+            normalize_monitored_pages: true,
+            alternate_policy: AlternatePolicy::default(),
+            alternates: Arc::new(AlternateMap::default()),
+            page_classes: Arc::new(PageClassMap::default()),
+            partitions: Arc::new(CrawlPartitions::default()),
+            tls_policy: Arc::new(TlsPolicy::default()),
+            script_cache: Arc::new(ScriptCache::default()),
+            stylesheet_cache: Arc::new(ScriptCache::default()),
+            header_findings: Arc::new(HeaderFindingLog::default()),
+            parameter_wordlist: None,
+            parameter_findings: Arc::new(ParameterFindingLog::default()),
+            external_link_checker: None,
+            circuit_breaker: Some(Arc::new(CircuitBreaker::new(5, Duration::from_secs(60)))),
+            host_byte_quota: None,
+            host_byte_quotas: HashMap::new(),
+            url_scorer: Arc::new(BreadthFirstScorer),
+            dedup_policy: Arc::new(ExactUrlDedup::default()),
+            page_renderer: None,
+            max_page_bytes: 10 * 1024 * 1024,
+            max_nesting_depth: 512,
+            page_timeout: Some(Duration::from_secs(300)),
+            max_script_bytes: 5 * 1024 * 1024,
+            dead_letters: Arc::new(DeadLetterQueue::default()),
+            metrics: Arc::new(CrawlMetrics::default()),
+            findings_lock: Mutex::new(()),
+        }
+    }
+
+    /// A config for archiving a site: follows links five levels deep and saves every media kind,
+    /// document, script, stylesheet and font, plus an HTML snapshot of each page. URLs that only
+    /// differ by tracking parameters, fragments or query order are fetched once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use knee_scraper::ScraperConfig;
+    /// let config = ScraperConfig::archive_preset();
+    /// assert!(config.save_html_snapshots() && config.download_documents());
+    /// ```
+    pub fn archive_preset() -> Self {
+        let mut config = ScraperConfig::new(true, 5, None);
+        config.set_download_documents(true);
+        config.set_download_scripts(true);
+        config.set_download_stylesheets(true);
+        config.set_download_fonts(true);
+        config.set_save_html_snapshots(true);
+        config.set_max_sitemap_urls(10_000);
+        config.set_dedup_policy(Arc::new(CanonicalUrlDedup::default()));
+        config
+    }
+
+    /// A config for reconnaissance: maps a site three levels deep without downloading media (their
+    /// URLs are still recorded), follows the API endpoints of inline scripts, probes forms and
+    /// endpoints with `DEFAULT_PARAMETER_WORDLIST`, and checks off-site links instead of crawling them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use knee_scraper::ScraperConfig;
+    /// let config = ScraperConfig::recon_preset();
+    /// assert!(config.record_media_only() && config.parameter_wordlist().is_some());
+    /// ```
+    pub fn recon_preset() -> Self {
+        let mut config = ScraperConfig::new(true, 3, None);
+        config.set_record_media_only(true);
+        config.set_discover_js_endpoints(true);
+        config.set_parameter_wordlist(Some(DEFAULT_PARAMETER_WORDLIST.iter().map(|name| name.to_string()).collect()));
+        config.set_external_link_checker(Some(ExternalLinkChecker::new(4, Duration::from_millis(250))));
+        config.set_dedup_policy(Arc::new(CanonicalUrlDedup::default()));
+        config
+    }
+
+    /// A config for monitoring a site for changes: an incremental crawl two levels deep whose
+    /// database is `crawl_db.json` in the output root, comparing normalized pages and recording media
+    /// URLs without downloading them. Each run reports only the new and changed pages.
+    ///
+    /// The database is opened when a crawl starts, so it follows a later `set_output_dir`;
+    /// `load_crawl_database` keeps it elsewhere.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use knee_scraper::ScraperConfig;
+    /// let config = ScraperConfig::monitor_preset();
+    /// assert!(config.crawl_database_in_output_dir() && config.normalize_monitored_pages());
+    /// ```
+    pub fn monitor_preset() -> Self {
+        let mut config = ScraperConfig::new(true, 2, None);
+        config.set_record_media_only(true);
+        config.set_page_retries(3);
+        config.set_crawl_database_in_output_dir(true);
+        config
+    }
+
+    // Opens the crawl database at `path`, starting a new one there (and logging why) when it cannot be read
+    fn open_crawl_database(&self, path: &Path) -> CrawlDatabase {
+        CrawlDatabase::open(path).unwrap_or_else(|e| {
+            let error_message = format!("Failed to open crawl database '{}', starting a new one: {}", path.display(), e);
+            console_error!("{}", error_message);
+            self.log_error(&error_message);
+            CrawlDatabase::new(path)
+        })
+    }
+
+    // Method to update whether or not to follow links
+    pub fn set_follow_links(&mut self, follow: bool) {
+        self.follow_links = follow;
+    }
+
+    // Method to update the max depth of scraping
+    pub fn set_max_depth(&mut self, depth: i32) {
+        self.max_depth = depth;
+    }
+
+    // Method to set a custom user agent
+    pub fn set_user_agent(&mut self, agent: Option<String>) {
+        self.user_agent = agent;
+    }
+
+    pub fn follow_links(&self) -> bool {
+        self.follow_links
+    }
+
+    pub fn max_depth(&self) -> i32 {
+        self.max_depth
+    }
+
+    // Method to enable or disable scraping of <audio> elements and their sources
+    pub fn set_scrape_audio(&mut self, enabled: bool) {
+        self.scrape_audio = enabled;
+    }
+
+    // Method to enable or disable scraping of <embed> elements
+    pub fn set_scrape_embeds(&mut self, enabled: bool) {
+        self.scrape_embeds = enabled;
+    }
+
+    // Method to enable or disable scraping of <object> elements
+    pub fn set_scrape_objects(&mut self, enabled: bool) {
+        self.scrape_objects = enabled;
+    }
+
+    // Method to enable or disable scraping of <track> subtitle and caption files
+    pub fn set_scrape_tracks(&mut self, enabled: bool) {
+        self.scrape_tracks = enabled;
+    }
+
+    // Method to enable or disable fetching linked stylesheets for the assets they reference
+    pub fn set_scrape_stylesheets(&mut self, enabled: bool) {
+        self.scrape_stylesheets = enabled;
+    }
+
+    // Method to set the preferred width of srcset images (None downloads the highest resolution)
+    pub fn set_target_image_width(&mut self, width: Option<u32>) {
+        self.target_image_width = width;
+    }
+
+    // Method to enable or disable downloading of images
+    pub fn set_download_images(&mut self, enabled: bool) {
+        self.download_images = enabled;
+    }
+
+    // Method to enable or disable downloading of videos and their subtitle tracks
+    pub fn set_download_videos(&mut self, enabled: bool) {
+        self.download_videos = enabled;
+    }
+
+    // Method to enable or disable downloading of audio files
+    pub fn set_download_audio(&mut self, enabled: bool) {
+        self.download_audio = enabled;
+    }
+
+    // Method to enable or disable downloading of linked documents (PDF, office files)
+    pub fn set_download_documents(&mut self, enabled: bool) {
+        self.download_documents = enabled;
+    }
+
+    // Method to enable or disable downloading of external scripts
+    pub fn set_download_scripts(&mut self, enabled: bool) {
+        self.download_scripts = enabled;
+    }
+
+    // Method to enable or disable downloading of stylesheets
+    pub fn set_download_stylesheets(&mut self, enabled: bool) {
+        self.download_stylesheets = enabled;
+    }
+
+    // Method to enable or disable downloading of web fonts
+    pub fn set_download_fonts(&mut self, enabled: bool) {
+        self.download_fonts = enabled;
+    }
+
+    // Method to skip every download and only record the media URLs found
+    pub fn set_record_media_only(&mut self, enabled: bool) {
+        self.record_media_only = enabled;
+    }
+
+    // Method to list and download `ftp://` links found in pages (needs the `ftp` feature)
+    pub fn set_follow_ftp(&mut self, enabled: bool) {
+        self.follow_ftp = enabled;
+    }
+
+    // Method to set how many times an interrupted download is resumed before giving up
+    pub fn set_download_retries(&mut self, retries: u32) {
+        self.download_retries = retries;
+    }
+
+    // Method to choose what happens when downloaded bytes don't match the claimed Content-Type
+    pub fn set_mime_policy(&mut self, policy: MimePolicy) {
+        self.mime_policy = policy;
+    }
+
+    // Method to enable or disable skipping media files that an earlier run downloaded and that a
+    // HEAD request reports unchanged (by ETag or Content-Length)
+    pub fn set_skip_unchanged_media(&mut self, skip: bool) {
+        self.skip_unchanged_media = skip;
+    }
+
+    // Method to fetch only the first `bytes` of videos and archives with a Range request, cataloging
+    // them by their magic bytes and true size instead of downloading them (None downloads them whole)
+    pub fn set_media_sample_size(&mut self, bytes: Option<u64>) {
+        self.media_sample_size = bytes;
+    }
+
+    // Method to keep writing the per-page emails files, `Form Issue` lines, `header_findings.txt`,
+    // `parameter_findings.txt`, `external_links.txt` and `robots_audit.txt`, and printing JS keyword,
+    // error trace and open directory findings, in addition to `findings.jsonl`
+    pub fn set_legacy_finding_files(&mut self, legacy: bool) {
+        self.legacy_finding_files = legacy;
+    }
+
+    // Method to fetch the same-origin frames and iframes of pages and merge their content into the page
+    pub fn set_follow_frames(&mut self, follow: bool) {
+        self.follow_frames = follow;
+    }
+
+    // Method to fetch and merge cross-origin frames as well, instead of only recording them as findings
+    pub fn set_follow_cross_origin_frames(&mut self, follow: bool) {
+        self.follow_cross_origin_frames = follow;
+    }
+
+    // Method to mask emails, phone numbers and other personal data in stored page text, snapshots
+    // and email findings, counting what was masked in the crawl metrics
+    pub fn set_redact_pii(&mut self, redact: bool) {
+        self.pii_redactor = redact.then(|| Arc::new(PiiRedactor::new()));
+    }
+
+    // Method to plug in a post-download check (e.g. a virus scanner) run before a file is kept
+    pub fn set_download_validator(&mut self, validator: Option<DownloadValidator>) {
+        self.download_validator = validator;
+    }
+
+    // Method to cap the combined download bandwidth, in bytes per second (None is unlimited)
+    pub fn set_bandwidth_limit(&mut self, bytes_per_sec: Option<u64>) {
+        self.bandwidth_limiter.set_global_limit(bytes_per_sec);
+    }
+
+    // Method to cap the download bandwidth towards each host, in bytes per second (None is unlimited)
+    pub fn set_per_host_bandwidth_limit(&mut self, bytes_per_sec: Option<u64>) {
+        self.bandwidth_limiter.set_per_host_limit(bytes_per_sec);
+    }
+
+    // Method to share one bandwidth limiter between several configs
+    pub fn set_bandwidth_limiter(&mut self, limiter: Arc<BandwidthLimiter>) {
+        self.bandwidth_limiter = limiter;
+    }
+
+    // Method to share one set of domain lists between several configs
+    pub fn set_domain_filters(&mut self, filters: Arc<DomainFilters>) {
+        self.domain_filters = filters;
+    }
+
+    // Method to restrict crawling to the hosts of a domain list (`None` allows every host)
+    pub fn set_allowed_domains(&mut self, domains: Option<DomainList>) {
+        self.domain_filters.set_allowed(domains);
+    }
+
+    // Method to never crawl the hosts of a domain list
+    pub fn set_denied_domains(&mut self, domains: Option<DomainList>) {
+        self.domain_filters.set_denied(domains);
+    }
+
+    // Method to set how many times a page request failing with a connection error or a 5xx/429
+    // answer is retried before it goes to the dead-letter list
+    pub fn set_page_retries(&mut self, retries: u32) {
+        self.page_retries = retries;
+    }
+
+    // Method to set the minimum interval between two requests to the same host (`None` for no limit)
+    pub fn set_per_host_request_interval(&mut self, interval: Option<Duration>) {
+        self.request_limiter.set_per_host_interval(interval);
+    }
+
+    // Method to override the request interval for URLs matching a pattern such as `/api/*`
+    // (`None` exempts them); the first matching rule wins
+    pub fn add_rate_limit_rule(&mut self, pattern: &str, interval: Option<Duration>) {
+        self.request_limiter.add_rule(pattern, interval);
+    }
+
+    // Method to add a query parameter, e.g. `nocache=1`, to the requests of URLs matching a pattern
+    pub fn add_query_rule(&mut self, pattern: &str, name: &str, value: &str) {
+        self.request_rules.add_query(pattern, name, value);
+    }
+
+    // Method to send a header with the requests of URLs matching a pattern; every matching rule applies
+    pub fn add_header_rule(&mut self, pattern: &str, name: &str, value: &str) {
+        self.request_rules.add_header(pattern, name, value);
+    }
+
+    // Method to extract the fields of a schema from the pages matching a pattern; the first
+    // matching rule picks the schema of a page
+    pub fn add_extraction_rule(&mut self, pattern: &str, schema: ExtractionSchema) {
+        self.extraction_rules.push(ExtractionRule { pattern: UrlPattern::new(pattern), schema });
+    }
+
+    // Method to replace every extraction rule, e.g. with the rules of `load_extraction_rules`
+    pub fn set_extraction_rules(&mut self, rules: Vec<ExtractionRule>) {
+        self.extraction_rules = rules;
+    }
+
+    // Method to share one request limiter between several configs
+    pub fn set_request_limiter(&mut self, limiter: Arc<RequestLimiter>) {
+        self.request_limiter = limiter;
+    }
+
+    // Method to store the raw HTML of every crawled page under `snapshots/`
+    pub fn set_save_html_snapshots(&mut self, enabled: bool) {
+        self.save_html_snapshots = enabled;
+    }
+
+    // Method to store snapshots even for pages marked noarchive/nosnippet, for crawls with
+    // explicit permission from the site owner
+    pub fn set_ignore_archive_directives(&mut self, ignore: bool) {
+        self.ignore_archive_directives = ignore;
+    }
+
+    // Method to enable incremental crawling with a database of previously seen pages (`None` disables it)
+    pub fn set_crawl_database(&mut self, database: Option<CrawlDatabase>) {
+        self.crawl_database = database.map(|db| Arc::new(Mutex::new(db)));
+        self.crawl_database_in_output_dir = false;
+    }
+
+    // Method to enable incremental crawling with the database `crawl_db.json` in the output root,
+    // opened (or created) when the next crawl starts
+    pub fn set_crawl_database_in_output_dir(&mut self, enabled: bool) {
+        self.crawl_database = None;
+        self.crawl_database_in_output_dir = enabled;
+    }
+
+    // Method to enable incremental crawling with the database stored at `path`, created on first use
+    pub fn load_crawl_database(&mut self, path: &Path) -> IoResult<()> {
+        self.set_crawl_database(Some(CrawlDatabase::open(path)?));
+        Ok(())
+    }
+
+    // Method to set the output root: one subdirectory per domain, plus scripts, CAPTCHA images, and the error log
+    pub fn set_output_dir(&mut self, dir: &Path) {
+        self.output_dir = dir.to_path_buf();
+    }
+
+    // Method to set the file errors are appended to, instead of `error.log` in the output root
+    pub fn set_error_log(&mut self, path: &Path) {
+        self.error_log = Some(path.to_path_buf());
+    }
+
+    // Method to load the allowlist from a file with one domain pattern per line
+    pub fn load_allowed_domains(&mut self, path: &Path) -> IoResult<()> {
+        self.set_allowed_domains(Some(DomainList::from_file(path)?));
+        Ok(())
+    }
+
+    // Method to load the denylist from a file with one domain pattern per line
+    pub fn load_denied_domains(&mut self, path: &Path) -> IoResult<()> {
+        self.set_denied_domains(Some(DomainList::from_file(path)?));
+        Ok(())
+    }
+
+    pub fn user_agent(&self) -> Option<&String> {
+        self.user_agent.as_ref()
+    }
+
+    pub fn scrape_audio(&self) -> bool {
+        self.scrape_audio
+    }
+
+    pub fn scrape_embeds(&self) -> bool {
+        self.scrape_embeds
+    }
+
+    pub fn scrape_objects(&self) -> bool {
+        self.scrape_objects
+    }
+
+    pub fn scrape_tracks(&self) -> bool {
+        self.scrape_tracks
+    }
+
+    pub fn scrape_stylesheets(&self) -> bool {
+        self.scrape_stylesheets
+    }
+
+    pub fn target_image_width(&self) -> Option<u32> {
+        self.target_image_width
+    }
+
+    pub fn download_images(&self) -> bool {
+        self.download_images
+    }
+
+    pub fn download_videos(&self) -> bool {
+        self.download_videos
+    }
+
+    pub fn download_audio(&self) -> bool {
+        self.download_audio
+    }
+
+    pub fn download_documents(&self) -> bool {
+        self.download_documents
+    }
+
+    pub fn download_scripts(&self) -> bool {
+        self.download_scripts
+    }
+
+    pub fn download_stylesheets(&self) -> bool {
+        self.download_stylesheets
+    }
+
+    pub fn download_fonts(&self) -> bool {
+        self.download_fonts
+    }
+
+    pub fn record_media_only(&self) -> bool {
+        self.record_media_only
+    }
+
+    pub fn follow_ftp(&self) -> bool {
+        self.follow_ftp
+    }
+
+    pub fn download_retries(&self) -> u32 {
+        self.download_retries
+    }
+
+    pub fn mime_policy(&self) -> MimePolicy {
+        self.mime_policy
+    }
+
+    pub fn skip_unchanged_media(&self) -> bool {
+        self.skip_unchanged_media
+    }
+
+    pub fn media_sample_size(&self) -> Option<u64> {
+        self.media_sample_size
+    }
+
+    pub fn legacy_finding_files(&self) -> bool {
+        self.legacy_finding_files
+    }
+
+    pub fn follow_frames(&self) -> bool {
+        self.follow_frames
+    }
+
+    pub fn follow_cross_origin_frames(&self) -> bool {
+        self.follow_cross_origin_frames
+    }
+
+    pub fn redact_pii(&self) -> bool {
+        self.pii_redactor.is_some()
+    }
+
+    /// Masks the personal data in text to be stored when PII is redacted, counting it in the metrics
+    /// as `redacted <kind>`; returns the text unchanged otherwise.
+    pub fn redact(&self, text: &str) -> String {
+        let Some(redactor) = &self.pii_redactor else { return text.to_string() };
+        let (redacted, counts) = redactor.redact(text);
+        self.record_redactions(counts);
+        redacted
+    }
+
+    // Masks the personal data of stored markup like `redact`, keeping its tags and attributes intact
+    pub(crate) fn redact_markup(&self, html: &str) -> String {
+        let Some(redactor) = &self.pii_redactor else { return html.to_string() };
+        let (redacted, counts) = redactor.redact_html(html);
+        self.record_redactions(counts);
+        redacted
+    }
+
+    // Masks the personal data of a stored JSON or XML payload like `redact`, keeping its structure intact
+    pub(crate) fn redact_payload(&self, body: &str, route: ContentRoute) -> String {
+        match (&self.pii_redactor, route) {
+            (Some(redactor), ContentRoute::Json) => match redactor.redact_json(body) {
+                Some((redacted, counts)) => {
+                    self.record_redactions(counts);
+                    redacted
+                }
+                None => self.redact(body),
+            },
+            _ => self.redact_markup(body),
+        }
+    }
+
+    // Masks a phone number taken from a `tel:` link, which need not look like one to the redactor
+    pub(crate) fn mask_phone(&self, phone: &str) -> String {
+        if self.redact_pii() { format!("[{}]", PiiKind::Phone) } else { phone.to_string() }
+    }
+
+    // Masks personal data like `redact` without counting it, for text already counted where it was found
+    pub(crate) fn mask_pii(&self, text: &str) -> String {
+        self.pii_redactor.as_ref().map_or_else(|| text.to_string(), |redactor| redactor.redact(text).0)
+    }
+
+    // Counts redactions in the metrics as `redacted <kind>`
+    fn record_redactions(&self, counts: BTreeMap<PiiKind, usize>) {
+        for (kind, count) in counts {
+            self.metrics.record_findings(&format!("redacted {}", kind), count);
+        }
+    }
+
+    /// Returns the file findings are appended to: `findings.jsonl` in the output directory.
+    pub fn findings_path(&self) -> PathBuf {
+        self.output_dir().join(FINDINGS_FILE)
+    }
+
+    /// Appends findings to `findings.jsonl`, logging instead of failing when it cannot be written.
+    pub fn log_findings(&self, findings: &[Finding]) {
+        let session_id = self.session_id();
+        // Findings are counted where they are found, so masking their evidence is not counted again
+        let findings: Vec<Finding> = findings
+            .iter()
+            .cloned()
+            .map(|finding| Finding { evidence: self.mask_pii(&finding.evidence), session_id: session_id.clone(), ..finding })
+            .collect();
+        let _guard = self.findings_lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(e) = append_findings(self.output_dir(), &findings) {
+            let error_message = format!("Failed to write findings to '{}': {}", self.findings_path().display(), e);
+            console_error!("{}", error_message);
+            self.log_error(&error_message);
+        }
+    }
+
+    pub fn manifest_index(&self) -> &Arc<ManifestIndex> {
+        &self.manifest_index
+    }
+
+    pub fn page_index(&self) -> &Arc<PageIndex> {
+        &self.page_index
+    }
+
+    pub fn download_validator(&self) -> Option<&DownloadValidator> {
+        self.download_validator.as_ref()
+    }
+
+    pub fn bandwidth_limit(&self) -> Option<u64> {
+        self.bandwidth_limiter.global_limit()
+    }
+
+    pub fn per_host_bandwidth_limit(&self) -> Option<u64> {
+        self.bandwidth_limiter.per_host_limit()
+    }
+
+    pub fn bandwidth_limiter(&self) -> &Arc<BandwidthLimiter> {
+        &self.bandwidth_limiter
+    }
+
+    pub fn save_html_snapshots(&self) -> bool {
+        self.save_html_snapshots
+    }
+
+    pub fn ignore_archive_directives(&self) -> bool {
+        self.ignore_archive_directives
+    }
+
+    pub fn crawl_database(&self) -> Option<&Arc<Mutex<CrawlDatabase>>> {
+        self.crawl_database.as_ref().or_else(|| self.output_crawl_database.get())
+    }
+
+    pub fn crawl_database_in_output_dir(&self) -> bool {
+        self.crawl_database_in_output_dir
+    }
+
+    // Returns where the crawl database is stored, or `None` without one (or with an in-memory one)
+    fn crawl_database_path(&self) -> Option<PathBuf> {
+        match self.crawl_database() {
+            Some(db) => db.lock().unwrap().path().map(Path::to_path_buf),
+            None => self.crawl_database_in_output_dir.then(|| self.output_dir.join("crawl_db.json")),
+        }
+    }
+
+    // Opens the crawl database in the output root on the first crawl that needs it
+    pub(crate) fn resolve_crawl_database(&self) {
+        if self.crawl_database.is_none() && self.crawl_database_in_output_dir {
+            self.output_crawl_database
+                .get_or_init(|| Arc::new(Mutex::new(self.open_crawl_database(&self.output_dir.join("crawl_db.json")))));
+        }
+    }
+
+    pub fn page_retries(&self) -> u32 {
+        self.page_retries
+    }
+
+    // Method to update whether `run_with_config` crawls the pages listed in robots.txt sitemaps
+    pub fn set_seed_from_sitemaps(&mut self, seed: bool) {
+        self.seed_from_sitemaps = seed;
+    }
+
+    // Method to add URLs crawled along with the start URL of `run_with_config` and `Scraper::crawl`
+    pub fn add_seeds(&mut self, seeds: &[String]) {
+        for seed in seeds {
+            if !self.seeds.contains(seed) {
+                self.seeds.push(seed.clone());
+            }
+        }
+    }
+
+    // Method to add the pages of a browser bookmark export or HAR file as seeds, see `read_seed_file`
+    pub fn load_seeds(&mut self, path: &Path) -> IoResult<usize> {
+        let seeds = read_seed_file(path)?;
+        self.add_seeds(&seeds);
+        Ok(seeds.len())
+    }
+
+    // Method to queue the pages of a frontier file written by an earlier pass, see `import_frontier`
+    pub fn import_frontier(&mut self, path: &Path) -> IoResult<usize> {
+        let entries = import_frontier(path)?;
+        let count = entries.len();
+        self.imported_frontier.extend(entries);
+        Ok(count)
+    }
+
+    // Method to write the pages a run crawled to a frontier file when it ends, for a later pass to
+    // import (None writes nothing)
+    pub fn set_frontier_export(&mut self, path: Option<&Path>) {
+        self.frontier_export = path.map(Path::to_path_buf);
+    }
+
+    // Method to only fetch pages and follow their links, skipping content extraction, media,
+    // findings and snapshots, for a fast discovery pass feeding a later extraction pass
+    pub fn set_discovery_only(&mut self, enabled: bool) {
+        self.discovery_only = enabled;
+    }
+
+    // Method to enable or disable downloading links that `classify_url` takes for assets through the
+    // media pipeline, instead of requesting them as pages and routing them by their Content-Type
+    pub fn set_route_assets(&mut self, enabled: bool) {
+        self.route_assets = enabled;
+    }
+
+    // Method to pin the session ID crawls with this config run under, e.g. one handed out by a job
+    // scheduler; `None` generates a new ID for every crawl
+    pub fn set_session_id(&mut self, session_id: Option<&str>) {
+        self.fixed_session_id = session_id.map(str::to_string);
+    }
+
+    // Returns the ID of a crawl starting now: the pinned one, or a new one; the crawl runs in it with `run::in_session`
+    pub(crate) fn new_session_id(&self) -> String {
+        self.fixed_session_id.clone().unwrap_or_else(run::new_session_id)
+    }
+
+    // Method to cap how many sitemap URLs seed a crawl
+    pub fn set_max_sitemap_urls(&mut self, max_urls: usize) {
+        self.max_sitemap_urls = max_urls;
+    }
+
+    pub fn seed_from_sitemaps(&self) -> bool {
+        self.seed_from_sitemaps
+    }
+
+    pub fn seeds(&self) -> &[String] {
+        &self.seeds
+    }
+
+    pub fn imported_frontier(&self) -> &[FrontierEntry] {
+        &self.imported_frontier
+    }
+
+    pub fn frontier_export(&self) -> Option<&Path> {
+        self.frontier_export.as_deref()
+    }
+
+    pub fn discovery_only(&self) -> bool {
+        self.discovery_only
+    }
+
+    pub fn route_assets(&self) -> bool {
+        self.route_assets
+    }
+
+    /// Returns the ID of the session of the crawl running on the current task, if any. Each crawl
+    /// has its own, even when several run concurrently with the same config.
+    pub fn session_id(&self) -> Option<String> {
+        run::current_session_id()
+    }
+
+    pub fn max_sitemap_urls(&self) -> usize {
+        self.max_sitemap_urls
+    }
+
+    // Method to update whether crawls follow the API endpoints that inline scripts request
+    pub fn set_discover_js_endpoints(&mut self, discover: bool) {
+        self.discover_js_endpoints = discover;
+    }
+
+    pub fn discover_js_endpoints(&self) -> bool {
+        self.discover_js_endpoints
+    }
+
+    // Method to update whether incremental crawls compare pages after `normalize_html` (the
+    // default), so nonces, CSRF tokens, timestamps and session ids don't count as changes
+    pub fn set_normalize_monitored_pages(&mut self, normalize: bool) {
+        self.normalize_monitored_pages = normalize;
+    }
+
+    pub fn normalize_monitored_pages(&self) -> bool {
+        self.normalize_monitored_pages
+    }
+
+    // Method to choose whether AMP and mobile versions of pages are stored separately, skipped
+    // (the default), or stored instead of the pages they belong to
+    pub fn set_alternate_policy(&mut self, policy: AlternatePolicy) {
+        self.alternate_policy = policy;
+    }
+
+    pub fn alternate_policy(&self) -> AlternatePolicy {
+        self.alternate_policy
+    }
+
+    pub fn alternates(&self) -> &Arc<AlternateMap> {
+        &self.alternates
+    }
+
+    pub fn page_classes(&self) -> &Arc<PageClassMap> {
+        &self.page_classes
+    }
+
+    // Method to crawl a section or language as its own partition, with its own page budget and output directory
+    pub fn add_partition(&mut self, partition: CrawlPartition) {
+        self.partitions.add(partition);
+    }
+
+    pub fn partitions(&self) -> &Arc<CrawlPartitions> {
+        &self.partitions
+    }
+
+    // Method to accept invalid and self-signed TLS certificates of the hosts of a domain list only,
+    // e.g. internal staging servers, keeping strict verification for every other host
+    pub fn set_insecure_hosts(&mut self, hosts: Option<DomainList>) {
+        self.tls_policy.set_insecure_hosts(hosts);
+    }
+
+    pub fn tls_policy(&self) -> &Arc<TlsPolicy> {
+        &self.tls_policy
+    }
+
+    // Method to cap the size of external scripts and stylesheets fetched during a crawl
+    pub fn set_max_script_bytes(&mut self, max_bytes: u64) {
+        self.max_script_bytes = max_bytes;
+    }
+
+    pub fn max_script_bytes(&self) -> u64 {
+        self.max_script_bytes
+    }
+
+    pub fn script_cache(&self) -> &Arc<ScriptCache> {
+        &self.script_cache
+    }
+
+    pub fn stylesheet_cache(&self) -> &Arc<ScriptCache> {
+        &self.stylesheet_cache
+    }
+
+    pub fn header_findings(&self) -> &Arc<HeaderFindingLog> {
+        &self.header_findings
+    }
+
+    // Method to probe the forms and script endpoints of crawled pages for undocumented
+    // parameters from a wordlist (`None`, the default, to not probe); see `discover_parameters`
+    pub fn set_parameter_wordlist(&mut self, wordlist: Option<Vec<String>>) {
+        self.parameter_wordlist = wordlist.map(Arc::new);
+    }
+
+    // Method to load the parameter wordlist from a file with one name per line
+    pub fn load_parameter_wordlist(&mut self, path: &Path) -> IoResult<()> {
+        let contents = std::fs::read_to_string(path)?;
+        let names = contents.lines().map(|line| line.split('#').next().unwrap_or("").trim()).filter(|name| !name.is_empty());
+        self.set_parameter_wordlist(Some(names.map(str::to_string).collect()));
+        Ok(())
+    }
+
+    pub fn parameter_wordlist(&self) -> Option<&[String]> {
+        self.parameter_wordlist.as_deref().map(Vec::as_slice)
+    }
+
+    pub fn parameter_findings(&self) -> &Arc<ParameterFindingLog> {
+        &self.parameter_findings
+    }
+
+    // Method to verify off-site links with HEAD requests instead of crawling them (`None` to crawl them)
+    pub fn set_external_link_checker(&mut self, checker: Option<ExternalLinkChecker>) {
+        self.external_link_checker = checker.map(Arc::new);
+    }
+
+    pub fn external_link_checker(&self) -> Option<&Arc<ExternalLinkChecker>> {
+        self.external_link_checker.as_ref()
+    }
+
+    // Method to change when hosts that keep failing are skipped (`None` to always retry them);
+    // the default opens a host's circuit for a minute after 5 failures in a row
+    pub fn set_circuit_breaker(&mut self, breaker: Option<CircuitBreaker>) {
+        self.circuit_breaker = breaker.map(Arc::new);
+    }
+
+    pub fn circuit_breaker(&self) -> Option<&Arc<CircuitBreaker>> {
+        self.circuit_breaker.as_ref()
+    }
+
+    // Method to cap the bytes sent and received per host (`None` for no cap), e.g. for metered proxies
+    pub fn set_host_byte_quota(&mut self, bytes: Option<u64>) {
+        self.host_byte_quota = bytes;
+    }
+
+    // Method to give one host its own byte quota, overriding `set_host_byte_quota`
+    pub fn set_host_byte_quota_for(&mut self, host: &str, bytes: u64) {
+        self.host_byte_quotas.insert(host.to_ascii_lowercase(), bytes);
+    }
+
+    pub fn host_byte_quota(&self, host: &str) -> Option<u64> {
+        self.host_byte_quotas.get(&host.to_ascii_lowercase()).copied().or(self.host_byte_quota)
+    }
+
+    /// Returns whether `host` has used up its byte quota, so no further requests are sent to it.
+    pub fn host_quota_exhausted(&self, host: &str) -> bool {
+        self.host_byte_quota(host).is_some_and(|quota| self.metrics.traffic(host).total() >= quota)
+    }
+
+    // Method to change the crawl order; the default `BreadthFirstScorer` crawls shallow pages first
+    pub fn set_url_scorer(&mut self, scorer: Arc<dyn UrlScorer>) {
+        self.url_scorer = scorer;
+    }
+
+    pub fn url_scorer(&self) -> &Arc<dyn UrlScorer> {
+        &self.url_scorer
+    }
+
+    // Method to change what counts as a duplicate page; the default `ExactUrlDedup` compares URLs exactly
+    pub fn set_dedup_policy(&mut self, policy: Arc<dyn DedupPolicy>) {
+        self.dedup_policy = policy;
+    }
+
+    pub fn dedup_policy(&self) -> &Arc<dyn DedupPolicy> {
+        &self.dedup_policy
+    }
+
+    // Method to retry pages that only yielded a bot wall once through a rendering backend (`None` to never render)
+    pub fn set_page_renderer(&mut self, renderer: Option<Arc<dyn PageRenderer>>) {
+        self.page_renderer = renderer;
+    }
+
+    pub fn page_renderer(&self) -> Option<&Arc<dyn PageRenderer>> {
+        self.page_renderer.as_ref()
+    }
+
+    // Method to cap the size of the pages downloaded and parsed; larger pages are skipped
+    pub fn set_max_page_bytes(&mut self, max_bytes: u64) {
+        self.max_page_bytes = max_bytes;
+    }
+
+    // Method to skip pages whose elements nest deeper than `max_depth`
+    pub fn set_max_nesting_depth(&mut self, max_depth: usize) {
+        self.max_nesting_depth = max_depth;
+    }
+
+    // Method to bound the parsing of one page (`None` for no limit); nothing of a page that times out is stored
+    pub fn set_page_timeout(&mut self, timeout: Option<Duration>) {
+        self.page_timeout = timeout;
+    }
+
+    pub fn max_page_bytes(&self) -> u64 {
+        self.max_page_bytes
+    }
+
+    pub fn max_nesting_depth(&self) -> usize {
+        self.max_nesting_depth
+    }
+
+    pub fn page_timeout(&self) -> Option<Duration> {
+        self.page_timeout
+    }
+
+    pub fn metrics(&self) -> &Arc<CrawlMetrics> {
+        &self.metrics
+    }
+
+    pub fn dead_letters(&self) -> &Arc<DeadLetterQueue> {
+        &self.dead_letters
+    }
+
+    /// Returns the file crawls record their run manifest in: `run_manifest.json` in the output directory.
+    pub fn run_manifest_path(&self) -> PathBuf {
+        self.output_dir.join(RUN_MANIFEST_FILE)
+    }
+
+    /// Returns the file the run manifest of one session is kept in: `runs/{session id}.json` in the
+    /// output directory, which later runs do not overwrite.
+    pub fn session_manifest_path(&self, session_id: &str) -> PathBuf {
+        self.output_dir.join(run::session_manifest_file(session_id))
+    }
+
+    /// Returns the file the dead letters of a crawl are appended to: `dead_letters.jsonl` in the output directory.
+    pub fn dead_letter_path(&self) -> PathBuf {
+        self.output_dir.join(DEAD_LETTER_FILE)
+    }
+
+    pub fn request_limiter(&self) -> &Arc<RequestLimiter> {
+        &self.request_limiter
+    }
+
+    pub fn request_rules(&self) -> &RequestRules {
+        &self.request_rules
+    }
+
+    pub fn extraction_rules(&self) -> &[ExtractionRule] {
+        &self.extraction_rules
+    }
+
+    /// Returns the schema of the first extraction rule matching `url`.
+    pub fn extraction_schema_for(&self, url: &str) -> Option<&ExtractionSchema> {
+        self.extraction_rules.iter().find(|rule| rule.pattern.matches(url)).map(|rule| &rule.schema)
+    }
+
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    pub fn error_log(&self) -> PathBuf {
+        self.error_log.clone().unwrap_or_else(|| self.output_dir.join(ERROR_LOG_FILE))
+    }
+
+    /// Returns the directory CAPTCHA images are downloaded into: `captcha_images` in the output root.
+    pub fn captcha_dir(&self) -> PathBuf {
+        self.output_dir.join("captcha_images")
+    }
+
+    /// Returns the output directory for the pages and media of the domain of `url`, or of its
+    /// partition (`<domain>/<partition>`) when it belongs to one.
+    pub fn domain_dir(&self, url: &str) -> PathBuf {
+        let dir = self.output_dir.join(extract_domain(url));
+        match self.partitions.partition_of(url) {
+            Some(partition) => dir.join(partition.name),
+            None => dir,
+        }
+    }
+
+    /// Returns the plain settings of this config, as recorded in run manifests.
+    pub fn settings(&self) -> CrawlSettings {
+        CrawlSettings {
+            follow_links: self.follow_links,
+            max_depth: self.max_depth,
+            user_agent: self.user_agent.clone(),
+            scrape_audio: self.scrape_audio,
+            scrape_embeds: self.scrape_embeds,
+            scrape_objects: self.scrape_objects,
+            scrape_tracks: self.scrape_tracks,
+            scrape_stylesheets: self.scrape_stylesheets,
+            target_image_width: self.target_image_width,
+            download_images: self.download_images,
+            download_videos: self.download_videos,
+            download_audio: self.download_audio,
+            download_documents: self.download_documents,
+            download_scripts: self.download_scripts,
+            download_stylesheets: self.download_stylesheets,
+            download_fonts: self.download_fonts,
+            record_media_only: self.record_media_only,
+            download_retries: self.download_retries,
+            mime_policy: self.mime_policy,
+            skip_unchanged_media: self.skip_unchanged_media,
+            media_sample_size: self.media_sample_size,
+            legacy_finding_files: self.legacy_finding_files,
+            follow_frames: self.follow_frames,
+            follow_cross_origin_frames: self.follow_cross_origin_frames,
+            redact_pii: self.redact_pii(),
+            follow_ftp: self.follow_ftp,
+            allowed_domains: self.allowed_domains().map(|list| list.patterns()),
+            insecure_hosts: self.tls_policy.insecure_hosts().map(|list| list.patterns()),
+            denied_domains: self.denied_domains().map(|list| list.patterns()),
+            save_html_snapshots: self.save_html_snapshots,
+            ignore_archive_directives: self.ignore_archive_directives,
+            output_dir: self.output_dir.clone(),
+            error_log: self.error_log(),
+            page_retries: self.page_retries,
+            seed_from_sitemaps: self.seed_from_sitemaps,
+            seeds: self.seeds.clone(),
+            frontier_export: self.frontier_export.clone(),
+            discovery_only: self.discovery_only,
+            route_assets: self.route_assets,
+            max_sitemap_urls: self.max_sitemap_urls,
+            discover_js_endpoints: self.discover_js_endpoints,
+            normalize_monitored_pages: self.normalize_monitored_pages,
+            alternate_policy: self.alternate_policy,
+            parameter_wordlist: self.parameter_wordlist.as_deref().cloned(),
+            circuit_breaker: self.circuit_breaker.as_ref().map(|breaker| (breaker.failure_threshold(), breaker.cooldown())),
+            host_byte_quota: self.host_byte_quota,
+            host_byte_quotas: self.host_byte_quotas.clone(),
+            max_page_bytes: self.max_page_bytes,
+            max_nesting_depth: self.max_nesting_depth,
+            page_timeout: self.page_timeout,
+            max_script_bytes: self.max_script_bytes,
+            per_host_request_interval: self.request_limiter.per_host_interval(),
+            rate_limit_rules: self.request_limiter.rules(),
+            bandwidth_limit: self.bandwidth_limiter.global_limit(),
+            per_host_bandwidth_limit: self.bandwidth_limiter.per_host_limit(),
+            request_rules: self.request_rules.clone(),
+            extraction_rules: self.extraction_rules.clone(),
+            partitions: self.partitions.partitions(),
+            external_link_checker: self.external_link_checker.as_ref().map(|checker| (checker.concurrency(), checker.min_interval())),
+            crawl_database: self.crawl_database_path(),
+            imported_frontier: self.imported_frontier.clone(),
+            session_id: self.fixed_session_id.clone(),
+        }
+    }
+
+    // Returns the parts of the config that `settings` cannot record
+    pub(crate) fn unrecorded_settings(&self) -> Vec<&'static str> {
+        let mut unrecorded = Vec::new();
+        if self.download_validator.is_some() {
+            unrecorded.push("download validator");
+        }
+        if self.page_renderer.is_some() {
+            unrecorded.push("page renderer");
+        }
+        if self.crawl_database.as_ref().is_some_and(|db| db.lock().unwrap().path().is_none()) {
+            unrecorded.push("in-memory crawl database");
+        }
+        unrecorded
+    }
+
+    /// Builds a config from recorded settings; everything the settings don't cover has its default.
+    pub fn from_settings(settings: &CrawlSettings) -> Self {
+        let parse_list = |patterns: &Option<Vec<String>>| patterns.as_ref().map(|patterns| DomainList::parse(&patterns.join("\n")));
+        let domain_filters = DomainFilters::default();
+        domain_filters.set_allowed(parse_list(&settings.allowed_domains));
+        domain_filters.set_denied(parse_list(&settings.denied_domains));
+        let tls_policy = TlsPolicy::default();
+        tls_policy.set_insecure_hosts(parse_list(&settings.insecure_hosts));
+        let request_limiter = RequestLimiter::new(settings.per_host_request_interval);
+        for rule in &settings.rate_limit_rules {
+            request_limiter.add_rule(rule.pattern.as_str(), rule.min_interval);
+        }
+        let partitions = CrawlPartitions::default();
+        for partition in &settings.partitions {
+            partitions.add(partition.clone());
+        }
+        let mut config = ScraperConfig {
+            user_agent: settings.user_agent.clone(),
+            scrape_audio: settings.scrape_audio,
+            scrape_embeds: settings.scrape_embeds,
+            scrape_objects: settings.scrape_objects,
+            scrape_tracks: settings.scrape_tracks,
+            scrape_stylesheets: settings.scrape_stylesheets,
+            target_image_width: settings.target_image_width,
+            download_images: settings.download_images,
+            download_videos: settings.download_videos,
+            download_audio: settings.download_audio,
+            download_documents: settings.download_documents,
+            download_scripts: settings.download_scripts,
+            download_stylesheets: settings.download_stylesheets,
+            download_fonts: settings.download_fonts,
+            record_media_only: settings.record_media_only,
+            download_retries: settings.download_retries,
+            mime_policy: settings.mime_policy,
+            skip_unchanged_media: settings.skip_unchanged_media,
+            media_sample_size: settings.media_sample_size,
+            legacy_finding_files: settings.legacy_finding_files,
+            follow_frames: settings.follow_frames,
+            follow_cross_origin_frames: settings.follow_cross_origin_frames,
+            pii_redactor: settings.redact_pii.then(|| Arc::new(PiiRedactor::new())),
+            manifest_index: Arc::new(ManifestIndex::default()),
+            page_index: Arc::new(PageIndex::default()),
+            follow_ftp: settings.follow_ftp,
+            domain_filters: Arc::new(domain_filters),
+            tls_policy: Arc::new(tls_policy),
+            save_html_snapshots: settings.save_html_snapshots,
+            ignore_archive_directives: settings.ignore_archive_directives,
+            output_dir: settings.output_dir.clone(),
+            error_log: Some(settings.error_log.clone()),
+            page_retries: settings.page_retries,
+            seed_from_sitemaps: settings.seed_from_sitemaps,
+            seeds: settings.seeds.clone(),
+            frontier_export: settings.frontier_export.clone(),
+            discovery_only: settings.discovery_only,
+            route_assets: settings.route_assets,
+            max_sitemap_urls: settings.max_sitemap_urls,
+            discover_js_endpoints: settings.discover_js_endpoints,
+            normalize_monitored_pages: settings.normalize_monitored_pages,
+            alternate_policy: settings.alternate_policy,
+            parameter_wordlist: settings.parameter_wordlist.clone().map(Arc::new),
+            circuit_breaker: settings.circuit_breaker.map(|(threshold, cooldown)| Arc::new(CircuitBreaker::new(threshold, cooldown))),
+            host_byte_quota: settings.host_byte_quota,
+            host_byte_quotas: settings.host_byte_quotas.clone(),
+            max_page_bytes: settings.max_page_bytes,
+            max_nesting_depth: settings.max_nesting_depth,
+            page_timeout: settings.page_timeout,
+            max_script_bytes: settings.max_script_bytes,
+            request_limiter: Arc::new(request_limiter),
+            bandwidth_limiter: Arc::new(BandwidthLimiter::new(settings.bandwidth_limit, settings.per_host_bandwidth_limit)),
+            request_rules: settings.request_rules.clone(),
+            extraction_rules: settings.extraction_rules.clone(),
+            partitions: Arc::new(partitions),
+            external_link_checker: settings.external_link_checker.map(|(concurrency, min_interval)| Arc::new(ExternalLinkChecker::new(concurrency, min_interval))),
+            imported_frontier: settings.imported_frontier.clone(),
+            fixed_session_id: settings.session_id.clone(),
+            ..ScraperConfig::new(settings.follow_links, settings.max_depth, None)
+        };
+        if let Some(path) = &settings.crawl_database {
+            let database = config.open_crawl_database(path);
+            config.set_crawl_database(Some(database));
+        }
+        config
+    }
+
+    /// Appends an error message to this config's error log, prefixed with the crawl's session ID.
+    pub fn log_error(&self, message: &str) {
+        match self.session_id() {
+            Some(session_id) => log_error_to(&self.error_log(), &format!("[{}] {}", session_id, message)),
+            None => log_error_to(&self.error_log(), message),
+        }
+    }
+
+    pub fn allowed_domains(&self) -> Option<Arc<DomainList>> {
+        self.domain_filters.allowed()
+    }
+
+    pub fn denied_domains(&self) -> Option<Arc<DomainList>> {
+        self.domain_filters.denied()
+    }
+
+    pub fn domain_filters(&self) -> &Arc<DomainFilters> {
+        &self.domain_filters
     }
 
-    pub fn follow_links(&self) -> bool {
-        self.follow_links
+    /// Returns a handle that changes the request limits, bandwidth limits and domain lists of this
+    /// config, and of the crawls running with it, while they run.
+    pub fn live_config(&self) -> LiveConfig {
+        LiveConfig::new(self)
     }
 
-    pub fn max_depth(&self) -> i32 {
-        self.max_depth
+    /// Returns whether a URL passes the domain lists: its host is not denied and, when an allowlist is set, allowed.
+    pub fn is_url_allowed(&self, url: &str) -> bool {
+        self.domain_filters.is_url_allowed(url)
     }
 
-    pub fn user_agent(&self) -> Option<&String> {
-        self.user_agent.as_ref()
+    /// Returns whether media of the given kind should be downloaded rather than only recorded.
+    pub fn download_enabled(&self, kind: MediaKind) -> bool {
+        if self.record_media_only {
+            return false;
+        }
+        match kind {
+            MediaKind::Image => self.download_images,
+            MediaKind::Video => self.download_videos,
+            MediaKind::Audio => self.download_audio,
+            MediaKind::Document => self.download_documents,
+            MediaKind::Script => self.download_scripts,
+            MediaKind::Stylesheet => self.download_stylesheets,
+            MediaKind::Font => self.download_fonts,
+            MediaKind::Other => true,
+        }
     }
 }
 
 
-pub async fn scrape_js_content(html: &str, url: &str, client: &Client, keywords: &[&str]) {
-    let document = Html::parse_document(html);
-    let script_selector = Selector::parse("script").unwrap();
+/// Searches the inline and external scripts of a page for keywords, saving external scripts
+/// to `./scraped_data/scraped_js`.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+/// * `url` - The URL of the page, used to resolve script URLs.
+/// * `client` - A reference to a `reqwest::Client` for fetching external scripts.
+/// * `keywords` - The strings to look for.
+///
+/// # Returns
+///
+/// The `JsFinding`s of every script; inline findings carry the page URL as `source_url`.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::scrape_js_content;
+/// # use reqwest::Client;
+/// # async fn example(client: Client, html: String) {
+/// let findings = scrape_js_content(&html, "https://example.com", &client, &["apiKey", "secret"]).await;
+/// for finding in findings {
+///     println!("{}", finding);
+/// }
+/// # }
+/// ```
+pub async fn scrape_js_content(html: &str, url: &str, client: &Client, keywords: &[&str]) -> Vec<JsFinding> {
+    scrape_js_content_with_config(html, url, client, keywords, None).await
+}
 
-    for script in document.select(&script_selector) {
-        // Check for inline JavaScript (within the HTML)
-        let script_content = script.inner_html();
-        if !script_content.is_empty() {
-            // Check for user-defined keywords in inline scripts
-            for &keyword in keywords {
-                if script_content.contains(keyword) {
-                    println!("Found '{}' in inline JS: {}", keyword, script_content);
-                }
+/// Like `scrape_js_content`, but with a `ScraperConfig`.
+///
+/// External scripts are saved under `scraped_js` in the config's output directory. Scripts larger
+/// than `max_script_bytes` are skipped, and scripts already seen by the config's `ScriptCache`, by
+/// URL or by content, are neither fetched nor scanned again, so shared bundles are handled once
+/// per crawl. The findings are also appended to the config's `findings.jsonl`, and printed only
+/// with legacy finding output (see `set_legacy_finding_files`).
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::{scrape_js_content_with_config, ScraperConfig};
+/// # use reqwest::Client;
+/// # async fn example(client: Client, pages: Vec<(String, String)>) {
+/// let mut config = ScraperConfig::default();
+/// config.set_max_script_bytes(512 * 1024);
+/// for (url, html) in &pages {
+///     scrape_js_content_with_config(html, url, &client, &["apiKey"], Some(&config)).await;
+/// }
+/// println!("{} distinct scripts", config.script_cache().len());
+/// # }
+/// ```
+pub async fn scrape_js_content_with_config(html: &str, url: &str, client: &Client, keywords: &[&str], config: Option<&ScraperConfig>) -> Vec<JsFinding> {
+    let default_config = ScraperConfig::default();
+    let config = config.unwrap_or(&default_config);
+    let mut findings = Vec::new();
+
+    // Collect the scripts before fetching, as the parsed document cannot be held across awaits
+    let (inline_scripts, script_urls): (Vec<String>, Vec<String>) = {
+        let document = Html::parse_document(html);
+        let script_selector = Selector::parse("script").unwrap();
+        let scripts: Vec<_> = document.select(&script_selector).collect();
+        (
+            scripts.iter().map(|script| script.inner_html()).filter(|content| !content.is_empty()).collect(),
+            scripts.iter().filter_map(|script| script.value().attr("src")).map(|src| normalize_link(src, url)).collect(),
+        )
+    };
+
+    // Check for user-defined keywords in inline scripts
+    for script_content in &inline_scripts {
+        for finding in find_js_keywords(script_content, url, keywords) {
+            if config.legacy_finding_files() {
+                console!("Found '{}' in inline JS: {}", finding.kind, finding);
             }
+            findings.push(finding);
         }
+    }
 
-        // Check if the script tag has a `src` attribute (external JS file)
-        if let Some(src) = script.value().attr("src") {
-            let js_url = normalize_link(src, url);
+    // Fetch and download external JS files
+    let js_dir = config.output_dir().join("scraped_js");
+    for js_url in script_urls {
+        if !config.is_url_allowed(&js_url) || !config.script_cache().insert_url(&js_url) {
+            continue;
+        }
 
-            // Fetch and download the JS file
-            match client.get(&js_url).send().await {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        if let Ok(js_content) = response.text().await {
-                            // Process the JS file content for user-defined keywords
-                            for &keyword in keywords {
-                                if js_content.contains(keyword) {
-                                    println!("Found '{}' in external JS: {}", keyword, js_content);
-                                }
-                            }
+        let js_content = match fetch_script(client, &js_url, config).await {
+            Ok(Some(js_content)) => js_content,
+            Ok(None) => {
+                console!("Skipping JS file larger than {} bytes: {}", config.max_script_bytes(), js_url);
+                continue;
+            }
+            Err(error_message) => {
+                console_error!("{}", error_message);
+                config.log_error(&error_message);
+                continue;
+            }
+        };
 
-                            // Optionally, save the JS content to a file
-                            let file_name = js_url.split('/').last().unwrap_or("script.js").to_string();
-                            let file_path = format!("./scraped_js/{}", file_name);
-                            if let Err(e) = save_js_file(&file_path, &js_content) {
-                                eprintln!("Failed to save JS file '{}': {}", file_path, e);
-                            }
-                        }
-                    } else {
-                        eprintln!("Failed to download JS file from '{}': Status code {}", js_url, response.status());
-                    }
-                }
-                Err(e) => eprintln!("Error fetching JS file '{}': {}", js_url, e),
+        // The same bundle is often served from several URLs (CDN mirrors, cache-busting queries)
+        let hash = sha256_hex(js_content.as_bytes());
+        if !config.script_cache().insert_hash(&hash) {
+            continue;
+        }
+
+        // Process the JS file content for user-defined keywords
+        for finding in find_js_keywords(&js_content, &js_url, keywords) {
+            if config.legacy_finding_files() {
+                console!("Found '{}' in external JS: {}", finding.kind, finding);
             }
+            findings.push(finding);
+        }
+
+        // Save the JS content, keeping distinct scripts that share a file name
+        let file_name = Url::parse(&js_url)
+            .ok()
+            .and_then(|parsed| parsed.path_segments().and_then(|mut segments| segments.next_back().map(str::to_string)))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "script.js".to_string());
+        let mut file_path = js_dir.join(&file_name);
+        if file_path.exists() {
+            file_path = js_dir.join(format!("{}-{}", &hash[..12], file_name));
         }
+        if let Err(e) = create_dir_all(&js_dir).and_then(|_| save_js_file(&file_path.to_string_lossy(), &js_content)) {
+            let error_message = format!("Failed to save JS file '{}': {}", file_path.display(), e);
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+        }
+    }
+    let logged: Vec<Finding> = findings.iter().map(|finding| Finding::new(FindingKind::JsKeyword, &finding.source_url, &finding.to_string())).collect();
+    config.log_findings(&logged);
+    findings
+}
+
+// Downloads a script or stylesheet, returning `None` when it exceeds the config's `max_script_bytes`
+pub(crate) async fn fetch_script(client: &Client, js_url: &str, config: &ScraperConfig) -> Result<Option<String>, String> {
+    let response = request::send(client, Method::GET, js_url, config, |request| request)
+        .await
+        .map_err(|e| format!("Error fetching JS file '{}': {}", js_url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download JS file from '{}': Status code {}", js_url, response.status()));
     }
+    let content_type = response.headers().get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).map(str::to_string);
+    let body = request::read_body(response, config.max_script_bytes(), config).await.map_err(|e| format!("Error reading JS file '{}': {}", js_url, e))?;
+    Ok(body.map(|body| page::decode_body(&body, content_type.as_deref())))
 }
 
 /// Save the JavaScript content to a file.
@@ -791,7 +3916,7 @@ pub async fn scrape_js_content(html: &str, url: &str, client: &Client, keywords:
 fn save_js_file(file_path: &str, js_content: &str) -> Result<(), std::io::Error> {
     let mut file = File::create(file_path)?;
     file.write_all(js_content.as_bytes())?;
-    println!("Saved JS file to '{}'", file_path);
+    console!("Saved JS file to '{}'", file_path);
     Ok(())
 }
 
@@ -855,7 +3980,7 @@ pub async fn cap_solver(client: &Client, html: &str, current_url: &str) -> IoRes
             if let Some(src) = img.value().attr("src") {
                 if src.ends_with(".png") || src.ends_with(".jpeg") || src.ends_with(".jpg") || src.ends_with(".gif") || src.ends_with(".gif") {
                     let img_url = normalize_link(src, current_url);
-                    let img_path = PathBuf::from("./captcha_images/captcha.png");
+                    let img_path = default_output_dir().join("captcha_images").join("captcha.png");
 
                     // Download CAPTCHA image
                     download_media(client, &img_url, &img_path).await;
@@ -868,7 +3993,7 @@ pub async fn cap_solver(client: &Client, html: &str, current_url: &str) -> IoRes
 
                     match ai(&comm).await {
                         Ok(captcha_text) => {
-                            println!("CAPTCHA solved: {}", captcha_text);
+                            console!("CAPTCHA solved: {}", captcha_text);
 
                             // Submit CAPTCHA
                             let mut form_data = vec![("captcha_response".to_string(), captcha_text.to_string())];
@@ -902,18 +4027,18 @@ pub async fn cap_solver(client: &Client, html: &str, current_url: &str) -> IoRes
                             match form_response {
                                 Ok(response) => {
                                     if response.status().is_success() {
-                                        println!("CAPTCHA submitted successfully.");
+                                        console!("CAPTCHA submitted successfully.");
                                     } else {
-                                        eprintln!("Failed to submit CAPTCHA. Status: {}", response.status());
+                                        console_error!("Failed to submit CAPTCHA. Status: {}", response.status());
                                     }
                                 }
                                 Err(e) => {
-                                    eprintln!("Failed to submit CAPTCHA to '{}': {}", captcha_submission_url, e);
+                                    console_error!("Failed to submit CAPTCHA to '{}': {}", captcha_submission_url, e);
                                 }
                             }
                         }
                         Err(e) => {
-                            eprintln!("Failed to solve CAPTCHA: {}", e);
+                            console_error!("Failed to solve CAPTCHA: {}", e);
                         }
                     }
                 }
@@ -963,20 +4088,14 @@ pub fn ai<'a>(comm: &'a Comm) -> Pin<Box<dyn Future<Output = IoResult<String>> +
                 if output.status.success() {
                     Ok(String::from_utf8_lossy(&output.stdout).to_string())
                 } else {
-                    Err(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!(
-                            "Command execution failed with status {}: {}",
-                            output.status,
-                            String::from_utf8_lossy(&output.stderr)
-                        ),
-                    ))
+                    Err(std::io::Error::other(format!(
+                        "Command execution failed with status {}: {}",
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr)
+                    )))
                 }
             }
-            Err(e) => Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to execute command: {}", e),
-            )),
+            Err(e) => Err(std::io::Error::other(format!("Failed to execute command: {}", e))),
         }
     })
 }
@@ -992,10 +4111,14 @@ pub fn ai<'a>(comm: &'a Comm) -> Pin<Box<dyn Future<Output = IoResult<String>> +
 /// * `visited` - A mutable reference to a `HashSet<String>` to track visited URLs.
 ///
 /// # Example
-/// ```
+/// ```no_run
+/// # use knee_scraper::ai_scrape;
+/// # use reqwest::Client;
+/// # use std::collections::HashSet;
+/// # async fn example(client: Client, mut visited: HashSet<String>) {
 /// ai_scrape("https://example.com", &client, &mut visited).await;
+/// # }
 /// ```
-
 pub fn ai_scrape<'a>(
     url: &'a str,
     client: &'a Client,
@@ -1012,7 +4135,7 @@ pub fn ai_scrape<'a>(
             Ok(response) => {
                 // Assuming CAPTCHA is detected via status codes 429 (Too Many Requests) or 403 (Forbidden)
                 if response.status().as_u16() == 429 || response.status().as_u16() == 403 {
-                    println!("CAPTCHA detected at: {}", url);
+                    console!("CAPTCHA detected at: {}", url);
                     
                     if let Ok(html) = response.text().await {
                         let document = Html::parse_document(&html);
@@ -1029,7 +4152,7 @@ pub fn ai_scrape<'a>(
                                 if let Some(src) = img.value().attr("src") {
                                     if src.ends_with(".png") || src.ends_with(".jpeg") || src.ends_with(".jpg") || src.ends_with(".gif") {
                                         let img_url = normalize_link(src, url);
-                                        let img_path = PathBuf::from("./captcha_images/captcha.png");
+                                        let img_path = default_output_dir().join("captcha_images").join("captcha.png");
 
                                         // Download CAPTCHA image
                                         download_media(client, &img_url, &img_path).await;
@@ -1042,7 +4165,7 @@ pub fn ai_scrape<'a>(
 
                                         match ai(&comm).await {
                                             Ok(captcha_text) => {
-                                                println!("CAPTCHA solved: {}", captcha_text);
+                                                console!("CAPTCHA solved: {}", captcha_text);
 
                                                 // Submit CAPTCHA
                                         let mut form_data = vec![("captcha_response".to_string(), captcha_text.to_string())];
@@ -1075,20 +4198,20 @@ pub fn ai_scrape<'a>(
                                                 match form_response {
                                                     Ok(response) => {
                                                         if response.status().is_success() {
-                                                            println!("CAPTCHA submitted successfully. Continuing with scraping...");
+                                                            console!("CAPTCHA submitted successfully. Continuing with scraping...");
                                                             // Retry scraping after submitting the CAPTCHA solution
                                                             ai_scrape(url, client, visited).await;
                                                         } else {
-                                                            eprintln!("Failed to submit CAPTCHA. Status: {}", response.status());
+                                                            console_error!("Failed to submit CAPTCHA. Status: {}", response.status());
                                                         }
                                                     }
                                                     Err(e) => {
-                                                        eprintln!("Failed to submit CAPTCHA to '{}': {}", captcha_submission_url, e);
+                                                        console_error!("Failed to submit CAPTCHA to '{}': {}", captcha_submission_url, e);
                                                     }
                                                 }
                                             }
                                             Err(e) => {
-                                                eprintln!("Failed to solve CAPTCHA: {}", e);
+                                                console_error!("Failed to solve CAPTCHA: {}", e);
                                             }
                                         }
                                     }
@@ -1099,7 +4222,7 @@ pub fn ai_scrape<'a>(
                 } else {
                     match response.text().await {
                         Ok(html) => {
-                            println!("Scraping: {}", url);
+                            console!("Scraping: {}", url);
                             scrape_content(&html, url, client).await;
                             scrape_js(&html);
                             scrape_for_errors(&html);
@@ -1114,7 +4237,7 @@ pub fn ai_scrape<'a>(
                         }
                         Err(e) => {
                             let error_message = format!("Failed to get HTML content from '{}': {}", url, e);
-                            eprintln!("{}", error_message);
+                            console_error!("{}", error_message);
                             log_error_to_file(&error_message);
                         }
                     }
@@ -1122,7 +4245,7 @@ pub fn ai_scrape<'a>(
             }
             Err(e) => {
                 let error_message = format!("Failed to request '{}': {}", url, e);
-                eprintln!("{}", error_message);
+                console_error!("{}", error_message);
                 log_error_to_file(&error_message);
             }
         }
@@ -1138,7 +4261,6 @@ pub fn ai_scrape<'a>(
 /// * `config`: An optional reference to `ScraperConfig` for controlling scraper behavior.
 /// * `visited`: A `HashSet` that tracks visited URLs.
 /// * `target_phrase`: The phrase to search for in the HTML content.
-
 pub async fn rec_ai_scrape(
     url: &str,
     client: &Client,
@@ -1150,27 +4272,23 @@ pub async fn rec_ai_scrape(
     queue.push_back(url.to_string());
     let mut current_depth = 0;
 
-    let follow_links = config.map_or(true, |c| c.follow_links()); // Default: true
+    let follow_links = config.is_none_or(|c| c.follow_links()); // Default: true
     let max_depth = config.map_or(3, |c| c.max_depth()); // Default: 3
-    let user_agent = config.and_then(|c| c.user_agent().cloned());
+    let in_scope = |link: &str| config.is_none_or(|c| c.is_url_allowed(link));
 
     while let Some(current_url) = queue.pop_front() {
-        if visited.contains(&current_url) {
+        if visited.contains(&current_url) || !in_scope(&current_url) {
             continue;
         }
 
-        println!("Visiting: {}", current_url);
+        console!("Visiting: {}", current_url);
         visited.insert(current_url.clone());
 
-        let mut request = client.get(&current_url);
-        if let Some(ref agent) = user_agent {
-            request = request.header(header::USER_AGENT, agent);
-        }
-
-        let response = match request.send().await {
-            Ok(response) => response,
-            Err(_) => continue,
+        let response = match config {
+            Some(config) => request::send(client, Method::GET, &current_url, config, |request| request).await.ok(),
+            None => client.get(&current_url).send().await.ok(),
         };
+        let Some(response) = response else { continue };
 
         if response.status().is_success() {
             let html = match response.text().await {
@@ -1179,22 +4297,22 @@ pub async fn rec_ai_scrape(
             };
 
             if should_scrape_content(&html, target_phrase) {
-                println!("Target phrase found in: {}", current_url);
+                console!("Target phrase found in: {}", current_url);
 
                 if follow_links && current_depth < max_depth {
                     let links = extract_links(&html, &current_url);
                     for link in links {
-                        if !visited.contains(&link) {
+                        if !visited.contains(&link) && in_scope(&link) {
                             queue.push_back(link);
                         }
                     }
                     current_depth += 1;
                 }
             } else {
-                println!("Target phrase not found in: {}", current_url);
+                console!("Target phrase not found in: {}", current_url);
             }
         } else if response.status().as_u16() == 429 || response.status().as_u16() == 403 {
-            println!("CAPTCHA detected at: {}", current_url);
+            console!("CAPTCHA detected at: {}", current_url);
 
             if let Ok(html) = response.text().await {
                 let document = Html::parse_document(&html);
@@ -1211,7 +4329,7 @@ pub async fn rec_ai_scrape(
                         if let Some(src) = img.value().attr("src") {
                             if src.ends_with(".png") || src.ends_with(".jpeg") || src.ends_with(".jpg") || src.ends_with(".gif") {
                                 let img_url = normalize_link(src, &current_url);
-                                let img_path = PathBuf::from("./captcha_images/captcha.png");
+                                let img_path = config.map_or_else(|| default_output_dir().join("captcha_images"), |c| c.captcha_dir()).join("captcha.png");
 
                                 // Download CAPTCHA image
                                 download_media(client, &img_url, &img_path).await;
@@ -1224,7 +4342,7 @@ pub async fn rec_ai_scrape(
 
                                 match ai(&comm).await {
                                     Ok(captcha_text) => {
-                                        println!("CAPTCHA solved: {}", captcha_text);
+                                        console!("CAPTCHA solved: {}", captcha_text);
 
                                         // Submit CAPTCHA
                                         let mut form_data = vec![("captcha_response".to_string(), captcha_text.to_string())];
@@ -1258,19 +4376,19 @@ pub async fn rec_ai_scrape(
                                         match form_response {
                                             Ok(response) => {
                                                 if response.status().is_success() {
-                                                    println!("CAPTCHA submitted successfully. Continuing with scraping...");
+                                                    console!("CAPTCHA submitted successfully. Continuing with scraping...");
                                                     queue.push_back(current_url.clone());
                                                 } else {
-                                                    eprintln!("Failed to submit CAPTCHA. Status: {}", response.status());
+                                                    console_error!("Failed to submit CAPTCHA. Status: {}", response.status());
                                                 }
                                             }
                                             Err(e) => {
-                                                eprintln!("Failed to submit CAPTCHA to '{}': {}", captcha_submission_url, e);
+                                                console_error!("Failed to submit CAPTCHA to '{}': {}", captcha_submission_url, e);
                                             }
                                         }
                                     }
                                     Err(e) => {
-                                        eprintln!("Failed to solve CAPTCHA: {}", e);
+                                        console_error!("Failed to solve CAPTCHA: {}", e);
                                     }
                                 }
                             }
@@ -1279,7 +4397,7 @@ pub async fn rec_ai_scrape(
                 }
             }
         } else {
-            println!("Failed to request '{}': Status: {}", current_url, response.status());
+            console!("Failed to request '{}': Status: {}", current_url, response.status());
         }
     }
 }
@@ -1291,6 +4409,7 @@ pub async fn rec_ai_scrape(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{http_response, serve};
     use reqwest::Client;
     use std::collections::HashSet;
     use std::path::Path;
@@ -1305,12 +4424,14 @@ mod tests {
     // Test for the extract_links function
     #[test]
     fn test_extract_links() {
-        let html = r#"<a href="/about">About</a> <a href="https://example.com">Home</a>"#;
+        let html = r#"<a href="/about">About</a> <a href="https://example.com">Home</a>
+            <a href="mailto:info@test.com">Mail</a> <a href="javascript:void(0)">Menu</a>"#;
         let base_url = "https://test.com";
         let links = extract_links(html, base_url);
 
         assert!(links.contains("https://test.com/about"));
         assert!(links.contains("https://example.com"));
+        assert_eq!(links.len(), 2);
     }
 
     // Test for the normalize_link function
@@ -1323,6 +4444,1121 @@ mod tests {
         assert_eq!(normalized, "https://example.com/about");
     }
 
+    // Test for collecting media sources per element type
+    #[test]
+    fn test_collect_media_urls() {
+        let html = r#"
+            <video><source src="/clip.mp4"></video>
+            <audio controls><source src="/episodes/ep1.mp3?dl=1"><track src="/episodes/ep1.vtt"></audio>"#;
+        let document = Html::parse_document(html);
+
+        let video_urls = collect_media_urls(&document, "video[src], video source[src]", "src", "https://pod.test");
+        let audio_urls = collect_media_urls(&document, "audio[src], audio source[src]", "src", "https://pod.test");
+        let track_urls = collect_media_urls(&document, "track[src]", "src", "https://pod.test");
+
+        assert_eq!(video_urls, vec!["https://pod.test/clip.mp4"]);
+        assert_eq!(audio_urls, vec!["https://pod.test/episodes/ep1.mp3?dl=1"]);
+        assert_eq!(track_urls, vec!["https://pod.test/episodes/ep1.vtt"]);
+        assert_eq!(media_file_name(&audio_urls[0], "audio.mp3"), "ep1.mp3");
+        assert_eq!(media_file_name("https://pod.test/", "audio.mp3"), "audio.mp3");
+    }
+
+    // Test for the per-media-type download toggles
+    #[test]
+    fn test_download_enabled() {
+        let mut config = ScraperConfig::default();
+        assert!(config.download_enabled(MediaKind::Image));
+        assert!(!config.download_enabled(MediaKind::Document));
+
+        config.set_download_images(false);
+        config.set_download_documents(true);
+        assert!(!config.download_enabled(MediaKind::Image));
+        assert!(config.download_enabled(MediaKind::Document));
+
+        config.set_record_media_only(true);
+        assert!(!config.download_enabled(MediaKind::Document));
+        assert!(!config.download_enabled(MediaKind::Other));
+    }
+
+    // Test that everything a crawl writes lives below the configured output root
+    #[test]
+    fn test_output_root_derives_all_paths() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::default();
+        config.set_output_dir(temp_dir.path());
+        assert_eq!(config.error_log(), temp_dir.path().join(ERROR_LOG_FILE));
+        assert_eq!(config.captcha_dir(), temp_dir.path().join("captcha_images"));
+        assert!(config.dead_letter_path().starts_with(temp_dir.path()));
+
+        config.log_error("boom");
+        assert_eq!(std::fs::read_to_string(temp_dir.path().join("error.log")).unwrap(), "boom\n");
+
+        config.set_error_log(&temp_dir.path().join("logs/crawl.log"));
+        config.log_error("again");
+        assert!(temp_dir.path().join("logs/crawl.log").exists());
+    }
+
+    // Test for incremental crawls answering conditional requests with 304 Not Modified
+    #[tokio::test]
+    async fn test_incremental_crawl_skips_unchanged_pages() {
+        let base = serve(|request| {
+            if request.to_ascii_lowercase().contains("if-none-match: \"v1\"") {
+                http_response("304 Not Modified", &[("ETag", "\"v1\"")], b"")
+            } else {
+                http_response("200 OK", &[("ETag", "\"v1\"")], b"<html><body><p>Hello</p></body></html>")
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("crawl_db.json");
+        let client = Client::new();
+
+        for expected in [(1, 0), (0, 1)] {
+            let mut config = ScraperConfig::new(false, 0, None);
+            config.set_output_dir(temp_dir.path());
+            config.load_crawl_database(&db_path).unwrap();
+            recursive_scrape_with_config(&base, &client, Some(&config), &mut HashSet::new()).await;
+
+            let db = config.crawl_database().unwrap().lock().unwrap();
+            assert_eq!((db.delta().new.len(), db.delta().unchanged), expected);
+        }
+    }
+
+    // Test that crawls sharing one crawl database compare pages they skipped as unchanged as unchanged
+    #[tokio::test]
+    async fn test_incremental_crawls_compare_unchanged_pages() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let base = serve(move |request| {
+            let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+            match path.as_str() {
+                "/" if request.to_ascii_lowercase().contains("if-none-match: \"v1\"") => http_response("304 Not Modified", &[("ETag", "\"v1\"")], b""),
+                "/" => http_response("200 OK", &[("ETag", "\"v1\"")], b"<a href='/same'>Same</a><a href='/moving'>Moving</a>"),
+                "/same" => http_response("200 OK", &[], b"<p>Always the same</p>"),
+                _ => http_response("200 OK", &[], format!("<p>Visit {}</p>", counter.fetch_add(1, Ordering::SeqCst)).as_bytes()),
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("crawl_db.json");
+        let client = Client::new();
+
+        for run in ["monday", "tuesday"] {
+            let mut config = ScraperConfig::new(true, 1, None);
+            config.set_output_dir(&temp_dir.path().join(run));
+            config.load_crawl_database(&db_path).unwrap();
+            recursive_scrape_with_config(&format!("{}/", base), &client, Some(&config), &mut HashSet::new()).await;
+            finish_crawl(&client, &config).await;
+        }
+
+        let diff = compare_crawls(&temp_dir.path().join("monday"), &temp_dir.path().join("tuesday")).unwrap();
+        assert!(diff.new_urls.is_empty() && diff.removed_urls.is_empty(), "{}", diff);
+        assert_eq!(diff.changed_pages, vec![format!("{}/moving", base)]);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    // Test that runs sharing an output directory keep their own manifests and are compared by session ID
+    #[tokio::test]
+    async fn test_runs_are_compared_by_session_id() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let visits = Arc::new(AtomicUsize::new(0));
+        let base = serve(move |_| {
+            let visit = visits.fetch_add(1, Ordering::SeqCst);
+            http_response("200 OK", &[], format!("<p>Mail ops{}@a.test</p>", visit).as_bytes())
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        for run in ["monday", "tuesday"] {
+            let mut config = ScraperConfig::new(false, 0, None);
+            config.set_output_dir(temp_dir.path());
+            config.set_session_id(Some(run));
+            Scraper::new(Client::new(), config).crawl(&format!("{}/", base)).await;
+        }
+
+        assert_eq!(RunManifest::read(&temp_dir.path().join(RUNS_DIR).join("monday.json")).unwrap().session_id.as_deref(), Some("monday"));
+        let diff = compare_runs(temp_dir.path(), "monday", "tuesday").unwrap();
+        let evidence: Vec<&str> = diff.new_findings.iter().map(|finding| finding.evidence.as_str()).collect();
+        assert_eq!(evidence, vec!["ops1@a.test"]);
+        assert_eq!(compare_runs(temp_dir.path(), "monday", "sunday").unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    }
+
+    // Test for crawling in the order chosen by a custom `UrlScorer`
+    #[tokio::test]
+    async fn test_url_scorer_orders_the_crawl() {
+        struct PreferPricing;
+        impl UrlScorer for PreferPricing {
+            fn score(&self, candidate: &UrlCandidate) -> f64 {
+                if candidate.anchor_text == "Pricing" { 10.0 } else { candidate.referrer_relevance - 1.0 }
+            }
+        }
+
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let log = requested.clone();
+        let base = serve(move |request| {
+            let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+            log.lock().unwrap().push(path.clone());
+            let body = match path.as_str() {
+                "/" => "<a href='/blog'>Blog</a><a href='/docs'>Docs</a><a href='/pricing'>Pricing</a>",
+                "/blog" => "<a href='/blog/post'>Post</a>",
+                _ => "<p>Leaf</p>",
+            };
+            http_response("200 OK", &[], body.as_bytes())
+        })
+        .await;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 2, Some("test-agent".to_string()));
+        config.set_output_dir(temp_dir.path());
+        config.set_scrape_stylesheets(false);
+        config.set_url_scorer(Arc::new(PreferPricing));
+        recursive_scrape_with_config(&base, &Client::new(), Some(&config), &mut HashSet::new()).await;
+
+        assert_eq!(*requested.lock().unwrap(), vec!["/", "/pricing", "/blog", "/docs", "/blog/post"]);
+    }
+
+    // Test for dead-lettering pages that keep failing and re-driving them later
+    #[tokio::test]
+    async fn test_dead_letters_are_redriven() {
+        let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = requests.clone();
+        let base = serve(move |_| {
+            if counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 {
+                http_response("503 Service Unavailable", &[], b"")
+            } else {
+                http_response("200 OK", &[], b"<p>Back</p>")
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(false, 0, None);
+        config.set_page_retries(1);
+        config.set_output_dir(temp_dir.path());
+        config.set_error_log(&temp_dir.path().join("error.log"));
+        let client = Client::new();
+
+        recursive_scrape_with_config(&base, &client, Some(&config), &mut HashSet::new()).await;
+        let letters = read_dead_letters(&config.dead_letter_path()).unwrap();
+        assert_eq!((letters.len(), letters[0].attempts), (1, 2));
+        assert!(letters[0].reason.contains("503"));
+
+        // A copy kept elsewhere is redriven in place: the URL fails again and is the only letter left in it
+        let copy = temp_dir.path().join("elsewhere").join("failed.jsonl");
+        std::fs::create_dir_all(copy.parent().unwrap()).unwrap();
+        std::fs::copy(config.dead_letter_path(), &copy).unwrap();
+        requests.store(0, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(redrive_dead_letters(&copy, &client, Some(&config)).await.unwrap(), 1);
+        assert_eq!(read_dead_letters(&copy).unwrap().len(), 1);
+        assert_eq!(read_dead_letters(&config.dead_letter_path()).unwrap().len(), 1);
+
+        requests.store(2, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(redrive_dead_letters(&config.dead_letter_path(), &client, Some(&config)).await.unwrap(), 1);
+        assert!(read_dead_letters(&config.dead_letter_path()).unwrap().is_empty());
+        let dir = temp_dir.path().join("127.0.0.1");
+        assert!(page_file(&dir, "content", &read_page_index(&dir).unwrap()[0].1).exists());
+    }
+
+    // Test that a host failing again and again stops being requested
+    #[tokio::test]
+    async fn test_failing_host_opens_circuit() {
+        let requests = Arc::new(Mutex::new(0));
+        let counter = requests.clone();
+        let base = serve(move |request| {
+            *counter.lock().unwrap() += 1;
+            match request.split_whitespace().nth(1) {
+                Some("/") => http_response("200 OK", &[], b"<a href='/a'>a</a><a href='/b'>b</a><a href='/c'>c</a><a href='/d'>d</a>"),
+                _ => http_response("503 Service Unavailable", &[], b""),
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_page_retries(0);
+        config.set_circuit_breaker(Some(CircuitBreaker::new(2, Duration::from_secs(60))));
+        config.set_output_dir(temp_dir.path());
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![base.clone()], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+
+        assert_eq!(*requests.lock().unwrap(), 3);
+        assert_eq!((stats.pages_failed, stats.pages_circuit_open), (2, 2));
+        let health = config.circuit_breaker().unwrap().health("127.0.0.1").unwrap();
+        assert_eq!((health.requests, health.failures), (3, 2));
+        let letters = config.dead_letters().entries();
+        assert_eq!(letters.iter().filter(|letter| letter.reason == "circuit open").count(), 2);
+    }
+
+    // Test that traffic is accounted per host and a used-up byte quota stops the crawl of that host
+    #[tokio::test]
+    async fn test_byte_quota_stops_requests_to_host() {
+        let base = serve(|request| match request.split_whitespace().nth(1) {
+            Some("/") => http_response("200 OK", &[], b"<a href='/a'>a</a><a href='/b'>b</a><a href='/c'>c</a>"),
+            _ => http_response("200 OK", &[], &[b'x'; 1_000]),
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_host_byte_quota_for("127.0.0.1", 1_000);
+        config.set_output_dir(temp_dir.path());
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![base.clone()], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+
+        assert_eq!((stats.pages_scraped, stats.pages_over_quota), (2, 2));
+        let traffic = config.metrics().traffic("127.0.0.1");
+        assert!(traffic.sent > 0 && traffic.received > 1_000);
+        assert!(config.host_quota_exhausted("127.0.0.1"));
+        assert_eq!(config.metrics().total_traffic(), traffic);
+    }
+
+    // Test that the dedup policy decides which URLs are the same page
+    #[tokio::test]
+    async fn test_dedup_policy_skips_equivalent_urls() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let log = requested.clone();
+        let base = serve(move |request| {
+            let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+            let body: &[u8] = if path == "/" { b"<a href='/a?x=1&y=2'>1</a><a href='/a/?y=2&x=1#top'>2</a><a href='/b'>3</a>" } else { b"<p>Same</p>" };
+            log.lock().unwrap().push(path);
+            http_response("200 OK", &[], body)
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_dedup_policy(Arc::new(CanonicalUrlDedup::default()));
+        config.set_output_dir(temp_dir.path());
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![base.clone()], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+
+        assert_eq!(*requested.lock().unwrap(), vec!["/", "/a?x=1&y=2", "/b"]);
+        assert_eq!((stats.pages_scraped, stats.pages_duplicate), (3, 1));
+    }
+
+    // Test that AMP versions are stored once, as the primary page or instead of it
+    #[tokio::test]
+    async fn test_amp_versions_are_not_stored_twice() {
+        let base = serve(|request| {
+            let body: &[u8] = if request.starts_with("GET /amp ") {
+                b"<html amp><head><link rel='canonical' href='/'></head><body><p>AMP</p></body></html>"
+            } else {
+                b"<html><head><link rel='amphtml' href='/amp'></head><body><p>Desktop</p><a href='/amp'>AMP</a></body></html>"
+            };
+            http_response("200 OK", &[], body)
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        // Skipping never requests the AMP page linked from the desktop one; preferring requests both
+        for (policy, stored, alternates_skipped) in [(AlternatePolicy::SkipAlternates, "Desktop", 0), (AlternatePolicy::PreferAlternates, "AMP", 1)] {
+            let mut config = ScraperConfig::new(true, 2, None);
+            config.set_alternate_policy(policy);
+            config.set_output_dir(&temp_dir.path().join(format!("{:?}", policy)));
+            let mut stats = CrawlStats::default();
+
+            crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+
+            assert_eq!((stats.pages_scraped, stats.pages_alternate), (1, alternates_skipped));
+            assert_eq!(config.alternates().primary_of(&format!("{}/amp", base)), Some(format!("{}/", base)));
+            let dir = config.domain_dir(&base);
+            let index = read_page_index(&dir).unwrap();
+            assert_eq!(index.len(), 1);
+            assert!(std::fs::read_to_string(page_file(&dir, "content", &index[0].1)).unwrap().contains(stored));
+        }
+    }
+
+    // Test that a crawl writes its findings to findings.jsonl, and the legacy files only on request
+    #[tokio::test]
+    async fn test_findings_are_unified_in_jsonl() {
+        let base = serve(|_| {
+            http_response("200 OK", &[], b"<p>Mail ops@a.test</p><script>var apiKey = 'k';</script><pre>NullPointerException at Main.java:3</pre>")
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        for legacy in [false, true] {
+            let mut config = ScraperConfig::new(false, 0, None);
+            config.set_output_dir(&temp_dir.path().join(legacy.to_string()));
+            config.set_legacy_finding_files(legacy);
+            crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut CrawlStats::default(), 0).await;
+
+            let findings = read_findings(&config.findings_path()).unwrap();
+            let kinds: Vec<(FindingKind, &str)> = findings.iter().map(|finding| (finding.kind, finding.url.as_str())).collect();
+            let page = format!("{}/", base);
+            assert_eq!(kinds, vec![(FindingKind::Email, page.as_str()), (FindingKind::JsKeyword, page.as_str()), (FindingKind::ErrorPage, page.as_str())]);
+            assert_eq!(findings[0].evidence, "ops@a.test");
+            assert!(findings[2].evidence.contains("NullPointerException at Main.java:3"));
+            assert_eq!(page_file(&config.domain_dir(&base), "emails", &page).exists(), legacy);
+        }
+    }
+
+    // Test that header and form findings reach findings.jsonl, and their text files only with legacy finding files
+    #[tokio::test]
+    async fn test_security_findings_are_unified_in_jsonl() {
+        let base = serve(|_| {
+            http_response("200 OK", &[("X-Powered-By", "PHP/8.1.2")], b"<form method='post' action='/login'><input name='user'></form>")
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        for legacy in [false, true] {
+            let mut config = ScraperConfig::new(false, 0, None);
+            config.set_output_dir(&temp_dir.path().join(legacy.to_string()));
+            config.set_legacy_finding_files(legacy);
+            let page = format!("{}/", base);
+            crawl_seeds(vec![page.clone()], &Client::new(), &config, &mut HashSet::new(), &mut CrawlStats::default(), 0).await;
+            finish_crawl(&Client::new(), &config).await;
+
+            let findings = read_findings(&config.findings_path()).unwrap();
+            let header = findings.iter().find(|finding| finding.kind == FindingKind::Header).unwrap();
+            assert!(header.url == page && header.evidence.contains("x-powered-by"));
+            assert!(findings.iter().any(|finding| finding.kind == FindingKind::FormIssue && finding.evidence.contains("CSRF")));
+            assert_eq!(config.domain_dir(&base).join("header_findings.txt").exists(), legacy);
+            let content = std::fs::read_to_string(page_file(&config.domain_dir(&base), "content", &page)).unwrap();
+            assert_eq!(content.contains("Form Issue"), legacy);
+        }
+    }
+
+    // Test that crawled pages are labelled and the labels written per domain
+    #[tokio::test]
+    async fn test_crawled_pages_are_classified() {
+        let base = serve(|request| match request.split_whitespace().nth(1) {
+            Some("/login") => http_response("200 OK", &[], b"<form><input name='user'><input type='password' name='pw'></form>"),
+            Some("/missing") => http_response("404 Not Found", &[], b"<h1>Gone</h1>"),
+            _ => http_response("200 OK", &[], b"<a href='/login'>Sign in</a><a href='/missing'>Old</a>"),
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+        finish_crawl(&Client::new(), &config).await;
+
+        assert_eq!(config.page_classes().class_of(&format!("{}/login", base)), Some(PageClass::Login));
+        assert_eq!(config.page_classes().class_of(&format!("{}/missing", base)), Some(PageClass::Error));
+        let classes = std::fs::read_to_string(config.domain_dir(&base).join(PAGE_CLASSES_FILE)).unwrap();
+        assert!(classes.contains(&format!("{}/login\tlogin\n", base)));
+    }
+
+    // Test that every output of a crawl carries its session ID, and that each crawl gets a new one
+    #[tokio::test]
+    async fn test_crawl_outputs_carry_session_id() {
+        let base = serve(|request| {
+            if request.starts_with("GET / ") {
+                http_response("200 OK", &[], b"<p>Mail sales@shop.test</p><a href='/logo.png'>Logo</a>")
+            } else {
+                http_response("200 OK", &[("Content-Type", "image/png")], b"\x89PNG\r\n\x1a\nimage")
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(&temp_dir.path().join("first"));
+
+        let summary = Scraper::new(Client::new(), config).crawl(&format!("{}/", base)).await;
+
+        let session_id = summary.session_id.clone().unwrap();
+        assert!(summary.to_string().contains(&session_id));
+        let output_dir = temp_dir.path().join("first");
+        let manifest: RunManifest = serde_json::from_str(&std::fs::read_to_string(output_dir.join(RUN_MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(manifest.session_id.as_deref(), Some(session_id.as_str()));
+        let findings = read_findings(&output_dir.join(FINDINGS_FILE)).unwrap();
+        assert!(!findings.is_empty() && findings.iter().all(|finding| finding.session_id.as_deref() == Some(session_id.as_str())));
+        let downloads = read_manifest(&manifest_path(&output_dir.join("127.0.0.1"))).unwrap();
+        assert_eq!(downloads[0].session_id.as_deref(), Some(session_id.as_str()));
+
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(&temp_dir.path().join("second"));
+        config.set_error_log(&temp_dir.path().join("error.log"));
+        let summary = run_with_config(&format!("{}/", base), &Client::new(), Some(&config)).await;
+        let second = summary.session_id.unwrap();
+        assert_ne!(second, session_id);
+        assert_eq!(config.session_id(), None);
+        run::in_session(Some(second.clone()), async { config.log_error("boom") }).await;
+        assert_eq!(std::fs::read_to_string(temp_dir.path().join("error.log")).unwrap(), format!("[{}] boom\n", second));
+    }
+
+    // Test that concurrent crawls sharing one config each tag their outputs with their own session
+    #[tokio::test]
+    async fn test_concurrent_crawls_keep_their_own_sessions() {
+        let first = serve(|_| http_response("200 OK", &[], b"<p>Mail first@a.test</p>")).await;
+        let second = serve(|_| http_response("200 OK", &[], b"<p>Mail second@b.test</p>")).await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(false, 0, None);
+        config.set_output_dir(temp_dir.path());
+
+        let (client, first, second) = (Client::new(), format!("{}/", first), format!("{}/", second));
+        let (first_summary, second_summary) = tokio::join!(run_with_config(&first, &client, Some(&config)), run_with_config(&second, &client, Some(&config)));
+        let (first_session, second_session) = (first_summary.session_id.unwrap(), second_summary.session_id.unwrap());
+        assert_ne!(first_session, second_session);
+
+        let findings = read_findings(&config.findings_path()).unwrap();
+        let session_of = |email: &str| findings.iter().find(|finding| finding.evidence == email).and_then(|finding| finding.session_id.clone());
+        assert_eq!(session_of("first@a.test"), Some(first_session));
+        assert_eq!(session_of("second@b.test"), Some(second_session));
+    }
+
+    // Test that the processing of a page on a blocking thread stays in the session of its crawl
+    #[tokio::test]
+    async fn test_page_processing_keeps_the_session() {
+        let config = ScraperConfig::default();
+        let processed = run::in_session(Some("session-1".to_string()), with_page_timeout("https://a.test/", &config, run::current_session_id)).await;
+        assert_eq!(processed, Some(Some("session-1".to_string())));
+        assert_eq!(with_page_timeout("https://a.test/", &config, run::current_session_id).await, Some(None));
+    }
+
+    // Test that the end of a crawl writes only the output files that have contents
+    #[tokio::test]
+    async fn test_finish_crawl_skips_empty_outputs() {
+        let base = serve(|_| http_response("200 OK", &[], b"<p>Nothing to report</p>")).await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(false, 0, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_legacy_finding_files(true);
+
+        crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut CrawlStats::default(), 0).await;
+        finish_crawl(&Client::new(), &config).await;
+
+        let dir = config.domain_dir(&base);
+        assert!(dir.join(PAGE_CLASSES_FILE).exists());
+        assert!(!dir.join("header_findings.txt").exists());
+        assert!(!dir.join(ALTERNATES_FILE).exists());
+    }
+
+    // Test that every visited page lands in exactly one of the fetched, skipped and failed buckets of the summary
+    #[tokio::test]
+    async fn test_summary_counts_each_visited_page_once() {
+        let base = serve(|request| match request.split_whitespace().nth(1) {
+            Some("/missing") => http_response("404 Not Found", &[], b""),
+            Some("/copy") | Some("/") => http_response("200 OK", &[], b"<a href='/about'>About</a><a href='/copy'>Copy</a><a href='/missing'>Old</a>"),
+            _ => http_response("200 OK", &[], b"<p>About us</p>"),
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_page_retries(0);
+        config.set_dedup_policy(Arc::new(ContentHashDedup::default()));
+        config.load_crawl_database(&temp_dir.path().join("crawl_db.json")).unwrap();
+
+        for run in 0..2 {
+            config.dedup_policy().reset();
+            let (mut visited, mut stats) = (HashSet::new(), CrawlStats::default());
+            crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut visited, &mut stats, 0).await;
+            finish_crawl(&Client::new(), &config).await;
+            let summary = CrawlSummary::new(&stats, config.metrics(), Duration::ZERO);
+            assert_eq!(summary.pages_fetched + summary.pages_skipped + summary.pages_failed, visited.len(), "run {}: {:?}", run, stats);
+            assert_eq!(stats.pages_unchanged > 0, run == 1);
+        }
+    }
+
+    // Test that the monitor preset opens its crawl database in the output root chosen after building it
+    #[tokio::test]
+    async fn test_monitor_preset_resolves_its_database_at_crawl_start() {
+        let base = serve(|_| http_response("200 OK", &[("Content-Type", "text/html")], b"<html><body><p>Watched</p></body></html>")).await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::monitor_preset();
+        config.set_output_dir(temp_dir.path());
+        assert!(config.crawl_database().is_none());
+        assert_eq!(config.settings().crawl_database, Some(temp_dir.path().join("crawl_db.json")));
+
+        let mut visited = HashSet::new();
+        recursive_scrape_with_config(&format!("{}/", base), &Client::new(), Some(&config), &mut visited).await;
+        assert_eq!(config.crawl_database().unwrap().lock().unwrap().path(), Some(temp_dir.path().join("crawl_db.json").as_path()));
+        assert!(temp_dir.path().join("crawl_db.json").exists());
+    }
+
+    // Test that robots.txt is fetched from the origin of a deep URL and read for our own user agent
+    #[tokio::test]
+    async fn test_robots_txt_is_fetched_from_the_origin() {
+        let base = serve(|request| match request.starts_with("GET /robots.txt ") {
+            true => http_response("200 OK", &[], b"User-agent: *\nDisallow: /\n\nUser-agent: origin-bot\nDisallow: /private\n"),
+            false => http_response("404 Not Found", &[], b""),
+        })
+        .await;
+        let config = ScraperConfig::new(true, 1, Some("origin-bot/1.0".to_string()));
+
+        let robots = fetch_robots_txt_with_config(&format!("{}/docs/guide/page.html?x=1", base), &Client::new(), &config).await;
+        assert_eq!(robots.disallowed, vec!["/private"]);
+        assert_eq!(fetch_robots_txt(&format!("{}/docs/", base), &Client::new()).await.disallowed, vec!["/"]);
+    }
+
+    // Test that a page whose parsing outlasts the page timeout is counted as failed and stores nothing
+    #[tokio::test]
+    async fn test_timed_out_page_stores_nothing() {
+        let page = format!("<h1>Slow</h1>{}", "<p><b>x</b> <i>y</i></p>".repeat(5_000));
+        let base = serve(move |_| http_response("200 OK", &[], page.as_bytes())).await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(false, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_save_html_snapshots(true);
+        config.set_page_timeout(Some(Duration::from_millis(1)));
+        config.load_crawl_database(&temp_dir.path().join("crawl_db.json")).unwrap();
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+
+        assert_eq!((stats.pages_scraped, stats.pages_failed), (0, 1));
+        assert!(!config.domain_dir(&base).exists());
+        assert!(std::fs::read_to_string(config.error_log()).unwrap().contains("Timed out processing"));
+        // Nor is it recorded in the crawl database, so the next incremental run does not skip it
+        assert!(config.crawl_database().unwrap().lock().unwrap().is_empty());
+    }
+
+    // Test that stylesheet, script and link-check traffic counts toward the byte quota of its host
+    #[tokio::test]
+    async fn test_asset_traffic_counts_toward_quota() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let checked = serve(move |request| {
+            seen.lock().unwrap().push(request.lines().next().unwrap_or("").to_string());
+            http_response("200 OK", &[], b"")
+        })
+        .await
+        .replace("127.0.0.1", "localhost");
+        let page = format!("<link rel='stylesheet' href='/a.css'><link rel='stylesheet' href='/b.css'><a href='{}/elsewhere'>Elsewhere</a>", checked);
+        let stylesheets = Arc::new(Mutex::new(0));
+        let fetched = stylesheets.clone();
+        let base = serve(move |request| match request.split_whitespace().nth(1) {
+            Some("/") => http_response("200 OK", &[], page.as_bytes()),
+            _ => {
+                *fetched.lock().unwrap() += 1;
+                http_response("200 OK", &[("Content-Type", "text/css")], format!("body {{ color: red; }}{}", " ".repeat(2_000)).as_bytes())
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(false, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_host_byte_quota_for("127.0.0.1", 1_500);
+        config.set_host_byte_quota_for("localhost", 0);
+        config.set_external_link_checker(Some(ExternalLinkChecker::new(2, Duration::ZERO)));
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+        // Off-site links wait in the queue of the checker until the crawl finishes
+        assert!(config.external_link_checker().unwrap().results().is_empty());
+        finish_crawl(&Client::new(), &config).await;
+
+        // The first stylesheet uses up the quota, so the second one is never requested
+        assert_eq!(*stylesheets.lock().unwrap(), 1);
+        assert!(config.metrics().traffic("127.0.0.1").received > 2_000);
+        assert!(fetch_script(&Client::new(), &format!("{}/app.js", base), &config).await.is_err());
+        assert!(requests.lock().unwrap().is_empty(), "requested {:?}", requests.lock().unwrap());
+        let results = config.external_link_checker().unwrap().results();
+        assert_eq!(results.iter().map(|link| link.status).collect::<Vec<_>>(), vec![None]);
+    }
+
+    // Test that media, scripts and stylesheets on a denylisted host are never requested
+    #[tokio::test]
+    async fn test_denylisted_host_is_never_requested() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let denied = serve(move |request| {
+            seen.lock().unwrap().push(request.lines().next().unwrap_or("").to_string());
+            http_response("200 OK", &[("Content-Type", "image/png")], b"\x89PNG\r\n\x1a\nimage")
+        })
+        .await
+        .replace("127.0.0.1", "localhost");
+        let page = format!("<img src='{0}/logo.png'><script src='{0}/app.js'></script><link rel='stylesheet' href='{0}/site.css'><a href='{0}/about'>About</a>", denied);
+        let base = serve(move |_| http_response("200 OK", &[], page.as_bytes())).await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_denied_domains(Some(DomainList::parse("localhost")));
+        config.set_external_link_checker(Some(ExternalLinkChecker::new(2, Duration::ZERO)));
+
+        let summary = Scraper::new(Client::new(), config).crawl(&format!("{}/", base)).await;
+
+        assert_eq!((summary.pages_fetched, summary.media_saved), (1, 0));
+        assert!(requests.lock().unwrap().is_empty(), "requested {:?}", requests.lock().unwrap());
+    }
+
+    // Test that with redaction on no output file of a crawl keeps the personal data of its pages
+    #[tokio::test]
+    async fn test_redacted_crawl_stores_no_pii() {
+        let base = serve(|request| {
+            if request.starts_with("GET /api/team") {
+                http_response("200 OK", &[("Content-Type", "application/json")], br#"{"lead": "carol@corp.test", "phone": "+44 20 7946 0958"}"#)
+            } else {
+                let page = r#"<h2>Contact jane.doe@corp.test</h2>
+                    <p>Mail <a href="mailto:jane.doe@corp.test">jane.doe@corp.test</a> or call <a href="tel:+15550100999">+1 555 010 0999</a>. See <a href="/api/team">the team of jane.doe@corp.test</a> now.</p>
+                    <script type="application/ld+json">{"@type": "Review", "author": {"@type": "Person", "name": "bob.smith@corp.test"}, "reviewBody": "Call 555-010-0199"}</script>"#;
+                http_response("200 OK", &[], page.as_bytes())
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_redact_pii(true);
+        config.set_save_html_snapshots(true);
+        config.set_legacy_finding_files(true);
+
+        Scraper::new(Client::new(), config).crawl(&format!("{}/", base)).await;
+
+        let mut files = vec![temp_dir.path().to_path_buf()];
+        let mut contents = String::new();
+        while let Some(path) = files.pop() {
+            match std::fs::read_dir(&path) {
+                Ok(entries) => files.extend(entries.map(|entry| entry.unwrap().path())),
+                Err(_) => contents.push_str(&String::from_utf8_lossy(&std::fs::read(&path).unwrap())),
+            }
+        }
+        for pii in ["jane.doe", "bob.smith", "carol", "555 010 0999", "5550100999", "555-010-0199", "7946"] {
+            assert!(!contents.contains(pii), "{} was stored", pii);
+        }
+        assert!(contents.contains(r#"<a href="mailto:***@corp.test">***@corp.test</a>"#));
+    }
+
+    // Test that an unparsable seed that looks like an asset is skipped instead of aborting the crawl
+    #[tokio::test]
+    async fn test_unparsable_asset_seed_is_skipped() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_route_assets(true);
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec!["https://[bad/x.png".to_string()], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+
+        assert_eq!((stats.pages_scraped, stats.pages_failed), (0, 1));
+        assert!(std::fs::read_to_string(config.error_log()).unwrap().contains("https://[bad/x.png"));
+    }
+
+    // Test that links classified as assets go to the media pipeline without being requested as pages
+    #[tokio::test]
+    async fn test_asset_links_skip_page_extraction() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let base = serve(move |request| {
+            seen.lock().unwrap().push(request.lines().next().unwrap_or("").to_string());
+            if request.starts_with("GET / ") {
+                http_response("200 OK", &[], b"<a href='/logo.png'>Logo</a><a href='/brochure.pdf'>Brochure</a><a href='/team'>Team</a>")
+            } else if request.starts_with("GET /logo.png") {
+                http_response("200 OK", &[("Content-Type", "image/png")], b"\x89PNG\r\n\x1a\nimage")
+            } else {
+                http_response("200 OK", &[], b"<p>Team</p>")
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+
+        assert_eq!(stats.pages_scraped, 2);
+        let dir = temp_dir.path().join("127.0.0.1");
+        assert_eq!(std::fs::read(dir.join("logo.png")).unwrap(), b"\x89PNG\r\n\x1a\nimage");
+        assert_eq!(read_page_index(&dir).unwrap().len(), 2);
+        // Documents are not downloaded by default, so the brochure is never requested
+        assert!(!requests.lock().unwrap().iter().any(|line| line.contains("brochure.pdf")));
+        assert_eq!(CrawlSummary::new(&stats, config.metrics(), Duration::ZERO).findings["assets routed"], 2);
+    }
+
+    // Test that entities found across pages are merged per domain in the summary and `entities.json`
+    #[tokio::test]
+    async fn test_entities_are_aggregated_per_domain() {
+        let base = serve(|request| {
+            if request.starts_with("GET / ") {
+                let home = r#"<a href="mailto:Sales@shop.test">Sales</a><a href="tel:+15550100">Call</a><a href="https://twitter.com/shop">Twitter</a>
+                    <a href="/about">About</a><script>fetch("/api/items")</script>"#;
+                http_response("200 OK", &[], home.as_bytes())
+            } else if request.starts_with("GET /about") {
+                http_response("200 OK", &[], b"<p>Mail sales@shop.test</p>")
+            } else {
+                http_response("200 OK", &[], b"[]")
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+
+        let summary = Scraper::new(Client::new(), config).crawl(&format!("{}/", base)).await;
+
+        let domain = &summary.entities[0];
+        assert_eq!(domain.domain, "127.0.0.1");
+        let kinds = [EntityKind::Email, EntityKind::Phone, EntityKind::SocialHandle, EntityKind::Endpoint];
+        assert_eq!(kinds.map(|kind| domain.count(kind)), [1, 1, 1, 1]);
+        let email = domain.entities.iter().find(|entity| entity.kind == EntityKind::Email).unwrap();
+        assert_eq!((email.value.as_str(), email.first_seen.clone(), email.occurrences), ("sales@shop.test", format!("{}/", base), 2));
+        let written: DomainEntities = serde_json::from_str(&std::fs::read_to_string(temp_dir.path().join("127.0.0.1").join(ENTITIES_FILE)).unwrap()).unwrap();
+        assert_eq!(&written, domain);
+    }
+
+    // Test that a discovery pass exports the pages it found and a later pass imports and extracts them
+    #[tokio::test]
+    async fn test_discovery_pass_feeds_extraction_pass() {
+        let base = serve(|request| {
+            if request.starts_with("GET / ") {
+                http_response("200 OK", &[], b"<p>Home</p><a href='/a'>A</a><a href='/b'>B</a>")
+            } else {
+                http_response("200 OK", &[], b"<p>Leaf</p>")
+            }
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let frontier_path = temp_dir.path().join("frontier.jsonl");
+        let mut discovery = ScraperConfig::new(true, 1, None);
+        discovery.set_output_dir(&temp_dir.path().join("discovery"));
+        discovery.set_discovery_only(true);
+        discovery.set_frontier_export(Some(&frontier_path));
+        Scraper::new(Client::new(), discovery).crawl(&format!("{}/", base)).await;
+
+        let entries = import_frontier(&frontier_path).unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.depth).collect::<Vec<_>>(), vec![0, 1, 1]);
+        assert!(read_page_index(&temp_dir.path().join("discovery").join("127.0.0.1")).unwrap().is_empty());
+
+        let mut extraction = ScraperConfig::new(false, 1, None);
+        extraction.set_output_dir(&temp_dir.path().join("extraction"));
+        assert_eq!(extraction.import_frontier(&frontier_path).unwrap(), 3);
+        let summary = Scraper::new(Client::new(), extraction).crawl(&format!("{}/", base)).await;
+        assert_eq!(summary.pages_fetched, 3);
+        assert_eq!(read_page_index(&temp_dir.path().join("extraction").join("127.0.0.1")).unwrap().len(), 3);
+    }
+
+    // Test that imported bookmarks are crawled along with the start URL and kept in the run manifest
+    #[tokio::test]
+    async fn test_imported_seeds_are_crawled() {
+        let base = serve(|_| http_response("200 OK", &[], b"<p>Page</p>")).await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bookmarks = temp_dir.path().join("bookmarks.html");
+        std::fs::write(&bookmarks, format!("<DL><p><DT><A HREF=\"{0}/saved/1\">One</A><DT><A HREF=\"{0}/saved/2\">Two</A></DL>", base)).unwrap();
+        let mut config = ScraperConfig::new(false, 0, None);
+        config.set_output_dir(&temp_dir.path().join("out"));
+        assert_eq!(config.load_seeds(&bookmarks).unwrap(), 2);
+
+        let mut scraper = Scraper::new(Client::new(), config);
+        let summary = scraper.crawl(&format!("{}/", base)).await;
+
+        assert_eq!(summary.pages_fetched, 3);
+        assert!(scraper.visited().contains(&format!("{}/saved/2", base)));
+        let manifest = RunManifest::read(&scraper.config().run_manifest_path()).unwrap();
+        assert_eq!(manifest.to_config().seeds(), [format!("{}/saved/1", base), format!("{}/saved/2", base)]);
+    }
+
+    // Test that redaction masks personal data in page text, snapshots and findings while counting it
+    #[tokio::test]
+    async fn test_pii_is_redacted_but_counted() {
+        let base = serve(|_| http_response("200 OK", &[], b"<h1>Contact</h1><p>Mail jane@corp.test or call +1 415 555 0100.</p>")).await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(false, 0, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_save_html_snapshots(true);
+        config.set_redact_pii(true);
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+
+        let page = format!("{}/", base);
+        let content = std::fs::read_to_string(page_file(&config.domain_dir(&base), "content", &page)).unwrap();
+        assert!(content.contains("Paragraph: Mail ***@corp.test or call [phone number]."));
+        let snapshot = std::fs::read_to_string(snapshot_path(&config.domain_dir(&base), &page)).unwrap();
+        assert!(snapshot.contains("***@corp.test") && !snapshot.contains("jane") && !snapshot.contains("555"));
+        let findings = read_findings(&config.findings_path()).unwrap();
+        assert_eq!(findings[0].evidence, "***@corp.test");
+        let summary = CrawlSummary::new(&stats, config.metrics(), Duration::ZERO);
+        assert_eq!((summary.findings["redacted email"], summary.findings["redacted phone number"]), (1, 1));
+    }
+
+    // Test that bot walls are retried once through the page renderer before being marked blocked
+    #[tokio::test]
+    async fn test_bot_walls_are_retried_with_the_renderer() {
+        struct FakeBrowser;
+        impl PageRenderer for FakeBrowser {
+            fn render<'a>(&'a self, url: &'a str) -> RenderFuture<'a> {
+                Box::pin(async move {
+                    if url.ends_with("/locked") {
+                        Ok("<title>Just a moment...</title>".to_string())
+                    } else {
+                        Ok("<h1>Catalog</h1><p>Rendered</p><a href='/locked'>Locked</a>".to_string())
+                    }
+                })
+            }
+        }
+        let base = serve(|_| http_response("403 Forbidden", &[], b"<title>Just a moment...</title><script src='/cdn-cgi/challenge-platform/h/b'></script>")).await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_page_renderer(Some(Arc::new(FakeBrowser)));
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+
+        let content = std::fs::read_to_string(page_file(&config.domain_dir(&base), "content", &format!("{}/", base))).unwrap();
+        assert!(content.contains("Paragraph: Rendered"));
+        assert_eq!(config.page_classes().class_of(&format!("{}/", base)), Some(PageClass::Other));
+        assert_eq!((stats.pages_scraped, stats.pages_failed), (1, 1));
+        let dead_letters = config.dead_letters().take();
+        assert_eq!(dead_letters[0].url, format!("{}/locked", base));
+        assert_eq!(dead_letters[0].reason, "bot challenge (also when rendered)");
+    }
+
+    // Test that frameset pages yield the content and links of their frames
+    #[tokio::test]
+    async fn test_frames_are_merged_into_their_page() {
+        let base = serve(|request| match request.split_whitespace().nth(1) {
+            Some("/") => http_response("200 OK", &[], b"<frameset cols='20%,80%,1'><frame src='/nav.html'><frame src='/main.html'><frame src='https://ads.invalid/slot'></frameset>"),
+            Some("/nav.html") => http_response("200 OK", &[], b"<a href='/about.html'>About</a>"),
+            Some("/main.html") => http_response("200 OK", &[], b"<h1>Welcome</h1><p>Legacy site</p>"),
+            _ => http_response("200 OK", &[], b"<p>About us</p>"),
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+
+        let content = std::fs::read_to_string(page_file(&config.domain_dir(&base), "content", &format!("{}/", base))).unwrap();
+        assert!(content.contains(&format!("Frame - URL: {}/main.html, Paragraph: Legacy site\n", base)));
+        assert!(content.contains(&format!("Frame - URL: {}/nav.html, Text: About\n", base)));
+        assert_eq!(stats.pages_scraped, 2);
+        let findings = read_findings(&config.findings_path()).unwrap();
+        assert_eq!((findings[0].kind, findings[0].evidence.as_str()), (FindingKind::CrossOriginFrame, "https://ads.invalid/slot"));
+    }
+
+    // Test that language partitions keep their own budgets and output directories
+    #[tokio::test]
+    async fn test_partitions_have_own_budgets_and_outputs() {
+        let base = serve(|request| match request.split_whitespace().nth(1) {
+            Some("/") => http_response("200 OK", &[], b"<link rel='alternate' hreflang='de' href='/startseite'><a href='/en/a'>A</a><a href='/en/b'>B</a><a href='/startseite'>DE</a>"),
+            Some(path) => http_response("200 OK", &[], format!("<p>{}</p>", path).as_bytes()),
+            None => http_response("400 Bad Request", &[], b""),
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.add_partition(CrawlPartition::language("en").max_pages(1));
+        config.add_partition(CrawlPartition::language("de"));
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+        finish_crawl(&Client::new(), &config).await;
+
+        assert_eq!((stats.pages_scraped, stats.pages_over_budget), (3, 1));
+        let domain_dir = config.output_dir().join(extract_domain(&base));
+        assert_eq!(read_page_index(&domain_dir.join("de")).unwrap()[0].1, format!("{}/startseite", base));
+        assert_eq!(read_page_index(&domain_dir.join("en")).unwrap().len(), 1);
+        assert_eq!(read_page_index(&domain_dir).unwrap()[0].1, format!("{}/", base));
+        assert_eq!(std::fs::read_to_string(temp_dir.path().join(PARTITIONS_FILE)).unwrap(), "en\t1\t1\nde\t1\t-\n");
+    }
+
+    // Test that each section of a site gets the extraction schema bound to it
+    #[tokio::test]
+    async fn test_extraction_schemas_follow_url_patterns() {
+        let base = serve(|request| match request.split_whitespace().nth(1) {
+            Some("/product/1") => http_response("200 OK", &[], b"<h1>Kettle</h1><span class='price'>19.99</span>"),
+            Some("/blog/1") => http_response("200 OK", &[], b"<h1>Launch</h1><span class='price'>0</span><p class='byline'>Ada</p>"),
+            _ => http_response("200 OK", &[], b"<a href='/product/1'>Kettle</a><a href='/blog/1'>Launch</a>"),
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.add_extraction_rule("/product/*", ExtractionSchema::new("product").field("name", "h1", None).field("price", ".price", None));
+        config.add_extraction_rule("/blog/*", ExtractionSchema::new("article").field("title", "h1", None).field("author", ".byline", None));
+        let mut stats = CrawlStats::default();
+
+        crawl_seeds(vec![format!("{}/", base)], &Client::new(), &config, &mut HashSet::new(), &mut stats, 0).await;
+
+        let content = |path: &str| std::fs::read_to_string(page_file(&config.domain_dir(&base), "content", &format!("{}{}", base, path))).unwrap();
+        assert!(content("/product/1").contains("Extracted - Schema: product, Field: price, Value: 19.99"));
+        let article = content("/blog/1");
+        assert!(article.contains("Extracted - Schema: article, Field: author, Value: Ada"));
+        assert!(!article.contains("Field: price"));
+        assert!(!content("/").contains("Extracted -"));
+    }
+
+    // Test that pages of the same domain get their own content files
+    #[tokio::test]
+    async fn test_pages_of_a_domain_are_kept_apart() {
+        let base = serve(|request| match request.split_whitespace().nth(1) {
+            Some("/two") => http_response("200 OK", &[], b"<p>Second page</p>"),
+            _ => http_response("200 OK", &[], b"<p>First page</p><a href='/two'>next</a>"),
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_error_log(&temp_dir.path().join("error.log"));
+
+        recursive_scrape_with_config(&base, &Client::new(), Some(&config), &mut HashSet::new()).await;
+
+        let dir = temp_dir.path().join("127.0.0.1");
+        let pages: Vec<String> = read_page_index(&dir)
+            .unwrap()
+            .iter()
+            .map(|(_, url)| std::fs::read_to_string(page_file(&dir, "content", url)).unwrap())
+            .collect();
+        assert_eq!(pages, vec!["Paragraph: First page\n", "Paragraph: Second page\n"]);
+    }
+
+    // Test that responses are routed by Content-Type instead of all being parsed as HTML
+    #[tokio::test]
+    async fn test_responses_are_routed_by_content_type() {
+        let base = serve(|request| match request.split_whitespace().nth(1) {
+            Some("/api/items") => http_response("200 OK", &[("Content-Type", "application/json")], br#"{"items":[1,2]}"#),
+            Some("/logo") => http_response("200 OK", &[("Content-Type", "image/png")], b"\x89PNG\r\n\x1a\nfake"),
+            _ => http_response("200 OK", &[("Content-Type", "text/html")], b"<a href='/api/items'>api</a><a href='/logo'>logo</a>"),
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 1, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_error_log(&temp_dir.path().join("error.log"));
+
+        recursive_scrape_with_config(&base, &Client::new(), Some(&config), &mut HashSet::new()).await;
+
+        let dir = temp_dir.path().join("127.0.0.1");
+        let api_url = format!("{}/api/items", base);
+        let capture = std::fs::read_to_string(capture_path(&dir, &api_url, ContentRoute::Json)).unwrap();
+        assert_eq!(capture, "{\n  \"items\": [\n    1,\n    2\n  ]\n}");
+        assert!(!page_file(&dir, "content", &api_url).exists());
+        assert_eq!(std::fs::read(dir.join("logo")).unwrap(), b"\x89PNG\r\n\x1a\nfake");
+    }
+
+    // Test that API endpoints found in inline scripts and JSON payloads are captured and mined
+    #[tokio::test]
+    async fn test_api_endpoints_are_discovered_and_captured() {
+        let base = serve(|request| match request.split_whitespace().nth(1) {
+            Some("/api/items") => http_response("200 OK", &[("Content-Type", "application/json")], br#"{"next":"/api/items?page=2","owner":"ops@a.test"}"#),
+            Some("/api/items?page=2") => http_response("200 OK", &[("Content-Type", "application/json")], b"{}"),
+            _ => http_response("200 OK", &[], b"<script>fetch('/api/items').then(r => r.json())</script>"),
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 2, None);
+        config.set_output_dir(temp_dir.path());
+        config.set_error_log(&temp_dir.path().join("error.log"));
+
+        recursive_scrape_with_config(&base, &Client::new(), Some(&config), &mut HashSet::new()).await;
+
+        let dir = temp_dir.path().join("127.0.0.1");
+        let api_url = format!("{}/api/items", base);
+        assert!(capture_path(&dir, &format!("{}?page=2", api_url), ContentRoute::Json).exists());
+        let findings = read_findings(&config.findings_path()).unwrap();
+        assert!(findings.iter().any(|finding| finding.kind == FindingKind::Email && finding.url == api_url && finding.evidence == "ops@a.test"));
+        let entities = config.metrics().entities();
+        assert!(entities.iter().any(|domain| domain.entities.iter().any(|entity| entity.kind == EntityKind::Email && entity.value == "ops@a.test")));
+        assert!(!page_file(&dir, "emails", &api_url).exists());
+    }
+
+    // Test for domain allow/deny lists loaded from files
+    #[test]
+    fn test_domain_lists_filter_urls() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let allow_path = temp_dir.path().join("allow.txt");
+        let deny_path = temp_dir.path().join("deny.txt");
+        std::fs::write(&allow_path, ".example.com\n").unwrap();
+        std::fs::write(&deny_path, "*.admin.example.com\n").unwrap();
+
+        let mut config = ScraperConfig::default();
+        assert!(config.is_url_allowed("https://anything.test/"));
+
+        config.load_allowed_domains(&allow_path).unwrap();
+        config.load_denied_domains(&deny_path).unwrap();
+        assert!(config.is_url_allowed("https://example.com/"));
+        assert!(config.is_url_allowed("https://blog.example.com/post"));
+        assert!(!config.is_url_allowed("https://eu.admin.example.com/"));
+        assert!(!config.is_url_allowed("https://elsewhere.test/"));
+    }
+
+    // Test for saving inline data: images found in src attributes and CSS
+    #[tokio::test]
+    async fn test_inline_data_images_are_saved() {
+        let html = r#"<img src="data:image/gif;base64,R0lGODlh">
+            <div style="background: url('data:image/gif;base64,R0lGODlh')"></div>"#;
+        let document = Html::parse_document(html);
+        let mut image_urls = collect_image_urls(&document, "https://example.com", None);
+        image_urls.extend(collect_css_urls(&document, "https://example.com"));
+        assert_eq!(image_urls.len(), 2);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().to_str().unwrap();
+        let mut text_file = File::create(temp_dir.path().join("content.txt")).unwrap();
+        let client = Client::new();
+        process_media_list(&client, &ScraperConfig::default(), &mut text_file, &image_urls, dir, "image.jpg", Some(MediaKind::Image)).await;
+
+        let saved: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok().map(|entry| entry.file_name().to_string_lossy().to_string()))
+            .filter(|name| name.starts_with("inline-"))
+            .collect();
+        assert_eq!(saved, vec![format!("inline-{}.gif", &sha256_hex(b"GIF89a")[..16])]);
+        assert_eq!(read_manifest(&manifest_path(temp_dir.path())).unwrap().len(), 1);
+    }
+
+    // Test that a later run skips media whose manifest entry and ETag are unchanged
+    #[tokio::test]
+    async fn test_unchanged_media_is_not_downloaded_again() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let version = Arc::new(AtomicUsize::new(1));
+        let downloads = Arc::new(AtomicUsize::new(0));
+        let (served_version, counter) = (version.clone(), downloads.clone());
+        let base = serve(move |request| {
+            if request.starts_with("GET ") {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+            let etag = format!("\"v{}\"", served_version.load(Ordering::SeqCst));
+            http_response("200 OK", &[("Content-Type", "image/gif"), ("ETag", &etag)], b"GIF89a-pixels")
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("pixel.gif");
+        let media_url = format!("{}/pixel.gif", base);
+        let client = Client::new();
+
+        // Each run gets a fresh config, as a re-crawl would
+        download_media_with_config(&client, &media_url, &file_path, Some(&ScraperConfig::default())).await.unwrap();
+        assert_eq!(download_media_with_config(&client, &media_url, &file_path, Some(&ScraperConfig::default())).await.unwrap(), 13);
+        assert_eq!(downloads.load(Ordering::SeqCst), 1);
+
+        version.store(2, Ordering::SeqCst);
+        download_media_with_config(&client, &media_url, &file_path, Some(&ScraperConfig::default())).await.unwrap();
+        assert_eq!(downloads.load(Ordering::SeqCst), 2);
+        let entries = read_manifest(&manifest_path(temp_dir.path())).unwrap();
+        assert_eq!(entries.last().unwrap().etag.as_deref(), Some("\"v2\""));
+    }
+
+    #[tokio::test]
+    async fn test_external_scripts_are_deduplicated_and_capped() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let base = serve(move |request| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let body = if request.starts_with("GET /huge.js") { "x".repeat(2_000) } else { "var token = 1;".to_string() };
+            http_response("200 OK", &[("Content-Type", "application/javascript")], body.as_bytes())
+        })
+        .await;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::default();
+        config.set_output_dir(temp_dir.path());
+        config.set_max_script_bytes(1_000);
+        let html = r#"<script src="/app.js"></script><script src="/app.js?v=2"></script><script src="/huge.js"></script>"#;
+        let client = Client::new();
+
+        let findings = scrape_js_content_with_config(html, &base, &client, &["token"], Some(&config)).await;
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].source_url, format!("{}/app.js", base));
+        assert!(scrape_js_content_with_config(html, &base, &client, &["token"], Some(&config)).await.is_empty());
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        let saved: Vec<_> = std::fs::read_dir(temp_dir.path().join("scraped_js")).unwrap().collect();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(config.script_cache().len(), 1);
+    }
+
     // Test for the scrape_for_emails function
     #[test]
     fn test_scrape_for_emails() {
@@ -1343,7 +5579,20 @@ mod tests {
         let media_url = "https://via.placeholder.com/150";
         let file_path = Path::new("./test_output/image.jpg");
 
-        download_media(&client, media_url, &file_path).await;
+        download_media(&client, media_url, file_path).await;
+
+        assert!(file_path.exists(), "Image should be downloaded and saved");
+    }
+
+    // Async test for downloading media from a local server
+    #[tokio::test]
+    async fn test_download_media_from_local_server() {
+        let base = serve(|_| http_response("200 OK", &[("Content-Type", "image/png")], b"\x89PNG\r\n\x1a\nfake")).await;
+        let client = Client::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("image.jpg");
+
+        download_media(&client, &format!("{}/150", base), &file_path).await;
 
         assert!(file_path.exists(), "Image should be downloaded and saved");
     }
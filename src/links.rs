@@ -0,0 +1,289 @@
+// src/links.rs
+
+use scraper::{ElementRef, Html, Selector};
+
+use crate::normalize_link;
+
+/// What a link found in a page points to, decided from its URL scheme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkKind {
+    Web(String),        // An http(s) page or resource, as an absolute URL
+    Mailto(String),     // An email address from a `mailto:` link
+    Tel(String),        // A phone number from a `tel:` link
+    Ftp(String),        // An `ftp://` URL
+    JavaScript,         // A `javascript:` pseudo-link, never followed
+    Data,               // An inline `data:` URI
+    Other(String),      // Any other scheme (`sms:`, app intents, ...), by scheme name
+}
+
+/// Classifies a link by its scheme, resolving relative links against the page URL.
+///
+/// # Arguments
+///
+/// * `link` - The raw `href` value.
+/// * `base_url` - The URL of the page the link was found on.
+///
+/// # Returns
+///
+/// The `LinkKind` of the link.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{classify_link, LinkKind};
+/// assert_eq!(classify_link("/about", "https://example.com"), LinkKind::Web("https://example.com/about".to_string()));
+/// assert_eq!(classify_link("mailto:info@example.com?subject=Hi", "https://example.com"), LinkKind::Mailto("info@example.com".to_string()));
+/// assert_eq!(classify_link("javascript:void(0)", "https://example.com"), LinkKind::JavaScript);
+/// ```
+pub fn classify_link(link: &str, base_url: &str) -> LinkKind {
+    let link = link.trim();
+    let scheme = link_scheme(link).map(|scheme| scheme.to_ascii_lowercase());
+
+    match scheme.as_deref() {
+        None => LinkKind::Web(normalize_link(link, base_url)),
+        Some("http") | Some("https") => LinkKind::Web(normalize_link(link, base_url)),
+        Some("mailto") => {
+            let address = link[7..].split('?').next().unwrap_or("");
+            LinkKind::Mailto(percent_decode(address).trim().to_string())
+        }
+        Some("tel") => {
+            let number = link[4..].split([';', '?']).next().unwrap_or("");
+            LinkKind::Tel(percent_decode(number).trim().to_string())
+        }
+        Some("ftp") => LinkKind::Ftp(link.to_string()),
+        Some("javascript") => LinkKind::JavaScript,
+        Some("data") => LinkKind::Data,
+        Some(other) => LinkKind::Other(other.to_string()),
+    }
+}
+
+// Returns the scheme of an absolute link, or `None` for relative links
+fn link_scheme(link: &str) -> Option<&str> {
+    let colon = link.find(':')?;
+    let scheme = &link[..colon];
+    let valid = scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+    if valid {
+        Some(scheme)
+    } else {
+        None
+    }
+}
+
+// Decodes `%XX` escapes, leaving malformed ones untouched
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Email addresses and phone numbers published through `mailto:` and `tel:` links.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Contacts {
+    pub emails: Vec<String>,
+    pub phones: Vec<String>,
+}
+
+/// Harvests the `mailto:` and `tel:` links of an HTML page.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+/// * `base_url` - The URL of the page.
+///
+/// # Returns
+///
+/// The `Contacts` found, without duplicates, in page order.
+pub fn extract_contacts(html: &str, base_url: &str) -> Contacts {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href], area[href]").unwrap();
+    let mut contacts = Contacts::default();
+
+    for element in document.select(&selector) {
+        if let Some(href) = element.value().attr("href") {
+            match classify_link(href, base_url) {
+                LinkKind::Mailto(address) => {
+                    for email in address.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                        if !contacts.emails.iter().any(|known| known == email) {
+                            contacts.emails.push(email.to_string());
+                        }
+                    }
+                }
+                LinkKind::Tel(number) if !number.is_empty() && !contacts.phones.contains(&number) => {
+                    contacts.phones.push(number);
+                }
+                _ => {}
+            }
+        }
+    }
+    contacts
+}
+
+/// A crawlable link together with where it was found on the page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRecord {
+    pub url: String,                // Absolute URL
+    pub anchor_text: String,        // Link text, or the `alt`/`title`/`aria-label` of image links
+    pub heading: Option<String>,    // Closest heading before the link
+    pub context: Option<String>,    // Sentence of the surrounding block the link sits in
+}
+
+// Longest context sentence kept per link, in characters
+const MAX_CONTEXT_CHARS: usize = 200;
+
+/// Extracts the http(s) links of an HTML page with their anchor text and context.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+/// * `base_url` - The URL of the page.
+///
+/// # Returns
+///
+/// One `LinkRecord` per link, in page order.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::extract_link_records;
+/// let html = "<h2>Docs</h2><p>Read the <a href='/guide'>user guide</a> first. Then ask.</p>";
+/// let records = extract_link_records(html, "https://example.com");
+/// assert_eq!(records[0].url, "https://example.com/guide");
+/// assert_eq!(records[0].anchor_text, "user guide");
+/// assert_eq!(records[0].heading.as_deref(), Some("Docs"));
+/// assert_eq!(records[0].context.as_deref(), Some("Read the user guide first."));
+/// ```
+pub fn extract_link_records(html: &str, base_url: &str) -> Vec<LinkRecord> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href], h1, h2, h3, h4, h5, h6").unwrap();
+    let mut records = Vec::new();
+    let mut heading = None;
+
+    // Headings and links come back in document order, so the last heading seen is the closest
+    for element in document.select(&selector) {
+        let Some(href) = element.value().attr("href").filter(|_| element.value().name() == "a") else {
+            let text = element_text(&element);
+            heading = (!text.is_empty()).then_some(text);
+            continue;
+        };
+        if let LinkKind::Web(url) = classify_link(href, base_url) {
+            let anchor_text = anchor_text(&element);
+            let context = link_context(&element, &anchor_text);
+            records.push(LinkRecord { url, anchor_text, heading: heading.clone(), context });
+        }
+    }
+    records
+}
+
+// Returns the text of an element with whitespace collapsed
+fn element_text(element: &ElementRef) -> String {
+    element.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Returns the visible text of a link, falling back to the labels of image-only links
+fn anchor_text(link: &ElementRef) -> String {
+    let text = element_text(link);
+    if !text.is_empty() {
+        return text;
+    }
+    let image_selector = Selector::parse("img[alt]").unwrap();
+    link.select(&image_selector)
+        .filter_map(|image| image.value().attr("alt"))
+        .chain(["title", "aria-label"].iter().filter_map(|name| link.value().attr(name)))
+        .map(str::trim)
+        .find(|label| !label.is_empty())
+        .unwrap_or("")
+        .to_string()
+}
+
+// Returns the sentence of the closest block element that contains the link text
+fn link_context(link: &ElementRef, anchor_text: &str) -> Option<String> {
+    const BLOCKS: [&str; 12] = ["p", "li", "td", "th", "dd", "dt", "blockquote", "figcaption", "caption", "div", "section", "article"];
+    let block = link
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|ancestor| BLOCKS.contains(&ancestor.value().name()))?;
+
+    let text = element_text(&block);
+    if text.is_empty() || text == anchor_text {
+        return None;
+    }
+
+    // Image labels are not part of the block text, so links labelled by them have no context
+    let position = text.find(anchor_text)?;
+    let after = position + anchor_text.len();
+    let start = [". ", "! ", "? "].iter().filter_map(|end| text.get(..position)?.rfind(end).map(|i| i + 2)).max().unwrap_or(0);
+    let end = [". ", "! ", "? "].iter().filter_map(|end| text.get(after..)?.find(end).map(|i| after + i + 1)).min().unwrap_or(text.len());
+
+    Some(text.get(start..end)?.trim().chars().take(MAX_CONTEXT_CHARS).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_link_schemes() {
+        let base = "https://example.com/contact/";
+        assert_eq!(classify_link("team", base), LinkKind::Web("https://example.com/contact/team".to_string()));
+        assert_eq!(classify_link("HTTPS://Example.com/x", base), LinkKind::Web("https://example.com/x".to_string()));
+        assert_eq!(classify_link("tel:+1%20555-0100", base), LinkKind::Tel("+1 555-0100".to_string()));
+        assert_eq!(classify_link("ftp://files.example.com/pub/", base), LinkKind::Ftp("ftp://files.example.com/pub/".to_string()));
+        assert_eq!(classify_link("data:image/png;base64,AAAA", base), LinkKind::Data);
+        assert_eq!(classify_link("whatsapp://send?text=hi", base), LinkKind::Other("whatsapp".to_string()));
+    }
+
+    #[test]
+    fn test_extract_contacts() {
+        let html = r#"
+            <a href="mailto:sales@example.com,support@example.com">Mail us</a>
+            <a href="MAILTO:sales@example.com">Sales</a>
+            <a href="tel:+44 20 7946 0958">Call</a>
+            <a href="javascript:void(0)">Menu</a>"#;
+        let contacts = extract_contacts(html, "https://example.com");
+
+        assert_eq!(contacts.emails, vec!["sales@example.com", "support@example.com"]);
+        assert_eq!(contacts.phones, vec!["+44 20 7946 0958"]);
+    }
+
+    #[test]
+    fn test_extract_link_records() {
+        let html = r#"
+            <nav><a href="/">Home</a></nav>
+            <h1>Pricing</h1>
+            <ul><li>Compare plans. See the <a href="/plans?tier=pro">pro plan</a> for teams!</li></ul>
+            <h2>  Support  </h2>
+            <div><a href="/help"><img src="help.png" alt="Help center"></a></div>
+            <a href="mailto:help@example.com">Mail</a>"#;
+        let records = extract_link_records(html, "https://example.com");
+
+        assert_eq!(records.len(), 3);
+        assert_eq!((records[0].anchor_text.as_str(), records[0].heading.as_deref(), records[0].context.as_deref()), ("Home", None, None));
+        assert_eq!(records[1].url, "https://example.com/plans?tier=pro");
+        assert_eq!(records[1].heading.as_deref(), Some("Pricing"));
+        assert_eq!(records[1].context.as_deref(), Some("See the pro plan for teams!"));
+        assert_eq!(records[2].anchor_text, "Help center");
+        assert_eq!(records[2].heading.as_deref(), Some("Support"));
+    }
+
+    #[test]
+    fn test_image_links_outside_block_text_have_no_context() {
+        let records = extract_link_records(r#"<p><a href="/x"><img alt="A long alt text"></a> Hi</p>"#, "https://example.com");
+        assert_eq!((records[0].anchor_text.as_str(), records[0].context.as_deref()), ("A long alt text", None));
+        let records = extract_link_records(r#"<p>Ünïcode <a href="/x"><img alt="Help"></a></p>"#, "https://example.com");
+        assert_eq!((records[0].anchor_text.as_str(), records[0].context.as_deref()), ("Help", None));
+    }
+}
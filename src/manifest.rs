@@ -0,0 +1,218 @@
+// src/manifest.rs
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Result as IoResult, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncReadExt;
+
+/// File name of the manifest kept in every directory media is downloaded into.
+pub const MANIFEST_FILE: &str = "manifest.jsonl";
+
+/// One downloaded file, as recorded in a `manifest.jsonl`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub url: String,
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+    pub timestamp: u64,     // Seconds since the Unix epoch
+    #[serde(default)]
+    pub etag: Option<String>,   // As served, for skipping unchanged files on later runs
+    #[serde(default)]
+    pub total_size: Option<u64>,    // Set for range samples: the size of the whole remote file
+    #[serde(default)]
+    pub session_id: Option<String>, // The crawl session that downloaded the file
+}
+
+/// Computes the SHA-256 checksum of a file as a lowercase hex string, reading it in chunks.
+///
+/// # Arguments
+///
+/// * `path` - The file to hash.
+///
+/// # Returns
+///
+/// An `IoResult<String>` with the hex-encoded digest.
+pub async fn sha256_file(path: &Path) -> IoResult<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Returns the SHA-256 checksum of a byte slice as a lowercase hex string.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Returns the path of the manifest describing the files downloaded into `dir`.
+pub fn manifest_path(dir: &Path) -> PathBuf {
+    dir.join(MANIFEST_FILE)
+}
+
+/// Hashes a freshly downloaded file and appends its entry to the manifest of its directory.
+///
+/// # Arguments
+///
+/// * `url` - The URL the file was downloaded from.
+/// * `file_path` - The path the file was saved to.
+/// * `size` - The size of the file in bytes.
+///
+/// # Returns
+///
+/// The `ManifestEntry` that was recorded.
+pub async fn record_download(url: &str, file_path: &Path, size: u64) -> IoResult<ManifestEntry> {
+    record_download_with_etag(url, file_path, size, None).await
+}
+
+/// Records a download like `record_download`, along with the `ETag` the file was served with.
+pub async fn record_download_with_etag(url: &str, file_path: &Path, size: u64, etag: Option<&str>) -> IoResult<ManifestEntry> {
+    record_session_download(url, file_path, size, etag, None, None).await
+}
+
+// Records a download of a crawl session; `total_size` is the size of the whole remote file for
+// samples fetched with a `Range` request
+pub(crate) async fn record_session_download(
+    url: &str,
+    file_path: &Path,
+    size: u64,
+    etag: Option<&str>,
+    total_size: Option<u64>,
+    session_id: Option<&str>,
+) -> IoResult<ManifestEntry> {
+    let entry = ManifestEntry {
+        url: url.to_string(),
+        path: file_path.display().to_string(),
+        size,
+        sha256: sha256_file(file_path).await?,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        etag: etag.map(str::to_string),
+        total_size,
+        session_id: session_id.map(str::to_string),
+    };
+
+    let dir = file_path.parent().unwrap_or(Path::new("."));
+    let mut manifest = OpenOptions::new().create(true).append(true).open(manifest_path(dir))?;
+    writeln!(manifest, "{}", serde_json::to_string(&entry)?)?;
+    Ok(entry)
+}
+
+/// Reads every entry of a manifest file, skipping lines that cannot be parsed.
+///
+/// # Arguments
+///
+/// * `path` - The path of the `manifest.jsonl` file.
+///
+/// # Returns
+///
+/// An `IoResult<Vec<ManifestEntry>>` in the order the downloads were recorded.
+pub fn read_manifest(path: &Path) -> IoResult<Vec<ManifestEntry>> {
+    let file = std::fs::File::open(path)?;
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Re-hashes the files listed in a manifest and reports those that no longer match.
+///
+/// Only the latest entry of each path is checked, so re-downloads recorded later win.
+///
+/// # Arguments
+///
+/// * `path` - The path of the `manifest.jsonl` file.
+///
+/// # Returns
+///
+/// The entries whose file is missing or whose current SHA-256 differs from the recorded one.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::{manifest_path, verify_manifest};
+/// # use std::path::Path;
+/// # async fn example() -> std::io::Result<()> {
+/// for entry in verify_manifest(&manifest_path(Path::new("./scraped_data/example.com"))).await? {
+///     println!("Changed or missing: {}", entry.path);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn verify_manifest(path: &Path) -> IoResult<Vec<ManifestEntry>> {
+    let mut latest: Vec<ManifestEntry> = Vec::new();
+    for entry in read_manifest(path)? {
+        latest.retain(|existing| existing.path != entry.path);
+        latest.push(entry);
+    }
+
+    let mut mismatches = Vec::new();
+    for entry in latest {
+        match sha256_file(Path::new(&entry.path)).await {
+            Ok(hash) if hash == entry.sha256 => {}
+            _ => mismatches.push(entry),
+        }
+    }
+    Ok(mismatches)
+}
+
+/// The manifests of earlier runs, read once per directory and looked up by file path.
+#[derive(Debug, Default)]
+pub struct ManifestIndex {
+    dirs: Mutex<HashMap<PathBuf, HashMap<String, ManifestEntry>>>,
+}
+
+impl ManifestIndex {
+    /// Returns the latest manifest entry recorded for `file_path`, reading the manifest of its
+    /// directory on first use. Downloads recorded after that are not seen.
+    pub fn lookup(&self, file_path: &Path) -> Option<ManifestEntry> {
+        let dir = file_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let mut dirs = self.dirs.lock().unwrap();
+        let entries = dirs.entry(dir).or_insert_with_key(|dir| {
+            let entries = read_manifest(&manifest_path(dir)).unwrap_or_default();
+            entries.into_iter().map(|entry| (entry.path.clone(), entry)).collect()
+        });
+        entries.get(&file_path.display().to_string()).cloned()
+    }
+
+    // Method to forget every manifest read, so they are read again
+    pub fn clear(&self) {
+        self.dirs.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_and_verify_manifest() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("logo.png");
+        std::fs::write(&file_path, b"abc").unwrap();
+
+        let entry = record_download("https://example.com/logo.png", &file_path, 3).await.unwrap();
+        assert_eq!(entry.sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let manifest = manifest_path(temp_dir.path());
+        assert_eq!(read_manifest(&manifest).unwrap(), vec![entry]);
+        assert!(verify_manifest(&manifest).await.unwrap().is_empty());
+
+        std::fs::write(&file_path, b"tampered").unwrap();
+        assert_eq!(verify_manifest(&manifest).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,492 @@
+// src/media.rs
+
+use base64::Engine;
+use scraper::{ElementRef, Html, Selector};
+use std::fmt;
+
+use crate::links::percent_decode;
+use crate::normalize_link;
+
+/// The categories of media a page can reference, each with its own download toggle in `ScraperConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaKind {
+    Image,
+    Video,
+    Audio,
+    Document,
+    Script,
+    Stylesheet,
+    Font,
+    Other,
+}
+
+impl MediaKind {
+    /// Guesses the kind of a media URL from its file extension.
+    ///
+    /// # Returns
+    ///
+    /// `Some(MediaKind)` for known extensions, otherwise `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use knee_scraper::MediaKind;
+    /// assert_eq!(MediaKind::from_url("https://example.com/report.pdf?v=2"), Some(MediaKind::Document));
+    /// assert_eq!(MediaKind::from_url("https://example.com/about"), None);
+    /// ```
+    pub fn from_url(url: &str) -> Option<MediaKind> {
+        let path = url.split(['?', '#']).next().unwrap_or("");
+        let file_name = path.rsplit('/').next().unwrap_or("");
+        let extension = file_name.rsplit_once('.')?.1.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "jpg" | "jpeg" | "png" | "gif" | "webp" | "avif" | "svg" | "bmp" | "ico" | "tif" | "tiff" => Some(MediaKind::Image),
+            "mp4" | "webm" | "mov" | "m4v" | "mkv" | "avi" | "ogv" | "m3u8" | "vtt" | "srt" => Some(MediaKind::Video),
+            "mp3" | "m4a" | "aac" | "ogg" | "oga" | "opus" | "wav" | "flac" => Some(MediaKind::Audio),
+            "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "odt" | "ods" | "odp" | "rtf" | "csv" | "epub" => Some(MediaKind::Document),
+            "js" | "mjs" => Some(MediaKind::Script),
+            "css" => Some(MediaKind::Stylesheet),
+            "woff" | "woff2" | "ttf" | "otf" | "eot" => Some(MediaKind::Font),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MediaKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MediaKind::Image => "image",
+            MediaKind::Video => "video",
+            MediaKind::Audio => "audio",
+            MediaKind::Document => "document",
+            MediaKind::Script => "script",
+            MediaKind::Stylesheet => "stylesheet",
+            MediaKind::Font => "font",
+            MediaKind::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Identifies the MIME type of file contents from their leading "magic" bytes.
+///
+/// # Arguments
+///
+/// * `bytes` - The first bytes of the file (512 are plenty).
+///
+/// # Returns
+///
+/// `Some(mime)` for recognised formats, otherwise `None`.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::sniff_mime;
+/// assert_eq!(sniff_mime(b"%PDF-1.7 ..."), Some("application/pdf"));
+/// assert_eq!(sniff_mime(b"<!DOCTYPE html><html>"), Some("text/html"));
+/// ```
+pub fn sniff_mime(bytes: &[u8]) -> Option<&'static str> {
+    let starts = |magic: &[u8]| bytes.starts_with(magic);
+    let at = |offset: usize, magic: &[u8]| bytes.len() >= offset + magic.len() && &bytes[offset..offset + magic.len()] == magic;
+
+    if starts(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if starts(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if starts(b"GIF87a") || starts(b"GIF89a") {
+        Some("image/gif")
+    } else if starts(b"RIFF") && at(8, b"WEBP") {
+        Some("image/webp")
+    } else if starts(b"RIFF") && at(8, b"WAVE") {
+        Some("audio/wav")
+    } else if starts(b"RIFF") && at(8, b"AVI ") {
+        Some("video/x-msvideo")
+    } else if at(4, b"ftypavif") {
+        Some("image/avif")
+    } else if at(4, b"ftyp") {
+        Some("video/mp4")
+    } else if starts(b"BM") && bytes.len() > 14 {
+        Some("image/bmp")
+    } else if starts(&[0x00, 0x00, 0x01, 0x00]) {
+        Some("image/x-icon")
+    } else if starts(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        Some("video/webm")
+    } else if starts(b"ID3") || starts(&[0xFF, 0xFB]) || starts(&[0xFF, 0xF3]) || starts(&[0xFF, 0xF2]) {
+        Some("audio/mpeg")
+    } else if starts(b"OggS") {
+        Some("audio/ogg")
+    } else if starts(b"fLaC") {
+        Some("audio/flac")
+    } else if starts(b"%PDF-") {
+        Some("application/pdf")
+    } else if starts(b"PK\x03\x04") {
+        Some("application/zip")
+    } else if starts(&[0xD0, 0xCF, 0x11, 0xE0]) {
+        Some("application/x-ole-storage")
+    } else if starts(b"WEBVTT") {
+        Some("text/vtt")
+    } else {
+        sniff_text(bytes)
+    }
+}
+
+// Text formats are recognised from their first tag, ignoring leading whitespace and case
+fn sniff_text(bytes: &[u8]) -> Option<&'static str> {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).trim_start().to_ascii_lowercase();
+    if head.starts_with("<!doctype html") || head.starts_with("<html") || head.starts_with("<head") || head.starts_with("<body") {
+        Some("text/html")
+    } else if head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg")) {
+        Some("image/svg+xml")
+    } else {
+        None
+    }
+}
+
+/// Checks whether a claimed `Content-Type` is consistent with the sniffed type of the contents.
+///
+/// Types are compared by family, so `image/jpg` matches `image/jpeg`, audio and video containers
+/// match each other, and office formats match the ZIP or OLE containers they are stored in.
+/// A missing, generic (`application/octet-stream`) or unrecognised type never counts as a mismatch.
+pub fn mime_matches(claimed: Option<&str>, sniffed: Option<&str>) -> bool {
+    match (claimed.map(mime_essence), sniffed) {
+        (Some(claimed), Some(sniffed)) => {
+            claimed.is_empty()
+                || claimed == "application/octet-stream"
+                || claimed == "binary/octet-stream"
+                || mime_family(&claimed) == mime_family(sniffed)
+        }
+        _ => true,
+    }
+}
+
+// Strips parameters such as `; charset=utf-8` and normalises case
+pub(crate) fn mime_essence(mime: &str) -> String {
+    mime.split(';').next().unwrap_or("").trim().to_ascii_lowercase()
+}
+
+fn mime_family(mime: &str) -> &str {
+    match mime {
+        m if m.starts_with("image/") => "image",
+        m if m.starts_with("audio/") || m.starts_with("video/") => "media",
+        "application/ogg" | "application/mp4" | "application/vnd.apple.mpegurl" => "media",
+        "text/html" | "application/xhtml+xml" => "html",
+        "application/zip" | "application/epub+zip" | "application/java-archive" => "zip",
+        m if m.starts_with("application/vnd.openxmlformats") || m.starts_with("application/vnd.oasis.opendocument") => "zip",
+        "application/x-ole-storage" | "application/msword" => "ole",
+        m if m.starts_with("application/vnd.ms-") => "ole",
+        other => other,
+    }
+}
+
+/// The descriptor attached to a `srcset` candidate: an intrinsic width (`640w`) or a pixel density (`2x`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SrcsetDescriptor {
+    Width(u32),
+    Density(f32),
+}
+
+/// One image candidate from a `srcset` attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SrcsetCandidate {
+    pub url: String,
+    pub descriptor: SrcsetDescriptor,
+}
+
+/// Parses a `srcset` attribute into its image candidates.
+///
+/// Candidates without a descriptor count as `1x`, as in the HTML specification.
+///
+/// # Arguments
+///
+/// * `srcset` - The value of the `srcset` attribute.
+/// * `base_url` - The base URL to resolve relative candidate URLs.
+///
+/// # Returns
+///
+/// A `Vec<SrcsetCandidate>` with absolute URLs, in attribute order.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{parse_srcset, SrcsetDescriptor};
+/// let candidates = parse_srcset("/small.jpg 480w, /large.jpg 1080w", "https://example.com");
+/// assert_eq!(candidates[1].url, "https://example.com/large.jpg");
+/// assert_eq!(candidates[1].descriptor, SrcsetDescriptor::Width(1080));
+/// ```
+pub fn parse_srcset(srcset: &str, base_url: &str) -> Vec<SrcsetCandidate> {
+    let mut candidates = Vec::new();
+    let mut rest = srcset;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+
+        // The URL runs up to the next whitespace; trailing commas end the candidate early
+        let url_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let raw_url = &rest[..url_end];
+        rest = &rest[url_end..];
+
+        let mut descriptor_text = "";
+        let url = if raw_url.ends_with(',') {
+            raw_url.trim_end_matches(',')
+        } else {
+            let descriptor_end = rest.find(',').unwrap_or(rest.len());
+            descriptor_text = rest[..descriptor_end].trim();
+            rest = &rest[descriptor_end..];
+            raw_url
+        };
+
+        if let Some(descriptor) = parse_descriptor(descriptor_text) {
+            candidates.push(SrcsetCandidate {
+                url: normalize_link(url, base_url),
+                descriptor,
+            });
+        }
+    }
+    candidates
+}
+
+// Parses the descriptor part of a candidate; unknown descriptors invalidate the candidate
+fn parse_descriptor(text: &str) -> Option<SrcsetDescriptor> {
+    let descriptor = match text.split_whitespace().next() {
+        None => return Some(SrcsetDescriptor::Density(1.0)),
+        Some(descriptor) => descriptor,
+    };
+
+    if let Some(width) = descriptor.strip_suffix('w') {
+        width.parse().ok().map(SrcsetDescriptor::Width)
+    } else if let Some(density) = descriptor.strip_suffix('x') {
+        density.parse().ok().map(SrcsetDescriptor::Density)
+    } else {
+        None
+    }
+}
+
+/// Picks the candidate to download from a list of `srcset` candidates.
+///
+/// Without a target width, the highest-resolution candidate is chosen. With a target width,
+/// the smallest candidate at least that wide is chosen, falling back to the widest one.
+/// Width descriptors carry real pixel sizes, so they take precedence over density descriptors
+/// when an attribute mixes both.
+///
+/// # Arguments
+///
+/// * `candidates` - The candidates returned by `parse_srcset`.
+/// * `target_width` - An optional preferred image width in pixels.
+///
+/// # Returns
+///
+/// The selected candidate, or `None` if the list is empty.
+pub fn select_srcset_candidate(candidates: &[SrcsetCandidate], target_width: Option<u32>) -> Option<&SrcsetCandidate> {
+    let widths: Vec<(u32, &SrcsetCandidate)> = candidates
+        .iter()
+        .filter_map(|candidate| match candidate.descriptor {
+            SrcsetDescriptor::Width(width) => Some((width, candidate)),
+            SrcsetDescriptor::Density(_) => None,
+        })
+        .collect();
+
+    if widths.is_empty() {
+        return candidates.iter().max_by(|a, b| density(a).total_cmp(&density(b)));
+    }
+
+    let widest = widths.iter().max_by_key(|(width, _)| *width).map(|(_, candidate)| *candidate);
+    match target_width {
+        Some(target) => widths
+            .iter()
+            .filter(|(width, _)| *width >= target)
+            .min_by_key(|(width, _)| *width)
+            .map(|(_, candidate)| *candidate)
+            .or(widest),
+        None => widest,
+    }
+}
+
+fn density(candidate: &SrcsetCandidate) -> f32 {
+    match candidate.descriptor {
+        SrcsetDescriptor::Density(density) => density,
+        SrcsetDescriptor::Width(_) => 1.0,
+    }
+}
+
+/// Collects the URL to download for every image of a page.
+///
+/// Images offering `srcset` variants, either on the `<img>` itself or on the `<source>`
+/// elements of an enclosing `<picture>`, resolve to the best candidate according to
+/// `select_srcset_candidate`; other images resolve to their `src`.
+///
+/// # Arguments
+///
+/// * `document` - The parsed HTML page.
+/// * `base_url` - The base URL to resolve relative links.
+/// * `target_width` - An optional preferred image width in pixels.
+///
+/// # Returns
+///
+/// A `Vec<String>` of absolute image URLs, without duplicates.
+pub fn collect_image_urls(document: &Html, base_url: &str, target_width: Option<u32>) -> Vec<String> {
+    let img_selector = Selector::parse("img[src], img[srcset]").unwrap();
+    let mut urls = Vec::new();
+
+    for img in document.select(&img_selector) {
+        let mut candidates = Vec::new();
+        if let Some(picture) = img.parent().and_then(ElementRef::wrap) {
+            if picture.value().name() == "picture" {
+                for source in picture.children().filter_map(ElementRef::wrap) {
+                    if source.value().name() == "source" {
+                        if let Some(srcset) = source.value().attr("srcset") {
+                            candidates.extend(parse_srcset(srcset, base_url));
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(srcset) = img.value().attr("srcset") {
+            candidates.extend(parse_srcset(srcset, base_url));
+        }
+
+        let image_url = match select_srcset_candidate(&candidates, target_width) {
+            Some(candidate) => Some(candidate.url.clone()),
+            None => img.value().attr("src").map(|src| normalize_link(src, base_url)),
+        };
+
+        if let Some(image_url) = image_url {
+            if !urls.contains(&image_url) {
+                urls.push(image_url);
+            }
+        }
+    }
+    urls
+}
+
+/// The decoded contents of an inline `data:` URI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataUri {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl DataUri {
+    /// Returns the file extension matching the MIME type, or `bin` when unknown.
+    pub fn extension(&self) -> &'static str {
+        match self.mime_type.as_str() {
+            "image/png" => "png",
+            "image/jpeg" | "image/jpg" => "jpg",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            "image/avif" => "avif",
+            "image/svg+xml" => "svg",
+            "image/bmp" => "bmp",
+            "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+            "image/tiff" => "tiff",
+            _ => "bin",
+        }
+    }
+}
+
+/// Decodes a `data:` URI, either base64 or percent-encoded.
+///
+/// # Returns
+///
+/// `Some(DataUri)` with the MIME type (lowercase, `text/plain` when omitted) and payload, or
+/// `None` when the URI is not a well-formed `data:` URI.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::parse_data_uri;
+/// let uri = parse_data_uri("data:image/gif;base64,R0lGODlh").unwrap();
+/// assert_eq!(uri.mime_type, "image/gif");
+/// assert_eq!(uri.data, b"GIF89a");
+/// assert_eq!(uri.extension(), "gif");
+/// ```
+pub fn parse_data_uri(uri: &str) -> Option<DataUri> {
+    let uri = uri.trim();
+    if !uri.get(..5)?.eq_ignore_ascii_case("data:") {
+        return None;
+    }
+    let (header, payload) = uri[5..].split_once(',')?;
+    let mut parameters = header.split(';').map(str::trim);
+    let mime_type = match parameters.next() {
+        Some(mime) if !mime.is_empty() => mime.to_ascii_lowercase(),
+        _ => "text/plain".to_string(),
+    };
+    let is_base64 = parameters.any(|parameter| parameter.eq_ignore_ascii_case("base64"));
+
+    let data = if is_base64 {
+        // Attribute values often wrap long payloads or carry percent-escaped padding
+        let payload: String = percent_decode(payload).chars().filter(|c| !c.is_whitespace()).collect();
+        base64::engine::general_purpose::STANDARD_NO_PAD.decode(payload.trim_end_matches('=')).ok()?
+    } else {
+        percent_decode(payload).into_bytes()
+    };
+    Some(DataUri { mime_type, data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_mime_and_matches() {
+        assert_eq!(sniff_mime(b"\x89PNG\r\n\x1a\n...."), Some("image/png"));
+        assert_eq!(sniff_mime(b"\x00\x00\x00\x18ftypmp42"), Some("video/mp4"));
+        assert_eq!(sniff_mime(b"  <HTML><body>Not found</body>"), Some("text/html"));
+        assert_eq!(sniff_mime(b"plain bytes"), None);
+
+        assert!(mime_matches(Some("image/jpg"), Some("image/jpeg")));
+        assert!(mime_matches(Some("audio/mp4"), Some("video/mp4")));
+        assert!(mime_matches(Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document"), Some("application/zip")));
+        assert!(mime_matches(Some("application/octet-stream"), Some("application/pdf")));
+        assert!(mime_matches(None, Some("image/png")));
+        assert!(!mime_matches(Some("image/png; charset=binary"), Some("text/html")));
+    }
+
+    #[test]
+    fn test_parse_data_uri() {
+        let png = parse_data_uri("data:image/PNG;base64,iVBORw0K\nGgo=").unwrap();
+        assert_eq!(png.mime_type, "image/png");
+        assert_eq!(png.data, b"\x89PNG\r\n\x1a\n");
+        assert_eq!(png.extension(), "png");
+
+        let svg = parse_data_uri("data:image/svg+xml,%3Csvg%3E%3C/svg%3E").unwrap();
+        assert_eq!(svg.data, b"<svg></svg>");
+        assert_eq!(svg.extension(), "svg");
+
+        assert!(parse_data_uri("https://example.com/a.png").is_none());
+        assert!(parse_data_uri("data:image/png;base64,@@@").is_none());
+    }
+
+    #[test]
+    fn test_parse_srcset() {
+        let candidates = parse_srcset("a.jpg, b.jpg 2x,c.jpg 1.5x , bad.jpg 10q", "https://img.test/");
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[0].descriptor, SrcsetDescriptor::Density(1.0));
+        assert_eq!(candidates[1].url, "https://img.test/b.jpg");
+        assert_eq!(candidates[2].descriptor, SrcsetDescriptor::Density(1.5));
+    }
+
+    #[test]
+    fn test_select_srcset_candidate() {
+        let candidates = parse_srcset("s.jpg 320w, m.jpg 800w, l.jpg 1600w", "https://img.test/");
+        assert_eq!(select_srcset_candidate(&candidates, None).unwrap().url, "https://img.test/l.jpg");
+        assert_eq!(select_srcset_candidate(&candidates, Some(640)).unwrap().url, "https://img.test/m.jpg");
+        assert_eq!(select_srcset_candidate(&candidates, Some(4000)).unwrap().url, "https://img.test/l.jpg");
+    }
+
+    #[test]
+    fn test_collect_image_urls_prefers_picture_sources() {
+        let html = r#"
+            <picture>
+                <source srcset="/hero-1200.webp 1200w, /hero-2400.webp 2400w" type="image/webp">
+                <img src="/hero.jpg" alt="Hero">
+            </picture>
+            <img src="/logo.png">"#;
+        let document = Html::parse_document(html);
+
+        let urls = collect_image_urls(&document, "https://img.test", None);
+        assert_eq!(urls, vec!["https://img.test/hero-2400.webp", "https://img.test/logo.png"]);
+    }
+}
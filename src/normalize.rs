@@ -0,0 +1,72 @@
+// src/normalize.rs
+
+use regex::{Captures, Regex};
+
+use crate::forms::CSRF_FIELD_MARKERS;
+
+// Query and path parameters that carry session ids
+const SESSION_PARAMS: &str = "jsessionid|phpsessid|aspsessionid[a-z]*|sessionid|session_id|sid";
+
+/// Removes the parts of a page that change on every request without the page changing: nonces,
+/// CSRF token values, timestamps, and session ids in URLs.
+///
+/// Monitoring (incremental) crawls hash the normalized page, so rotating tokens no longer make every
+/// page look changed. Token attributes are emptied rather than dropped, and timestamps become
+/// `<timestamp>`, so the structure of the page is kept.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::normalize_html;
+/// let monday = r#"<script nonce="r4nd0m">init()</script><a href="/cart;jsessionid=A1B2">Cart</a> Updated 2024-05-06T08:00:00Z"#;
+/// let tuesday = r#"<script nonce="0th3r">init()</script><a href="/cart;jsessionid=C3D4">Cart</a> Updated 2024-05-07T09:30:00Z"#;
+/// assert_eq!(normalize_html(monday), normalize_html(tuesday));
+/// ```
+pub fn normalize_html(html: &str) -> String {
+    let token_tag_regex = Regex::new(r"(?is)<(?:input|meta)\b[^>]*>").unwrap();
+    let token_value_regex = Regex::new(r#"(?i)\b(value|content)\s*=\s*(?:"[^"]*"|'[^']*'|[^\s>]+)"#).unwrap();
+    let nonce_regex = Regex::new(r#"(?i)\bnonce\s*=\s*(?:"[^"]*"|'[^']*'|[^\s>]+)"#).unwrap();
+    let session_regex = Regex::new(&format!(r#"(?i)([;?&](?:{})=)[^&#;"'\s<>]*"#, SESSION_PARAMS)).unwrap();
+    let timestamp_regex = Regex::new(r"\b\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(?::\d{2}(?:\.\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?|\b1\d{9}(?:\d{3})?\b").unwrap();
+
+    let html = token_tag_regex.replace_all(html, |caps: &Captures| {
+        let tag = &caps[0];
+        if is_token_tag(tag) {
+            token_value_regex.replace_all(tag, "$1=\"\"").into_owned()
+        } else {
+            tag.to_string()
+        }
+    });
+    let html = nonce_regex.replace_all(&html, "nonce=\"\"");
+    let html = session_regex.replace_all(&html, "$1");
+    timestamp_regex.replace_all(&html, "<timestamp>").into_owned()
+}
+
+// Returns whether an `<input>` or `<meta>` tag is named like an anti-CSRF token
+fn is_token_tag(tag: &str) -> bool {
+    let name_regex = Regex::new(r#"(?i)\bname\s*=\s*["']?([^"'\s>]+)"#).unwrap();
+    name_regex.captures(tag).is_some_and(|caps| {
+        let name = caps[1].to_ascii_lowercase();
+        CSRF_FIELD_MARKERS.iter().any(|marker| name.contains(marker))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotating_tokens_are_normalized_away() {
+        let page = |token: &str, session: &str, epoch: &str| {
+            format!(
+                r#"<meta name="csrf-token" content="{0}"><form><input type="hidden" name="authenticity_token" value='{0}'><input name="q" value="shoes"></form><a href="/a?id=7&PHPSESSID={1}&x=1">A</a><span data-ts="{2}">Price: 42</span>"#,
+                token, session, epoch
+            )
+        };
+        let first = normalize_html(&page("aaa", "s1", "1714982400"));
+        assert_eq!(first, normalize_html(&page("bbb", "s2", "1714982460123")));
+        assert!(first.contains(r#"name="q" value="shoes""#));
+        assert!(first.contains("/a?id=7&PHPSESSID=&x=1"));
+        assert_ne!(first, normalize_html(&page("aaa", "s1", "1714982400").replace("42", "43")));
+    }
+}
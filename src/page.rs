@@ -0,0 +1,260 @@
+// src/page.rs
+
+use std::collections::HashMap;
+
+use encoding_rs::{Encoding, UTF_8};
+use reqwest::{Request, Response};
+
+use crate::media::mime_essence;
+use crate::{sniff_mime, MediaKind};
+
+/// Where a fetched response is processed, decided by its `Content-Type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentRoute {
+    Html,                   // Parsed and extracted like any page
+    Json,                   // Captured raw, pretty-printed
+    Xml,
+    Media(MediaKind),       // Handed to the media pipeline
+}
+
+/// Decides how a response is processed from its `Content-Type`, sniffing the body when the
+/// header is missing or generic (`application/octet-stream`).
+///
+/// Plain text and unrecognised bodies go to the HTML extractor, as before routing existed.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{route_response, ContentRoute, MediaKind};
+/// assert_eq!(route_response(Some("application/ld+json; charset=utf-8"), b"{}"), ContentRoute::Json);
+/// assert_eq!(route_response(Some("application/octet-stream"), b"%PDF-1.7"), ContentRoute::Media(MediaKind::Document));
+/// assert_eq!(route_response(None, b"<p>Hi</p>"), ContentRoute::Html);
+/// ```
+pub fn route_response(content_type: Option<&str>, body: &[u8]) -> ContentRoute {
+    let claimed = content_type.map(mime_essence).filter(|mime| !mime.is_empty() && !mime.ends_with("/octet-stream"));
+    let mime = match claimed {
+        Some(mime) => mime,
+        None => match sniff_mime(body) {
+            Some(sniffed) => sniffed.to_string(),
+            None => return ContentRoute::Html,
+        },
+    };
+
+    match mime.as_str() {
+        "text/html" | "application/xhtml+xml" | "text/plain" => ContentRoute::Html,
+        "application/json" | "text/json" => ContentRoute::Json,
+        m if m.ends_with("+json") => ContentRoute::Json,
+        "application/xml" | "text/xml" => ContentRoute::Xml,
+        m if m.ends_with("+xml") && !m.starts_with("image/") => ContentRoute::Xml,
+        m if m.starts_with("image/") => ContentRoute::Media(MediaKind::Image),
+        m if m.starts_with("video/") || m == "application/vnd.apple.mpegurl" => ContentRoute::Media(MediaKind::Video),
+        m if m.starts_with("audio/") || m == "application/ogg" => ContentRoute::Media(MediaKind::Audio),
+        m if m.starts_with("font/") || m.starts_with("application/font") => ContentRoute::Media(MediaKind::Font),
+        "text/css" => ContentRoute::Media(MediaKind::Stylesheet),
+        "text/javascript" | "application/javascript" | "application/x-javascript" => ContentRoute::Media(MediaKind::Script),
+        "application/pdf" | "application/msword" | "application/rtf" | "text/csv" | "application/epub+zip" => ContentRoute::Media(MediaKind::Document),
+        m if m.starts_with("application/vnd.openxmlformats") || m.starts_with("application/vnd.oasis.opendocument") || m.starts_with("application/vnd.ms-") => {
+            ContentRoute::Media(MediaKind::Document)
+        }
+        m if m.starts_with("text/") => ContentRoute::Html,
+        _ => ContentRoute::Media(MediaKind::Other),
+    }
+}
+
+/// Reads a response body of at most `max_bytes`, passing the size of each chunk received to `on_chunk`.
+///
+/// # Returns
+///
+/// `Ok(None)` as soon as the announced or received size exceeds `max_bytes`, so oversized bodies
+/// are never held in memory.
+pub(crate) async fn read_body_capped(mut response: Response, max_bytes: u64, mut on_chunk: impl FnMut(u64)) -> Result<Option<Vec<u8>>, reqwest::Error> {
+    if response.content_length().is_some_and(|length| length > max_bytes) {
+        return Ok(None);
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        on_chunk(chunk.len() as u64);
+        body.extend_from_slice(&chunk);
+        if body.len() as u64 > max_bytes {
+            return Ok(None);
+        }
+    }
+    Ok(Some(body))
+}
+
+/// Estimates the bytes a request puts on the wire: its request line and headers.
+pub(crate) fn request_size(request: &Request) -> u64 {
+    let url = request.url();
+    let target = url.path().len() + url.query().map_or(0, |query| query.len() + 1);
+    let line = request.method().as_str().len() + target + " HTTP/1.1\r\n".len();
+    let host = "Host: \r\n".len() + url.host_str().map_or(0, str::len);
+    let headers: usize = request.headers().iter().map(|(name, value)| name.as_str().len() + value.len() + 4).sum();
+    (line + host + headers + 2) as u64
+}
+
+/// Estimates the bytes of a response head: its status line and headers.
+pub(crate) fn response_head_size(response: &Response) -> u64 {
+    let headers: usize = response.headers().iter().map(|(name, value)| name.as_str().len() + value.len() + 4).sum();
+    (format!("HTTP/1.1 {}\r\n", response.status()).len() + headers + 2) as u64
+}
+
+/// Decodes a body with the charset of its `Content-Type`, defaulting to UTF-8 like
+/// `reqwest::Response::text`.
+pub(crate) fn decode_body(bytes: &[u8], content_type: Option<&str>) -> String {
+    let encoding = content_type
+        .and_then(|content_type| {
+            content_type.split(';').skip(1).find_map(|param| {
+                let (name, value) = param.split_once('=')?;
+                name.trim().eq_ignore_ascii_case("charset").then(|| value.trim().trim_matches('"').to_string())
+            })
+        })
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .unwrap_or(UTF_8);
+    encoding.decode(bytes).0.into_owned()
+}
+
+// Elements that never have content, so never nest
+const VOID_ELEMENTS: [&str; 14] = ["area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr"];
+
+// Elements whose end tag may be left out, closed by the next element of the same kind
+const SIBLING_CLOSED_ELEMENTS: [&str; 9] = ["dd", "dt", "li", "option", "optgroup", "p", "td", "th", "tr"];
+
+/// Returns the deepest element nesting of an HTML document, counted from its tags in a single
+/// pass without building a DOM, so it is cheap to run on a page before deciding to parse it.
+/// Counting stops as soon as the nesting passes `max_depth`, so a result above `max_depth` means
+/// the page nests too deep.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::document_depth;
+/// assert_eq!(document_depth("<div><p><b>deep</b></p></div>", 512), 3);
+/// assert_eq!(document_depth("<ul><li>one<li>two</ul>", 512), 2);      // `<li>` closes the previous one
+/// assert_eq!(document_depth(&"<div>".repeat(1_000), 512), 513);
+/// ```
+pub fn document_depth(html: &str, max_depth: usize) -> usize {
+    let mut open: Vec<String> = Vec::new();
+    // How many elements of each name are open, so an end tag of an element that is not open is
+    // dropped without searching the stack
+    let mut open_counts: HashMap<String, usize> = HashMap::new();
+    let mut deepest = 0;
+    let mut position = 0;
+    while let Some(offset) = html[position..].find('<') {
+        let start = position + offset + 1;
+        if html[start..].starts_with("!--") {
+            position = html[start..].find("-->").map_or(html.len(), |end| start + end + 3);
+            continue;
+        }
+        let closing = html[start..].starts_with('/');
+        let name_start = start + usize::from(closing);
+        let name_end = html[name_start..].find(|c: char| !c.is_ascii_alphanumeric() && c != '-').map_or(html.len(), |end| name_start + end);
+        let name = html[name_start..name_end].to_ascii_lowercase();
+        let tag_end = html[name_end..].find('>').map_or(html.len(), |end| name_end + end);
+        position = (tag_end + 1).min(html.len());
+
+        // `<!doctype>`, `<?xml?>` and a stray `<` in text open nothing
+        if name.is_empty() {
+            continue;
+        }
+        if closing {
+            // The search stops at the element it closes, so it costs no more than the elements it closes
+            if open_counts.get(&name).is_some_and(|count| *count > 0) {
+                if let Some(index) = open.iter().rposition(|tag| *tag == name) {
+                    close_from(&mut open, index, &mut open_counts);
+                }
+            }
+            continue;
+        }
+        if VOID_ELEMENTS.contains(&name.as_str()) || html[..tag_end].ends_with('/') {
+            deepest = deepest.max(open.len() + 1);
+            if deepest > max_depth {
+                return deepest;
+            }
+            continue;
+        }
+        // An open element of the same kind is closed when only such elements were opened after it
+        // (a new `<tr>` closes the open `<td>` and `<tr>`). Those runs hold each name at most once,
+        // so the search is short
+        let sibling_closed = |tag: &str| SIBLING_CLOSED_ELEMENTS.contains(&tag);
+        if sibling_closed(&name) && open_counts.get(&name).is_some_and(|count| *count > 0) {
+            if let Some(index) = open.iter().rposition(|tag| *tag == name || !sibling_closed(tag)) {
+                if open[index] == name {
+                    close_from(&mut open, index, &mut open_counts);
+                }
+            }
+        }
+        let raw_text = name == "script" || name == "style";
+        *open_counts.entry(name.clone()).or_default() += 1;
+        open.push(name);
+        deepest = deepest.max(open.len());
+        if deepest > max_depth {
+            return deepest;
+        }
+
+        // The text of scripts and styles is not markup; skip to their end tag
+        if raw_text {
+            let end_tag = format!("</{}", open[open.len() - 1]);
+            position = html.as_bytes()[position..]
+                .windows(end_tag.len())
+                .position(|window| window.eq_ignore_ascii_case(end_tag.as_bytes()))
+                .map_or(html.len(), |end| position + end);
+        }
+    }
+    deepest
+}
+
+// Closes the open elements from `index` on
+fn close_from(open: &mut Vec<String>, index: usize, open_counts: &mut HashMap<String, usize>) {
+    for tag in open.drain(index..) {
+        if let Some(count) = open_counts.get_mut(&tag) {
+            *count -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_support::{http_response, serve};
+
+    #[tokio::test]
+    async fn test_page_body_guards() {
+        let base = serve(|_| http_response("200 OK", &[], &[b'x'; 4_096])).await;
+        let response = reqwest::get(&base).await.unwrap();
+        assert!(read_body_capped(response, 1_000, |_| ()).await.unwrap().is_none());
+        let response = reqwest::get(&base).await.unwrap();
+        assert_eq!(read_body_capped(response, 4_096, |_| ()).await.unwrap().map(|body| body.len()), Some(4_096));
+
+        assert_eq!(decode_body(b"caf\xe9", Some("text/html; charset=ISO-8859-1")), "café");
+        assert_eq!(decode_body("café".as_bytes(), Some("text/html")), "café");
+
+        assert_eq!(route_response(Some("text/xml"), b"<urlset/>"), ContentRoute::Xml);
+        assert_eq!(route_response(Some("image/svg+xml"), b"<svg/>"), ContentRoute::Media(MediaKind::Image));
+        assert_eq!(route_response(None, b"\x89PNG\r\n\x1a\n"), ContentRoute::Media(MediaKind::Image));
+        assert_eq!(route_response(Some("application/zip"), b"PK\x03\x04"), ContentRoute::Media(MediaKind::Other));
+
+        let nested = format!("{}x{}", "<div>".repeat(600), "</div>".repeat(600));
+        assert_eq!(document_depth(&nested, 1_000), 600);
+        assert_eq!(document_depth(&nested, 512), 513);
+        let flat = format!("<table>{}</table><script>if (a <b) {{ x = '<div><div>'; }}</script><!-- <div><div> -->", "<tr><td>cell<td><img src=x>".repeat(600));
+        assert_eq!(document_depth(&flat, 512), 4);
+    }
+
+    #[test]
+    fn test_document_depth_is_linear_on_adversarial_pages() {
+        // Unmatched end tags under a deep stack, and sibling-closed runs that never close anything
+        let unmatched = format!("{}{}", "<a>".repeat(100_000), "</b>".repeat(100_000));
+        let siblings = format!("<div>{}", "<li><p><dd><span></span>".repeat(200_000));
+        let interleaved = "<li><div>".repeat(200_000);
+        let closing = format!("{}{}", "<div><p>".repeat(250), "</div>x</p>".repeat(200_000));
+        let started = std::time::Instant::now();
+        assert_eq!(document_depth(&unmatched, 512), 513);
+        assert_eq!(document_depth(&siblings, 512), 5);
+        assert_eq!(document_depth(&interleaved, 512), 513);
+        assert_eq!(document_depth(&closing, 512), 500);
+        assert!(started.elapsed() < Duration::from_secs(5), "took {:?}", started.elapsed());
+    }
+}
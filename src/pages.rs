@@ -0,0 +1,141 @@
+// src/pages.rs
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{read_to_string, OpenOptions};
+use std::io::{ErrorKind, Result as IoResult, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::manifest::sha256_hex;
+
+/// The file in each domain output directory mapping page ids to page URLs, one `id<TAB>url` per line.
+/// Incremental crawls add the content hash of their crawl database as a third column.
+pub const PAGE_INDEX_FILE: &str = "pages.tsv";
+
+/// Returns the id of a page: the first 16 hex digits of the SHA-256 of its URL.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::page_id;
+/// assert_eq!(page_id("https://example.com/").len(), 16);
+/// assert_ne!(page_id("https://example.com/a"), page_id("https://example.com/b"));
+/// ```
+pub fn page_id(url: &str) -> String {
+    sha256_hex(url.as_bytes())[..16].to_string()
+}
+
+/// Returns the path of a per-page output file, e.g. `{dir}/content-{id}.txt` for the stem `content`.
+pub fn page_file(dir: &Path, stem: &str, url: &str) -> PathBuf {
+    dir.join(format!("{}-{}.txt", stem, page_id(url)))
+}
+
+/// Adds a page to the index of a domain output directory, unless it is listed already.
+///
+/// This reads the whole index on every call; a crawl records its pages through the `PageIndex` of its config.
+///
+/// # Returns
+///
+/// The id of the page.
+pub fn record_page(dir: &Path, url: &str) -> IoResult<String> {
+    PageIndex::default().record(dir, url)
+}
+
+/// The page ids listed in the index of each domain output directory of a crawl, so recording a page
+/// does not read the index again.
+#[derive(Debug, Default)]
+pub struct PageIndex {
+    dirs: Mutex<HashMap<PathBuf, HashSet<String>>>,
+}
+
+impl PageIndex {
+    /// Adds a page to the index of a domain output directory like `record_page`, reading the index
+    /// of the directory on first use. Pages other writers add after that are not seen.
+    pub fn record(&self, dir: &Path, url: &str) -> IoResult<String> {
+        self.record_hashed(dir, url, None)
+    }
+
+    /// Adds a page to the index like `record`, along with the content hash the crawl database
+    /// compares it by, so crawls that skip unchanged pages can still be compared.
+    pub fn record_hashed(&self, dir: &Path, url: &str, content_hash: Option<&str>) -> IoResult<String> {
+        let id = page_id(url);
+        let mut dirs = self.dirs.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let known = match dirs.get_mut(dir) {
+            Some(known) => known,
+            None => {
+                let known = read_page_index(dir)?.into_iter().map(|(id, _)| id).collect();
+                dirs.entry(dir.to_path_buf()).or_insert(known)
+            }
+        };
+        if !known.contains(&id) {
+            let mut file = OpenOptions::new().create(true).append(true).open(dir.join(PAGE_INDEX_FILE))?;
+            match content_hash {
+                Some(content_hash) => writeln!(file, "{}\t{}\t{}", id, url, content_hash)?,
+                None => writeln!(file, "{}\t{}", id, url)?,
+            }
+            known.insert(id.clone());
+        }
+        Ok(id)
+    }
+
+    // Method to forget every index read, so they are read again
+    pub fn clear(&self) {
+        self.dirs.lock().unwrap().clear();
+    }
+}
+
+/// Reads the page index of a domain output directory as `(id, url)` pairs, in crawl order.
+///
+/// # Returns
+///
+/// An empty list when the directory has no index yet.
+pub fn read_page_index(dir: &Path) -> IoResult<Vec<(String, String)>> {
+    Ok(read_hashed_page_index(dir)?.into_iter().map(|(id, url, _)| (id, url)).collect())
+}
+
+// Reads the page index like `read_page_index`, with the content hash of each page when recorded
+pub(crate) fn read_hashed_page_index(dir: &Path) -> IoResult<Vec<(String, String, Option<String>)>> {
+    let contents = match read_to_string(dir.join(PAGE_INDEX_FILE)) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.splitn(3, '\t');
+            Some((columns.next()?.to_string(), columns.next()?.to_string(), columns.next().map(str::to_string)))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_index_lists_each_page_once() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let a = record_page(temp_dir.path(), "https://a.test/one").unwrap();
+        let b = record_page(temp_dir.path(), "https://a.test/two").unwrap();
+        assert_eq!(record_page(temp_dir.path(), "https://a.test/one").unwrap(), a);
+
+        let index = read_page_index(temp_dir.path()).unwrap();
+        assert_eq!(index, vec![(a.clone(), "https://a.test/one".to_string()), (b.clone(), "https://a.test/two".to_string())]);
+        assert_eq!(page_file(temp_dir.path(), "content", "https://a.test/one"), temp_dir.path().join(format!("content-{}.txt", a)));
+
+        // An index seeded from the file keeps listing each page once without reading it again
+        let pages = PageIndex::default();
+        assert_eq!(pages.record(temp_dir.path(), "https://a.test/two").unwrap(), b);
+        std::fs::remove_file(temp_dir.path().join(PAGE_INDEX_FILE)).unwrap();
+        pages.record(temp_dir.path(), "https://a.test/one").unwrap();
+        pages.record(temp_dir.path(), "https://a.test/three").unwrap();
+        let index = read_page_index(temp_dir.path()).unwrap();
+        assert_eq!(index, vec![(page_id("https://a.test/three"), "https://a.test/three".to_string())]);
+
+        pages.record_hashed(temp_dir.path(), "https://a.test/four", Some("abc")).unwrap();
+        let hashes: Vec<Option<String>> = read_hashed_page_index(temp_dir.path()).unwrap().into_iter().map(|(_, _, hash)| hash).collect();
+        assert_eq!(hashes, vec![None, Some("abc".to_string())]);
+        assert_eq!(read_page_index(temp_dir.path()).unwrap()[1].1, "https://a.test/four");
+    }
+}
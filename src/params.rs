@@ -0,0 +1,178 @@
+// src/params.rs
+
+use reqwest::{Client, Method};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::{request, ScraperConfig};
+
+/// Parameter names that web applications commonly accept without advertising them.
+pub const DEFAULT_PARAMETER_WORDLIST: [&str; 30] = [
+    "id", "page", "q", "query", "search", "s", "debug", "test", "admin", "user", "username", "email",
+    "redirect", "url", "next", "return", "callback", "format", "lang", "file", "path", "token",
+    "key", "sort", "order", "limit", "offset", "view", "mode", "type",
+];
+
+// The benign value sent with every probed parameter
+const PROBE_VALUE: &str = "kneeprobe1";
+
+// A parameter name no application knows, sent to measure how much a response varies on its own
+const CONTROL_PARAMETER: &str = "kneeprobe_control";
+
+/// A parameter that changed the response of an endpoint when probed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParameterFinding {
+    pub url: String,
+    pub method: String,        // `GET` probes the query string, `POST` a form body
+    pub parameter: String,
+    pub evidence: String,      // How the response changed, e.g. `status 200 -> 500`
+}
+
+impl fmt::Display for ParameterFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Parameter Finding - Method: {}, URL: {}, Parameter: {}, Evidence: {}", self.method, self.url, self.parameter, self.evidence)
+    }
+}
+
+// The parts of a response that are compared between probes
+struct ProbeResponse {
+    status: u16,
+    body: String,
+}
+
+/// Probes an endpoint with every parameter of a wordlist, one at a time and with a benign value,
+/// and reports the parameters that change the response.
+///
+/// A plain request and a request with a made-up parameter are sent first, to learn how much the
+/// response varies on its own. A parameter is reported when it changes the status code, when its
+/// value is reflected in the body, or when it changes the body length by more than that variation.
+///
+/// # Arguments
+///
+/// * `method` - `GET` to probe query parameters, `POST` to probe form fields.
+/// * `url` - The endpoint, e.g. a form action or an API URL found in a script.
+/// * `wordlist` - The parameter names to try.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `config` - An optional reference to `ScraperConfig` for the user agent, request limits and traffic accounting.
+///
+/// # Returns
+///
+/// The parameters that changed the response, in wordlist order; empty if the endpoint cannot be reached.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::{discover_parameters, DEFAULT_PARAMETER_WORDLIST};
+/// # use reqwest::Client;
+/// # async fn example(client: Client) {
+/// let wordlist: Vec<String> = DEFAULT_PARAMETER_WORDLIST.iter().map(|name| name.to_string()).collect();
+/// for finding in discover_parameters("GET", "https://example.com/search", &wordlist, &client, None).await {
+///     println!("{}", finding);
+/// }
+/// # }
+/// ```
+pub async fn discover_parameters(method: &str, url: &str, wordlist: &[String], client: &Client, config: Option<&ScraperConfig>) -> Vec<ParameterFinding> {
+    let default_config = ScraperConfig::default();
+    let config = config.unwrap_or(&default_config);
+
+    let Some(plain) = probe(method, url, None, client, config).await else { return Vec::new() };
+    let Some(control) = probe(method, url, Some(CONTROL_PARAMETER), client, config).await else { return Vec::new() };
+    let jitter = plain.body.len().abs_diff(control.body.len());
+    let tolerance = jitter + 16.max(control.body.len() / 50);
+    let reflects_everything = control.body.contains(PROBE_VALUE);
+
+    let mut findings = Vec::new();
+    for parameter in wordlist {
+        let Some(response) = probe(method, url, Some(parameter), client, config).await else { continue };
+        let evidence = if response.status != control.status {
+            format!("status {} -> {}", control.status, response.status)
+        } else if !reflects_everything && response.body.contains(PROBE_VALUE) {
+            "value reflected".to_string()
+        } else if response.body.len().abs_diff(control.body.len()) > tolerance {
+            format!("length {} -> {} bytes", control.body.len(), response.body.len())
+        } else {
+            continue;
+        };
+        findings.push(ParameterFinding { url: url.to_string(), method: method.to_uppercase(), parameter: parameter.clone(), evidence });
+    }
+    findings
+}
+
+// Sends one probe, with `parameter` set to the probe value, honoring the config's limits
+async fn probe(method: &str, url: &str, parameter: Option<&str>, client: &Client, config: &ScraperConfig) -> Option<ProbeResponse> {
+    let params: Vec<(&str, &str)> = parameter.map(|name| (name, PROBE_VALUE)).into_iter().collect();
+    let post = method.eq_ignore_ascii_case("POST");
+    let method = if post { Method::POST } else { Method::GET };
+    let response = request::send(client, method, url, config, |request| if post { request.form(&params) } else { request.query(&params) }).await.ok()?;
+    let status = response.status().as_u16();
+    let body = request::read_body(response, config.max_page_bytes(), config).await.ok().flatten().unwrap_or_default();
+    Some(ProbeResponse { status, body: String::from_utf8_lossy(&body).into_owned() })
+}
+
+/// Collects the parameter findings of a crawl per host, and remembers which endpoints were
+/// probed so each is probed once.
+#[derive(Debug, Default)]
+pub struct ParameterFindingLog {
+    probed: Mutex<HashSet<(String, String)>>,
+    hosts: Mutex<BTreeMap<String, Vec<ParameterFinding>>>,
+}
+
+impl ParameterFindingLog {
+    /// Marks an endpoint as probed, returning `false` if it was probed before.
+    pub fn claim(&self, method: &str, url: &str) -> bool {
+        self.probed.lock().unwrap().insert((method.to_uppercase(), url.to_string()))
+    }
+
+    pub fn record(&self, host: &str, findings: &[ParameterFinding]) {
+        self.hosts.lock().unwrap().entry(host.to_string()).or_default().extend_from_slice(findings);
+    }
+
+    pub fn for_host(&self, host: &str) -> Vec<ParameterFinding> {
+        self.hosts.lock().unwrap().get(host).cloned().unwrap_or_default()
+    }
+
+    /// Returns a copy of every host's findings, by host name.
+    pub fn hosts(&self) -> BTreeMap<String, Vec<ParameterFinding>> {
+        self.hosts.lock().unwrap().clone()
+    }
+
+    // Method to forget every finding and probed endpoint
+    pub fn clear(&self) {
+        self.probed.lock().unwrap().clear();
+        self.hosts.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{http_response, serve};
+
+    #[tokio::test]
+    async fn test_parameters_that_change_the_response_are_reported() {
+        let base = serve(|request| {
+            let target = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+            if target.contains("debug=") {
+                http_response("500 Internal Server Error", &[], b"trace")
+            } else if let Some(query) = target.split("q=").nth(1) {
+                http_response("200 OK", &[], format!("<p>Results for {}</p>", query).as_bytes())
+            } else if target.contains("limit=") {
+                http_response("200 OK", &[], "<li>row</li>".repeat(50).as_bytes())
+            } else {
+                http_response("200 OK", &[], b"<p>Search</p>")
+            }
+        })
+        .await;
+
+        let wordlist: Vec<String> = ["debug", "q", "limit", "sort"].iter().map(|name| name.to_string()).collect();
+        let findings = discover_parameters("get", &format!("{}/search", base), &wordlist, &Client::new(), None).await;
+        let found: Vec<(&str, &str)> = findings.iter().map(|finding| (finding.parameter.as_str(), finding.evidence.as_str())).collect();
+        assert_eq!(found, vec![("debug", "status 200 -> 500"), ("q", "value reflected"), ("limit", "length 13 -> 600 bytes")]);
+        assert_eq!(findings[0].method, "GET");
+
+        let log = ParameterFindingLog::default();
+        assert!(log.claim("GET", &findings[0].url));
+        assert!(!log.claim("get", &findings[0].url));
+    }
+}
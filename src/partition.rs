@@ -0,0 +1,157 @@
+// src/partition.rs
+
+use reqwest::Url;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, RwLock};
+
+use crate::{normalize_link, UrlPattern};
+
+/// The file in the output root listing the pages crawled in every partition, one
+/// `name<TAB>pages<TAB>budget` per line (`-` for no budget).
+pub const PARTITIONS_FILE: &str = "partitions.tsv";
+
+/// A section or language of a site crawled as its own partition, with its own page budget and
+/// output directory (`<domain>/<name>`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrawlPartition {
+    pub name: String,
+    pub pattern: Option<UrlPattern>,    // The URLs of a section partition
+    pub language: Option<String>,       // The language of a language partition, lowercase
+    pub max_pages: Option<usize>,
+}
+
+impl CrawlPartition {
+    /// A partition of the URLs matching `pattern`, e.g. `/docs/*`.
+    pub fn section(name: &str, pattern: &str) -> Self {
+        CrawlPartition { name: name.to_string(), pattern: Some(UrlPattern::new(pattern)), language: None, max_pages: None }
+    }
+
+    /// A partition of the pages in `language`, e.g. `de`: the pages under a `/de/` path prefix,
+    /// and the pages another page declares as its `de` version with `hreflang`.
+    pub fn language(language: &str) -> Self {
+        let language = language.trim().to_ascii_lowercase();
+        CrawlPartition { name: language.clone(), pattern: None, language: Some(language), max_pages: None }
+    }
+
+    /// Caps the pages crawled in this partition.
+    pub fn max_pages(mut self, pages: usize) -> Self {
+        self.max_pages = Some(pages);
+        self
+    }
+}
+
+/// The partitions of a crawl and the pages crawled in each. Pages outside every partition are
+/// crawled and stored as usual.
+#[derive(Debug, Default)]
+pub struct CrawlPartitions {
+    partitions: RwLock<Vec<CrawlPartition>>,
+    hreflang: Mutex<HashMap<String, String>>,   // Language of the pages declared as `hreflang` alternates
+    pages: Mutex<BTreeMap<String, usize>>,      // Pages crawled, by partition name
+}
+
+impl CrawlPartitions {
+    // Method to add a partition; the first partition a URL matches is the one it belongs to
+    pub fn add(&self, partition: CrawlPartition) {
+        self.partitions.write().unwrap().push(partition);
+    }
+
+    pub fn partitions(&self) -> Vec<CrawlPartition> {
+        self.partitions.read().unwrap().clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.partitions.read().unwrap().is_empty()
+    }
+
+    /// Returns the partition `url` belongs to.
+    pub fn partition_of(&self, url: &str) -> Option<CrawlPartition> {
+        let declared = self.hreflang.lock().unwrap().get(url).cloned();
+        let path_language = Url::parse(url).ok().and_then(|parsed| parsed.path_segments()?.next().map(str::to_ascii_lowercase));
+        let partitions = self.partitions.read().unwrap();
+        let partition = partitions.iter().find(|partition| match (&partition.pattern, &partition.language) {
+            (Some(pattern), _) => pattern.matches(url),
+            (None, Some(language)) => declared.as_ref().map_or(path_language.as_ref() == Some(language), |declared| language_matches(declared, language)),
+            (None, None) => false,
+        });
+        partition.cloned()
+    }
+
+    /// Records the `hreflang` alternates a page declares, so they land in their language's partition.
+    pub fn record_hreflang(&self, html: &str, url: &str) {
+        let links = extract_hreflang(html, url);
+        let mut hreflang = self.hreflang.lock().unwrap();
+        for (language, link) in links {
+            hreflang.entry(link).or_insert(language);
+        }
+    }
+
+    /// Counts a page against the budget of its partition, returning `false` (and not counting it)
+    /// when the budget is used up.
+    pub fn admit(&self, url: &str) -> bool {
+        let Some(partition) = self.partition_of(url) else { return true };
+        let mut pages = self.pages.lock().unwrap();
+        let crawled = pages.entry(partition.name.clone()).or_insert(0);
+        if partition.max_pages.is_some_and(|max_pages| *crawled >= max_pages) {
+            return false;
+        }
+        *crawled += 1;
+        true
+    }
+
+    /// Returns the pages crawled in every partition, in the order the partitions were added.
+    pub fn pages(&self) -> Vec<(String, usize)> {
+        let pages = self.pages.lock().unwrap();
+        self.partitions.read().unwrap().iter().map(|partition| (partition.name.clone(), pages.get(&partition.name).copied().unwrap_or(0))).collect()
+    }
+
+    // Method to forget the pages crawled and the `hreflang` alternates seen
+    pub fn clear(&self) {
+        self.hreflang.lock().unwrap().clear();
+        self.pages.lock().unwrap().clear();
+    }
+}
+
+// Returns whether a declared `hreflang` value such as `de-AT` belongs to `language`
+fn language_matches(declared: &str, language: &str) -> bool {
+    declared == language || declared.split(['-', '_']).next() == Some(language)
+}
+
+// Returns the `(language, url)` pairs of the `hreflang` alternates a page declares, skipping `x-default`
+fn extract_hreflang(html: &str, base_url: &str) -> Vec<(String, String)> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("link[rel~=alternate][hreflang][href], a[hreflang][href]").unwrap();
+    document
+        .select(&selector)
+        .filter_map(|link| {
+            let language = link.value().attr("hreflang")?.trim().to_ascii_lowercase();
+            (language != "x-default").then(|| (language, normalize_link(link.value().attr("href").unwrap_or(""), base_url)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls_are_assigned_to_partitions() {
+        let partitions = CrawlPartitions::default();
+        partitions.add(CrawlPartition::section("docs", "/docs/*").max_pages(1));
+        partitions.add(CrawlPartition::language("en"));
+        partitions.add(CrawlPartition::language("de"));
+        partitions.record_hreflang(r#"<link rel="alternate" hreflang="de-AT" href="https://shop.test/at/start"><link rel="alternate" hreflang="x-default" href="/">"#, "https://shop.test/en/");
+
+        let name = |url: &str| partitions.partition_of(url).map(|partition| partition.name);
+        assert_eq!(name("https://shop.test/docs/setup").as_deref(), Some("docs"));
+        assert_eq!(name("https://shop.test/EN/about").as_deref(), Some("en"));
+        assert_eq!(name("https://shop.test/at/start").as_deref(), Some("de"));
+        assert_eq!(name("https://shop.test/"), None);
+
+        assert!(partitions.admit("https://shop.test/docs/setup"));
+        assert!(!partitions.admit("https://shop.test/docs/faq"));
+        assert!(partitions.admit("https://shop.test/"));
+        assert_eq!(partitions.pages(), vec![("docs".to_string(), 1), ("en".to_string(), 0), ("de".to_string(), 0)]);
+    }
+}
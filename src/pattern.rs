@@ -0,0 +1,92 @@
+// src/pattern.rs
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+/// A glob pattern matched against URLs, where `*` stands for any run of characters.
+///
+/// Patterns starting with `/` are matched against the path (and query string) of a URL, so
+/// `/api/*` covers that section on every host; other patterns are matched against the whole
+/// URL, e.g. `https://*.example.com/static/*`.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::UrlPattern;
+/// let api = UrlPattern::new("/api/*");
+/// assert!(api.matches("https://example.com/api/v1/users?page=2"));
+/// assert!(!api.matches("https://example.com/blog/api"));
+///
+/// let assets = UrlPattern::new("*.png");
+/// assert!(assets.matches("https://cdn.example.com/img/logo.png"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UrlPattern {
+    pattern: String,
+}
+
+impl UrlPattern {
+    pub fn new(pattern: &str) -> Self {
+        UrlPattern { pattern: pattern.trim().to_string() }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns whether `url` matches the pattern.
+    pub fn matches(&self, url: &str) -> bool {
+        if self.pattern.starts_with('/') {
+            let Ok(parsed) = Url::parse(url) else { return false };
+            let target = match parsed.query() {
+                Some(query) => format!("{}?{}", parsed.path(), query),
+                None => parsed.path().to_string(),
+            };
+            glob_match(&self.pattern, &target)
+        } else {
+            glob_match(&self.pattern, url)
+        }
+    }
+}
+
+// Matches `text` against a pattern where `*` matches any sequence, backtracking to the last star
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_pattern_matching() {
+        assert!(UrlPattern::new("/api/*").matches("https://a.test/api/"));
+        assert!(!UrlPattern::new("/api/*").matches("https://a.test/apis"));
+        assert!(UrlPattern::new("/search?*q=*").matches("https://a.test/search?lang=en&q=rust"));
+        assert!(UrlPattern::new("https://*.a.test/*").matches("https://img.a.test/x.jpg"));
+        assert!(!UrlPattern::new("https://*.a.test/*").matches("https://a.test/x.jpg"));
+        assert!(UrlPattern::new("*").matches("https://anything.test/"));
+        assert!(!UrlPattern::new("/api/*").matches("not a url"));
+    }
+}
@@ -0,0 +1,236 @@
+// src/redact.rs
+
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A kind of personal data `PiiRedactor` masks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PiiKind {
+    Email,          // Masked as `***@domain`, keeping the domain
+    Iban,
+    CardNumber,     // Digit runs passing the Luhn check
+    NationalId,     // US social security numbers
+    Phone,
+}
+
+impl fmt::Display for PiiKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PiiKind::Email => "email",
+            PiiKind::Iban => "IBAN",
+            PiiKind::CardNumber => "card number",
+            PiiKind::NationalId => "national ID",
+            PiiKind::Phone => "phone number",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Masks emails, phone numbers, IBANs, card numbers and social security numbers in text, counting
+/// what it masked.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{PiiKind, PiiRedactor};
+/// let redactor = PiiRedactor::new();
+/// let (text, counts) = redactor.redact("Call +44 20 7946 0958 or mail jane.doe@example.com");
+/// assert_eq!(text, "Call [phone number] or mail ***@example.com");
+/// assert_eq!(counts[&PiiKind::Phone], 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PiiRedactor {
+    patterns: Vec<(PiiKind, Regex)>,    // Applied in order, so masked text is not matched again
+    tel_link: Regex,                    // `tel:` targets in attributes
+}
+
+impl Default for PiiRedactor {
+    fn default() -> Self {
+        PiiRedactor::new()
+    }
+}
+
+impl PiiRedactor {
+    pub fn new() -> Self {
+        let patterns = [
+            (PiiKind::Email, r"[a-zA-Z0-9._%+-]+@([a-zA-Z0-9.-]+\.[a-zA-Z]{2,})"),
+            (PiiKind::Iban, r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b"),
+            (PiiKind::CardNumber, r"\b(?:\d[ -]?){12,18}\d\b"),
+            (PiiKind::NationalId, r"\b\d{3}-\d{2}-\d{4}\b"),
+            (PiiKind::Phone, r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,4}\)[ .-]?)?\b\d{2,4}(?:[ .-]\d{2,5}){1,4}\b"),
+        ];
+        PiiRedactor {
+            patterns: patterns.into_iter().map(|(kind, pattern)| (kind, Regex::new(pattern).unwrap())).collect(),
+            tel_link: Regex::new(r#"(?i)\btel:[^"'\s>]+"#).unwrap(),
+        }
+    }
+
+    /// Returns `html` (or XML) with the personal data of its text masked, and how many of each kind
+    /// were masked. The markup is kept intact: inside tags only emails and `tel:` targets are masked,
+    /// so numbers in attributes such as sizes and coordinates are left alone.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use knee_scraper::PiiRedactor;
+    /// let html = r#"<a href="tel:+15550100" data-id="2024 0131 5555">Call 555-010-0100</a>"#;
+    /// let (redacted, _) = PiiRedactor::new().redact_html(html);
+    /// assert_eq!(redacted, r#"<a href="tel:[phone number]" data-id="2024 0131 5555">Call [phone number]</a>"#);
+    /// ```
+    pub fn redact_html(&self, html: &str) -> (String, BTreeMap<PiiKind, usize>) {
+        let mut counts = BTreeMap::new();
+        let mut redacted = String::with_capacity(html.len());
+        let mut rest = html;
+        while !rest.is_empty() {
+            let (segment, in_tag) = match markup_start(rest) {
+                Some(0) => (&rest[..rest.find('>').map_or(rest.len(), |end| end + 1)], true),
+                Some(start) => (&rest[..start], false),
+                None => (rest, false),
+            };
+            let (masked, segment_counts) = if in_tag { self.redact_tag(segment) } else { self.redact(segment) };
+            redacted.push_str(&masked);
+            merge_counts(&mut counts, segment_counts);
+            rest = &rest[segment.len()..];
+        }
+        (redacted, counts)
+    }
+
+    /// Returns a JSON document with the personal data of its string values masked, and how many of
+    /// each kind were masked, or `None` when `json` is not valid JSON.
+    pub fn redact_json(&self, json: &str) -> Option<(String, BTreeMap<PiiKind, usize>)> {
+        let mut value: Value = serde_json::from_str(json).ok()?;
+        let mut counts = BTreeMap::new();
+        self.redact_value(&mut value, &mut counts);
+        Some((serde_json::to_string(&value).ok()?, counts))
+    }
+
+    // Masks the emails and `tel:` targets of a tag
+    fn redact_tag(&self, tag: &str) -> (String, BTreeMap<PiiKind, usize>) {
+        let mut counts = BTreeMap::new();
+        let (_, email) = self.patterns.iter().find(|(kind, _)| *kind == PiiKind::Email).unwrap();
+        let emails = email.find_iter(tag).count();
+        let tag = email.replace_all(tag, |captures: &Captures| format!("***@{}", &captures[1]));
+        let phones = self.tel_link.find_iter(&tag).count();
+        let tag = self.tel_link.replace_all(&tag, format!("tel:[{}]", PiiKind::Phone).as_str()).into_owned();
+        counts.extend([(PiiKind::Email, emails), (PiiKind::Phone, phones)].into_iter().filter(|(_, count)| *count > 0));
+        (tag, counts)
+    }
+
+    // Masks the string values of a JSON value in place
+    fn redact_value(&self, value: &mut Value, counts: &mut BTreeMap<PiiKind, usize>) {
+        match value {
+            Value::String(text) => {
+                let (masked, masked_counts) = self.redact(text);
+                *text = masked;
+                merge_counts(counts, masked_counts);
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_value(item, counts)),
+            Value::Object(fields) => fields.values_mut().for_each(|field| self.redact_value(field, counts)),
+            _ => {}
+        }
+    }
+
+    /// Returns `text` with its personal data masked, and how many of each kind were masked.
+    pub fn redact(&self, text: &str) -> (String, BTreeMap<PiiKind, usize>) {
+        let mut counts = BTreeMap::new();
+        let mut text = text.to_string();
+        for (kind, regex) in &self.patterns {
+            let mut masked = 0;
+            let replaced = regex.replace_all(&text, |captures: &Captures| {
+                let found = &captures[0];
+                match mask(*kind, found, captures) {
+                    Some(mask) => {
+                        masked += 1;
+                        mask
+                    }
+                    None => found.to_string(),
+                }
+            });
+            if masked > 0 {
+                text = replaced.into_owned();
+                counts.insert(*kind, masked);
+            }
+        }
+        (text, counts)
+    }
+}
+
+// Returns the mask of a match, or `None` for matches that are not what they look like
+fn mask(kind: PiiKind, found: &str, captures: &Captures) -> Option<String> {
+    let digits: Vec<u32> = found.chars().filter_map(|c| c.to_digit(10)).collect();
+    match kind {
+        PiiKind::Email => Some(format!("***@{}", &captures[1])),
+        PiiKind::CardNumber if !luhn_valid(&digits) => None,
+        // Dates and version-like numbers are not phone numbers
+        PiiKind::Phone if digits.len() < 8 || digits.len() > 15 || is_date(found) => None,
+        _ => Some(format!("[{}]", kind)),
+    }
+}
+
+// Returns where the next tag, comment or declaration of markup starts; a `<` before anything else is text
+fn markup_start(text: &str) -> Option<usize> {
+    text.match_indices('<').map(|(i, _)| i).find(|&i| text[i + 1..].starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?')))
+}
+
+// Adds the counts of one redaction to another
+fn merge_counts(counts: &mut BTreeMap<PiiKind, usize>, more: BTreeMap<PiiKind, usize>) {
+    for (kind, count) in more {
+        *counts.entry(kind).or_insert(0) += count;
+    }
+}
+
+// Returns whether a digit sequence passes the Luhn checksum of card numbers
+fn luhn_valid(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2, digit * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => digit,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+// Returns whether a match is a date such as `2024-01-31` or `31.01.2024`
+fn is_date(found: &str) -> bool {
+    let parts: Vec<&str> = found.split(['-', '.', ' ']).collect();
+    let lengths: Vec<usize> = parts.iter().map(|part| part.len()).collect();
+    matches!(lengths.as_slice(), [4, 1..=2, 1..=2] | [1..=2, 1..=2, 4])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_personal_data_is_masked_and_counted() {
+        let redactor = PiiRedactor::new();
+        let text = "Pay DE89 3704 0044 0532 0130 00 or card 4111 1111 1111 1111 (not 4111 1111 1111 1112). SSN 078-05-1120, tel (555) 123-4567, 555.987.6543. Released 2024-01-31, build 1.2.3.";
+        let (redacted, counts) = redactor.redact(text);
+        assert_eq!(
+            redacted,
+            "Pay [IBAN] or card [card number] (not 4111 1111 1111 1112). SSN [national ID], tel [phone number], [phone number]. Released 2024-01-31, build 1.2.3."
+        );
+        assert_eq!(counts, BTreeMap::from([(PiiKind::Iban, 1), (PiiKind::CardNumber, 1), (PiiKind::NationalId, 1), (PiiKind::Phone, 2)]));
+        assert_eq!(redactor.redact("No personal data").1.len(), 0);
+    }
+
+    #[test]
+    fn test_markup_and_json_keep_their_structure() {
+        let redactor = PiiRedactor::new();
+        let html = r#"<p title="a@b.test" style="width: 1200 3400px">If 1 < 2 mail <a href="mailto:jo@b.test">jo@b.test</a></p>"#;
+        let (redacted, counts) = redactor.redact_html(html);
+        assert_eq!(redacted, r#"<p title="***@b.test" style="width: 1200 3400px">If 1 < 2 mail <a href="mailto:***@b.test">***@b.test</a></p>"#);
+        assert_eq!(counts, BTreeMap::from([(PiiKind::Email, 3)]));
+
+        let (json, counts) = redactor.redact_json(r#"{"owner": {"mail": "ops@b.test", "id": 5550100123}, "tags": ["tel 555-010-0100"]}"#).unwrap();
+        assert_eq!(json, r#"{"owner":{"id":5550100123,"mail":"***@b.test"},"tags":["tel [phone number]"]}"#);
+        assert_eq!(counts, BTreeMap::from([(PiiKind::Email, 1), (PiiKind::Phone, 1)]));
+        assert!(redactor.redact_json("{oops").is_none());
+    }
+}
@@ -0,0 +1,177 @@
+// src/reload.rs
+
+use serde::{Deserialize, Serialize};
+use std::fs::{metadata, read_to_string};
+use std::io::{Error as IoError, ErrorKind, Result as IoResult};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+use crate::{BandwidthLimiter, DomainFilters, DomainList, RequestLimiter, ScraperConfig};
+
+/// A rate-limit rule of `LiveSettings`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiveRateLimitRule {
+    pub pattern: String,
+    pub interval_ms: Option<u64>,   // `None` exempts the matching URLs
+}
+
+/// The settings that can change while a crawl is running, as read from a reload file (JSON).
+///
+/// Every field is optional in the file; a missing limit or domain list is removed. The rules
+/// replace the previous rules as a whole.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LiveSettings {
+    pub request_interval_ms: Option<u64>,
+    pub rate_limit_rules: Vec<LiveRateLimitRule>,
+    pub bandwidth_limit: Option<u64>,           // Bytes per second
+    pub per_host_bandwidth_limit: Option<u64>,  // Bytes per second
+    pub allowed_domains: Option<Vec<String>>,   // Domain list patterns, e.g. `*.example.com`
+    pub denied_domains: Option<Vec<String>>,
+}
+
+impl LiveSettings {
+    /// Parses settings from the JSON of a reload file.
+    pub fn parse(json: &str) -> IoResult<Self> {
+        serde_json::from_str(json).map_err(|e| IoError::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// A handle on the parts of a config that crawls read on every request: the request limiter,
+/// the bandwidth limiter and the domain lists. Applying settings through it changes them for
+/// every crawl running with the config, without a restart.
+#[derive(Debug, Clone)]
+pub struct LiveConfig {
+    request_limiter: Arc<RequestLimiter>,
+    bandwidth_limiter: Arc<BandwidthLimiter>,
+    domain_filters: Arc<DomainFilters>,
+}
+
+impl LiveConfig {
+    /// Returns a handle on the live parts of `config`.
+    pub fn new(config: &ScraperConfig) -> Self {
+        LiveConfig {
+            request_limiter: Arc::clone(config.request_limiter()),
+            bandwidth_limiter: Arc::clone(config.bandwidth_limiter()),
+            domain_filters: Arc::clone(config.domain_filters()),
+        }
+    }
+
+    /// Replaces the limits and domain lists with `settings`.
+    pub fn apply(&self, settings: &LiveSettings) {
+        self.request_limiter.set_per_host_interval(settings.request_interval_ms.map(Duration::from_millis));
+        self.request_limiter.clear_rules();
+        for rule in &settings.rate_limit_rules {
+            self.request_limiter.add_rule(&rule.pattern, rule.interval_ms.map(Duration::from_millis));
+        }
+        self.bandwidth_limiter.set_global_limit(settings.bandwidth_limit);
+        self.bandwidth_limiter.set_per_host_limit(settings.per_host_bandwidth_limit);
+
+        let parse_list = |patterns: &Option<Vec<String>>| patterns.as_ref().map(|patterns| DomainList::parse(&patterns.join("\n")));
+        self.domain_filters.set_allowed(parse_list(&settings.allowed_domains));
+        self.domain_filters.set_denied(parse_list(&settings.denied_domains));
+    }
+
+    /// Returns the settings currently in effect.
+    pub fn settings(&self) -> LiveSettings {
+        LiveSettings {
+            request_interval_ms: self.request_limiter.per_host_interval().map(|interval| interval.as_millis() as u64),
+            rate_limit_rules: self
+                .request_limiter
+                .rules()
+                .iter()
+                .map(|rule| LiveRateLimitRule { pattern: rule.pattern.as_str().to_string(), interval_ms: rule.min_interval.map(|interval| interval.as_millis() as u64) })
+                .collect(),
+            bandwidth_limit: self.bandwidth_limiter.global_limit(),
+            per_host_bandwidth_limit: self.bandwidth_limiter.per_host_limit(),
+            allowed_domains: self.domain_filters.allowed().map(|list| list.patterns()),
+            denied_domains: self.domain_filters.denied().map(|list| list.patterns()),
+        }
+    }
+}
+
+/// Watches a reload file and applies it to a `LiveConfig` whenever it changes.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::{run_with_config, ConfigWatcher, ScraperConfig};
+/// # use reqwest::Client;
+/// # use std::time::Duration;
+/// # async fn example(client: Client) {
+/// let config = ScraperConfig::default();
+/// let watcher = ConfigWatcher::new("live.json", config.live_config()).spawn(Duration::from_secs(5));
+/// run_with_config("https://example.com", &client, Some(&config)).await;
+/// watcher.abort();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    path: PathBuf,
+    live: LiveConfig,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new<P: AsRef<Path>>(path: P, live: LiveConfig) -> Self {
+        ConfigWatcher { path: path.as_ref().to_path_buf(), live, modified: None }
+    }
+
+    /// Applies the file if it was modified since the last poll, returning whether it was applied.
+    /// A file that cannot be parsed is reported and leaves the settings as they were.
+    pub fn poll(&mut self) -> IoResult<bool> {
+        let modified = metadata(&self.path)?.modified()?;
+        if self.modified == Some(modified) {
+            return Ok(false);
+        }
+        let settings = LiveSettings::parse(&read_to_string(&self.path)?)?;
+        self.live.apply(&settings);
+        self.modified = Some(modified);
+        Ok(true)
+    }
+
+    /// Polls the file every `interval` on a background task until the task is aborted.
+    pub fn spawn(mut self, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.poll() {
+                    console_error!("Failed to reload settings from '{}': {}", self.path.display(), e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_changes_a_running_config() {
+        let mut config = ScraperConfig::default();
+        config.set_per_host_request_interval(Some(Duration::from_secs(2)));
+        config.add_rate_limit_rule("/api/*", Some(Duration::from_secs(5)));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("live.json");
+        std::fs::write(&path, r#"{"request_interval_ms": 250, "rate_limit_rules": [{"pattern": "*.png"}], "denied_domains": ["ads.example.com"]}"#).unwrap();
+
+        let mut watcher = ConfigWatcher::new(&path, config.live_config());
+        assert!(watcher.poll().unwrap());
+        assert!(!watcher.poll().unwrap());
+
+        assert_eq!(config.request_limiter().per_host_interval(), Some(Duration::from_millis(250)));
+        let rules = config.request_limiter().rules();
+        assert_eq!((rules.len(), rules[0].pattern.as_str(), rules[0].min_interval), (1, "*.png", None));
+        assert!(!config.is_url_allowed("https://ads.example.com/banner"));
+        assert!(config.is_url_allowed("https://example.com/"));
+        assert_eq!(config.live_config().settings().denied_domains, Some(vec!["ads.example.com".to_string()]));
+
+        std::fs::write(&path, "{ not json").unwrap();
+        let mut broken = ConfigWatcher::new(&path, config.live_config());
+        assert!(broken.poll().is_err());
+        assert!(!config.is_url_allowed("https://ads.example.com/banner"));
+    }
+}
@@ -0,0 +1,95 @@
+// src/render.rs
+
+use std::future::Future;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// The future a `PageRenderer` returns: the rendered HTML, or why rendering failed.
+pub type RenderFuture<'a> = Pin<Box<dyn Future<Output = Result<String, String>> + Send + 'a>>;
+
+/// A rendering backend, e.g. a headless browser, used to retry pages a static fetch only got a
+/// bot wall for (see `detect_bot_wall` and `ScraperConfig::set_page_renderer`).
+pub trait PageRenderer: Send + Sync {
+    fn render<'a>(&'a self, url: &'a str) -> RenderFuture<'a>;
+}
+
+/// Renders pages by running a command that prints the rendered HTML, such as
+/// `chromium --headless --dump-dom {url}`. Every `{url}` argument is replaced with the page URL;
+/// without one, the URL is passed as the last argument.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::{CommandRenderer, ScraperConfig};
+/// # use std::sync::Arc;
+/// # use std::time::Duration;
+/// let renderer = CommandRenderer::new("chromium", &["--headless", "--disable-gpu", "--dump-dom", "{url}"]).timeout(Duration::from_secs(30));
+/// let mut config = ScraperConfig::default();
+/// config.set_page_renderer(Some(Arc::new(renderer)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandRenderer {
+    program: String,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+impl CommandRenderer {
+    pub fn new(program: &str, args: &[&str]) -> Self {
+        CommandRenderer { program: program.to_string(), args: args.iter().map(|arg| arg.to_string()).collect(), timeout: Duration::from_secs(60) }
+    }
+
+    /// Kills the command when it runs longer than `timeout` (one minute by default).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // Returns the arguments for rendering `url`
+    fn args_for(&self, url: &str) -> Vec<String> {
+        let mut args: Vec<String> = self.args.iter().map(|arg| arg.replace("{url}", url)).collect();
+        if !self.args.iter().any(|arg| arg.contains("{url}")) {
+            args.push(url.to_string());
+        }
+        args
+    }
+}
+
+impl PageRenderer for CommandRenderer {
+    fn render<'a>(&'a self, url: &'a str) -> RenderFuture<'a> {
+        Box::pin(async move {
+            let child = Command::new(&self.program)
+                .args(self.args_for(url))
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| format!("failed to run '{}': {}", self.program, e))?;
+            let output = match tokio::time::timeout(self.timeout, child.wait_with_output()).await {
+                Ok(output) => output.map_err(|e| e.to_string())?,
+                Err(_) => return Err(format!("'{}' timed out after {:?}", self.program, self.timeout)),
+            };
+            if !output.status.success() {
+                return Err(format!("'{}' exited with {}: {}", self.program, output.status, String::from_utf8_lossy(&output.stderr).trim()));
+            }
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_command_renderer_prints_the_page() {
+        let renderer = CommandRenderer::new("echo", &["<p>{url}</p>"]);
+        assert_eq!(renderer.render("https://a.test/").await.unwrap(), "<p>https://a.test/</p>\n");
+        assert_eq!(CommandRenderer::new("echo", &["-n"]).render("https://a.test/").await.unwrap(), "https://a.test/");
+        assert!(CommandRenderer::new("false", &[]).render("https://a.test/").await.is_err());
+        assert!(CommandRenderer::new("sleep", &[]).timeout(Duration::from_millis(50)).render("5").await.unwrap_err().contains("timed out"));
+    }
+}
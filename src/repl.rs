@@ -0,0 +1,353 @@
+// src/repl.rs
+
+use reqwest::Url;
+use std::fs::read_to_string;
+use std::io::{BufRead, Result as IoResult, Write};
+
+use crate::{finish_crawl, page_file, run, queue_links, read_findings, CrawlSummary, FindingKind, Frontier, FrontierEntry, PageClass, Scraper, UrlCandidate};
+
+/// What one step of a `CrawlSession` scraped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageResult {
+    pub url: String,
+    pub depth: i32,
+    pub links: Vec<String>,     // Every link found on the page, followed or not
+    pub class: Option<PageClass>,   // `None` for pages that were not classified, e.g. JSON captures
+}
+
+/// The outcome of stepping a `CrawlSession`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepOutcome {
+    Scraped(PageResult),
+    Skipped(String),    // Visited already, out of scope, over quota, circuit open, or a duplicate
+    Done,               // The frontier is empty
+}
+
+/// A crawl driven one page at a time, for exploring a site before writing a full config.
+///
+/// The session owns a `Scraper` and its frontier: `next` scrapes the highest-priority queued
+/// URL, `follow` scrapes any URL right away, and `skip_host` drops a host from the rest of the
+/// crawl. The config (domain filters, depth, ...) can be changed between steps through
+/// `scraper_mut().config_mut()`. `run_repl` wraps a session in a line-based command loop.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::{CrawlSession, Scraper, ScraperConfig, StepOutcome};
+/// # use reqwest::Client;
+/// # async fn example() {
+/// let mut session = CrawlSession::new(Scraper::new(Client::new(), ScraperConfig::default()), "https://example.com");
+/// if let StepOutcome::Scraped(page) = session.next().await {
+///     println!("{} links on {}", page.links.len(), page.url);
+/// }
+/// session.skip_host("ads.example.com");
+/// let summary = session.finish().await;
+/// # }
+/// ```
+pub struct CrawlSession {
+    scraper: Scraper,
+    frontier: Frontier,
+    last_page: Option<PageResult>,
+}
+
+impl CrawlSession {
+    /// Starts a session that crawls from `seed`.
+    pub fn new(scraper: Scraper, seed: &str) -> Self {
+        let mut session = CrawlSession { scraper, frontier: Frontier::new(), last_page: None };
+        session.scraper.start_session();
+        let candidate = UrlCandidate { url: seed, depth: 0, anchor_text: "", referrer: None, referrer_relevance: 0.0 };
+        let priority = session.scraper.config().url_scorer().score(&candidate);
+        session.frontier.push(seed, 0, priority);
+        session
+    }
+
+    pub fn scraper(&self) -> &Scraper {
+        &self.scraper
+    }
+
+    // Method to adjust the config or inspect the state between steps
+    pub fn scraper_mut(&mut self) -> &mut Scraper {
+        &mut self.scraper
+    }
+
+    pub fn frontier(&self) -> &Frontier {
+        &self.frontier
+    }
+
+    /// Returns the page the last step scraped.
+    pub fn last_page(&self) -> Option<&PageResult> {
+        self.last_page.as_ref()
+    }
+
+    /// Scrapes the next queued URL and queues its links.
+    pub async fn next(&mut self) -> StepOutcome {
+        match self.frontier.pop() {
+            Some(entry) => self.visit(entry).await,
+            None => StepOutcome::Done,
+        }
+    }
+
+    /// Scrapes `url` right away, as the start of a new branch at depth 0, and queues its links.
+    pub async fn follow(&mut self, url: &str) -> StepOutcome {
+        self.visit(FrontierEntry { url: url.to_string(), depth: 0, priority: 0.0 }).await
+    }
+
+    /// Denies `host` for the rest of the crawl and drops its queued URLs.
+    ///
+    /// # Returns
+    ///
+    /// How many queued URLs were dropped.
+    pub fn skip_host(&mut self, host: &str) -> usize {
+        let mut denied = self.scraper.config().denied_domains().map(|list| (*list).clone()).unwrap_or_default();
+        denied.insert(host);
+        self.scraper.config_mut().set_denied_domains(Some(denied));
+        let config = self.scraper.config();
+        self.frontier.retain(|entry| config.is_url_allowed(&entry.url))
+    }
+
+    /// Describes the last scraped page: its links and the text and emails extracted from it.
+    pub fn dump_page(&self) -> Option<String> {
+        let page = self.last_page.as_ref()?;
+        let dir = self.scraper.config().domain_dir(&page.url);
+        let mut dump = format!("{} (depth {})\nLinks ({}):\n", page.url, page.depth, page.links.len());
+        for link in &page.links {
+            dump.push_str(&format!("  {}\n", link));
+        }
+        let findings = read_findings(&self.scraper.config().findings_path()).unwrap_or_default();
+        let emails: Vec<&str> = findings.iter().filter(|finding| finding.kind == FindingKind::Email && finding.url == page.url).map(|finding| finding.evidence.as_str()).collect();
+        if !emails.is_empty() {
+            dump.push_str(&format!("Emails:\n{}\n", emails.join("\n")));
+        }
+        if let Ok(text) = read_to_string(page_file(&dir, "content", &page.url)) {
+            dump.push_str(&format!("Content:\n{}\n", text.trim_end()));
+        }
+        Some(dump)
+    }
+
+    /// Persists the dead letters and other end-of-crawl output, returning the summary so far.
+    pub async fn finish(&mut self) -> CrawlSummary {
+        let session_id = self.scraper.session_id().map(str::to_string);
+        run::in_session(session_id, finish_crawl(self.scraper.client(), self.scraper.config())).await;
+        self.scraper.summary()
+    }
+
+    async fn visit(&mut self, entry: FrontierEntry) -> StepOutcome {
+        let Some(links) = self.scraper.visit(&entry).await else { return StepOutcome::Skipped(entry.url) };
+        let page = PageResult {
+            url: entry.url.clone(),
+            depth: entry.depth,
+            links: links.iter().map(|(link, _)| link.clone()).collect(),
+            class: self.scraper.config().page_classes().class_of(&entry.url),
+        };
+        queue_links(&mut self.frontier, &entry, links, self.scraper.config(), self.scraper.visited());
+        self.last_page = Some(page.clone());
+        StepOutcome::Scraped(page)
+    }
+}
+
+/// A command of the interactive crawl loop, see `run_repl`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplCommand {
+    Next(usize),                // `next [count]`
+    SkipHost(Option<String>),   // `skip host [host]`, the host of the next queued URL by default
+    DumpPage,                   // `dump page`
+    Follow(String),             // `follow <url>`
+    Queue(usize),               // `queue [count]`
+    Allow(String),              // `allow <domain pattern>`
+    Deny(String),               // `deny <domain pattern>`
+    Depth(i32),                 // `depth <max depth>`
+    Stats,
+    Help,
+    Quit,
+}
+
+impl ReplCommand {
+    /// Parses one input line.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use knee_scraper::ReplCommand;
+    /// assert_eq!(ReplCommand::parse("next 3"), Ok(ReplCommand::Next(3)));
+    /// assert_eq!(ReplCommand::parse("skip host ads.test"), Ok(ReplCommand::SkipHost(Some("ads.test".to_string()))));
+    /// assert!(ReplCommand::parse("follow").is_err());
+    /// ```
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let count = |word: &str| word.parse().map_err(|_| format!("Not a count: {}", word));
+        match words.as_slice() {
+            ["next" | "n"] => Ok(ReplCommand::Next(1)),
+            ["next" | "n", n] => count(n).map(ReplCommand::Next),
+            ["skip", "host"] => Ok(ReplCommand::SkipHost(None)),
+            ["skip", "host", host] => Ok(ReplCommand::SkipHost(Some(host.to_string()))),
+            ["dump", "page"] | ["dump"] => Ok(ReplCommand::DumpPage),
+            ["follow", url] => Ok(ReplCommand::Follow(url.to_string())),
+            ["queue"] => Ok(ReplCommand::Queue(10)),
+            ["queue", n] => count(n).map(ReplCommand::Queue),
+            ["allow", pattern] => Ok(ReplCommand::Allow(pattern.to_string())),
+            ["deny", pattern] => Ok(ReplCommand::Deny(pattern.to_string())),
+            ["depth", depth] => depth.parse().map(ReplCommand::Depth).map_err(|_| format!("Not a depth: {}", depth)),
+            ["stats"] => Ok(ReplCommand::Stats),
+            ["help" | "?"] => Ok(ReplCommand::Help),
+            ["quit" | "exit" | "q"] => Ok(ReplCommand::Quit),
+            _ => Err(format!("Unknown command: {} (type `help`)", line.trim())),
+        }
+    }
+}
+
+const HELP: &str = "\
+next [n]            scrape the next n queued URLs (default 1)
+skip host [host]    drop a host from the crawl (default: the host of the next URL)
+dump page           show the links, emails and text of the last scraped page
+follow <url>        scrape a URL right away
+queue [n]           list the next n queued URLs (default 10)
+allow <pattern>     add a domain pattern to the allowlist
+deny <pattern>      add a domain pattern to the denylist
+depth <n>           change the max depth
+stats               show the crawl summary so far
+quit                finish the crawl and leave";
+
+/// Runs an interactive crawl: reads commands from `input` one line at a time (see `ReplCommand`)
+/// and writes their results to `output`, until `quit` or the end of the input.
+///
+/// # Arguments
+///
+/// * `session` - The session to step.
+/// * `input` - Where commands are read from, e.g. `std::io::stdin().lock()`.
+/// * `output` - Where prompts and results are written, e.g. `std::io::stdout()`.
+///
+/// # Returns
+///
+/// The `CrawlSummary` of the session, after its end-of-crawl output has been written.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::{run_repl, CrawlSession, Scraper, ScraperConfig};
+/// # use reqwest::Client;
+/// # async fn example() -> std::io::Result<()> {
+/// let scraper = Scraper::new(Client::new(), ScraperConfig::default());
+/// let mut session = CrawlSession::new(scraper, "https://example.com");
+/// let summary = run_repl(&mut session, std::io::stdin().lock(), &mut std::io::stdout()).await?;
+/// println!("{}", summary);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn run_repl<R: BufRead, W: Write>(session: &mut CrawlSession, input: R, output: &mut W) -> IoResult<CrawlSummary> {
+    let mut lines = input.lines();
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+        let Some(line) = lines.next().transpose()? else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let command = match ReplCommand::parse(&line) {
+            Ok(command) => command,
+            Err(e) => {
+                writeln!(output, "{}", e)?;
+                continue;
+            }
+        };
+        match command {
+            ReplCommand::Next(count) => {
+                for _ in 0..count {
+                    let outcome = session.next().await;
+                    write_outcome(output, &outcome)?;
+                    if outcome == StepOutcome::Done {
+                        break;
+                    }
+                }
+            }
+            ReplCommand::SkipHost(host) => {
+                let host = host.or_else(|| session.frontier().peek().and_then(|entry| url_host(&entry.url)));
+                match host {
+                    Some(host) => writeln!(output, "Skipping {} ({} queued URLs dropped)", host, session.skip_host(&host))?,
+                    None => writeln!(output, "Nothing queued")?,
+                }
+            }
+            ReplCommand::DumpPage => writeln!(output, "{}", session.dump_page().unwrap_or_else(|| "No page scraped yet".to_string()))?,
+            ReplCommand::Follow(url) => write_outcome(output, &session.follow(&url).await)?,
+            ReplCommand::Queue(count) => {
+                let entries = session.frontier().entries();
+                writeln!(output, "{} queued", entries.len())?;
+                for entry in entries.into_iter().take(count) {
+                    writeln!(output, "  {} (depth {}, priority {:.2})", entry.url, entry.depth, entry.priority)?;
+                }
+            }
+            ReplCommand::Allow(pattern) => {
+                let config = session.scraper_mut().config_mut();
+                let mut allowed = config.allowed_domains().map(|list| (*list).clone()).unwrap_or_default();
+                allowed.insert(&pattern);
+                config.set_allowed_domains(Some(allowed));
+                writeln!(output, "Allowing {}", pattern)?;
+            }
+            ReplCommand::Deny(pattern) => {
+                let config = session.scraper_mut().config_mut();
+                let mut denied = config.denied_domains().map(|list| (*list).clone()).unwrap_or_default();
+                denied.insert(&pattern);
+                config.set_denied_domains(Some(denied));
+                writeln!(output, "Denying {}", pattern)?;
+            }
+            ReplCommand::Depth(depth) => {
+                session.scraper_mut().config_mut().set_max_depth(depth);
+                writeln!(output, "Max depth is now {}", depth)?;
+            }
+            ReplCommand::Stats => writeln!(output, "{}", session.scraper().summary())?,
+            ReplCommand::Help => writeln!(output, "{}", HELP)?,
+            ReplCommand::Quit => break,
+        }
+    }
+    Ok(session.finish().await)
+}
+
+// Prints the result of one step
+fn write_outcome<W: Write>(output: &mut W, outcome: &StepOutcome) -> IoResult<()> {
+    match outcome {
+        StepOutcome::Scraped(page) => writeln!(output, "Scraped {} (depth {}, {} links)", page.url, page.depth, page.links.len()),
+        StepOutcome::Skipped(url) => writeln!(output, "Skipped {}", url),
+        StepOutcome::Done => writeln!(output, "Nothing left to crawl"),
+    }
+}
+
+fn url_host(url: &str) -> Option<String> {
+    Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{http_response, serve};
+    use crate::ScraperConfig;
+    use reqwest::Client;
+
+    #[tokio::test]
+    async fn test_repl_steps_and_skips_hosts() {
+        let base = serve(|request| {
+            let port = request.to_ascii_lowercase().lines().find_map(|line| line.strip_prefix("host: 127.0.0.1:")).unwrap_or("").trim().to_string();
+            let body = format!("<p>Write to ops@a.test</p><a href='/next'>next</a><a href='http://localhost:{}/other'>other</a>", port);
+            http_response("200 OK", &[], body.as_bytes())
+        })
+        .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 3, None);
+        config.set_output_dir(temp_dir.path());
+        let mut session = CrawlSession::new(Scraper::new(Client::new(), config), &base);
+
+        let input = "next\ndump page\nbogus\nskip host localhost\nqueue\nnext 5\nquit\nnext\n";
+        let mut output = Vec::new();
+        let summary = run_repl(&mut session, input.as_bytes(), &mut output).await.unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains(&format!("Scraped {} (depth 0, 2 links)", base)));
+        assert!(output.contains("Emails:\nops@a.test"));
+        assert!(output.contains("Unknown command: bogus"));
+        assert!(output.contains("Skipping localhost (1 queued URLs dropped)"));
+        assert!(output.contains("1 queued\n"));
+        assert!(output.contains("Nothing left to crawl"));
+        assert_eq!(summary.pages_fetched, 2);
+        assert!(summary.session_id.is_some() && summary.session_id.as_deref() == session.scraper().session_id());
+        assert!(!session.scraper().config().is_url_allowed("http://localhost/"));
+    }
+}
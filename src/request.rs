@@ -0,0 +1,118 @@
+// src/request.rs
+
+use reqwest::header::USER_AGENT;
+use reqwest::{Client, Method, RequestBuilder, Response, Url};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::{page, random_user_agent, ScraperConfig};
+
+/// Why a request of a crawl was not answered.
+#[derive(Debug)]
+pub(crate) enum RequestError {
+    OutOfScope,                 // The host is outside the domain lists of the config
+    QuotaExhausted(String),     // The host used up its byte quota
+    Failed(reqwest::Error),     // Building or sending the request failed
+}
+
+impl RequestError {
+    // Returns whether the request was never sent, so retrying it is pointless
+    pub(crate) fn is_skipped(&self) -> bool {
+        !matches!(self, RequestError::Failed(_))
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RequestError::OutOfScope => write!(f, "host out of scope"),
+            RequestError::QuotaExhausted(host) => write!(f, "byte quota of {} exhausted", host),
+            RequestError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// Returns the host of a URL, or an empty string when it has none
+pub(crate) fn host_of(url: &str) -> String {
+    Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string)).unwrap_or_default()
+}
+
+// Sends a request of a crawl, the way every request of a crawl is sent: only to hosts in scope with
+// byte quota left, paced by the request limiter, through the TLS client of the host, with the user
+// agent and request rules of the config, and counted in the traffic of the host. `build` adds the
+// method-specific parts of the request (headers, query, body)
+pub(crate) async fn send(
+    client: &Client,
+    method: Method,
+    url: &str,
+    config: &ScraperConfig,
+    build: impl FnOnce(RequestBuilder) -> RequestBuilder,
+) -> Result<Response, RequestError> {
+    send_timed(client, method, url, config, build).await.0
+}
+
+// Sends a request like `send`, also returning how long the server took to answer (zero when it was not sent)
+pub(crate) async fn send_timed(
+    client: &Client,
+    method: Method,
+    url: &str,
+    config: &ScraperConfig,
+    build: impl FnOnce(RequestBuilder) -> RequestBuilder,
+) -> (Result<Response, RequestError>, Duration) {
+    if !config.is_url_allowed(url) {
+        return (Err(RequestError::OutOfScope), Duration::ZERO);
+    }
+    let host = host_of(url);
+    if config.host_quota_exhausted(&host) {
+        return (Err(RequestError::QuotaExhausted(host)), Duration::ZERO);
+    }
+
+    config.request_limiter().wait(url).await;
+    let client = config.tls_policy().client_for(client, url);
+    let user_agent = config.user_agent().cloned().unwrap_or_else(random_user_agent);
+    let mut request = match build(client.request(method, url).header(USER_AGENT, user_agent)).build() {
+        Ok(request) => request,
+        Err(e) => return (Err(RequestError::Failed(e)), Duration::ZERO),
+    };
+    config.request_rules().apply(&mut request);
+    config.metrics().record_traffic(&host, page::request_size(&request), 0);
+
+    let started = Instant::now();
+    let result = client.execute(request).await;
+    let elapsed = started.elapsed();
+    if let Ok(response) = &result {
+        config.metrics().record_traffic(&host, 0, page::response_head_size(response));
+    }
+    (result.map_err(RequestError::Failed), elapsed)
+}
+
+// Reads a response body like `page::read_body_capped`, counting the bytes read in the traffic of its host
+pub(crate) async fn read_body(response: Response, max_bytes: u64, config: &ScraperConfig) -> Result<Option<Vec<u8>>, reqwest::Error> {
+    let host = response.url().host_str().unwrap_or_default().to_string();
+    page::read_body_capped(response, max_bytes, |received| config.metrics().record_traffic(&host, 0, received)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{http_response, serve};
+    use crate::DomainList;
+
+    #[tokio::test]
+    async fn test_requests_stay_in_scope_and_quota() {
+        let base = serve(|request| {
+            let agent = request.lines().find_map(|line| line.strip_prefix("user-agent: ")).unwrap_or("none").to_string();
+            http_response("200 OK", &[], agent.as_bytes())
+        })
+        .await;
+        let mut config = ScraperConfig::new(true, 1, Some("scope-test".to_string()));
+        config.set_host_byte_quota(Some(1_000));
+
+        let response = send(&Client::new(), Method::GET, &base, &config, |request| request).await.unwrap();
+        assert_eq!(read_body(response, 1_000, &config).await.unwrap().unwrap(), b"scope-test");
+        assert!(config.metrics().traffic("127.0.0.1").received > 0);
+
+        config.set_denied_domains(Some(DomainList::parse("127.0.0.1")));
+        assert!(matches!(send(&Client::new(), Method::GET, &base, &config, |request| request).await, Err(RequestError::OutOfScope)));
+    }
+}
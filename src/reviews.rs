@@ -0,0 +1,337 @@
+// src/reviews.rs
+
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use serde_json::Value;
+
+/// A single review or user comment extracted from a page.
+///
+/// Every field is optional because review widgets rarely expose all of them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Review {
+    pub author: Option<String>,
+    pub rating: Option<f32>,
+    pub date: Option<String>,
+    pub text: Option<String>,
+}
+
+/// The overall rating summary of a page, as published through schema.org `AggregateRating`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AggregateRating {
+    pub rating_value: Option<f32>,
+    pub best_rating: Option<f32>,
+    pub review_count: Option<u32>,
+}
+
+/// Extracts reviews and comments from an HTML page.
+///
+/// Three sources are inspected, in order: schema.org `Review` objects in JSON-LD
+/// scripts, schema.org `Review` microdata, and common comment list markup
+/// (`.comment`, `.review`, WordPress-style `.comment-author` / `.comment-content`).
+/// Reviews found by more than one source are only returned once.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+///
+/// # Returns
+///
+/// A `Vec<Review>` with one entry per review or comment found.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::extract_reviews;
+/// let html = r#"<div class="comment"><span class="author">Ann</span><p>Great post!</p></div>"#;
+/// let reviews = extract_reviews(html);
+/// assert_eq!(reviews[0].author.as_deref(), Some("Ann"));
+/// ```
+pub fn extract_reviews(html: &str) -> Vec<Review> {
+    let document = Html::parse_document(html);
+    let mut reviews = Vec::new();
+
+    for value in json_ld_values(&document) {
+        collect_json_ld_reviews(&value, &mut reviews);
+    }
+    collect_microdata_reviews(&document, &mut reviews);
+    collect_comment_widgets(&document, &mut reviews);
+
+    let mut unique: Vec<Review> = Vec::new();
+    for review in reviews {
+        if !unique.contains(&review) {
+            unique.push(review);
+        }
+    }
+    unique
+}
+
+/// Extracts the schema.org `AggregateRating` of a page from JSON-LD or microdata.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page as a string slice.
+///
+/// # Returns
+///
+/// `Some(AggregateRating)` if the page publishes one, otherwise `None`.
+pub fn extract_aggregate_rating(html: &str) -> Option<AggregateRating> {
+    let document = Html::parse_document(html);
+
+    for value in json_ld_values(&document) {
+        if let Some(rating) = find_json_ld_aggregate(&value) {
+            return Some(rating);
+        }
+    }
+
+    let selector = Selector::parse(r#"[itemtype*="schema.org/AggregateRating"]"#).unwrap();
+    document.select(&selector).next().map(|item| AggregateRating {
+        rating_value: itemprop(item, "ratingValue").and_then(|v| parse_number(&v)),
+        best_rating: itemprop(item, "bestRating").and_then(|v| parse_number(&v)),
+        review_count: itemprop(item, "reviewCount")
+            .or_else(|| itemprop(item, "ratingCount"))
+            .and_then(|v| parse_number(&v))
+            .map(|count| count as u32),
+    })
+}
+
+// Parses every JSON-LD script block, skipping the ones that are not valid JSON
+fn json_ld_values(document: &Html) -> Vec<Value> {
+    let selector = Selector::parse(r#"script[type="application/ld+json"]"#).unwrap();
+    document
+        .select(&selector)
+        .filter_map(|script| serde_json::from_str(&script.inner_html()).ok())
+        .collect()
+}
+
+// Checks whether a JSON-LD object declares the given `@type` (which may be a string or an array)
+fn has_json_ld_type(object: &serde_json::Map<String, Value>, wanted: &str) -> bool {
+    match object.get("@type") {
+        Some(Value::String(kind)) => kind == wanted,
+        Some(Value::Array(kinds)) => kinds.iter().any(|kind| kind.as_str() == Some(wanted)),
+        _ => false,
+    }
+}
+
+// Walks a JSON-LD value recursively, collecting every object typed as `Review`
+fn collect_json_ld_reviews(value: &Value, reviews: &mut Vec<Review>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                collect_json_ld_reviews(item, reviews);
+            }
+        }
+        Value::Object(object) => {
+            if has_json_ld_type(object, "Review") {
+                reviews.push(Review {
+                    author: object.get("author").and_then(json_ld_name),
+                    rating: object
+                        .get("reviewRating")
+                        .and_then(|rating| rating.get("ratingValue"))
+                        .and_then(json_ld_number),
+                    date: object.get("datePublished").and_then(json_ld_text),
+                    text: object
+                        .get("reviewBody")
+                        .or_else(|| object.get("description"))
+                        .and_then(json_ld_text),
+                });
+            }
+            for child in object.values() {
+                collect_json_ld_reviews(child, reviews);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Finds the first `AggregateRating` object anywhere inside a JSON-LD value
+fn find_json_ld_aggregate(value: &Value) -> Option<AggregateRating> {
+    match value {
+        Value::Array(items) => items.iter().find_map(find_json_ld_aggregate),
+        Value::Object(object) => {
+            if has_json_ld_type(object, "AggregateRating") {
+                return Some(AggregateRating {
+                    rating_value: object.get("ratingValue").and_then(json_ld_number),
+                    best_rating: object.get("bestRating").and_then(json_ld_number),
+                    review_count: object
+                        .get("reviewCount")
+                        .or_else(|| object.get("ratingCount"))
+                        .and_then(json_ld_number)
+                        .map(|count| count as u32),
+                });
+            }
+            object.values().find_map(find_json_ld_aggregate)
+        }
+        _ => None,
+    }
+}
+
+// Authors are either a plain string or a `Person`/`Organization` object with a `name`
+fn json_ld_name(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(object) => object.get("name").and_then(json_ld_text),
+        Value::Array(items) => items.first().and_then(json_ld_name),
+        other => json_ld_text(other),
+    }
+}
+
+fn json_ld_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(clean_text(text)).filter(|text| !text.is_empty()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+// Ratings are published both as numbers and as strings such as "4.5"
+fn json_ld_number(value: &Value) -> Option<f32> {
+    match value {
+        Value::Number(number) => number.as_f64().map(|n| n as f32),
+        Value::String(text) => parse_number(text),
+        _ => None,
+    }
+}
+
+fn collect_microdata_reviews(document: &Html, reviews: &mut Vec<Review>) {
+    let selector = Selector::parse(r#"[itemtype*="schema.org/Review"]"#).unwrap();
+    for item in document.select(&selector) {
+        let author = item
+            .select(&Selector::parse(r#"[itemprop="author"]"#).unwrap())
+            .next()
+            .and_then(|author| itemprop(author, "name").or_else(|| element_value(author)));
+
+        reviews.push(Review {
+            author,
+            rating: itemprop(item, "ratingValue").and_then(|v| parse_number(&v)),
+            date: itemprop(item, "datePublished"),
+            text: itemprop(item, "reviewBody").or_else(|| itemprop(item, "description")),
+        });
+    }
+}
+
+fn collect_comment_widgets(document: &Html, reviews: &mut Vec<Review>) {
+    let item_selector = Selector::parse(".comment, .review, .user-review, .customer-review").unwrap();
+    let author_selector =
+        Selector::parse(".comment-author .fn, .comment-author, .review-author, .author, .byline, [rel=\"author\"]").unwrap();
+    let date_selector = Selector::parse("time[datetime], .comment-date, .review-date, .date").unwrap();
+    let text_selector = Selector::parse(
+        ".comment-content, .comment-body, .comment-text, .review-text, .review-body, .review-content, p",
+    )
+    .unwrap();
+    let rating_selector = Selector::parse("[data-rating], .rating, .stars, .star-rating").unwrap();
+
+    for item in document.select(&item_selector) {
+        // Microdata reviews are already captured with their structured fields
+        if item.value().attr("itemtype").is_some() {
+            continue;
+        }
+
+        let author = item.select(&author_selector).next().and_then(element_text);
+        let text = item.select(&text_selector).next().and_then(element_text);
+        if author.is_none() && text.is_none() {
+            continue;
+        }
+
+        let date = item.select(&date_selector).next().and_then(|date| {
+            date.value().attr("datetime").map(|d| d.to_string()).or_else(|| element_text(date))
+        });
+        let rating = item.select(&rating_selector).next().and_then(|rating| {
+            rating
+                .value()
+                .attr("data-rating")
+                .or_else(|| rating.value().attr("aria-label"))
+                .and_then(parse_number)
+                .or_else(|| element_text(rating).and_then(|text| parse_number(&text)))
+        });
+
+        reviews.push(Review { author, rating, date, text });
+    }
+}
+
+// Reads the value of the first descendant carrying the given `itemprop`
+fn itemprop(item: ElementRef, name: &str) -> Option<String> {
+    let selector = Selector::parse(&format!(r#"[itemprop="{}"]"#, name)).ok()?;
+    item.select(&selector).next().and_then(element_value)
+}
+
+// Microdata values live in `content`/`datetime` attributes when present, otherwise in the text
+fn element_value(element: ElementRef) -> Option<String> {
+    element
+        .value()
+        .attr("content")
+        .or_else(|| element.value().attr("datetime"))
+        .map(clean_text)
+        .filter(|value| !value.is_empty())
+        .or_else(|| element_text(element))
+}
+
+fn element_text(element: ElementRef) -> Option<String> {
+    Some(clean_text(&element.text().collect::<Vec<_>>().join(" "))).filter(|text| !text.is_empty())
+}
+
+// Collapses whitespace runs left over from the HTML layout
+fn clean_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Parses the first number in a string, e.g. "4.5", "4 out of 5 stars" or "Rated 3/5"
+fn parse_number(text: &str) -> Option<f32> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let number: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    number.trim_end_matches('.').parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_json_ld_reviews_and_aggregate() {
+        let html = r#"<script type="application/ld+json">
+            {"@context": "https://schema.org", "@type": "Product", "name": "Knee brace",
+             "aggregateRating": {"@type": "AggregateRating", "ratingValue": "4.4", "reviewCount": 89},
+             "review": [{"@type": "Review", "author": {"@type": "Person", "name": "Jo"},
+                         "datePublished": "2024-09-01", "reviewBody": "Fits well.",
+                         "reviewRating": {"@type": "Rating", "ratingValue": 5}}]}
+        </script>"#;
+
+        let reviews = extract_reviews(html);
+        assert_eq!(reviews.len(), 1);
+        assert_eq!(reviews[0].author.as_deref(), Some("Jo"));
+        assert_eq!(reviews[0].rating, Some(5.0));
+        assert_eq!(reviews[0].date.as_deref(), Some("2024-09-01"));
+        assert_eq!(reviews[0].text.as_deref(), Some("Fits well."));
+
+        let aggregate = extract_aggregate_rating(html).unwrap();
+        assert_eq!(aggregate.rating_value, Some(4.4));
+        assert_eq!(aggregate.review_count, Some(89));
+    }
+
+    #[test]
+    fn test_extract_microdata_and_comment_reviews() {
+        let html = r#"
+            <div itemscope itemtype="https://schema.org/Review">
+                <span itemprop="author" itemscope itemtype="https://schema.org/Person"><span itemprop="name">Sam</span></span>
+                <meta itemprop="datePublished" content="2024-10-02">
+                <div itemprop="reviewRating" itemscope itemtype="https://schema.org/Rating"><meta itemprop="ratingValue" content="3"></div>
+                <p itemprop="reviewBody">Average.</p>
+            </div>
+            <ol>
+                <li class="comment">
+                    <div class="comment-author"><b class="fn">Kim</b></div>
+                    <time datetime="2024-10-03T10:00:00Z">Oct 3</time>
+                    <div class="comment-content"><p>Thanks   for sharing!</p></div>
+                </li>
+            </ol>"#;
+
+        let reviews = extract_reviews(html);
+        assert_eq!(reviews.len(), 2);
+        assert_eq!(reviews[0].author.as_deref(), Some("Sam"));
+        assert_eq!(reviews[0].rating, Some(3.0));
+        assert_eq!(reviews[1].author.as_deref(), Some("Kim"));
+        assert_eq!(reviews[1].date.as_deref(), Some("2024-10-03T10:00:00Z"));
+        assert_eq!(reviews[1].text.as_deref(), Some("Thanks for sharing!"));
+    }
+}
@@ -0,0 +1,333 @@
+// src/robots.rs
+
+use regex::Regex;
+use reqwest::{header, Client, Method, Url};
+use std::collections::HashSet;
+
+use crate::{page, request, ScraperConfig};
+
+/// The parts of a `robots.txt` file the scraper acts on.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotsInfo {
+    pub disallowed: Vec<String>,
+    pub allowed: Vec<String>,
+    pub sitemaps: Vec<String>,      // Absolute sitemap URLs from `Sitemap:` lines
+    pub crawl_delay: Option<f64>,   // Seconds
+}
+
+impl RobotsInfo {
+    /// Returns whether the rules allow crawling a URL path (with its query): the longest matching
+    /// rule wins, and `Allow` wins ties. Rules may use `*` wildcards and a trailing `$` anchor.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use knee_scraper::parse_robots_txt;
+    /// let robots = parse_robots_txt("Disallow: /private\nAllow: /private/press\nDisallow: /*.pdf$\n");
+    /// assert!(!robots.is_allowed("/private/staff"));
+    /// assert!(robots.is_allowed("/private/press/2024"));
+    /// assert!(!robots.is_allowed("/docs/a.pdf") && robots.is_allowed("/docs/a.pdf?inline"));
+    /// ```
+    pub fn is_allowed(&self, path: &str) -> bool {
+        let longest = |rules: &[String]| rules.iter().filter(|rule| rule_matches(rule, path)).map(String::len).max();
+        match (longest(&self.allowed), longest(&self.disallowed)) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(allow), Some(disallow)) => allow >= disallow,
+        }
+    }
+}
+
+// Matches a robots.txt path rule against a path, honoring `*` and a trailing `$`
+fn rule_matches(rule: &str, path: &str) -> bool {
+    let (rule, anchored) = match rule.strip_suffix('$') {
+        Some(rule) => (rule, true),
+        None => (rule, false),
+    };
+    let mut parts = rule.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or("")) else { return false };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        let last = i == parts.len() - 1;
+        if last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// Parses the body of a `robots.txt` file for crawlers without a name of their own.
+///
+/// Only the rules of the `*` user-agent group apply; see `parse_robots_txt_for`.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::parse_robots_txt;
+/// let robots = parse_robots_txt("User-agent: *\nDisallow: /admin\nSitemap: https://example.com/sitemap.xml\n");
+/// assert_eq!(robots.disallowed, vec!["/admin"]);
+/// assert_eq!(robots.sitemaps, vec!["https://example.com/sitemap.xml"]);
+/// ```
+pub fn parse_robots_txt(body: &str) -> RobotsInfo {
+    parse_robots_txt_for(body, "*")
+}
+
+/// Parses the body of a `robots.txt` file for the crawler sending a user agent (RFC 9309).
+///
+/// The rules of the groups naming the product token of the user agent (case-insensitively) apply,
+/// falling back to the `*` groups when no group names it. Rules before the first `User-agent:` line
+/// count as `*` rules. `Sitemap:` lines apply to the whole file wherever they appear.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::parse_robots_txt_for;
+/// let body = "User-agent: *\nDisallow: /\n\nUser-agent: knee-bot\nDisallow: /admin\n";
+/// assert_eq!(parse_robots_txt_for(body, "Knee-Bot/1.0").disallowed, vec!["/admin"]);
+/// assert_eq!(parse_robots_txt_for(body, "other-bot/2.0").disallowed, vec!["/"]);
+/// ```
+pub fn parse_robots_txt_for(body: &str, user_agent: &str) -> RobotsInfo {
+    let product = user_agent.split(|c: char| c == '/' || c.is_whitespace()).next().unwrap_or("").to_ascii_lowercase();
+    let (mut named, mut wildcard) = (RobotsInfo::default(), RobotsInfo::default());
+    let mut named_found = false;
+    let mut sitemaps: Vec<String> = Vec::new();
+
+    // A group is a run of `User-agent:` lines followed by its rules
+    let mut agents = vec!["*".to_string()];
+    let mut in_rules = true;
+    for line in body.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((field, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+
+        let field = field.trim().to_ascii_lowercase();
+        match field.as_str() {
+            "user-agent" => {
+                if in_rules {
+                    agents.clear();
+                    in_rules = false;
+                }
+                let agent = value.to_ascii_lowercase();
+                named_found |= !product.is_empty() && agent == product;
+                agents.push(agent);
+            }
+            "sitemap" if !value.is_empty() && !sitemaps.iter().any(|known| known == value) => {
+                sitemaps.push(value.to_string());
+            }
+            "disallow" | "allow" | "crawl-delay" => {
+                in_rules = true;
+                let targets = [
+                    (agents.iter().any(|agent| !product.is_empty() && *agent == product), &mut named),
+                    (agents.iter().any(|agent| agent == "*"), &mut wildcard),
+                ];
+                for (applies, robots) in targets {
+                    if !applies {
+                        continue;
+                    }
+                    match field.as_str() {
+                        "disallow" if !value.is_empty() => robots.disallowed.push(value.to_string()),
+                        "allow" if !value.is_empty() => robots.allowed.push(value.to_string()),
+                        // Negative, NaN and infinite delays are no delay at all
+                        "crawl-delay" => robots.crawl_delay = value.parse().ok().filter(|delay: &f64| delay.is_finite() && *delay >= 0.0).or(robots.crawl_delay),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut robots = if named_found { named } else { wildcard };
+    robots.sitemaps = sitemaps;
+    robots
+}
+
+/// The entries of one sitemap document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sitemap {
+    pub urls: Vec<String>,      // Page URLs of a `<urlset>`
+    pub sitemaps: Vec<String>,  // Child sitemaps of a `<sitemapindex>`
+}
+
+/// Parses a sitemap, either a `<urlset>` of pages or a `<sitemapindex>` of further sitemaps.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::parse_sitemap;
+/// let sitemap = parse_sitemap("<urlset><url><loc>https://example.com/a?x=1&amp;y=2</loc></url></urlset>");
+/// assert_eq!(sitemap.urls, vec!["https://example.com/a?x=1&y=2"]);
+/// ```
+pub fn parse_sitemap(xml: &str) -> Sitemap {
+    let loc_regex = Regex::new(r"(?is)<loc>\s*(?:<!\[CDATA\[)?(.*?)(?:\]\]>)?\s*</loc>").unwrap();
+    let locations = loc_regex.captures_iter(xml).map(|captures| unescape_xml(captures[1].trim()));
+
+    let mut sitemap = Sitemap::default();
+    if xml.to_ascii_lowercase().contains("<sitemapindex") {
+        sitemap.sitemaps.extend(locations);
+    } else {
+        sitemap.urls.extend(locations);
+    }
+    sitemap
+}
+
+pub(crate) fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Fetches sitemaps and collects their page URLs, following sitemap indexes.
+///
+/// # Arguments
+///
+/// * `sitemap_urls` - The sitemaps to start from, e.g. `RobotsInfo::sitemaps`.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+/// * `limit` - The maximum number of page URLs to return.
+///
+/// # Returns
+///
+/// The page URLs in sitemap order, without duplicates. Sitemaps that cannot be fetched, or
+/// are compressed, are skipped.
+pub async fn fetch_sitemap_urls(sitemap_urls: &[String], client: &Client, limit: usize) -> Vec<String> {
+    fetch_sitemap_urls_with_config(sitemap_urls, client, limit, &ScraperConfig::default()).await
+}
+
+// Fetches the page URLs of sitemaps like `fetch_sitemap_urls`, through the request layer of the config
+pub(crate) async fn fetch_sitemap_urls_with_config(sitemap_urls: &[String], client: &Client, limit: usize, config: &ScraperConfig) -> Vec<String> {
+    let mut pending: Vec<String> = sitemap_urls.iter().rev().cloned().collect();
+    let mut fetched = HashSet::new();
+    let mut urls = Vec::new();
+    let mut seen = HashSet::new();
+
+    // Indexes are bounded so a misconfigured site cannot keep us fetching sitemaps forever
+    while let Some(sitemap_url) = pending.pop() {
+        if urls.len() >= limit || fetched.len() >= 100 {
+            break;
+        }
+        if !fetched.insert(sitemap_url.clone()) {
+            continue;
+        }
+        if sitemap_url.ends_with(".gz") {
+            console_error!("Skipping compressed sitemap: {}", sitemap_url);
+            continue;
+        }
+
+        let body = match request::send(client, Method::GET, &sitemap_url, config, |request| request).await {
+            Ok(response) if response.status().is_success() => {
+                let content_type = response.headers().get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).map(str::to_string);
+                match request::read_body(response, config.max_page_bytes(), config).await {
+                    Ok(Some(body)) => page::decode_body(&body, content_type.as_deref()),
+                    Ok(None) => {
+                        console_error!("Skipping sitemap larger than {} bytes: {}", config.max_page_bytes(), sitemap_url);
+                        continue;
+                    }
+                    Err(e) => {
+                        console_error!("Failed to read sitemap '{}': {}", sitemap_url, e);
+                        continue;
+                    }
+                }
+            }
+            Ok(response) => {
+                console_error!("Failed to fetch sitemap '{}': HTTP {}", sitemap_url, response.status());
+                continue;
+            }
+            Err(e) => {
+                console_error!("Failed to fetch sitemap '{}': {}", sitemap_url, e);
+                continue;
+            }
+        };
+
+        // Some sites use relative locations even though the protocol requires absolute ones
+        let base = Url::parse(&sitemap_url).ok();
+        let resolve = |location: String| match &base {
+            Some(base) => base.join(&location).map(|url| url.to_string()).unwrap_or(location),
+            None => location,
+        };
+
+        let sitemap = parse_sitemap(&body);
+        pending.extend(sitemap.sitemaps.into_iter().map(resolve).rev());
+        for url in sitemap.urls.into_iter().map(resolve) {
+            if urls.len() < limit && seen.insert(url.clone()) {
+                urls.push(url);
+            }
+        }
+    }
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{http_response, serve};
+
+    #[test]
+    fn test_parse_robots_txt() {
+        let robots = parse_robots_txt(
+            "User-agent: *\nDisallow: /private # staff only\nAllow: /private/press\nDisallow:\nCrawl-delay: 2.5\n\nsitemap: https://a.test/s1.xml\nSITEMAP: https://a.test/s1.xml\n",
+        );
+        assert_eq!(robots.disallowed, vec!["/private"]);
+        assert_eq!(robots.allowed, vec!["/private/press"]);
+        assert_eq!(robots.sitemaps, vec!["https://a.test/s1.xml"]);
+        assert_eq!(robots.crawl_delay, Some(2.5));
+
+        for delay in ["-1", "NaN", "inf", "-inf", "soon"] {
+            assert_eq!(parse_robots_txt(&format!("User-agent: *\nCrawl-delay: {}\n", delay)).crawl_delay, None, "{}", delay);
+        }
+        assert_eq!(parse_robots_txt("User-agent: *\nCrawl-delay: 3\nCrawl-delay: -1\n").crawl_delay, Some(3.0));
+    }
+
+    #[test]
+    fn test_parse_robots_txt_honours_our_group_only() {
+        let body = "User-agent: *\nDisallow: /everyone\nCrawl-delay: 1\n\nUser-agent: other-bot\nUser-agent: knee-bot\nDisallow: /ours\n\nUser-agent: other-bot\nDisallow: /theirs\nSitemap: https://a.test/s.xml\n";
+        let ours = parse_robots_txt_for(body, "Knee-Bot/1.0 (+https://a.test/bot)");
+        assert_eq!(ours.disallowed, vec!["/ours"]);
+        assert_eq!(ours.crawl_delay, None);
+        assert_eq!(ours.sitemaps, vec!["https://a.test/s.xml"]);
+
+        let other = parse_robots_txt_for(body, "other-bot");
+        assert_eq!(other.disallowed, vec!["/ours", "/theirs"]);
+
+        let anyone = parse_robots_txt_for(body, "Mozilla/5.0 (X11; Linux x86_64)");
+        assert_eq!(anyone.disallowed, vec!["/everyone"]);
+        assert_eq!(anyone.crawl_delay, Some(1.0));
+        assert_eq!(parse_robots_txt(body), anyone);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_sitemap_urls_follows_indexes() {
+        let base = serve(|request| {
+            if request.contains("user-agent: blocked-agent") {
+                return http_response("403 Forbidden", &[], b"");
+            }
+            let body = if request.starts_with("GET /sitemap_index.xml") {
+                "<sitemapindex><sitemap><loc>/pages.xml</loc></sitemap><sitemap><loc>/archive.xml.gz</loc></sitemap></sitemapindex>"
+            } else {
+                &format!(
+                    "<urlset><url><loc><![CDATA[https://a.test/one]]></loc></url><url><loc>/two</loc></url><url><loc>https://a.test/one</loc></url><!-- {} --></urlset>",
+                    "x".repeat(5_000)
+                )
+            };
+            http_response("200 OK", &[("Content-Type", "application/xml")], body.as_bytes())
+        })
+        .await;
+
+        let index = vec![format!("{}/sitemap_index.xml", base)];
+        let urls = fetch_sitemap_urls(&index, &Client::new(), 10).await;
+        assert_eq!(urls, vec!["https://a.test/one".to_string(), format!("{}/two", base)]);
+        assert_eq!(fetch_sitemap_urls(&index, &Client::new(), 1).await.len(), 1);
+
+        // Sitemaps of a crawl are requested with the user agent of its config and counted in its traffic
+        let config = ScraperConfig::new(true, 1, Some("sitemap-agent".to_string()));
+        assert_eq!(fetch_sitemap_urls_with_config(&index, &Client::new(), 10, &config).await, urls);
+        assert!(config.metrics().traffic("127.0.0.1").received > 5_000);
+        let blocked = ScraperConfig::new(true, 1, Some("blocked-agent".to_string()));
+        assert!(fetch_sitemap_urls_with_config(&index, &Client::new(), 10, &blocked).await.is_empty());
+    }
+}
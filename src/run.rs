@@ -0,0 +1,332 @@
+// src/run.rs
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::future::Future;
+use std::io::Result as IoResult;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    run_with_config, AlternatePolicy, CrawlPartition, CrawlSummary, ExtractionRule, FrontierEntry, MimePolicy, RateLimitRule, RequestRules,
+    ScraperConfig,
+};
+
+/// The file name of the run manifest in the output root.
+pub const RUN_MANIFEST_FILE: &str = "run_manifest.json";
+
+/// The directory of the output root keeping the run manifest of every session, as `{session id}.json`.
+pub const RUNS_DIR: &str = "runs";
+
+// Bumped whenever the manifest layout changes incompatibly
+const FORMAT_VERSION: u32 = 1;
+
+/// The plain settings of a `ScraperConfig`, as stored in a run manifest.
+///
+/// Behavior supplied as code (URL scorer, dedup policy, download validator, page renderer) and
+/// the runtime state of a crawl (caches, findings, metrics) are not part of the settings; a
+/// replayed run uses the defaults for those. Starting a run whose config holds a download
+/// validator, a page renderer or an in-memory crawl database logs that its manifest leaves them out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrawlSettings {
+    pub follow_links: bool,
+    pub max_depth: i32,
+    pub user_agent: Option<String>,
+    pub scrape_audio: bool,
+    pub scrape_embeds: bool,
+    pub scrape_objects: bool,
+    pub scrape_tracks: bool,
+    pub scrape_stylesheets: bool,
+    pub target_image_width: Option<u32>,
+    pub download_images: bool,
+    pub download_videos: bool,
+    pub download_audio: bool,
+    pub download_documents: bool,
+    pub download_scripts: bool,
+    pub download_stylesheets: bool,
+    pub download_fonts: bool,
+    pub record_media_only: bool,
+    pub download_retries: u32,
+    pub mime_policy: MimePolicy,
+    pub skip_unchanged_media: bool,
+    pub media_sample_size: Option<u64>,
+    pub legacy_finding_files: bool,
+    pub follow_frames: bool,
+    pub follow_cross_origin_frames: bool,
+    pub redact_pii: bool,
+    pub follow_ftp: bool,
+    pub allowed_domains: Option<Vec<String>>,   // Domain list patterns
+    pub denied_domains: Option<Vec<String>>,
+    pub insecure_hosts: Option<Vec<String>>,    // Hosts whose certificates are not verified
+    pub save_html_snapshots: bool,
+    pub ignore_archive_directives: bool,
+    pub output_dir: PathBuf,
+    pub error_log: PathBuf,
+    pub page_retries: u32,
+    pub seed_from_sitemaps: bool,
+    pub seeds: Vec<String>,
+    pub frontier_export: Option<PathBuf>,
+    pub discovery_only: bool,
+    pub route_assets: bool,
+    pub max_sitemap_urls: usize,
+    pub discover_js_endpoints: bool,
+    pub normalize_monitored_pages: bool,
+    pub alternate_policy: AlternatePolicy,
+    pub parameter_wordlist: Option<Vec<String>>,
+    pub circuit_breaker: Option<(u32, Duration)>,   // Failure threshold, cooldown
+    pub host_byte_quota: Option<u64>,
+    pub host_byte_quotas: HashMap<String, u64>,
+    pub max_page_bytes: u64,
+    pub max_nesting_depth: usize,
+    pub page_timeout: Option<Duration>,
+    pub max_script_bytes: u64,
+    pub per_host_request_interval: Option<Duration>,
+    pub rate_limit_rules: Vec<RateLimitRule>,
+    pub bandwidth_limit: Option<u64>,               // Bytes per second, all hosts together
+    pub per_host_bandwidth_limit: Option<u64>,
+    pub request_rules: RequestRules,
+    pub extraction_rules: Vec<ExtractionRule>,
+    pub partitions: Vec<CrawlPartition>,
+    pub external_link_checker: Option<(usize, Duration)>,  // Concurrency, minimum interval
+    pub crawl_database: Option<PathBuf>,            // Set for incremental crawls
+    pub imported_frontier: Vec<FrontierEntry>,
+    pub session_id: Option<String>,                 // Set when the session ID is pinned
+}
+
+impl Default for CrawlSettings {
+    fn default() -> Self {
+        ScraperConfig::default().settings()
+    }
+}
+
+/// Where a run wrote its results.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunOutputs {
+    pub output_dir: PathBuf,
+    pub error_log: PathBuf,
+    pub dead_letters: PathBuf,
+}
+
+/// A machine-readable record of one crawl: what it started from, how it was configured, which
+/// version of the crate ran it, when, and where its results went.
+///
+/// Crawls write it to `run_manifest.json` in the output root when they start, and again with
+/// `finished_at` and the summary filled in when they end. Passing the manifest to `replay_run`
+/// repeats the run with the same seed and settings, which gives security and research work an
+/// audit trail that can be re-checked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub format_version: u32,
+    pub crate_version: String,
+    pub seed: String,
+    pub settings: CrawlSettings,
+    pub outputs: RunOutputs,
+    pub started_at: u64,                // Seconds since the Unix epoch
+    pub finished_at: Option<u64>,
+    pub summary: Option<CrawlSummary>,
+    #[serde(default)]
+    pub session_id: Option<String>,     // Also in the error log, findings and download manifests of the run
+}
+
+impl RunManifest {
+    /// Describes a run from `seed` with `config` that is starting now.
+    pub fn start(seed: &str, config: &ScraperConfig) -> Self {
+        let unrecorded = config.unrecorded_settings();
+        if !unrecorded.is_empty() {
+            let error_message = format!("The run manifest of '{}' does not record its {}; a replay uses the defaults", seed, unrecorded.join(", "));
+            console_error!("{}", error_message);
+            config.log_error(&error_message);
+        }
+        RunManifest {
+            format_version: FORMAT_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            seed: seed.to_string(),
+            settings: config.settings(),
+            outputs: RunOutputs {
+                output_dir: config.output_dir().to_path_buf(),
+                error_log: config.error_log(),
+                dead_letters: config.dead_letter_path(),
+            },
+            started_at: unix_now(),
+            finished_at: None,
+            summary: None,
+            session_id: config.session_id(),
+        }
+    }
+
+    // Method to mark the run as finished with its summary
+    pub fn finish(&mut self, summary: &CrawlSummary) {
+        self.finished_at = Some(unix_now());
+        self.summary = Some(summary.clone());
+    }
+
+    /// Builds a config with the settings of the run.
+    pub fn to_config(&self) -> ScraperConfig {
+        ScraperConfig::from_settings(&self.settings)
+    }
+
+    /// Writes the manifest as pretty-printed JSON.
+    pub fn write(&self, path: &Path) -> IoResult<()> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            create_dir_all(parent)?;
+        }
+        write(path, serde_json::to_string_pretty(self).map_err(std::io::Error::other)?)
+    }
+
+    pub fn read(path: &Path) -> IoResult<Self> {
+        serde_json::from_str(&read_to_string(path)?).map_err(std::io::Error::other)
+    }
+}
+
+/// Repeats the run described by a manifest: same seed, same settings, same output root.
+///
+/// # Arguments
+///
+/// * `manifest_path` - A `run_manifest.json` written by an earlier run, or written by hand.
+/// * `client` - A reference to a `reqwest::Client` for making HTTP requests.
+///
+/// # Returns
+///
+/// The `CrawlSummary` of the new run, or an error if the manifest cannot be read.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::replay_run;
+/// # use reqwest::Client;
+/// # use std::path::Path;
+/// # async fn example(client: Client) -> std::io::Result<()> {
+/// let summary = replay_run(Path::new("./scraped_data/run_manifest.json"), &client).await?;
+/// println!("{}", summary);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn replay_run(manifest_path: &Path, client: &Client) -> IoResult<CrawlSummary> {
+    let manifest = RunManifest::read(manifest_path)?;
+    let config = manifest.to_config();
+    Ok(run_with_config(&manifest.seed, client, Some(&config)).await)
+}
+
+tokio::task_local! {
+    // The session of the crawl whose future is being polled, see `in_session`
+    static SESSION_ID: String;
+}
+
+// Runs the future of a crawl in its session, so its log lines, findings, downloads and summary carry
+// the ID no matter how many other crawls run concurrently; `None` runs it without a session
+pub(crate) async fn in_session<F: Future>(session_id: Option<String>, crawl: F) -> F::Output {
+    match session_id {
+        Some(session_id) => SESSION_ID.scope(session_id, crawl).await,
+        None => crawl.await,
+    }
+}
+
+// Runs blocking work of a crawl in its session, like `in_session` does for its futures, since task
+// locals do not follow work onto blocking threads
+pub(crate) fn in_session_blocking<T>(session_id: Option<String>, work: impl FnOnce() -> T) -> T {
+    match session_id {
+        Some(session_id) => SESSION_ID.sync_scope(session_id, work),
+        None => work(),
+    }
+}
+
+// Returns the session of the crawl running on the current task, if any
+pub(crate) fn current_session_id() -> Option<String> {
+    SESSION_ID.try_with(String::clone).ok()
+}
+
+// Prints a line of console output, prefixed with `[<session>]` inside a crawl session; use the
+// `console!` and `console_error!` macros instead of calling it
+pub(crate) fn console_line(to_stderr: bool, line: fmt::Arguments) {
+    let line = match current_session_id() {
+        Some(session_id) => format!("[{}] {}", session_id, line),
+        None => line.to_string(),
+    };
+    if to_stderr {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+// Returns a new session ID: the start time in seconds since the Unix epoch and a random suffix,
+// e.g. `1760436900-3f9a2c1b`, so IDs sort by start time and concurrent runs never share one
+// Returns the path of the manifest of a session below an output root; pinned session IDs may hold
+// any character, so only file-name-safe ones are kept
+pub(crate) fn session_manifest_file(session_id: &str) -> PathBuf {
+    let name: String = session_id.chars().map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' }).collect();
+    Path::new(RUNS_DIR).join(format!("{}.json", name.trim_start_matches('.')))
+}
+
+pub(crate) fn new_session_id() -> String {
+    format!("{}-{:08x}", unix_now(), rand::random::<u32>())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DomainList, ExtractionSchema, ExternalLinkChecker};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_run_manifest_round_trips_settings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(true, 2, Some("audit-bot".to_string()));
+        config.set_output_dir(temp_dir.path());
+        config.set_download_documents(true);
+        config.set_denied_domains(Some(DomainList::parse("ads.test\n*.tracker.test")));
+        config.set_host_byte_quota_for("a.test", 5_000);
+        config.set_page_timeout(None);
+
+        let mut manifest = RunManifest::start("https://a.test/", &config);
+        manifest.finish(&CrawlSummary { pages_fetched: 3, ..CrawlSummary::default() });
+        let path = temp_dir.path().join(RUN_MANIFEST_FILE);
+        manifest.write(&path).unwrap();
+
+        let read = RunManifest::read(&path).unwrap();
+        assert_eq!(read, manifest);
+        assert_eq!(read.settings.denied_domains, Some(vec!["*.tracker.test".to_string(), "ads.test".to_string()]));
+
+        let replayed = read.to_config();
+        assert_eq!(replayed.settings(), config.settings());
+        assert_eq!(replayed.user_agent().map(String::as_str), Some("audit-bot"));
+        assert!(replayed.is_url_allowed("https://a.test/") && !replayed.is_url_allowed("https://x.tracker.test/"));
+    }
+
+    #[test]
+    fn test_every_setting_survives_from_settings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = ScraperConfig::new(false, 4, None);
+        config.set_per_host_request_interval(Some(Duration::from_millis(750)));
+        config.add_rate_limit_rule("/api/*", Some(Duration::from_secs(5)));
+        config.add_rate_limit_rule("*.png", None);
+        config.set_bandwidth_limit(Some(1_000_000));
+        config.set_per_host_bandwidth_limit(Some(250_000));
+        config.add_query_rule("/news/*", "nocache", "1");
+        config.add_header_rule("/api/*", "X-Requested-With", "XMLHttpRequest");
+        config.add_extraction_rule("/products/*", ExtractionSchema::new("product").field("name", "h1", None));
+        config.add_partition(CrawlPartition::section("docs", "/docs/*").max_pages(10));
+        config.add_partition(CrawlPartition::language("de"));
+        config.set_external_link_checker(Some(ExternalLinkChecker::new(3, Duration::from_millis(200))));
+        config.load_crawl_database(&temp_dir.path().join("crawl_db.json")).unwrap();
+        config.set_session_id(Some("job-42"));
+
+        let settings = config.settings();
+        let json = serde_json::to_string(&settings).unwrap();
+        let replayed = ScraperConfig::from_settings(&serde_json::from_str(&json).unwrap());
+        assert_eq!(replayed.settings(), settings);
+        assert_ne!(settings, ScraperConfig::new(false, 4, None).settings());
+        assert!(config.unrecorded_settings().is_empty());
+
+        config.set_download_validator(Some(Arc::new(|_: &crate::DownloadCheck| Ok(()))));
+        assert_eq!(config.unrecorded_settings(), vec!["download validator"]);
+    }
+}
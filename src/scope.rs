@@ -0,0 +1,171 @@
+// src/scope.rs
+
+use reqwest::Url;
+use std::collections::HashSet;
+use std::io::Result as IoResult;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// A set of domain patterns used to keep a crawl inside (allowlist) or away from (denylist) hosts.
+///
+/// Patterns are matched case-insensitively against URL hosts:
+///
+/// * `example.com` matches exactly `example.com`.
+/// * `*.example.com` matches any subdomain (`www.example.com`, `a.b.example.com`), but not `example.com` itself.
+/// * `.example.com` matches `example.com` and all of its subdomains.
+///
+/// Lookups cost one hash probe per label of the host, so lists with hundreds of thousands of
+/// entries stay cheap to query.
+#[derive(Debug, Clone, Default)]
+pub struct DomainList {
+    exact: HashSet<String>,
+    subdomains: HashSet<String>,
+}
+
+impl DomainList {
+    pub fn new() -> Self {
+        DomainList::default()
+    }
+
+    /// Parses a list with one pattern per line; blank lines and `#` comments are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use knee_scraper::DomainList;
+    /// let list = DomainList::parse("# in scope\nexample.com\n*.example.org\n");
+    /// assert!(list.matches_host("example.com"));
+    /// assert!(list.matches_host("WWW.example.org"));
+    /// assert!(!list.matches_host("example.org"));
+    /// ```
+    pub fn parse(text: &str) -> Self {
+        let mut list = DomainList::new();
+        for line in text.lines() {
+            let pattern = line.split('#').next().unwrap_or("").trim();
+            if !pattern.is_empty() {
+                list.insert(pattern);
+            }
+        }
+        list
+    }
+
+    /// Loads a list from a file with one pattern per line.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the list file.
+    ///
+    /// # Returns
+    ///
+    /// An `IoResult<DomainList>` with every pattern of the file.
+    pub fn from_file(path: &Path) -> IoResult<Self> {
+        Ok(DomainList::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Adds one pattern to the list.
+    pub fn insert(&mut self, pattern: &str) {
+        let pattern = pattern.trim().trim_end_matches('.').to_ascii_lowercase();
+        if let Some(domain) = pattern.strip_prefix("*.") {
+            self.subdomains.insert(domain.to_string());
+        } else if let Some(domain) = pattern.strip_prefix('.') {
+            self.exact.insert(domain.to_string());
+            self.subdomains.insert(domain.to_string());
+        } else if !pattern.is_empty() {
+            self.exact.insert(pattern);
+        }
+    }
+
+    /// Returns the patterns of the list, sorted, in the form `parse` accepts.
+    pub fn patterns(&self) -> Vec<String> {
+        let mut patterns: Vec<String> = self.exact.iter().cloned().chain(self.subdomains.iter().map(|domain| format!("*.{}", domain))).collect();
+        patterns.sort();
+        patterns
+    }
+
+    pub fn len(&self) -> usize {
+        self.exact.len() + self.subdomains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.subdomains.is_empty()
+    }
+
+    /// Returns whether a host name matches any pattern of the list.
+    pub fn matches_host(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        if self.exact.contains(&host) {
+            return true;
+        }
+        let mut rest = host.as_str();
+        while let Some((_, parent)) = rest.split_once('.') {
+            if self.subdomains.contains(parent) {
+                return true;
+            }
+            rest = parent;
+        }
+        false
+    }
+
+    /// Returns whether the host of a URL matches the list; URLs without a host never match.
+    pub fn matches_url(&self, url: &str) -> bool {
+        Url::parse(url).ok().and_then(|url| url.host_str().map(|host| self.matches_host(host))).unwrap_or(false)
+    }
+}
+
+/// The allowlist and denylist of a config. Either can be replaced while crawls sharing the
+/// config are running; URLs are checked against the lists current at that moment.
+#[derive(Debug, Default)]
+pub struct DomainFilters {
+    allowed: RwLock<Option<Arc<DomainList>>>,
+    denied: RwLock<Option<Arc<DomainList>>>,
+}
+
+impl DomainFilters {
+    // Method to replace the allowlist (`None` allows every host)
+    pub fn set_allowed(&self, domains: Option<DomainList>) {
+        *self.allowed.write().unwrap() = domains.map(Arc::new);
+    }
+
+    // Method to replace the denylist
+    pub fn set_denied(&self, domains: Option<DomainList>) {
+        *self.denied.write().unwrap() = domains.map(Arc::new);
+    }
+
+    pub fn allowed(&self) -> Option<Arc<DomainList>> {
+        self.allowed.read().unwrap().clone()
+    }
+
+    pub fn denied(&self) -> Option<Arc<DomainList>> {
+        self.denied.read().unwrap().clone()
+    }
+
+    /// Returns whether a URL passes the lists: its host is not denied and, when there is an allowlist, allowed.
+    pub fn is_url_allowed(&self, url: &str) -> bool {
+        if self.denied.read().unwrap().as_ref().is_some_and(|denied| denied.matches_url(url)) {
+            return false;
+        }
+        self.allowed.read().unwrap().as_ref().is_none_or(|allowed| allowed.matches_url(url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_list_patterns() {
+        let list = DomainList::parse("shop.test\n*.cdn.test   # any CDN node\n.corp.test\n\n");
+        assert_eq!(list.len(), 4);
+
+        assert!(list.matches_host("shop.test"));
+        assert!(!list.matches_host("www.shop.test"));
+        assert!(list.matches_host("eu.edge.cdn.test"));
+        assert!(!list.matches_host("cdn.test"));
+        assert!(list.matches_host("corp.test."));
+        assert!(list.matches_host("intranet.corp.test"));
+        assert!(!list.matches_host("notcorp.test"));
+
+        assert!(list.matches_url("https://Shop.test:8443/cart"));
+        assert!(!list.matches_url("mailto:sales@shop.test"));
+    }
+}
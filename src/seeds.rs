@@ -0,0 +1,111 @@
+// src/seeds.rs
+
+use scraper::{Html, Selector};
+use serde_json::Value;
+use std::fs::read_to_string;
+use std::io::{Error as IoError, ErrorKind, Result as IoResult};
+use std::path::Path;
+
+/// Reads the bookmarked URLs of a Netscape bookmark file, the HTML format every browser exports
+/// bookmarks in. Only `http` and `https` bookmarks are kept, each once, in file order.
+///
+/// # Example
+///
+/// ```no_run
+/// # use knee_scraper::read_bookmarks;
+/// # use std::path::Path;
+/// let seeds = read_bookmarks(Path::new("bookmarks.html")).unwrap();
+/// println!("{} bookmarked pages", seeds.len());
+/// ```
+pub fn read_bookmarks(path: &Path) -> IoResult<Vec<String>> {
+    Ok(bookmark_urls(&read_to_string(path)?))
+}
+
+/// Reads the page URLs of a HAR file (the network log browsers' developer tools export): the
+/// `GET` requests answered with HTML, or marked as documents, each once, in file order. Scripts,
+/// images and API calls are left out.
+pub fn read_har(path: &Path) -> IoResult<Vec<String>> {
+    let har: Value = serde_json::from_str(&read_to_string(path)?).map_err(|e| IoError::new(ErrorKind::InvalidData, e))?;
+    let entries = har["log"]["entries"].as_array().ok_or_else(|| IoError::new(ErrorKind::InvalidData, "not a HAR file: missing log.entries"))?;
+    let mut urls: Vec<String> = Vec::new();
+    for entry in entries {
+        let url = entry["request"]["url"].as_str().unwrap_or("");
+        let is_get = entry["request"]["method"].as_str().is_none_or(|method| method.eq_ignore_ascii_case("GET"));
+        let is_page = entry["_resourceType"].as_str() == Some("document") || entry["response"]["content"]["mimeType"].as_str().is_some_and(|mime| mime.contains("html"));
+        if is_get && is_page && is_web_url(url) && !urls.iter().any(|known| known == url) {
+            urls.push(url.to_string());
+        }
+    }
+    Ok(urls)
+}
+
+/// Reads crawl seeds from a HAR file (by its `.har` extension or JSON content) or a bookmark export.
+pub fn read_seed_file(path: &Path) -> IoResult<Vec<String>> {
+    let is_har = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("har"));
+    let contents = read_to_string(path)?;
+    if is_har || contents.trim_start().starts_with('{') {
+        read_har(path)
+    } else {
+        Ok(bookmark_urls(&contents))
+    }
+}
+
+// Returns the web links of a bookmark export, each once
+fn bookmark_urls(html: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href]").unwrap();
+    let mut urls: Vec<String> = Vec::new();
+    for link in document.select(&selector) {
+        let url = link.value().attr("href").unwrap_or("").trim();
+        if is_web_url(url) && !urls.iter().any(|known| known == url) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
+
+// Returns whether a URL can be crawled: `javascript:` bookmarklets, `place:` queries and the like cannot
+fn is_web_url(url: &str) -> bool {
+    let scheme = url.split(':').next().unwrap_or("").to_ascii_lowercase();
+    url.contains("://") && (scheme == "http" || scheme == "https")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeds_are_read_from_bookmarks_and_har() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bookmarks = temp_dir.path().join("bookmarks.html");
+        std::fs::write(
+            &bookmarks,
+            r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<TITLE>Bookmarks</TITLE><H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3>Shops</H3>
+    <DL><p>
+        <DT><A HREF="https://shop.test/deals" ADD_DATE="1700000000">Deals</A>
+        <DT><A HREF="javascript:alert(1)">Bookmarklet</A>
+        <DT><A HREF="place:sort=8">Recent</A>
+    </DL><p>
+    <DT><A HREF="http://blog.test/">Blog</A>
+    <DT><A HREF="https://shop.test/deals">Deals again</A>
+</DL><p>"#,
+        )
+        .unwrap();
+        assert_eq!(read_seed_file(&bookmarks).unwrap(), vec!["https://shop.test/deals", "http://blog.test/"]);
+
+        let har = temp_dir.path().join("session.har");
+        let entry = |method: &str, url: &str, mime: &str| format!(r#"{{"request": {{"method": "{}", "url": "{}"}}, "response": {{"content": {{"mimeType": "{}"}}}}}}"#, method, url, mime);
+        let entries = [
+            entry("GET", "https://shop.test/", "text/html; charset=utf-8"),
+            entry("GET", "https://shop.test/app.js", "application/javascript"),
+            entry("POST", "https://shop.test/cart", "text/html"),
+            entry("GET", "https://shop.test/p/1", "text/html"),
+        ];
+        std::fs::write(&har, format!(r#"{{"log": {{"version": "1.2", "entries": [{}]}}}}"#, entries.join(","))).unwrap();
+        assert_eq!(read_seed_file(&har).unwrap(), vec!["https://shop.test/", "https://shop.test/p/1"]);
+        assert!(read_har(&bookmarks).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+// src/snapshot.rs
+
+use reqwest::header::HeaderMap;
+use scraper::{Html, Selector};
+use std::io::Result as IoResult;
+use std::path::{Path, PathBuf};
+
+use crate::manifest::sha256_hex;
+
+/// The robots directives a page declares through `<meta name="robots">` (or a crawler-specific
+/// name such as `googlebot`) and the `X-Robots-Tag` response header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RobotsDirectives {
+    pub noindex: bool,
+    pub nofollow: bool,
+    pub noarchive: bool,
+    pub nosnippet: bool,
+}
+
+impl RobotsDirectives {
+    /// Parses a comma-separated directive list such as `noarchive, nosnippet` or `googlebot: none`.
+    pub fn parse(value: &str) -> Self {
+        let mut directives = RobotsDirectives::default();
+        for token in value.split(',') {
+            let mut token = token.trim().to_ascii_lowercase();
+            // `X-Robots-Tag` values may be scoped to a crawler: "googlebot: noarchive"
+            if let Some((scope, directive)) = token.split_once(':') {
+                if !scope.contains(' ') && scope != "unavailable_after" {
+                    token = directive.trim().to_string();
+                }
+            }
+            match token.as_str() {
+                "noindex" => directives.noindex = true,
+                "nofollow" => directives.nofollow = true,
+                "noarchive" | "nocache" => directives.noarchive = true,
+                "nosnippet" => directives.nosnippet = true,
+                "none" => {
+                    directives.noindex = true;
+                    directives.nofollow = true;
+                }
+                _ => {}
+            }
+        }
+        directives
+    }
+
+    /// Reads the directives of the `<meta>` robots tags of an HTML page.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use knee_scraper::RobotsDirectives;
+    /// let directives = RobotsDirectives::from_html(r#"<meta name="robots" content="noarchive">"#);
+    /// assert!(directives.noarchive);
+    /// assert!(!directives.nofollow);
+    /// ```
+    pub fn from_html(html: &str) -> Self {
+        let document = Html::parse_document(html);
+        let selector = Selector::parse("meta[name][content]").unwrap();
+        document
+            .select(&selector)
+            .filter(|meta| {
+                let name = meta.value().attr("name").unwrap_or("").to_ascii_lowercase();
+                name == "robots" || name.ends_with("bot")
+            })
+            .map(|meta| RobotsDirectives::parse(meta.value().attr("content").unwrap_or("")))
+            .fold(RobotsDirectives::default(), RobotsDirectives::merge)
+    }
+
+    /// Reads the directives of every `X-Robots-Tag` header of a response.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get_all("x-robots-tag")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .map(RobotsDirectives::parse)
+            .fold(RobotsDirectives::default(), RobotsDirectives::merge)
+    }
+
+    /// Combines two sets of directives; a directive set in either applies.
+    pub fn merge(self, other: RobotsDirectives) -> Self {
+        RobotsDirectives {
+            noindex: self.noindex || other.noindex,
+            nofollow: self.nofollow || other.nofollow,
+            noarchive: self.noarchive || other.noarchive,
+            nosnippet: self.nosnippet || other.nosnippet,
+        }
+    }
+
+    /// Returns whether the page asks not to have copies of its content stored.
+    pub fn forbids_snapshot(&self) -> bool {
+        self.noarchive || self.nosnippet
+    }
+}
+
+/// Returns the path of the raw HTML snapshot of `url` inside a domain output directory.
+pub fn snapshot_path(dir: &Path, url: &str) -> PathBuf {
+    dir.join("snapshots").join(format!("{}.html", &sha256_hex(url.as_bytes())[..16]))
+}
+
+/// Stores the raw HTML of a page under `{dir}/snapshots`, unless its robots directives forbid it.
+///
+/// # Arguments
+///
+/// * `html` - The HTML content of the page.
+/// * `url` - The URL of the page.
+/// * `dir` - The domain output directory.
+/// * `directives` - The robots directives of the page.
+/// * `ignore_directives` - Stores the snapshot even when `noarchive`/`nosnippet` is set.
+///
+/// # Returns
+///
+/// `Ok(Some(path))` when a snapshot was written, `Ok(None)` when it was skipped.
+pub fn save_html_snapshot(html: &str, url: &str, dir: &Path, directives: RobotsDirectives, ignore_directives: bool) -> IoResult<Option<PathBuf>> {
+    if directives.forbids_snapshot() && !ignore_directives {
+        return Ok(None);
+    }
+    let path = snapshot_path(dir, url);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, html)?;
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_robots_directives_from_meta_and_headers() {
+        let html = r#"<meta name="GoogleBot" content="nosnippet"><meta name="description" content="noarchive">"#;
+        let from_html = RobotsDirectives::from_html(html);
+        assert!(from_html.nosnippet && !from_html.noarchive);
+
+        let mut headers = HeaderMap::new();
+        headers.append("X-Robots-Tag", HeaderValue::from_static("otherbot: noarchive"));
+        headers.append("X-Robots-Tag", HeaderValue::from_static("none"));
+        let from_headers = RobotsDirectives::from_headers(&headers);
+        assert!(from_headers.noarchive && from_headers.noindex && from_headers.nofollow);
+        assert!(!from_headers.nosnippet);
+    }
+
+    #[test]
+    fn test_save_html_snapshot_honours_noarchive() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let noarchive = RobotsDirectives::parse("noarchive");
+
+        assert!(save_html_snapshot("<p>hi</p>", "https://a.test/", temp_dir.path(), noarchive, false).unwrap().is_none());
+        let path = save_html_snapshot("<p>hi</p>", "https://a.test/", temp_dir.path(), noarchive, true).unwrap().unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "<p>hi</p>");
+    }
+}
@@ -0,0 +1,256 @@
+// src/summary.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::{run, CrawlStats, DomainEntities, EntityKind, EntitySummary};
+
+#[derive(Debug, Default)]
+struct Metrics {
+    bytes_downloaded: u64,
+    media_saved: usize,
+    findings: BTreeMap<String, usize>,
+    hosts: HashMap<String, (usize, Duration, Duration)>,   // Requests, total time, slowest time
+    traffic: BTreeMap<String, HostTraffic>,
+    entities: BTreeMap<(String, EntityKind, String), (String, usize)>,    // By domain, kind and value: first page, pages
+}
+
+/// The bytes exchanged with one host (or all hosts) during a crawl.
+///
+/// Requests are counted by their request line and headers, responses by their status line,
+/// headers, and the body bytes actually received.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HostTraffic {
+    pub sent: u64,
+    pub received: u64,
+}
+
+impl HostTraffic {
+    pub fn total(&self) -> u64 {
+        self.sent + self.received
+    }
+}
+
+/// Counters shared by everything a crawl does: downloads, findings, and response times per host.
+#[derive(Debug, Default)]
+pub struct CrawlMetrics {
+    metrics: Mutex<Metrics>,
+}
+
+impl CrawlMetrics {
+    pub fn record_download(&self, bytes: u64) {
+        let mut metrics = self.metrics.lock().unwrap();
+        metrics.bytes_downloaded += bytes;
+        metrics.media_saved += 1;
+    }
+
+    pub fn record_findings(&self, category: &str, count: usize) {
+        if count > 0 {
+            *self.metrics.lock().unwrap().findings.entry(category.to_string()).or_insert(0) += count;
+        }
+    }
+
+    pub fn record_response_time(&self, host: &str, elapsed: Duration) {
+        let mut metrics = self.metrics.lock().unwrap();
+        let entry = metrics.hosts.entry(host.to_string()).or_insert((0, Duration::ZERO, Duration::ZERO));
+        entry.0 += 1;
+        entry.1 += elapsed;
+        entry.2 = entry.2.max(elapsed);
+    }
+
+    pub fn record_traffic(&self, host: &str, sent: u64, received: u64) {
+        let mut metrics = self.metrics.lock().unwrap();
+        let traffic = metrics.traffic.entry(host.to_string()).or_default();
+        traffic.sent += sent;
+        traffic.received += received;
+    }
+
+    pub fn traffic(&self, host: &str) -> HostTraffic {
+        self.metrics.lock().unwrap().traffic.get(host).copied().unwrap_or_default()
+    }
+
+    /// Returns the traffic of every host, by host name.
+    pub fn host_traffic(&self) -> BTreeMap<String, HostTraffic> {
+        self.metrics.lock().unwrap().traffic.clone()
+    }
+
+    /// Returns the traffic summed over all hosts.
+    pub fn total_traffic(&self) -> HostTraffic {
+        self.metrics.lock().unwrap().traffic.values().fold(HostTraffic::default(), |total, traffic| HostTraffic {
+            sent: total.sent + traffic.sent,
+            received: total.received + traffic.received,
+        })
+    }
+
+    /// Counts an entity found on the page `url`, under the domain of the page. Record each entity
+    /// once per page: occurrences count the pages it was found on.
+    pub fn record_entity(&self, url: &str, kind: EntityKind, value: &str) {
+        let domain = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default();
+        let mut metrics = self.metrics.lock().unwrap();
+        metrics.entities.entry((domain, kind, value.to_string())).or_insert_with(|| (url.to_string(), 0)).1 += 1;
+    }
+
+    /// Returns the entities found so far, merged per domain: every distinct email, phone number,
+    /// social handle and endpoint with the first page it was found on and how many pages had it.
+    pub fn entities(&self) -> Vec<DomainEntities> {
+        let metrics = self.metrics.lock().unwrap();
+        let mut domains: Vec<DomainEntities> = Vec::new();
+        for ((domain, kind, value), (first_seen, occurrences)) in &metrics.entities {
+            if domains.last().is_none_or(|last| last.domain != *domain) {
+                domains.push(DomainEntities { domain: domain.clone(), entities: Vec::new() });
+            }
+            let entity = EntitySummary { kind: *kind, value: value.clone(), first_seen: first_seen.clone(), occurrences: *occurrences };
+            domains.last_mut().unwrap().entities.push(entity);
+        }
+        domains
+    }
+
+    // Method to clear every counter
+    pub fn reset(&self) {
+        *self.metrics.lock().unwrap() = Metrics::default();
+    }
+}
+
+/// Response times of one host during a crawl.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostLatency {
+    pub host: String,
+    pub requests: usize,
+    pub average: Duration,
+    pub slowest: Duration,
+}
+
+/// The end-of-run report of a crawl.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrawlSummary {
+    pub pages_fetched: usize,       // Pages downloaded, changed or not
+    pub pages_skipped: usize,       // Out-of-scope, oversized, circuit-broken, over-quota, duplicate, alternate, and over-budget pages
+    pub pages_failed: usize,
+    pub bytes_downloaded: u64,      // Media bytes saved
+    pub bytes_sent: u64,            // Page and media traffic, see `HostTraffic`
+    pub bytes_received: u64,
+    pub media_saved: usize,
+    pub findings: BTreeMap<String, usize>,
+    pub elapsed: Duration,
+    pub slowest_hosts: Vec<HostLatency>,    // Up to five, slowest average first
+    #[serde(default)]
+    pub entities: Vec<DomainEntities>,      // See `CrawlMetrics::entities`
+    #[serde(default)]
+    pub session_id: Option<String>,         // The session of the crawl, when built inside one
+}
+
+impl CrawlSummary {
+    /// Builds a summary from the page counters and metrics of a crawl.
+    pub fn new(stats: &CrawlStats, metrics: &CrawlMetrics, elapsed: Duration) -> Self {
+        let entities = metrics.entities();
+        let metrics = metrics.metrics.lock().unwrap();
+        let mut slowest_hosts: Vec<HostLatency> = metrics
+            .hosts
+            .iter()
+            .map(|(host, &(requests, total, slowest))| HostLatency {
+                host: host.clone(),
+                requests,
+                average: total / requests.max(1) as u32,
+                slowest,
+            })
+            .collect();
+        slowest_hosts.sort_by(|a, b| b.average.cmp(&a.average).then_with(|| a.host.cmp(&b.host)));
+        slowest_hosts.truncate(5);
+        let (bytes_sent, bytes_received) = metrics.traffic.values().fold((0, 0), |(sent, received), traffic| (sent + traffic.sent, received + traffic.received));
+
+        CrawlSummary {
+            pages_fetched: stats.pages_scraped + stats.pages_unchanged,
+            pages_skipped: stats.links_out_of_scope + stats.pages_too_large + stats.pages_circuit_open + stats.pages_over_quota + stats.pages_duplicate + stats.pages_alternate + stats.pages_over_budget,
+            pages_failed: stats.pages_failed,
+            bytes_downloaded: metrics.bytes_downloaded,
+            bytes_sent,
+            bytes_received,
+            media_saved: metrics.media_saved,
+            findings: metrics.findings.clone(),
+            elapsed,
+            slowest_hosts,
+            entities,
+            session_id: run::current_session_id(),
+        }
+    }
+}
+
+impl fmt::Display for CrawlSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.session_id {
+            Some(session_id) => writeln!(f, "Crawl summary of session {} ({:.1}s)", session_id, self.elapsed.as_secs_f64())?,
+            None => writeln!(f, "Crawl summary ({:.1}s)", self.elapsed.as_secs_f64())?,
+        }
+        writeln!(f, "  Pages: {} fetched, {} skipped, {} failed", self.pages_fetched, self.pages_skipped, self.pages_failed)?;
+        writeln!(f, "  Media: {} files saved, {} bytes", self.media_saved, self.bytes_downloaded)?;
+        writeln!(f, "  Traffic: {} bytes sent, {} bytes received", self.bytes_sent, self.bytes_received)?;
+        if !self.findings.is_empty() {
+            let findings: Vec<String> = self.findings.iter().map(|(category, count)| format!("{} {}", count, category)).collect();
+            writeln!(f, "  Findings: {}", findings.join(", "))?;
+        }
+        for domain in &self.entities {
+            let counts: Vec<String> = [EntityKind::Email, EntityKind::Phone, EntityKind::SocialHandle, EntityKind::Endpoint]
+                .into_iter()
+                .filter(|&kind| domain.count(kind) > 0)
+                .map(|kind| format!("{} {}", domain.count(kind), kind))
+                .collect();
+            writeln!(f, "  Entities of {}: {}", domain.domain, counts.join(", "))?;
+        }
+        for host in &self.slowest_hosts {
+            writeln!(
+                f,
+                "  Slow host: {} ({} requests, {} ms average, {} ms slowest)",
+                host.host,
+                host.requests,
+                host.average.as_millis(),
+                host.slowest.as_millis()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crawl_summary_from_metrics() {
+        let metrics = CrawlMetrics::default();
+        metrics.record_download(1_000);
+        metrics.record_download(500);
+        metrics.record_findings("forms", 2);
+        metrics.record_findings("emails", 0);
+        metrics.record_response_time("fast.test", Duration::from_millis(10));
+        metrics.record_response_time("slow.test", Duration::from_millis(300));
+        metrics.record_response_time("slow.test", Duration::from_millis(100));
+
+        metrics.record_traffic("slow.test", 120, 4_000);
+        metrics.record_traffic("fast.test", 80, 1_000);
+
+        let stats = CrawlStats { pages_scraped: 3, pages_unchanged: 1, pages_failed: 1, links_out_of_scope: 3, pages_too_large: 1, ..CrawlStats::default() };
+        let summary = CrawlSummary::new(&stats, &metrics, Duration::from_secs(2));
+
+        assert_eq!((summary.pages_fetched, summary.pages_skipped, summary.pages_failed), (4, 4, 1));
+        assert_eq!((summary.media_saved, summary.bytes_downloaded), (2, 1_500));
+        assert_eq!(summary.findings.get("forms"), Some(&2));
+        assert!(!summary.findings.contains_key("emails"));
+        assert_eq!(summary.slowest_hosts[0].host, "slow.test");
+        assert_eq!(summary.slowest_hosts[0].average, Duration::from_millis(200));
+        assert_eq!((summary.bytes_sent, summary.bytes_received), (200, 5_000));
+        assert_eq!(metrics.traffic("fast.test").total(), 1_080);
+        assert!(summary.to_string().contains("4 fetched, 4 skipped, 1 failed"));
+
+        metrics.record_entity("https://shop.test/contact", EntityKind::Email, "sales@shop.test");
+        metrics.record_entity("https://shop.test/", EntityKind::Email, "sales@shop.test");
+        metrics.record_entity("https://blog.test/", EntityKind::SocialHandle, "twitter:shop");
+        let summary = CrawlSummary::new(&stats, &metrics, Duration::from_secs(2));
+        assert_eq!(summary.entities.iter().map(|domain| domain.domain.as_str()).collect::<Vec<_>>(), vec!["blog.test", "shop.test"]);
+        let email = &summary.entities[1].entities[0];
+        assert_eq!((email.first_seen.as_str(), email.occurrences), ("https://shop.test/contact", 2));
+        assert!(summary.to_string().contains("Entities of shop.test: 1 email"));
+    }
+}
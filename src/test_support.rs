@@ -0,0 +1,53 @@
+// src/test_support.rs
+
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Starts a local HTTP server answering every request with `handler`, so tests
+/// can exercise the network code paths without reaching the internet.
+///
+/// The handler receives the raw request head (request line and headers) and
+/// returns the full raw response. Returns the base URL, e.g. `http://127.0.0.1:4321`.
+pub async fn serve<F>(handler: F) -> String
+where
+    F: Fn(&str) -> Vec<u8> + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let response = handler(&String::from_utf8_lossy(&request));
+                let _ = stream.write_all(&response).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+/// Builds a raw HTTP/1.1 response with the given status line, extra headers and body.
+pub fn http_response(status: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+    let mut head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n", status, body.len());
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    let mut response = head.into_bytes();
+    response.extend_from_slice(body);
+    response
+}
@@ -0,0 +1,232 @@
+// src/throttle.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+use crate::pattern::UrlPattern;
+
+/// Paces a byte stream to a fixed rate by tracking when the link is next free.
+#[derive(Debug)]
+struct Pace {
+    bytes_per_sec: u64,
+    next_free: Instant,
+}
+
+impl Pace {
+    fn new(bytes_per_sec: u64) -> Self {
+        Pace { bytes_per_sec, next_free: Instant::now() }
+    }
+
+    // Books `bytes` of transfer time and returns the instant the transfer is paid off
+    fn reserve(&mut self, bytes: u64, now: Instant) -> Instant {
+        let start = self.next_free.max(now);
+        self.next_free = start + Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec.max(1) as f64);
+        self.next_free
+    }
+}
+
+#[derive(Debug, Default)]
+struct Limits {
+    global: Option<Pace>,
+    per_host_rate: Option<u64>,
+    hosts: HashMap<String, Pace>,
+}
+
+/// Caps the bandwidth used by streaming downloads, globally and per host.
+///
+/// Every download sharing a limiter (all downloads made with the same `ScraperConfig`, or with
+/// configs given the same limiter through `ScraperConfig::set_bandwidth_limiter`) is paced so the
+/// combined rate stays under the global cap, and the rate towards any single host stays under
+/// the per-host cap. `None` leaves a rate unlimited.
+#[derive(Debug, Default)]
+pub struct BandwidthLimiter {
+    limits: Mutex<Limits>,
+}
+
+impl BandwidthLimiter {
+    /// Creates a limiter with the given caps, in bytes per second.
+    pub fn new(global_bytes_per_sec: Option<u64>, per_host_bytes_per_sec: Option<u64>) -> Self {
+        let limiter = BandwidthLimiter::default();
+        limiter.set_global_limit(global_bytes_per_sec);
+        limiter.set_per_host_limit(per_host_bytes_per_sec);
+        limiter
+    }
+
+    // Method to update the global cap, in bytes per second
+    pub fn set_global_limit(&self, bytes_per_sec: Option<u64>) {
+        let mut limits = self.limits.lock().unwrap();
+        limits.global = bytes_per_sec.map(Pace::new);
+    }
+
+    // Method to update the per-host cap, in bytes per second
+    pub fn set_per_host_limit(&self, bytes_per_sec: Option<u64>) {
+        let mut limits = self.limits.lock().unwrap();
+        limits.per_host_rate = bytes_per_sec;
+        limits.hosts.clear();
+    }
+
+    pub fn global_limit(&self) -> Option<u64> {
+        self.limits.lock().unwrap().global.as_ref().map(|pace| pace.bytes_per_sec)
+    }
+
+    pub fn per_host_limit(&self) -> Option<u64> {
+        self.limits.lock().unwrap().per_host_rate
+    }
+
+    /// Accounts for `bytes` just received from `host`, sleeping as long as needed to stay under the caps.
+    pub async fn consume(&self, host: &str, bytes: u64) {
+        let deadline = {
+            let mut limits = self.limits.lock().unwrap();
+            let now = Instant::now();
+            let global = limits.global.as_mut().map(|pace| pace.reserve(bytes, now));
+            let per_host = limits.per_host_rate.map(|rate| {
+                limits
+                    .hosts
+                    .entry(host.to_string())
+                    .or_insert_with(|| Pace::new(rate))
+                    .reserve(bytes, now)
+            });
+            global.max(per_host)
+        };
+
+        if let Some(deadline) = deadline {
+            sleep_until(deadline).await;
+        }
+    }
+}
+
+/// A request-rate override for the URLs matching a pattern; `None` leaves them unlimited.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitRule {
+    pub pattern: UrlPattern,
+    pub min_interval: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct RequestLimits {
+    per_host_interval: Option<Duration>,
+    rules: Vec<RateLimitRule>,
+    next_slot: HashMap<(String, Option<usize>), Instant>,   // Keyed by host and matching rule
+}
+
+/// Spaces out requests, per host and per URL-pattern rule.
+///
+/// Requests to a host are at least the per-host interval apart. A URL matching a rule (the first
+/// one added wins) uses the rule's interval instead, tracked separately per host, so `/api/*` can
+/// be held to one request every 5 seconds while `*.png` is exempt and the rest of the site keeps
+/// the per-host pace.
+#[derive(Debug, Default)]
+pub struct RequestLimiter {
+    limits: Mutex<RequestLimits>,
+}
+
+impl RequestLimiter {
+    /// Creates a limiter with a per-host minimum interval between requests and no rules.
+    pub fn new(per_host_interval: Option<Duration>) -> Self {
+        let limiter = RequestLimiter::default();
+        limiter.set_per_host_interval(per_host_interval);
+        limiter
+    }
+
+    // Method to update the minimum interval between two requests to the same host
+    pub fn set_per_host_interval(&self, interval: Option<Duration>) {
+        self.limits.lock().unwrap().per_host_interval = interval;
+    }
+
+    // Method to add a rule overriding the interval for URLs matching `pattern`
+    pub fn add_rule(&self, pattern: &str, min_interval: Option<Duration>) {
+        let mut limits = self.limits.lock().unwrap();
+        limits.rules.push(RateLimitRule { pattern: UrlPattern::new(pattern), min_interval });
+    }
+
+    // Method to remove every rule, e.g. before adding a reloaded set
+    pub fn clear_rules(&self) {
+        let mut limits = self.limits.lock().unwrap();
+        limits.rules.clear();
+        limits.next_slot.retain(|(_, rule), _| rule.is_none());
+    }
+
+    pub fn per_host_interval(&self) -> Option<Duration> {
+        self.limits.lock().unwrap().per_host_interval
+    }
+
+    pub fn rules(&self) -> Vec<RateLimitRule> {
+        self.limits.lock().unwrap().rules.clone()
+    }
+
+    /// Waits until a request to `url` is allowed by the per-host pace or its matching rule.
+    pub async fn wait(&self, url: &str) {
+        let deadline = {
+            let mut limits = self.limits.lock().unwrap();
+            let host = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default();
+            let (rule, interval) = match limits.rules.iter().position(|rule| rule.pattern.matches(url)) {
+                Some(index) => (Some(index), limits.rules[index].min_interval),
+                None => (None, limits.per_host_interval),
+            };
+            interval.map(|interval| {
+                let now = Instant::now();
+                let slot = limits.next_slot.entry((host, rule)).or_insert(now);
+                let start = (*slot).max(now);
+                *slot = start + interval;
+                start
+            })
+        };
+
+        if let Some(deadline) = deadline {
+            sleep_until(deadline).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bandwidth_limiter_paces_per_host() {
+        let limiter = BandwidthLimiter::new(None, Some(10_000));
+        let started = Instant::now();
+        for _ in 0..3 {
+            limiter.consume("slow.test", 1_000).await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(250));
+
+        // Other hosts have their own budget
+        let started = Instant::now();
+        limiter.consume("other.test", 100).await;
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_request_limiter_rules_override_host_pace() {
+        let limiter = RequestLimiter::new(Some(Duration::from_millis(100)));
+        limiter.add_rule("/api/*", Some(Duration::from_millis(300)));
+        limiter.add_rule("*.png", None);
+
+        let started = Instant::now();
+        for _ in 0..5 {
+            limiter.wait("https://site.test/logo.png").await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(50));
+
+        limiter.wait("https://site.test/api/a").await;
+        limiter.wait("https://site.test/api/b").await;
+        assert!(started.elapsed() >= Duration::from_millis(300));
+
+        // Pages outside the rules keep the per-host pace, on their own schedule
+        let started = Instant::now();
+        limiter.wait("https://site.test/about").await;
+        assert!(started.elapsed() < Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_bandwidth_does_not_wait() {
+        let limiter = BandwidthLimiter::default();
+        let started = Instant::now();
+        limiter.consume("fast.test", 10_000_000).await;
+        assert!(started.elapsed() < Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,107 @@
+// src/tls.rs
+
+use reqwest::redirect::Policy;
+use reqwest::{Client, Url};
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::DomainList;
+
+// Redirects the certificate-ignoring client follows, like reqwest's default policy
+const MAX_REDIRECTS: usize = 10;
+
+/// Which hosts are crawled without verifying their TLS certificates, e.g. internal staging servers
+/// with self-signed certificates. Every other host keeps strict verification.
+///
+/// Requests to the listed hosts go through a second client that accepts invalid certificates. It
+/// only follows redirects between listed hosts: a redirect anywhere else is handed back as the
+/// response instead of being followed without verification. That client is built with reqwest's
+/// defaults, so settings of the client passed to the crawl (a proxy, its timeouts) don't apply to
+/// the listed hosts.
+///
+/// # Example
+///
+/// ```
+/// # use knee_scraper::{DomainList, ScraperConfig};
+/// let mut config = ScraperConfig::default();
+/// config.set_insecure_hosts(Some(DomainList::parse("staging.internal.test\n*.dev.internal.test")));
+/// assert!(config.tls_policy().accepts_invalid_certs("https://api.dev.internal.test/health"));
+/// assert!(!config.tls_policy().accepts_invalid_certs("https://example.com/"));
+/// ```
+#[derive(Debug, Default)]
+pub struct TlsPolicy {
+    insecure_hosts: RwLock<Option<Arc<DomainList>>>,
+    client: Mutex<Option<Client>>,      // Built on first use
+}
+
+impl TlsPolicy {
+    pub fn set_insecure_hosts(&self, hosts: Option<DomainList>) {
+        *self.insecure_hosts.write().unwrap() = hosts.filter(|hosts| !hosts.is_empty()).map(Arc::new);
+        *self.client.lock().unwrap() = None;
+    }
+
+    pub fn insecure_hosts(&self) -> Option<Arc<DomainList>> {
+        self.insecure_hosts.read().unwrap().clone()
+    }
+
+    /// Returns whether the certificate of the host of `url` is accepted even when invalid.
+    pub fn accepts_invalid_certs(&self, url: &str) -> bool {
+        let host = Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default();
+        self.insecure_hosts().is_some_and(|hosts| hosts.matches_host(&host))
+    }
+
+    // Returns the client to request `url` with: `client` itself, unless the host of `url` is listed
+    pub(crate) fn client_for(&self, client: &Client, url: &str) -> Client {
+        let Some(hosts) = self.insecure_hosts().filter(|_| self.accepts_invalid_certs(url)) else {
+            return client.clone();
+        };
+        let mut insecure = self.client.lock().unwrap();
+        if insecure.is_none() {
+            let redirect = Policy::custom(move |attempt| {
+                let listed = attempt.url().host_str().is_some_and(|host| hosts.matches_host(host));
+                if listed && attempt.previous().len() < MAX_REDIRECTS {
+                    attempt.follow()
+                } else {
+                    attempt.stop()
+                }
+            });
+            match Client::builder().danger_accept_invalid_certs(true).redirect(redirect).build() {
+                Ok(built) => *insecure = Some(built),
+                Err(e) => {
+                    console_error!("Failed to build a client accepting invalid certificates, verifying them: {}", e);
+                    return client.clone();
+                }
+            }
+        }
+        insecure.clone().unwrap_or_else(|| client.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{http_response, serve};
+
+    #[tokio::test]
+    async fn test_insecure_client_stays_on_listed_hosts() {
+        let base = serve(|request| {
+            if request.starts_with("GET /moved") {
+                http_response("302 Found", &[("Location", "http://localhost:1/elsewhere")], b"")
+            } else {
+                http_response("200 OK", &[], b"ok")
+            }
+        })
+        .await;
+        let policy = TlsPolicy::default();
+        policy.set_insecure_hosts(Some(DomainList::parse("127.0.0.1")));
+        assert!(policy.accepts_invalid_certs(&format!("{}/page", base)));
+        assert!(!policy.accepts_invalid_certs("https://localhost/page"));
+
+        // The redirect to an unlisted host is not followed
+        let client = policy.client_for(&Client::new(), &base);
+        let response = client.get(format!("{}/moved", base)).send().await.unwrap();
+        assert_eq!(response.status(), 302);
+
+        policy.set_insecure_hosts(Some(DomainList::new()));
+        assert!(policy.insecure_hosts().is_none());
+    }
+}